@@ -48,6 +48,15 @@ pub enum CliError {
     #[error("Second argument OUTPUT_FILE missing. You may only omit this when compiling to html and using the live preview.")]
     MissingOutputFile,
 
+    #[error("The --watch flag can't be used when reading from stdin or writing to stdout")]
+    WatchStdio,
+
     #[error("Could not resolve template tag: '{0}'.")]
     TemplateTag(String),
+
+    #[error("Could not create package: {0}.")]
+    TemplateArgument(String),
+
+    #[error("Could not test package: {0}.")]
+    PackageTest(String),
 }
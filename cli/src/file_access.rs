@@ -1,7 +1,7 @@
 use crate::CompileArgs;
 use modmark_core::AccessPolicy;
 use std::collections::HashMap;
-use std::io::{stdin, stdout, Write};
+use std::io::{stderr, stdin, Write};
 use std::path::Path;
 
 #[derive(Default)]
@@ -58,21 +58,26 @@ impl AccessPolicy for CliAccessManager {
 
 // TODO LATER: make async to work better with the rest of the CLI
 fn prompt_user(prompt: &str) -> bool {
-    println!("{prompt}");
+    eprintln!("{prompt}");
 
     let allowed_input = ["y", "n", "Y", "N"];
 
     loop {
         let mut buffer = String::new();
-        stdin().read_line(&mut buffer).expect("");
+        // If stdin is closed (such as when the document itself is read from stdin), we can't ask
+        // the user, so we deny the access
+        if stdin().read_line(&mut buffer).expect("") == 0 {
+            eprintln!("Could not read answer from stdin, denying access");
+            return false;
+        }
 
         let input = buffer.trim();
         if allowed_input.contains(&input) {
             return input == "y" || input == "Y";
         }
 
-        print!("Unexpected input. Please enter (y/n): ");
-        stdout().flush().expect("Could not flush output");
+        eprint!("Unexpected input. Please enter (y/n): ");
+        stderr().flush().expect("Could not flush output");
     }
 }
 
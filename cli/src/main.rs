@@ -1,8 +1,8 @@
 use std::{
     collections::HashMap,
     env,
-    fs::{self, remove_dir_all, remove_file, File},
-    io::{stdout, Write},
+    fs::{self, File},
+    io::{self, stderr, stdout, Read, Write},
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicUsize, Ordering},
@@ -10,6 +10,7 @@ use std::{
     },
 };
 
+use base64::{engine::general_purpose, Engine as _};
 use clap::{Parser, Subcommand};
 use crossterm::{
     cursor,
@@ -39,10 +40,14 @@ use parser::{parse, Ast};
 
 use crate::file_access::CliAccessManager;
 use crate::package::PackageManager;
+use crate::package_test::run_package_tests;
+use crate::templates::{colliding_identifiers, find_template, PackageOptions, TEMPLATES};
 
 mod error;
 mod file_access;
 mod package;
+mod package_test;
+mod templates;
 
 #[derive(Parser)]
 #[command(author, version, about)]
@@ -53,10 +58,10 @@ struct Args {
 
 #[derive(Parser)]
 struct CompileArgs {
-    #[arg(index = 1, help = "Path to input file")]
+    #[arg(index = 1, help = "Path to input file, or '-' to read from stdin")]
     input: PathBuf,
 
-    #[arg(index = 2, help = "Path to output file")]
+    #[arg(index = 2, help = "Path to output file, or '-' to write to stdout")]
     output: Option<PathBuf>,
 
     #[arg(short = 'f', long = "format", help = "The output format of the file")]
@@ -102,6 +107,16 @@ struct CompileArgs {
 }
 
 impl CompileArgs {
+    /// Check if the input document should be read from stdin
+    fn reads_from_stdin(&self) -> bool {
+        self.input.as_os_str() == "-"
+    }
+
+    /// Check if the output document should be written to stdout
+    fn writes_to_stdout(&self) -> bool {
+        self.output.as_deref() == Some(Path::new("-"))
+    }
+
     /// Get the output format from the cli and, if need be,
     /// infer the format based on the file extension on the output file
     fn get_output_format(&self) -> Result<OutputFormat, CliError> {
@@ -110,6 +125,12 @@ impl CompileArgs {
                 Some("tex") => Some(OutputFormat::new("latex")),
                 Some("html") => Some(OutputFormat::new("html")),
                 Some("htm") => Some(OutputFormat::new("html")),
+                Some("md") => Some(OutputFormat::new("markdown")),
+                Some("txt") => Some(OutputFormat::new("plaintext")),
+                Some("docx") => Some(OutputFormat::new("docx")),
+                Some("epub") => Some(OutputFormat::new("epub")),
+                Some("typ") => Some(OutputFormat::new("typst")),
+                Some("json") => Some(OutputFormat::new("pandoc-json")),
                 _ => None,
             })
         };
@@ -126,6 +147,11 @@ impl CompileArgs {
 
     /// Check if a html live preview should be used
     fn use_html_preview(&self) -> Result<bool, CliError> {
+        // A document read from stdin can't be watched for changes
+        if self.reads_from_stdin() {
+            return Ok(false);
+        }
+
         // If no output file was provided and the output format is "html" (or left unspecified)
         // we know that the user wants to use the live preview.
         if self.output.is_none() {
@@ -156,6 +182,55 @@ struct InitArgs {
         help = "What language the template will be made in. Enter 'list' for a list of languages."
     )]
     language: String,
+
+    #[arg(
+        index = 2,
+        default_value = "my-package",
+        help = "The name of the package, which is also the name of the folder it is created in"
+    )]
+    name: String,
+
+    #[arg(
+        long = "description",
+        default_value = "A ModMark package",
+        help = "A short description of the package"
+    )]
+    description: String,
+
+    #[arg(
+        short = 't',
+        long = "transform",
+        value_delimiter = ',',
+        default_value = "example",
+        help = "The modules to create transforms for, separated by commas"
+    )]
+    transforms: Vec<String>,
+
+    #[arg(
+        short = 'f',
+        long = "format",
+        value_delimiter = ',',
+        default_value = "html,latex",
+        help = "The output formats each transform supports, separated by commas"
+    )]
+    formats: Vec<String>,
+}
+
+#[derive(Parser)]
+struct TestArgs {
+    #[arg(
+        index = 1,
+        default_value = ".",
+        help = "Path to a .wasm package, or to a Cargo package which is built for wasm32-wasi"
+    )]
+    package: PathBuf,
+
+    #[arg(
+        short = 't',
+        long = "tests",
+        help = "Path to the folder with the tests, by default the 'tests' folder of the package"
+    )]
+    tests: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -166,6 +241,7 @@ enum Command {
         command: CacheCommand,
     },
     Init(InitArgs),
+    Test(TestArgs),
 }
 
 #[derive(Subcommand)]
@@ -190,9 +266,18 @@ type PreviewDoc = Arc<Mutex<String>>;
 
 type CompilationResult = Result<(String, CompilationState, Ast), Vec<CoreError>>;
 
-/// Compile a file and return the transpiled content, compilation state and ast.
+/// Compile a file and return the transpiled content, compilation state and ast. If the path of the
+/// file is '-', the document is read from stdin instead.
 async fn compile_file(input_file: &Path, output_format: &OutputFormat) -> CompilationResult {
-    let source = fs::read_to_string(input_file).map_err(|e| vec![e.into()])?;
+    let source = if input_file.as_os_str() == "-" {
+        let mut buffer = String::new();
+        io::stdin()
+            .read_to_string(&mut buffer)
+            .map_err(|e| vec![e.into()])?;
+        buffer
+    } else {
+        fs::read_to_string(input_file).map_err(|e| vec![e.into()])?
+    };
     let ast = parse(&source).map_err(|e| vec![e.into()])?;
 
     for i in 1..=MAX_COMPILATION_TRIES {
@@ -221,107 +306,158 @@ async fn compile_file(input_file: &Path, output_format: &OutputFormat) -> Compil
     Err(vec![])
 }
 
-fn print_compiling_message() -> Result<(), CliError> {
-    let mut stdout = stdout();
-    stdout.execute(terminal::Clear(terminal::ClearType::All))?;
-    stdout.execute(cursor::MoveTo(0, 0))?;
-    stdout.execute(style::PrintStyledContent("Compiling...".yellow()))?;
+/// Clear the terminal, unless the document is written to stdout since the terminal may then
+/// contain the document
+fn clear_terminal(args: &CompileArgs) -> Result<(), CliError> {
+    if !args.writes_to_stdout() {
+        let mut stderr = stderr();
+        stderr.execute(terminal::Clear(terminal::ClearType::All))?;
+        stderr.execute(cursor::MoveTo(0, 0))?;
+    }
 
     Ok(())
 }
 
-fn print_result(result: &CompilationResult, args: &CompileArgs) -> Result<(), CliError> {
-    let mut stdout = stdout();
+// Note that all messages are printed to stderr, so that they don't end up in the document when it
+// is written to stdout
+
+fn print_compiling_message(args: &CompileArgs) -> Result<(), CliError> {
+    let mut stderr = stderr();
+    clear_terminal(args)?;
+    stderr.execute(style::PrintStyledContent("Compiling...".yellow()))?;
+
+    Ok(())
+}
+
+fn print_result(
+    result: &CompilationResult,
+    saved: bool,
+    args: &CompileArgs,
+) -> Result<(), CliError> {
+    let mut stderr = stderr();
 
     let (_, state, ast) = match result {
         Ok(result) => result,
         Err(errors) => {
-            stdout.execute(terminal::Clear(terminal::ClearType::All))?;
-            stdout.execute(cursor::MoveTo(0, 0))?;
+            clear_terminal(args)?;
             let num_errors = errors.len();
             if num_errors == 0 {
-                stdout.execute(style::PrintStyledContent(
+                stderr.execute(style::PrintStyledContent(
                     "No result retrieved from compiler\n".red(),
                 ))?;
             } else if num_errors == 1 {
                 let error = errors.first().unwrap();
-                stdout.execute(style::PrintStyledContent(
+                stderr.execute(style::PrintStyledContent(
                     format!("1 compilation error:\n{error}\n").red(),
                 ))?;
             } else {
-                stdout.execute(style::PrintStyledContent(
+                stderr.execute(style::PrintStyledContent(
                     format!("{} compilation errors:\n", num_errors).red(),
                 ))?;
                 for error in errors {
-                    stdout.execute(style::PrintStyledContent(format!("{error:?}\n").red()))?;
+                    stderr.execute(style::PrintStyledContent(format!("{error:?}\n").red()))?;
                 }
             }
             return Ok(());
         }
     };
 
-    stdout.execute(terminal::Clear(terminal::ClearType::All))?;
-    stdout.execute(cursor::MoveTo(0, 0))?;
-    stdout.execute(style::PrintStyledContent(
+    clear_terminal(args)?;
+    stderr.execute(style::PrintStyledContent(
         "File successfully compiled!\n".green(),
     ))?;
 
     // Print the path to the live preview (if using one)
     if args.use_html_preview()? {
         let port = get_port()?;
-        println!("Live preview available at: http://localhost:{port}");
+        eprintln!("Live preview available at: http://localhost:{port}");
     }
 
-    println!();
+    eprintln!();
 
     if !state.warnings.is_empty() {
-        stdout.execute(style::PrintStyledContent("Warnings:\n".yellow()))?;
+        stderr.execute(style::PrintStyledContent("Warnings:\n".yellow()))?;
         for warning in &state.warnings {
-            stdout.execute(style::PrintStyledContent(format!("{warning}\n").yellow()))?;
+            stderr.execute(style::PrintStyledContent(format!("{warning}\n").yellow()))?;
         }
     }
 
     if !state.errors.is_empty() {
-        stdout.execute(style::PrintStyledContent("Errors:\n".red()))?;
+        stderr.execute(style::PrintStyledContent("Errors:\n".red()))?;
         for error in &state.errors {
-            stdout.execute(style::PrintStyledContent(format!("{error}\n").red()))?;
+            stderr.execute(style::PrintStyledContent(format!("{error}\n").red()))?;
         }
     }
 
-    // Print the path to the output
+    // Print the path to the output, unless it isn't saved
     // (If we have already saved the file and have gotten the absolute path
     // print that otherwise we print the provided path from the cli)
-    if let Some(output_path) = ABSOLUTE_OUTPUT_PATH.get() {
-        println!("Your file can be found at {}.", output_path.display());
-    } else if let Some(output_path) = args.output.as_ref() {
-        println!("Your file can be found at {}.", output_path.display());
+    if !saved {
+        stderr.execute(style::PrintStyledContent(
+            "The output could not be created, so nothing was written\n".red(),
+        ))?;
+    } else if let Some(output_path) = ABSOLUTE_OUTPUT_PATH.get() {
+        eprintln!("Your file can be found at {}.", output_path.display());
+    } else if let Some(output_path) = args.output.as_ref().filter(|_| !args.writes_to_stdout()) {
+        eprintln!("Your file can be found at {}.", output_path.display());
     }
 
     if args.dev {
-        println!("{}", ast.tree_string());
+        eprintln!("{}", ast.tree_string());
     }
 
-    stdout.flush()?;
+    stderr.flush()?;
 
     Ok(())
 }
 
-/// Write the result to a file
-fn save_result(result: &CompilationResult, args: &CompileArgs) -> Result<(), CliError> {
+/// Get the bytes to save for a compiled document, or None if there is nothing to save. The output
+/// of binary formats is encoded as base64, and is decoded here. If the compilation of a binary
+/// format has errors, the output may not be a valid file, since a failing output filter leaves
+/// the output as it was, so it is not saved.
+fn document_bytes(
+    result: &CompilationResult,
+    args: &CompileArgs,
+) -> Result<Option<Vec<u8>>, CliError> {
+    let Ok((document, state, _)) = result else {
+        return Ok(None);
+    };
+
+    let format = args.get_output_format()?;
+    if !CTX.get().unwrap().lock().unwrap().is_binary_format(&format) {
+        return Ok(Some(document.as_bytes().to_vec()));
+    }
+    if !state.errors.is_empty() {
+        return Ok(None);
+    }
+    Ok(general_purpose::STANDARD.decode(document.trim()).ok())
+}
+
+/// Write the bytes of the document to a file, or to stdout if the path of the file is '-'
+fn save_result(bytes: Option<&[u8]>, args: &CompileArgs) -> Result<(), CliError> {
+    let Some(bytes) = bytes else {
+        return Ok(());
+    };
+
+    if args.writes_to_stdout() {
+        let mut stdout = stdout();
+        stdout.write_all(bytes)?;
+        stdout.flush()?;
+        return Ok(());
+    }
+
     if let Some(output) = &args.output {
-        if let Ok((document, _, _)) = result {
-            let mut file = File::create(output)?;
-
-            // Save the absolute path to the output file
-            // now once we have created it.
-            ABSOLUTE_OUTPUT_PATH.get_or_init(|| {
-                output
-                    .canonicalize()
-                    .expect("Failed to find absolute path of output file")
-            });
+        let mut file = File::create(output)?;
 
-            file.write_all(document.as_bytes())?;
-        }
+        // Save the absolute path to the output file
+        // now once we have created it.
+        ABSOLUTE_OUTPUT_PATH.get_or_init(|| {
+            output
+                .canonicalize()
+                .expect("Failed to find absolute path of output file")
+        });
+
+        file.write_all(bytes)?;
     }
 
     Ok(())
@@ -335,8 +471,8 @@ async fn main() {
         Command::Compile(compile_args) => match run_compile(compile_args).await {
             Ok(_) => (),
             Err(error) => {
-                let mut stdout = stdout();
-                stdout
+                let mut stderr = stderr();
+                stderr
                     .execute(style::PrintStyledContent(format!("{error}").red()))
                     .unwrap();
             }
@@ -344,8 +480,8 @@ async fn main() {
         Command::Cache { command } => match run_cache(command).await {
             Ok(_) => (),
             Err(error) => {
-                let mut stdout = stdout();
-                stdout
+                let mut stderr = stderr();
+                stderr
                     .execute(style::PrintStyledContent(format!("{error}").red()))
                     .unwrap();
             }
@@ -353,16 +489,36 @@ async fn main() {
         Command::Init(init_args) => match run_init(init_args).await {
             Ok(_) => (),
             Err(error) => {
-                let mut stdout = stdout();
-                stdout
+                let mut stderr = stderr();
+                stderr
                     .execute(style::PrintStyledContent(format!("{error}").red()))
                     .unwrap();
             }
         },
+        Command::Test(test_args) => {
+            match run_package_tests(&test_args.package, test_args.tests.as_deref()) {
+                Ok(true) => (),
+                // Exit with a non-zero exit code if any test failed, so that the command can be
+                // used in CI
+                Ok(false) => std::process::exit(1),
+                Err(error) => {
+                    let mut stderr = stderr();
+                    stderr
+                        .execute(style::PrintStyledContent(format!("{error}").red()))
+                        .unwrap();
+                    std::process::exit(1);
+                }
+            }
+        }
     }
 }
 
 async fn run_compile(args: &CompileArgs) -> Result<(), CliError> {
+    // A document read from stdin or written to stdout can't be watched
+    if args.watch && (args.reads_from_stdin() || args.writes_to_stdout()) {
+        return Err(CliError::WatchStdio);
+    }
+
     let current_path = env::current_dir()?;
 
     let catalog = args
@@ -388,6 +544,8 @@ async fn run_compile(args: &CompileArgs) -> Result<(), CliError> {
         })
         .unwrap();
         context.verbose = args.verbose;
+        // The live preview needs to know which line each block comes from
+        context.track_source_lines = args.use_html_preview().unwrap_or(false);
         context
     }))
     .unwrap();
@@ -399,7 +557,7 @@ async fn run_compile(args: &CompileArgs) -> Result<(), CliError> {
         let document = PreviewDoc::default();
         let port = get_port()?;
 
-        println!("started server and watching dir {current_path:?}");
+        eprintln!("started server and watching dir {current_path:?}");
 
         // Spawn a seperate task for the preview server
         {
@@ -427,10 +585,11 @@ async fn run_compile(args: &CompileArgs) -> Result<(), CliError> {
     // Otherwise, if they are not using the watcher or live preview
     // just compile the file once, assuming that they actually provided a output file
     if args.output.is_some() {
-        print_compiling_message()?;
+        print_compiling_message(args)?;
         let compilation_result = compile_file(&args.input, &args.get_output_format()?).await;
-        save_result(&compilation_result, &args)?;
-        print_result(&compilation_result, &args)?;
+        let bytes = document_bytes(&compilation_result, &args)?;
+        print_result(&compilation_result, bytes.is_some(), &args)?;
+        save_result(bytes.as_deref(), &args)?;
     } else {
         return Err(CliError::MissingOutputFile);
     }
@@ -477,51 +636,100 @@ async fn run_cache(command: &CacheCommand) -> Result<(), CliError> {
 }
 
 async fn run_init(args: &InitArgs) -> Result<(), CliError> {
-    let template_json = include_str!("templates.json");
-    let mappings: HashMap<String, Vec<String>> = serde_json::from_str(template_json)?;
-
-    match args.language.to_lowercase().trim() {
-        "list" => {
-            println!("These are the available languages:");
-            mappings
-                .iter()
-                .for_each(|(_, languages)| println!("\t{}", languages[0]));
-            Ok(())
-        }
-        language => {
-            let language = language.to_string();
+    let language = args.language.to_lowercase();
 
-            let Some(url) = mappings
-        .iter()
-        .find_map(|(k, v)| v.contains(&language).then_some(k)) else {
-            return Err(CliError::TemplateTag(language));
-        };
+    if language.trim() == "list" {
+        println!("These are the available languages:");
+        TEMPLATES
+            .iter()
+            .for_each(|template| println!("\t{}", template.names[0]));
+        return Ok(());
+    }
 
-            let Some(folder_name) = url.rsplit_once('/').map(|(_, second)| second) else {
-            return Err(CliError::TemplateTag(language));
-        };
+    let Some(template) = find_template(language.trim()) else {
+        return Err(CliError::TemplateTag(language));
+    };
+
+    // Use the same naming rules as the package tests, so that the package could be added to the
+    // standard packages as-is
+    let valid_name = |name: &str| {
+        name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+    };
+
+    if !valid_name(&args.name) {
+        return Err(CliError::TemplateArgument(format!(
+            "Package name '{}' must start with a lowercase letter, and must only contain lowercase letters, digits, underscores and dashes",
+            args.name
+        )));
+    }
 
-            std::process::Command::new("git")
-                .arg("clone")
-                .arg(url)
-                .output()?;
+    // The description is put in the README and in manifests such as Cargo.toml, where it must be
+    // on a single line
+    if args.description.chars().any(char::is_control) {
+        return Err(CliError::TemplateArgument(
+            "The description must be a single line, without control characters".to_string(),
+        ));
+    }
 
-            let project_path = fs::canonicalize(folder_name)?;
+    if let Some(from) = args.transforms.iter().find(|from| {
+        from.is_empty()
+            || !from.chars().all(|c| {
+                c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-' || c == '.'
+            })
+    }) {
+        return Err(CliError::TemplateArgument(format!(
+            "Module name '{from}' must only contain lowercase letters, digits, underscores, dashes and dots"
+        )));
+    }
 
-            let mut git_path = project_path.clone();
-            git_path.push(".git");
+    // Every transform gets its own function in the generated code, named after the module
+    if let Some((first, second)) = colliding_identifiers(&args.transforms) {
+        return Err(CliError::TemplateArgument(if first == second {
+            format!("The module '{first}' is given more than once")
+        } else {
+            format!("The modules '{first}' and '{second}' would get the same function name, since dashes, dots and underscores are treated the same")
+        }));
+    }
 
-            let mut gitignore_path = project_path.clone();
-            gitignore_path.push(".gitignore");
+    if let Some(to) = args
+        .formats
+        .iter()
+        .find(|to| to.is_empty() || !to.chars().all(|c| c.is_ascii_lowercase()))
+    {
+        return Err(CliError::TemplateArgument(format!(
+            "Output format '{to}' must only contain lowercase letters"
+        )));
+    }
 
-            remove_dir_all(&git_path)?;
-            remove_file(&gitignore_path)?;
+    let project_path = PathBuf::from(&args.name);
+    if project_path.exists() {
+        return Err(CliError::TemplateArgument(format!(
+            "The folder '{}' already exists",
+            args.name
+        )));
+    }
 
-            println!("{}{}", "Package template created in: ", folder_name);
+    let options = PackageOptions {
+        name: args.name.clone(),
+        description: args.description.clone(),
+        transforms: args.transforms.clone(),
+        formats: args.formats.clone(),
+    };
 
-            Ok(())
+    for (path, content) in template.render(&options) {
+        let path = project_path.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
         }
+        fs::write(path, content)?;
     }
+
+    println!("Package template created in: {}", args.name);
+
+    Ok(())
 }
 
 /// Choose a free port for hosting the html live preview
@@ -584,12 +792,20 @@ async fn handle_connection(socket: WebSocket, connections: PreviewConnections) {
         }
     });
 
-    // Await input from the client.
-    // Note that we don't actually care about what they send us
-    // but this will keep the connection alive until the client disconnects
+    // Await input from the client, and relay any "click" or "scroll" message to all other clients.
+    // This is how a preview tells editors which line was clicked, and how editors tell previews
+    // which line to scroll to
     while let Some(result) = ws_rx.next().await {
         match result {
-            Ok(_) => (),
+            Ok(message) => {
+                if is_sync_message(&message) {
+                    for (other_id, other_tx) in connections.read().await.iter() {
+                        if *other_id != id {
+                            other_tx.send(message.clone()).ok();
+                        }
+                    }
+                }
+            }
             Err(e) => {
                 eprintln!("websocket error: {e}");
                 break;
@@ -601,7 +817,19 @@ async fn handle_connection(socket: WebSocket, connections: PreviewConnections) {
     connections.write().await.remove(&id);
 }
 
-/// Watch a path for file changes and send a reload message to all connected clients
+/// Check if a message from a websocket client is a `{"type": "click" | "scroll", "line": N}`
+/// message, which should be relayed to the other clients
+fn is_sync_message(message: &Message) -> bool {
+    let Ok(text) = message.to_str() else {
+        return false;
+    };
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(text) else {
+        return false;
+    };
+    (json["type"] == "click" || json["type"] == "scroll") && json["line"].is_u64()
+}
+
+/// Watch a path for file changes and send the new document to all connected clients
 async fn watch_files(
     document: Option<PreviewDoc>,
     connections: Option<PreviewConnections>,
@@ -613,7 +841,7 @@ async fn watch_files(
         connections: Option<&PreviewConnections>,
         args: &CompileArgs,
     ) -> Result<(), CliError> {
-        print_compiling_message()?;
+        print_compiling_message(args)?;
 
         let compilation_result = compile_file(
             &args.input,
@@ -623,11 +851,13 @@ async fn watch_files(
         )
         .await;
 
-        // Write to the output file (if there was one)
-        save_result(&compilation_result, args)?;
+        // Print the result to the terminal, before the output is written so that the errors are
+        // shown even if it can't be
+        let bytes = document_bytes(&compilation_result, args)?;
+        print_result(&compilation_result, bytes.is_some(), args)?;
 
-        // Print the result to the terminal
-        print_result(&compilation_result, args)?;
+        // Write to the output file (if there was one)
+        save_result(bytes.as_deref(), args)?;
 
         // Also save the result to the live preview document
         if let Some(document) = &document {
@@ -636,10 +866,15 @@ async fn watch_files(
             }
         }
 
-        // Also, send a "reload" message to every connected preview client
-        if let Some(connections) = &connections {
+        // Also, send the updated document to every connected preview client, which only patches
+        // the blocks that changed
+        if let (Some(document), Some(connections)) = (&document, &connections) {
+            let update = serde_json::json!({
+                "type": "update",
+                "html": *document.lock().unwrap(),
+            });
             for (_, ws_tx) in connections.read().await.iter() {
-                ws_tx.send(Message::text("reload")).unwrap();
+                ws_tx.send(Message::text(update.to_string())).unwrap();
             }
         }
 
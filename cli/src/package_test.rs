@@ -0,0 +1,221 @@
+use std::{
+    fs,
+    io::{stdout, BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crossterm::{
+    style::{self, Stylize},
+    ExecutableCommand,
+};
+use diffy::create_patch;
+use serde_json::Value;
+
+use modmark_core::{
+    package_store::DenyAllResolver, Context, DefaultAccessManager, OutputFormat, Package,
+};
+
+use crate::error::CliError;
+
+/// The outcome of running a single test file
+enum TestOutcome {
+    Passed,
+    Failed(String),
+}
+
+/// Runs all tests in `tests` (or the `tests` folder of the package if omitted, which is the one in
+/// the current directory for a `.wasm` file) against the package at `package_path`, which is
+/// either a `.wasm` file or a Cargo package that is built for `wasm32-wasi`. The tests have the same format as the tests of the standard packages.
+/// Returns true if all tests passed.
+pub fn run_package_tests(package_path: &Path, tests: Option<&Path>) -> Result<bool, CliError> {
+    let ctx = Context::new_without_standard(DenyAllResolver, DefaultAccessManager);
+
+    let wasm_path = if package_path.extension() == Some("wasm".as_ref()) {
+        package_path.to_path_buf()
+    } else {
+        build_cargo_package(package_path)?
+    };
+
+    let wasm_source = fs::read(&wasm_path)?;
+    // Compiling the package also reads the manifest and verifies it against the `PackageInfo`
+    // schema
+    let package = ctx
+        .compile_package(&wasm_source)
+        .map_err(|e| CliError::PackageTest(format!("Could not load the package: {e}")))?;
+
+    let tests_path = tests.map_or_else(
+        || {
+            if package_path.is_dir() {
+                package_path.join("tests")
+            } else if package_path.extension() == Some("wasm".as_ref()) {
+                PathBuf::from("tests")
+            } else {
+                // The tests of a package given by its Cargo.toml are next to the manifest
+                package_path.parent().unwrap_or(Path::new("")).join("tests")
+            }
+        },
+        Path::to_path_buf,
+    );
+
+    let mut test_files: Vec<PathBuf> = fs::read_dir(&tests_path)
+        .map_err(|_| {
+            CliError::PackageTest(format!(
+                "Could not read the tests folder '{}'",
+                tests_path.display()
+            ))
+        })?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension() == Some("json".as_ref()))
+        .collect();
+    test_files.sort();
+
+    let mut stdout = stdout();
+    println!(
+        "Running {} tests for {} {}",
+        test_files.len(),
+        package.info.name,
+        package.info.version
+    );
+
+    let mut failed = 0;
+    for file in &test_files {
+        let name = file.file_stem().unwrap_or_default().to_string_lossy();
+        match run_test(&ctx, &package, file)? {
+            TestOutcome::Passed => {
+                stdout.execute(style::PrintStyledContent("PASS".green()))?;
+                println!(" {name}");
+            }
+            TestOutcome::Failed(reason) => {
+                failed += 1;
+                stdout.execute(style::PrintStyledContent("FAIL".red()))?;
+                println!(" {name}\n{reason}");
+            }
+        }
+    }
+
+    let summary = format!(
+        "\n{} passed, {} failed\n",
+        test_files.len() - failed,
+        failed
+    );
+    if failed == 0 {
+        stdout.execute(style::PrintStyledContent(summary.green()))?;
+    } else {
+        stdout.execute(style::PrintStyledContent(summary.red()))?;
+    }
+
+    Ok(failed == 0)
+}
+
+/// Runs a single test file. The file is given to the package as-is, and the output is compared
+/// to `__test_expected_result`.
+fn run_test(
+    ctx: &Context<DenyAllResolver, DefaultAccessManager>,
+    package: &Package,
+    file: &Path,
+) -> Result<TestOutcome, CliError> {
+    let input = fs::read_to_string(file)?;
+    let input_json: Value = serde_json::from_str(&input)?;
+
+    let Some(from) = input_json["name"].as_str() else {
+        return Ok(TestOutcome::Failed("The test has no 'name'".to_string()));
+    };
+    let Some(to) = input_json["__test_transform_to"].as_str() else {
+        return Ok(TestOutcome::Failed(
+            "The test has no '__test_transform_to'".to_string(),
+        ));
+    };
+    let expected_result = &input_json["__test_expected_result"];
+
+    // The transform must be declared in the manifest for the package to ever be used for it
+    let declared = package.info.transforms.iter().any(|transform| {
+        transform.from == from
+            && (transform.to.contains(&OutputFormat::Any)
+                || transform.to.contains(&OutputFormat::new(to)))
+    });
+    if !declared {
+        return Ok(TestOutcome::Failed(format!(
+            "The manifest has no transform from '{from}' to '{to}'"
+        )));
+    }
+
+    // If the value is a string, it is put in the environment as-is, otherwise it is JSON encoded
+    let env: Vec<(String, String)> = input_json["__test_env"]
+        .as_object()
+        .map(|env| {
+            env.iter()
+                .map(|(k, v)| (k.clone(), v.as_str().map_or(v.to_string(), String::from)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let (output, errors) = match ctx.run_package_transform(package, from, to, &input, &env) {
+        Ok(result) => result,
+        Err(e) => return Ok(TestOutcome::Failed(format!("The package crashed: {e}"))),
+    };
+
+    let Ok(json_out) = serde_json::from_str::<Value>(&output) else {
+        return Ok(TestOutcome::Failed(format!(
+            "The output is not valid JSON\nOUTPUT\n{output}\nSTDERR\n{errors}"
+        )));
+    };
+
+    if &json_out != expected_result {
+        let expected = serde_json::to_string_pretty(expected_result)?;
+        let actual = serde_json::to_string_pretty(&json_out)?;
+        return Ok(TestOutcome::Failed(format!(
+            "EXPECTED\n{expected}\nGOT\n{actual}\nDIFF\n{}",
+            create_patch(&expected, &actual)
+        )));
+    }
+
+    Ok(TestOutcome::Passed)
+}
+
+/// Builds the Cargo package at `path` (a folder or a `Cargo.toml`) for `wasm32-wasi` and returns
+/// the path to the resulting `.wasm` file
+fn build_cargo_package(path: &Path) -> Result<PathBuf, CliError> {
+    let manifest_path = if path.is_dir() {
+        path.join("Cargo.toml")
+    } else {
+        path.to_path_buf()
+    };
+
+    println!("Building {}", manifest_path.display());
+
+    let mut cmd = Command::new("cargo")
+        .arg("build")
+        .arg("--release")
+        .arg("--target=wasm32-wasi")
+        .arg(format!("--manifest-path={}", manifest_path.display()))
+        .arg("--message-format=json-render-diagnostics")
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    // Cargo reports every artifact it builds as a JSON message, and the binary target of the
+    // package is the one with an executable
+    let mut executable = None;
+    for line in BufReader::new(cmd.stdout.take().unwrap()).lines() {
+        let message: Value = serde_json::from_str(&line?)?;
+        if message["reason"] == "compiler-artifact" {
+            if let Some(path) = message["executable"].as_str() {
+                executable = Some(PathBuf::from(path));
+            }
+        }
+    }
+
+    if !cmd.wait()?.success() {
+        return Err(CliError::PackageTest(format!(
+            "Could not build '{}'",
+            manifest_path.display()
+        )));
+    }
+
+    executable.ok_or_else(|| {
+        CliError::PackageTest(format!(
+            "Building '{}' gave no binary",
+            manifest_path.display()
+        ))
+    })
+}
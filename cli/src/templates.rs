@@ -0,0 +1,243 @@
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+/// A package template which is embedded in the binary, so that `modmark init` works without
+/// network access. The files are rendered by replacing `{{key}}` placeholders, where the
+/// placeholders `{{dispatch}}` and `{{functions}}` are built from the snippets below, repeated
+/// once per transform. `{{description_string}}` is the description as a quoted and escaped
+/// string, which is valid in both JSON and TOML since the description has no control characters.
+pub struct Template {
+    /// The names of the language, where the first one is the one listed to the user
+    pub names: &'static [&'static str],
+    /// The files of the template, as pairs of (path within the package, content)
+    files: &'static [(&'static str, &'static str)],
+    /// Snippet dispatching a transform to its function, with `{{from}}` and `{{ident}}`
+    dispatch: &'static str,
+    /// Snippet for the function of a transform, with `{{from}}`, `{{ident}}` and `{{formats}}`
+    function: &'static str,
+    /// Snippet for the output of a transform to one format, with `{{to}}`, `{{stub}}` and
+    /// `{{output}}`
+    format: &'static str,
+    /// What to put between the lines of `{{manifest_literal}}` to concatenate them
+    literal_joiner: &'static str,
+}
+
+/// The options given to `modmark init`
+pub struct PackageOptions {
+    pub name: String,
+    pub description: String,
+    pub transforms: Vec<String>,
+    pub formats: Vec<String>,
+}
+
+pub static TEMPLATES: &[Template] = &[
+    Template {
+        names: &["rust", "rs"],
+        files: &[
+            (
+                "Cargo.toml",
+                include_str!("templates/rust/Cargo.toml.template"),
+            ),
+            ("src/main.rs", include_str!("templates/rust/src/main.rs")),
+            ("README.md", include_str!("templates/rust/README.md")),
+        ],
+        dispatch: include_str!("templates/rust/dispatch.in"),
+        function: include_str!("templates/rust/function.in"),
+        format: include_str!("templates/rust/format.in"),
+        literal_joiner: "",
+    },
+    Template {
+        names: &["c++", "cpp"],
+        files: &[
+            ("src/main.cpp", include_str!("templates/cpp/src/main.cpp")),
+            ("Makefile", include_str!("templates/cpp/Makefile")),
+            ("README.md", include_str!("templates/cpp/README.md")),
+        ],
+        dispatch: include_str!("templates/cpp/dispatch.in"),
+        function: include_str!("templates/cpp/function.in"),
+        format: include_str!("templates/cpp/format.in"),
+        literal_joiner: "",
+    },
+    Template {
+        names: &["c"],
+        files: &[
+            ("src/main.c", include_str!("templates/c/src/main.c")),
+            ("Makefile", include_str!("templates/c/Makefile")),
+            ("README.md", include_str!("templates/c/README.md")),
+        ],
+        dispatch: include_str!("templates/c/dispatch.in"),
+        function: include_str!("templates/c/function.in"),
+        format: include_str!("templates/c/format.in"),
+        literal_joiner: "",
+    },
+    Template {
+        names: &["assemblyscript", "assembly-script", "as"],
+        files: &[
+            (
+                "assembly/index.ts",
+                include_str!("templates/assemblyscript/assembly/index.ts"),
+            ),
+            (
+                "package.json",
+                include_str!("templates/assemblyscript/package.json"),
+            ),
+            (
+                "README.md",
+                include_str!("templates/assemblyscript/README.md"),
+            ),
+        ],
+        dispatch: include_str!("templates/assemblyscript/dispatch.in"),
+        function: include_str!("templates/assemblyscript/function.in"),
+        format: include_str!("templates/assemblyscript/format.in"),
+        literal_joiner: " +",
+    },
+];
+
+/// Finds the template for the given language, matching any of its names
+pub fn find_template(language: &str) -> Option<&'static Template> {
+    TEMPLATES
+        .iter()
+        .find(|template| template.names.contains(&language))
+}
+
+impl Template {
+    /// Renders all files of this template, together with one test per transform and format,
+    /// returning pairs of (path within the package, content)
+    pub fn render(&self, options: &PackageOptions) -> Vec<(PathBuf, String)> {
+        let manifest = serde_json::to_string_pretty(&manifest(options)).unwrap();
+        let manifest_block = manifest.lines().collect::<Vec<_>>().join("\n    ");
+        let manifest_literal = manifest
+            .lines()
+            .map(|line| serde_json::to_string(line).unwrap())
+            .collect::<Vec<_>>()
+            .join(&format!("{}\n    ", self.literal_joiner));
+
+        let dispatch: String = options
+            .transforms
+            .iter()
+            .map(|from| {
+                self.dispatch
+                    .replace("{{from}}", from)
+                    .replace("{{ident}}", &identifier(from))
+            })
+            .collect();
+
+        let functions: String = options
+            .transforms
+            .iter()
+            .map(|from| {
+                let formats: String = options
+                    .formats
+                    .iter()
+                    .map(|to| {
+                        let stub = stub_output(from, to);
+                        self.format
+                            .replace("{{to}}", to)
+                            .replace("{{stub}}", &stub.to_string())
+                            .replace(
+                                "{{output}}",
+                                &serde_json::to_string(&json!([stub]).to_string()).unwrap(),
+                            )
+                    })
+                    .collect();
+                self.function
+                    .replace("{{from}}", from)
+                    .replace("{{ident}}", &identifier(from))
+                    .replace("{{formats}}", formats.trim_end())
+            })
+            .collect();
+
+        let mut files: Vec<(PathBuf, String)> = self
+            .files
+            .iter()
+            .map(|(path, content)| {
+                let content = content
+                    .replace("{{name}}", &options.name)
+                    .replace("{{description}}", &options.description)
+                    .replace(
+                        "{{description_string}}",
+                        &serde_json::to_string(&options.description).unwrap(),
+                    )
+                    .replace("{{manifest}}", &manifest_block)
+                    .replace("{{manifest_literal}}", &manifest_literal)
+                    .replace("{{dispatch}}", dispatch.trim_end())
+                    .replace("{{functions}}", functions.trim_end());
+                (PathBuf::from(path), content)
+            })
+            .collect();
+
+        files.extend(render_tests(options));
+        files
+    }
+}
+
+/// Creates the package manifest with one transform per module name, each of which supports
+/// all of the output formats
+fn manifest(options: &PackageOptions) -> Value {
+    let transforms: Vec<Value> = options
+        .transforms
+        .iter()
+        .map(|from| {
+            json!({
+                "from": from,
+                "to": options.formats,
+                "description": format!("TODO: describe what [{from}] does"),
+                "arguments": []
+            })
+        })
+        .collect();
+
+    json!({
+        "name": options.name,
+        "version": "0.1",
+        "description": options.description,
+        "transforms": transforms
+    })
+}
+
+/// Creates the tests for the package, in the same format as the tests used by `package-tests`
+fn render_tests(options: &PackageOptions) -> Vec<(PathBuf, String)> {
+    let mut tests = vec![];
+    for from in &options.transforms {
+        for to in &options.formats {
+            let test = json!({
+                "name": from,
+                "data": "",
+                "arguments": {},
+                "inline": false,
+                "__test_transform_to": to,
+                "__test_expected_result": [stub_output(from, to)]
+            });
+            let path = PathBuf::from("tests").join(format!(
+                "test_{}_{}.json",
+                identifier(from),
+                identifier(to)
+            ));
+            tests.push((path, serde_json::to_string_pretty(&test).unwrap()));
+        }
+    }
+    tests
+}
+
+/// The raw output that a newly created package gives for a transform
+fn stub_output(from: &str, to: &str) -> Value {
+    json!(format!("TODO: implement [{from}] for {to}"))
+}
+
+/// Converts a module name to something that can be used as (a part of) an identifier in all
+/// template languages. Different names may give the same identifier, such as `a-b` and `a_b`, so
+/// the names should be checked with `colliding_identifiers` first.
+pub fn identifier(name: &str) -> String {
+    name.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+}
+
+/// Finds two names that would get the same identifier in the generated code, if any
+pub fn colliding_identifiers(names: &[String]) -> Option<(&str, &str)> {
+    names.iter().enumerate().find_map(|(idx, name)| {
+        names[..idx]
+            .iter()
+            .find(|other| identifier(other) == identifier(name))
+            .map(|other| (other.as_str(), name.as_str()))
+    })
+}
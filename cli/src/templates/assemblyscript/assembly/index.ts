@@ -0,0 +1,22 @@
+import { CommandLine, Console } from "as-wasi/assembly";
+
+const MANIFEST: string = {{manifest_literal}};
+{{functions}}
+
+function transform(from: string, to: string): void {
+  const input = Console.readAll();
+
+{{dispatch}}
+  Console.error("Package does not support " + from);
+}
+
+const args = CommandLine.all;
+const action = args.length >= 2 ? args[1] : "";
+
+if (action == "manifest") {
+  Console.write(MANIFEST, false);
+} else if (action == "transform" && args.length >= 4) {
+  transform(args[2], args[3]);
+} else {
+  Console.error("Invalid action " + action);
+}
@@ -0,0 +1,45 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+static const char *MANIFEST = {{manifest_literal}};
+
+/* Reads all of stdin into a null-terminated string, which the caller must free */
+static char *read_input(void) {
+    size_t capacity = 4096;
+    size_t length = 0;
+    char *buffer = malloc(capacity);
+    size_t read;
+
+    while ((read = fread(buffer + length, 1, capacity - length - 1, stdin)) > 0) {
+        length += read;
+        if (capacity - length <= 1) {
+            capacity *= 2;
+            buffer = realloc(buffer, capacity);
+        }
+    }
+    buffer[length] = '\0';
+    return buffer;
+}
+{{functions}}
+
+int main(int argc, char **argv) {
+    if (argc >= 2 && strcmp(argv[1], "manifest") == 0) {
+        fputs(MANIFEST, stdout);
+        return 0;
+    }
+
+    if (argc >= 4 && strcmp(argv[1], "transform") == 0) {
+        const char *from = argv[2];
+        const char *to = argv[3];
+        char *input = read_input();
+
+{{dispatch}}
+        fprintf(stderr, "Package does not support %s\n", from);
+        free(input);
+        return 0;
+    }
+
+    fprintf(stderr, "Invalid action %s\n", argc >= 2 ? argv[1] : "");
+    return 1;
+}
@@ -0,0 +1,29 @@
+#include <iostream>
+#include <iterator>
+#include <string>
+
+static const char *MANIFEST = {{manifest_literal}};
+{{functions}}
+
+int main(int argc, char **argv) {
+    const std::string action = argc >= 2 ? argv[1] : "";
+
+    if (action == "manifest") {
+        std::cout << MANIFEST;
+        return 0;
+    }
+
+    if (action == "transform" && argc >= 4) {
+        const std::string from = argv[2];
+        const std::string to = argv[3];
+        const std::string input((std::istreambuf_iterator<char>(std::cin)),
+                                std::istreambuf_iterator<char>());
+
+{{dispatch}}
+        std::cerr << "Package does not support " << from << std::endl;
+        return 0;
+    }
+
+    std::cerr << "Invalid action " << action << std::endl;
+    return 1;
+}
@@ -0,0 +1,37 @@
+use std::env;
+use std::io::{self, Read};
+
+use serde_json::{json, Value};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let action = &args[0];
+    match action.as_str() {
+        "manifest" => manifest(),
+        "transform" => transform(&args[1], &args[2]),
+        other => {
+            eprintln!("Invalid action {other}");
+        }
+    }
+}
+
+fn manifest() {
+    let manifest = json!({{manifest}});
+    print!("{manifest}");
+}
+
+fn transform(from: &str, to: &str) {
+    let input: Value = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        serde_json::from_str(&buffer).unwrap()
+    };
+
+    match from {
+{{dispatch}}
+        other => {
+            eprintln!("Package does not support {other}");
+        }
+    }
+}
+{{functions}}
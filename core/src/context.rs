@@ -23,7 +23,8 @@ use parser::ModuleArguments;
 
 use crate::element::GranularId;
 use crate::fs::CoreFs;
-use crate::package::{ArgValue, PackageImplementation};
+use crate::macros::{Macro, MAX_MACRO_EXPANSIONS};
+use crate::package::{ArgValue, PackageImplementation, TransformType};
 use crate::package_store::{PackageID, PackageStore};
 use crate::variables::{VarAccess, VarType, VariableStore};
 use crate::{std_packages, AccessPolicy, Element, Resolve};
@@ -38,6 +39,9 @@ pub struct Context<T, U> {
     pub filesystem: CoreFs<U>,
     /// If true verbose errors and warnings will be used
     pub verbose: bool,
+    /// If true the source line of each top-level element is sent to the packages, which lets
+    /// output formats such as HTML map the output back to the source
+    pub track_source_lines: bool,
     policy: Arc<Mutex<U>>,
 }
 
@@ -49,6 +53,12 @@ pub struct CompilationState {
     pub warnings: Vec<Issue>,
     pub errors: Vec<Issue>,
     pub counter: RefCell<RangeFrom<u64>>,
+    /// The line in the source document that each top-level element starts on
+    pub source_lines: HashMap<GranularId, usize>,
+    /// How many times macros have been expanded
+    pub macro_expansions: usize,
+    /// The ids of the `[define]` modules whose macros have been defined
+    pub macro_definitions: HashSet<GranularId>,
 }
 
 impl Default for CompilationState {
@@ -58,6 +68,9 @@ impl Default for CompilationState {
             warnings: Default::default(),
             errors: Default::default(),
             counter: RefCell::new(100..),
+            source_lines: HashMap::new(),
+            macro_expansions: 0,
+            macro_definitions: HashSet::new(),
         }
     }
 }
@@ -92,6 +105,9 @@ impl CompilationState {
         self.warnings.clear();
         self.errors.clear();
         self.variables.clear();
+        self.source_lines.clear();
+        self.macro_expansions = 0;
+        self.macro_definitions.clear();
     }
 }
 
@@ -176,6 +192,7 @@ impl<T, U> Context<T, U> {
             state: CompilationState::default(),
             filesystem: CoreFs::new(Arc::clone(&policy)),
             verbose: false,
+            track_source_lines: false,
             policy,
         }
     }
@@ -215,7 +232,8 @@ where
         let arc_mutex = Arc::clone(&self.package_store);
         let resolve_tasks = store_guard.generate_resolve_tasks(arc_mutex, &config)?;
         if resolve_tasks.is_empty() {
-            store_guard.expose_transforms(config.try_into()?)?;
+            let import_order: Vec<PackageID> = config.imports.iter().map(Into::into).collect();
+            store_guard.expose_transforms(config.try_into()?, &import_order)?;
             Ok(true)
         } else {
             // IMPORTANT: It is important that we drop the lock here. If resolve_all were to resolve
@@ -423,8 +441,253 @@ where
                         from,
                         output_format,
                     ),
+                    PackageImplementation::Macro(definition) => {
+                        self.transform_from_macro(definition, from, output_format)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Runs all filters to the output format on the whole document before it is evaluated. A
+    /// filter is given the serialized document and outputs the new document, in the same format
+    /// as other transforms. If a filter fails, its output is ignored and an error is reported.
+    /// The elements get new ids, so the source lines of the top-level elements are taken from the
+    /// `line` fields of the output, and a filter that drops them loses the source lines.
+    pub fn filter_document(
+        &mut self,
+        document: Element,
+        output_format: &OutputFormat,
+    ) -> Result<Element, CoreError> {
+        let filters = {
+            let store_guard = self.package_store.lock().unwrap();
+            store_guard.find_filters(TransformType::Filter, output_format)
+        };
+
+        let mut document = document;
+        for (transform, package) in filters {
+            let input_data = {
+                let mut counter = self.state.counter.borrow_mut();
+                let mut func = move || counter.next().expect("Counter is infinite");
+                self.serialize_element(&document, output_format, &mut func)?
+            };
+
+            let Some(output) = self.run_filter(&transform, &package, output_format, &input_data)?
+            else {
+                continue;
+            };
+
+            match Self::document_from_filter(&document, &output) {
+                Some((filtered, source_lines)) => {
+                    document = filtered;
+                    self.state.source_lines = source_lines;
+                }
+                None => self.push_filter_issue(
+                    true,
+                    &transform,
+                    output_format,
+                    "Error deserializing result from filter".to_string(),
+                    &input_data,
+                ),
+            }
+        }
+
+        Ok(document)
+    }
+
+    /// Collects the macros defined with `[define]` in the document and exposes them as transforms,
+    /// so that they can be used like any other module. This has to be done before the document is
+    /// evaluated, since the schedule needs to know every transform in the document. Macros defined
+    /// in content that is parsed during evaluation are therefore not found. A `[define]` whose
+    /// macro can't be defined is replaced by an error module, so that the rest of the document is
+    /// still compiled.
+    pub fn define_macros(&mut self, document: &mut Element, output_format: &OutputFormat) {
+        let mut definitions = vec![];
+        self.find_macro_definitions(document, output_format, &mut definitions);
+
+        for (id, definition) in definitions {
+            let result = definition.and_then(|definition| {
+                let mut store_guard = self.package_store.lock().unwrap();
+                store_guard.expose_macro(definition)
+            });
+
+            match result {
+                Ok(()) => {
+                    self.state.macro_definitions.insert(id);
+                }
+                Err(error) => {
+                    let element = document
+                        .get_by_id_mut(id.clone())
+                        .expect("The define module is in the document");
+                    let inline = matches!(element, Element::Module { inline: true, .. });
+                    *element =
+                        std_packages::define_error(error.to_string(), inline, &id, output_format);
+                }
+            }
+        }
+    }
+
+    fn find_macro_definitions(
+        &self,
+        element: &Element,
+        output_format: &OutputFormat,
+        definitions: &mut Vec<(GranularId, Result<Macro, CoreError>)>,
+    ) {
+        match element {
+            Element::Parent { children, .. } | Element::Compound(children) => {
+                for child in children {
+                    self.find_macro_definitions(child, output_format, definitions);
                 }
             }
+            Element::Module {
+                name,
+                args,
+                body,
+                id,
+                ..
+            } if name == "define" => {
+                let definition = self
+                    .collect_module_arguments(args, name, output_format)
+                    .and_then(|args| {
+                        let macro_name: String = args.get("name").cloned().unwrap().into();
+                        let params: String = args.get("params").cloned().unwrap().into();
+                        Macro::new(&macro_name, &params, body)
+                    });
+                definitions.push((id.clone(), definition));
+            }
+            Element::Module { .. } | Element::Raw(_) => {}
+        }
+    }
+
+    /// Runs all output filters to the output format on the final output of a document, in the
+    /// same way as `filter_document`. The filter is given a `raw` module with the output as its
+    /// data, and should return a list of raw elements.
+    pub fn filter_output(
+        &mut self,
+        output: String,
+        output_format: &OutputFormat,
+    ) -> Result<String, CoreError> {
+        let filters = {
+            let store_guard = self.package_store.lock().unwrap();
+            store_guard.find_filters(TransformType::OutputFilter, output_format)
+        };
+
+        let mut output = output;
+        for (transform, package) in filters {
+            let input_data = serde_json::to_string_pretty(&JsonEntry::Module {
+                name: "raw".to_string(),
+                data: output.clone(),
+                arguments: HashMap::new(),
+                inline: false,
+                id: self.state.counter.borrow_mut().next().expect("Counter is infinite"),
+                line: None,
+            })?;
+
+            let Some(result) = self.run_filter(&transform, &package, output_format, &input_data)?
+            else {
+                continue;
+            };
+
+            let flattened = Self::output_from_filter(&result);
+
+            match flattened {
+                Some(flattened) => output = flattened,
+                None => self.push_filter_issue(
+                    true,
+                    &transform,
+                    output_format,
+                    "An output filter may only return raw elements".to_string(),
+                    &input_data,
+                ),
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Checks if the output format is binary, meaning that the output of `eval` is encoded as
+    /// base64 and should be decoded before it is saved
+    pub fn is_binary_format(&self, output_format: &OutputFormat) -> bool {
+        self.package_store
+            .lock()
+            .unwrap()
+            .is_binary_format(output_format)
+    }
+
+    /// Runs a filter with the variables it has read access to, reporting anything it logs as
+    /// warnings. If the filter crashed or logged errors, None is returned.
+    fn run_filter(
+        &mut self,
+        transform: &Transform,
+        package: &Package,
+        output_format: &OutputFormat,
+        input_data: &str,
+    ) -> Result<Option<String>, CoreError> {
+        let PackageImplementation::Wasm(module) = &package.implementation else {
+            return Err(CoreError::NativePackage(package.info.name.clone()));
+        };
+
+        let vars_to_read: Vec<(String, String)> = transform
+            .variables
+            .iter()
+            .filter(|(_, access)| access.is_read())
+            .filter_map(|(name, access)| {
+                self.state
+                    .variables
+                    .get(name)
+                    .filter(|value| value.get_type() == access.get_type())
+                    .map(|value| (name.to_string(), value.to_string()))
+            })
+            .collect();
+
+        let outputs = self.run_wasm_transform(
+            module,
+            &transform.from,
+            output_format,
+            input_data,
+            vars_to_read,
+        )?;
+        let (result, err_str) = match outputs {
+            Ok(outputs) => outputs,
+            Err(crash) => {
+                self.push_filter_issue(true, transform, output_format, crash, input_data);
+                return Ok(None);
+            }
+        };
+
+        // Just like with other transforms, anything logged is a warning if the filter succeeded
+        // and an error if it didn't
+        let failed = serde_json::from_str::<Value>(&result).is_err();
+        for line in err_str.lines() {
+            let description = if failed {
+                format!("Logged error: {line}")
+            } else {
+                format!("Logged warning: {line}")
+            };
+            self.push_filter_issue(failed, transform, output_format, description, input_data);
+        }
+
+        Ok((!failed || err_str.is_empty()).then_some(result))
+    }
+
+    fn push_filter_issue(
+        &mut self,
+        error: bool,
+        transform: &Transform,
+        output_format: &OutputFormat,
+        description: String,
+        input_data: &str,
+    ) {
+        let issue = Issue {
+            source: transform.from.clone(),
+            target: output_format.to_string(),
+            description,
+            input: self.verbose.then(|| input_data.to_string()),
+        };
+        if error {
+            self.state.errors.push(issue);
+        } else {
+            self.state.warnings.push(issue);
         }
     }
 
@@ -438,25 +701,12 @@ where
         from: &Element,
         output_format: &OutputFormat,
     ) -> Result<Element, CoreError> {
-        // Create a new store
-        #[cfg(feature = "native")]
-        let mut store = Store::new(&self.engine);
-
-        #[cfg(feature = "web")]
-        let mut store = Store::new();
-
-        // Create pipes for stdin, stdwasmerout, stderr
-        let mut input = Pipe::new();
-        let mut output = Pipe::new();
-        let mut err_out = Pipe::new();
-
         // Generate the input data (by serializing elements)
         let input_data = {
             let mut counter = self.state.counter.borrow_mut();
             let mut func = move || counter.next().expect("Counter is infinite");
             self.serialize_element(from, output_format, &mut func)?
         };
-        write!(&mut input, "{input_data}")?;
 
         // Function to create an issue given a body text and if it is an error or not. This closure
         // captures references to the appropriate variables from this scope to generate correct
@@ -486,6 +736,108 @@ where
             }
         };
 
+        // Get all the variables that this element has read access to
+        let vars_to_read: Vec<(String, String)> = self
+            .get_vars_to_read(from, output_format)?
+            .into_iter()
+            .filter_map(|(name, ty)| {
+                self.state
+                    .variables
+                    .get(&name)
+                    .filter(|value| value.get_type() == ty)
+                    .map(|value| (name.to_string(), value.to_string()))
+            })
+            .collect();
+
+        // A transform to the base format of the output format is run as a transform to the base
+        // format, since the package doesn't know about the output format
+        let target = {
+            let store_guard = self.package_store.lock().unwrap();
+            store_guard
+                .find_transform(name, output_format)
+                .map(|(transform, _)| store_guard.transform_target(&transform, output_format))
+                .unwrap_or_else(|| output_format.clone())
+        };
+
+        let outputs = self.run_wasm_transform(module, name, &target, &input_data, vars_to_read)?;
+        let (result_str, err_str) = match outputs {
+            Ok(outputs) => outputs,
+            Err(crash) => return Ok(create_issue(true, crash, &input_data, module_id.clone())),
+        };
+
+        let result = Self::deserialize_compound(&result_str, module_id.clone());
+
+        // If we have no stderr, just return the result early
+        if err_str.is_empty() {
+            return match result {
+                // This is the only fully successful exit point, where we have a result and no
+                // stderr => no errors/warnings logged
+                Ok(res) => Ok(Element::Compound(res)),
+                // If there is an issue in "result", the result was deserialized incorrectly.
+                // The CoreError error message is misleading so we skip printing it and only print
+                // our custom message. This is the only element we return, so it should have the
+                // same ID as module_id
+                Err(_) => Ok(create_issue(
+                    true,
+                    "Error deserializing result from module".to_string(),
+                    &input_data,
+                    module_id.clone(),
+                )),
+            };
+        }
+
+        // If we have stderr, check if result is successful or not
+        // If successful, we treat the messages in stderr as warnings
+        // If not, we treat them as if they are errors
+        if let Ok(mut elems) = result {
+            // We have multiple warnings, and their IDs should be children of module_id, and since
+            // we already have `elems.len()` elements, so skip that many children
+            let warnings = err_str
+                .lines()
+                .zip(module_id.children().skip(elems.len()))
+                .map(|(line, id)| {
+                    create_issue(false, format!("Logged warning: {line}"), &input_data, id)
+                });
+            elems.extend(warnings);
+            Ok(Element::Compound(elems))
+        } else {
+            // We have multiple errors and their IDs should be children of module_id, and since we
+            // don't have any other elements, we zip with `module_id.children()`
+            let errors = err_str
+                .lines()
+                .zip(module_id.children())
+                .map(|(line, id)| {
+                    create_issue(true, format!("Logged error: {line}"), &input_data, id)
+                })
+                .collect();
+            Ok(Element::Compound(errors))
+        }
+    }
+
+    /// Runs the transform `name` of a Wasm module, with `input` as stdin and `env` as the
+    /// environment variables. On success, the stdout and stderr of the module are returned, and if
+    /// the module crashed, a description of the crash is returned instead.
+    fn run_wasm_transform(
+        &self,
+        module: &Module,
+        name: &str,
+        output_format: &OutputFormat,
+        input: &str,
+        env: Vec<(String, String)>,
+    ) -> Result<Result<(String, String), String>, CoreError> {
+        // Create a new store
+        #[cfg(feature = "native")]
+        let mut store = Store::new(&self.engine);
+
+        #[cfg(feature = "web")]
+        let mut store = Store::new();
+
+        // Create pipes for stdin, stdout, stderr
+        let mut input_pipe = Pipe::new();
+        let mut output = Pipe::new();
+        let mut err_out = Pipe::new();
+        write!(&mut input_pipe, "{input}")?;
+
         let fs = self.filesystem.clone_for_module(name.to_string());
 
         // check the access policy
@@ -502,26 +854,13 @@ where
         let has_fs_access = root.is_some() && (read || write || create);
 
         let wasi_env = {
-            // Get all the variables that this element has read access to
-            let vars_to_read: Vec<(String, String)> = self
-                .get_vars_to_read(from, output_format)?
-                .into_iter()
-                .filter_map(|(name, ty)| {
-                    self.state
-                        .variables
-                        .get(&name)
-                        .filter(|value| value.get_type() == ty)
-                        .map(|value| (name.to_string(), value.to_string()))
-                })
-                .collect();
-
             let mut state_builder = WasiState::new("");
             state_builder
-                .stdin(Box::new(input))
+                .stdin(Box::new(input_pipe))
                 .stdout(Box::new(output.clone()))
                 .stderr(Box::new(err_out.clone()))
                 .args(["transform", name, &output_format.to_string()])
-                .envs(vars_to_read);
+                .envs(env);
 
             if has_fs_access {
                 let path = Path::new(root.as_ref().unwrap());
@@ -556,90 +895,91 @@ where
             match downcast {
                 Ok(WasiError::Exit(0)) => {}
                 Ok(WasiError::Exit(x)) => {
-                    return Ok(create_issue(
-                        true,
-                        format!("Wasm module exited with exit code '{x}'"),
-                        &input_data,
-                        module_id.clone(),
-                    ))
+                    return Ok(Err(format!("Wasm module exited with exit code '{x}'")))
                 }
                 Ok(WasiError::UnknownWasiVersion) => {
-                    return Ok(create_issue(
-                        true,
-                        "Could not determine WASI version for Wasm module".to_string(),
-                        &input_data,
-                        module_id.clone(),
-                    ))
-                }
-                Err(e) => {
-                    return Ok(create_issue(
-                        true,
-                        format!("Wasm module crash: {e}"),
-                        &input_data,
-                        module_id.clone(),
+                    return Ok(Err(
+                        "Could not determine WASI version for Wasm module".to_string()
                     ))
                 }
+                Err(e) => return Ok(Err(format!("Wasm module crash: {e}"))),
             }
         }
 
-        // Read (possible) warnings and errors
-        let err_str = {
-            let mut buffer = String::new();
-            err_out.read_to_string(&mut buffer)?;
-            buffer
-        };
+        let mut stdout = String::new();
+        output.read_to_string(&mut stdout)?;
+        let mut stderr = String::new();
+        err_out.read_to_string(&mut stderr)?;
+
+        Ok(Ok((stdout, stderr)))
+    }
+}
+
+#[cfg(feature = "native")]
+impl<T, U> Context<T, U> {
+    /// Compiles a package from the binary data of a `.wasm` file, without loading it into the
+    /// package store. The manifest of the package is read and verified in the process.
+    pub fn compile_package(&self, wasm_source: &[u8]) -> Result<Package, CoreError> {
+        Package::new(wasm_source, &self.engine)
+    }
 
-        let result = {
-            let mut buffer = String::new();
-            output.read_to_string(&mut buffer)?;
-            Self::deserialize_compound(&buffer, module_id.clone())
+    /// Runs the transform from `from` to `to` of a package directly, bypassing the scheduling
+    /// and the variable store. `input` is passed as-is on stdin and `env` is passed as
+    /// environment variables, and the package gets read access to the current directory. This
+    /// returns what the package wrote to stdout and stderr, without deserializing it, which is
+    /// used to test packages.
+    pub fn run_package_transform(
+        &self,
+        package: &Package,
+        from: &str,
+        to: &str,
+        input: &str,
+        env: &[(String, String)],
+    ) -> Result<(String, String), CoreError> {
+        let PackageImplementation::Wasm(module) = &package.implementation else {
+            return Err(CoreError::NativePackage(package.info.name.clone()));
         };
 
-        // If we have no stderr, just return the result early
-        if err_str.is_empty() {
-            return match result {
-                // This is the only fully successful exit point, where we have a result and no
-                // stderr => no errors/warnings logged
-                Ok(res) => Ok(Element::Compound(res)),
-                // If there is an issue in "result", the result was deserialized incorrectly.
-                // The CoreError error message is misleading so we skip printing it and only print
-                // our custom message. This is the only element we return, so it should have the
-                // same ID as module_id
-                Err(_) => Ok(create_issue(
-                    true,
-                    "Error deserializing result from module".to_string(),
-                    &input_data,
-                    module_id.clone(),
-                )),
-            };
-        }
+        let mut store = Store::new(&self.engine);
 
-        // If we have stderr, check if result is successful or not
-        // If successful, we treat the messages in stderr as warnings
-        // If not, we treat them as if they are errors
-        if let Ok(mut elems) = result {
-            // We have multiple warnings, and their IDs should be children of module_id, and since
-            // we already have `elems.len()` elements, so skip that many children
-            let warnings = err_str
-                .lines()
-                .zip(module_id.children().skip(elems.len()))
-                .map(|(line, id)| {
-                    create_issue(false, format!("Logged warning: {line}"), &input_data, id)
-                });
-            elems.extend(warnings);
-            Ok(Element::Compound(elems))
-        } else {
-            // We have multiple errors and their IDs should be children of module_id, and since we
-            // don't have any other elements, we zip with `module_id.children()`
-            let errors = err_str
-                .lines()
-                .zip(module_id.children())
-                .map(|(line, id)| {
-                    create_issue(true, format!("Logged error: {line}"), &input_data, id)
-                })
-                .collect();
-            Ok(Element::Compound(errors))
+        let mut input_pipe = Pipe::new();
+        let mut output = Pipe::new();
+        let mut err_out = Pipe::new();
+        write!(&mut input_pipe, "{input}")?;
+
+        let wasi_env = WasiState::new("")
+            .stdin(Box::new(input_pipe))
+            .stdout(Box::new(output.clone()))
+            .stderr(Box::new(err_out.clone()))
+            .args(["transform", from, to])
+            .envs(env.iter().cloned())
+            .preopen(|p| p.directory(".").alias(".").read(true))?
+            .finalize(&mut store)?;
+
+        let import_object = wasi_env.import_object(&mut store, module)?;
+        let instance = Instance::new(&mut store, module, &import_object)?;
+
+        // Attach the memory export
+        let memory = instance.exports.get_memory("memory")?;
+        wasi_env.data_mut(&mut store).set_memory(memory.clone());
+
+        let main_fn = instance.exports.get_function("_start")?;
+        if let Err(e) = main_fn.call(&mut store, &[]) {
+            match e.downcast::<WasiError>() {
+                Ok(WasiError::Exit(0)) => {}
+                Ok(wasi_error) => return Err(wasi_error.into()),
+                Err(original_error) => {
+                    return Err(CoreError::WasmerRuntimeError(Box::new(original_error)))
+                }
+            }
         }
+
+        let mut stdout = String::new();
+        output.read_to_string(&mut stdout)?;
+        let mut stderr = String::new();
+        err_out.read_to_string(&mut stderr)?;
+
+        Ok((stdout, stderr))
     }
 }
 
@@ -684,6 +1024,56 @@ impl<T, U> Context<T, U> {
         std_packages::handle_native(self, package_name, node_name, element, args, output_format)
     }
 
+    /// Expands a macro, by substituting the arguments into its template and re-parsing the result
+    /// in the same way as `inline_content` or `block_content`
+    fn transform_from_macro(
+        &mut self,
+        definition: &Macro,
+        element: &Element,
+        output_format: &OutputFormat,
+    ) -> Result<Element, CoreError> {
+        let Element::Module {
+            name,
+            args,
+            body,
+            inline,
+            id,
+        } = element
+        else {
+            unreachable!("Macros are always modules")
+        };
+
+        // A macro that uses itself would otherwise be expanded forever
+        self.state.macro_expansions += 1;
+        if self.state.macro_expansions > MAX_MACRO_EXPANSIONS {
+            return Ok(Element::Module {
+                name: "error".to_string(),
+                args: ModuleArguments::from([
+                    ("source".to_string(), name.to_string()),
+                    ("target".to_string(), output_format.to_string()),
+                ]),
+                body: format!(
+                    "The macro '{name}' was expanded too many times. Does it use itself?"
+                ),
+                inline: *inline,
+                id: id.clone(),
+            });
+        }
+
+        let args = self.collect_module_arguments(args, name, output_format)?;
+        Ok(Element::Module {
+            name: if *inline {
+                "inline_content".to_string()
+            } else {
+                "block_content".to_string()
+            },
+            args: ModuleArguments::default(),
+            body: definition.expand(&args, body),
+            inline: *inline,
+            id: id.clone(),
+        })
+    }
+
     /// Serialize and element into a string that can be sent to a package. `counter` should be a
     /// function, when called giving a new `u64` each time, that is unique to the compilation cycle.
     pub fn serialize_element<F>(
@@ -719,6 +1109,63 @@ impl<T, U> Context<T, U> {
         Ok(elements)
     }
 
+    /// Converts the output of a filter to the new document, replacing `document`. A filter that
+    /// returns a single element replaces the document element, and anything else is treated as a
+    /// compound of top-level elements. The source lines of the new top-level elements are returned
+    /// as well. If the output isn't a list of elements, None is returned.
+    pub(crate) fn document_from_filter(
+        document: &Element,
+        output: &str,
+    ) -> Option<(Element, HashMap<GranularId, usize>)> {
+        let entries: Vec<JsonEntry> = serde_json::from_str(output).ok()?;
+
+        let (document, top_level) = match (document, entries.len()) {
+            (Element::Parent { id, .. }, 1) => {
+                let entry = entries.into_iter().next().unwrap();
+                let top_level = match &entry {
+                    JsonEntry::ParentNode { children, .. } => {
+                        children.iter().map(JsonEntry::line).collect()
+                    }
+                    _ => vec![],
+                };
+                (Self::entry_to_element(entry, id.clone()), top_level)
+            }
+            _ => {
+                let top_level = entries.iter().map(JsonEntry::line).collect();
+                let elements = entries
+                    .into_iter()
+                    .zip(GranularId::root().children())
+                    .map(|(entry, id)| Self::entry_to_element(entry, id))
+                    .collect();
+                (Element::Compound(elements), top_level)
+            }
+        };
+
+        let source_lines = top_level
+            .into_iter()
+            .zip(GranularId::root().children())
+            .filter_map(|(line, id)| line.map(|line| (id, line)))
+            .collect();
+        Some((document, source_lines))
+    }
+
+    /// Converts the output of an output filter to the new output. An output filter may only return
+    /// raw elements, and None is returned otherwise.
+    pub(crate) fn output_from_filter(result: &str) -> Option<String> {
+        Self::deserialize_compound(result, GranularId::root())
+            .ok()
+            .and_then(|elements| {
+                elements
+                    .into_iter()
+                    .map(|element| match element {
+                        Element::Raw(raw) => Some(raw),
+                        Element::Module { name, body, .. } if name == "raw" => Some(body),
+                        _ => None,
+                    })
+                    .collect::<Option<String>>()
+            })
+    }
+
     /// Convert a `JsonEntry` to an `Element`
     fn entry_to_element(entry: JsonEntry, id: GranularId) -> Element {
         let type_erase = |mut map: HashMap<String, Value>| {
@@ -748,7 +1195,7 @@ impl<T, U> Context<T, U> {
                 name,
                 arguments,
                 children,
-                id: _,
+                ..
             } => Element::Parent {
                 name,
                 args: type_erase(arguments),
@@ -764,7 +1211,7 @@ impl<T, U> Context<T, U> {
                 data,
                 arguments,
                 inline,
-                id: _,
+                ..
             } => Element::Module {
                 name,
                 args: ModuleArguments {
@@ -800,7 +1247,7 @@ impl<T, U> Context<T, U> {
                 name,
                 args,
                 children,
-                id: _,
+                id,
             } => {
                 let converted_children: Result<Vec<JsonEntry>, CoreError> = children
                     .iter()
@@ -817,6 +1264,7 @@ impl<T, U> Context<T, U> {
                     arguments: type_erased_args,
                     children: converted_children?,
                     id: counter(),
+                    line: self.source_line(id),
                 })
             }
             Element::Module {
@@ -824,7 +1272,7 @@ impl<T, U> Context<T, U> {
                 args,
                 body,
                 inline: one_line,
-                id: _,
+                id,
             } => {
                 let mut collected_args =
                     self.collect_module_arguments(args, name, output_format)
@@ -837,12 +1285,22 @@ impl<T, U> Context<T, U> {
                     data: body.clone(),
                     inline: *one_line,
                     id: counter(),
+                    line: self.source_line(id),
                 })
             }
             Element::Raw(string) => Ok(JsonEntry::Raw(string.clone())),
         }
     }
 
+    /// Gets the source line of the element with the given id, if source lines are tracked and the
+    /// element is in the source document
+    fn source_line(&self, id: &GranularId) -> Option<usize> {
+        if !self.track_source_lines {
+            return None;
+        }
+        self.state.source_lines.get(id).copied()
+    }
+
     fn collect_parent_arguments(
         &self,
         args: &HashMap<String, String>,
@@ -980,6 +1438,8 @@ enum JsonEntry {
         children: Vec<Self>,
         #[serde(skip_deserializing)]
         id: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        line: Option<usize>,
     },
     Module {
         name: String,
@@ -991,11 +1451,23 @@ enum JsonEntry {
         inline: bool,
         #[serde(skip_deserializing)]
         id: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        line: Option<usize>,
     },
     Compound(Vec<Self>),
     Raw(String),
 }
 
+impl JsonEntry {
+    /// The source line of this entry, if it has one
+    fn line(&self) -> Option<usize> {
+        match self {
+            JsonEntry::ParentNode { line, .. } | JsonEntry::Module { line, .. } => *line,
+            JsonEntry::Compound(_) | JsonEntry::Raw(_) => None,
+        }
+    }
+}
+
 #[derive(Default)]
 #[repr(transparent)]
 pub(crate) struct ModuleImport(pub(crate) HashMap<PackageID, ModuleImportConfig>);
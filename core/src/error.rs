@@ -21,6 +21,12 @@ pub enum CoreError {
     OccupiedTransform(String, String, String),
     #[error("Could not load package '{1}'. There is another native transform from '{0}'.")]
     OccupiedNativeTransform(String, String),
+    #[error("Could not define the macro '{0}'. There is already a module with that name.")]
+    OccupiedMacroName(String),
+    #[error("'{0}' is not a valid macro name. Use only letters, digits, '-' and '_'.")]
+    InvalidMacroName(String),
+    #[error("'{0}' is not a valid parameter of the macro '{1}'. Use only letters, digits, '-' and '_', don't repeat a parameter and don't use the name 'body', which is always the body of the module.")]
+    InvalidMacroParameter(String, String),
     #[cfg(feature = "native")]
     #[error("Compiler error")]
     WasmerCompiler(Box<CompileError>),
@@ -134,6 +140,8 @@ pub enum CoreError {
     ExpectedMultilineModule(String),
     #[error("'{0}' may only appear as a parent")]
     ExpectedParent(String),
+    #[error("Package '{0}' is a native package and cannot be run on its own")]
+    NativePackage(String),
 }
 
 impl From<WasiError> for CoreError {
@@ -6,6 +6,8 @@ use std::str::FromStr;
 use serde::de::{Error, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use parser::Ast;
+
 pub use context::Context;
 pub use element::Element;
 pub use error::CoreError;
@@ -20,6 +22,7 @@ pub mod context;
 mod element;
 mod error;
 mod fs;
+mod macros;
 mod package;
 pub mod package_store;
 mod schedule;
@@ -184,6 +187,7 @@ where
     ctx.clear_state();
 
     let (doc_ast, config) = parser::parse_with_config(source).map_err(|e| vec![e.into()])?;
+    record_source_lines(&doc_ast, ctx);
     let document: Element =
         Element::try_from_ast(doc_ast, GranularId::root()).map_err(|e| vec![e])?;
     let success = ctx.configure(config)?;
@@ -191,7 +195,10 @@ where
         return Ok(None);
     }
 
-    let res = evaluate_scheduled(document, ctx, format);
+    let mut document = ctx.filter_document(document, format).map_err(|e| vec![e])?;
+    ctx.define_macros(&mut document, format);
+    let res = evaluate_scheduled(document, ctx, format)
+        .and_then(|output| ctx.filter_output(output, format));
 
     res.map(|s| Some((s, ctx.take_state())))
         .map_err(|e| vec![e])
@@ -215,6 +222,7 @@ where
     ctx.clear_state();
 
     let (doc_ast, config) = parser::parse_with_config(source).map_err(|e| vec![e.into()])?;
+    record_source_lines(&doc_ast, ctx);
     let document: Element =
         Element::try_from_ast(doc_ast, GranularId::root()).map_err(|e| vec![e])?;
     let no_doc = if let Element::Parent { children, .. } = document {
@@ -228,12 +236,26 @@ where
         return Ok(None);
     }
 
-    let res = evaluate_scheduled(no_doc, ctx, format);
+    let mut no_doc = ctx.filter_document(no_doc, format).map_err(|e| vec![e])?;
+    ctx.define_macros(&mut no_doc, format);
+    let res = evaluate_scheduled(no_doc, ctx, format)
+        .and_then(|output| ctx.filter_output(output, format));
 
     res.map(|s| Some((s, ctx.take_state())))
         .map_err(|e| vec![e])
 }
 
+/// Stores the source line of each top-level element of the document in the compilation state,
+/// keyed by the id that the element gets when the document is converted to an `Element`
+fn record_source_lines<T, U>(doc_ast: &Ast, ctx: &mut Context<T, U>) {
+    if let Ast::Document(doc) = doc_ast {
+        ctx.state.source_lines = GranularId::root()
+            .children()
+            .zip(doc.lines.iter().copied())
+            .collect();
+    }
+}
+
 /// This function evaluates an element and all its children by creating a schedule, adding all the
 /// children to that schedule, and letting the schedule determine what element to evaluate next.
 /// This ensures that dependencies are handled in a correct manner. The function errors if the
@@ -268,8 +290,9 @@ mod tests {
 
     use serde_json::Value;
 
+    use crate::context::ModuleImport;
     use crate::package::{ArgType, PrimitiveArgType, TransformType};
-    use crate::package_store::ResolveTask;
+    use crate::package_store::{PackageID, PackageSource, PackageStore, ResolveTask};
 
     use super::*;
 
@@ -293,7 +316,15 @@ mod tests {
             description: "This package supports [table] modules".to_string(),
             transforms: vec![Transform {
                     from: "table".to_string(),
-                    to: vec![OutputFormat::new("html"), OutputFormat::new("latex")],
+                    to: vec![
+                        OutputFormat::new("html"),
+                        OutputFormat::new("latex"),
+                        OutputFormat::new("markdown"),
+                        OutputFormat::new("plaintext"),
+                        OutputFormat::new("docx"),
+                        OutputFormat::new("typst"),
+                        OutputFormat::new("pandoc-json"),
+                    ],
                     description: Some("Makes a table. Use one row for each row in the table, and separate the columns by the delimiter (default = |)".to_string()),
                     arguments: vec![
                         ArgInfo {
@@ -339,7 +370,15 @@ mod tests {
                     r#type: TransformType::Module,
                 }, Transform {
                      from: "big-table".to_string(),
-                     to: vec![OutputFormat::new("html"), OutputFormat::new("latex")],
+                     to: vec![
+                         OutputFormat::new("html"),
+                         OutputFormat::new("latex"),
+                         OutputFormat::new("markdown"),
+                         OutputFormat::new("plaintext"),
+                         OutputFormat::new("docx"),
+                         OutputFormat::new("typst"),
+                         OutputFormat::new("pandoc-json"),
+                     ],
                      description: Some("Large variant of the table, which accepts block content. Write the content of each cell on multiple lines, and use column-delimiter between cells on the same row. Then, use row-delimiter between rows.".to_string()),
                      arguments: vec![
                          ArgInfo {
@@ -378,10 +417,317 @@ mod tests {
                      unknown_content: true,
                      evaluate_before_children: false,
                      r#type: TransformType::Module,
+                }, Transform {
+                     from: "table-file".to_string(),
+                     to: vec![
+                         OutputFormat::new("html"),
+                         OutputFormat::new("latex"),
+                         OutputFormat::new("markdown"),
+                         OutputFormat::new("plaintext"),
+                         OutputFormat::new("docx"),
+                         OutputFormat::new("typst"),
+                         OutputFormat::new("pandoc-json"),
+                     ],
+                     description: Some("Makes a table from a CSV or TSV file. Fields may be quoted like in RFC 4180".to_string()),
+                     arguments: vec![
+                         ArgInfo {
+                             name: "path".to_string(),
+                             default: None,
+                             description: "The path to the CSV or TSV file".to_string(),
+                             r#type: PrimitiveArgType::String.into(),
+                         }, ArgInfo {
+                             name: "caption".to_string(),
+                             default: Some(Value::String("".to_string())),
+                             description: "The caption for the table".to_string(),
+                             r#type: PrimitiveArgType::String.into(),
+                         }, ArgInfo {
+                             name: "label".to_string(),
+                             default: Some(Value::String("".to_string())),
+                             description: "The label to use for the table, to be able to refer to it from the document".to_string(),
+                             r#type: PrimitiveArgType::String.into(),
+                         }, ArgInfo {
+                             name: "header".to_string(),
+                             default: Some(Value::String("auto".to_string())),
+                             description: "Style to apply to heading, none/bold. With auto, the first row is a heading if none of its cells are empty or numbers".to_string(),
+                             r#type: ArgType::Enum(vec!["auto".to_string(), "bold".to_string(), "none".to_string()]),
+                         }, ArgInfo {
+                             name: "alignment".to_string(),
+                             default: Some(Value::String("auto".to_string())),
+                             description: "Horizontal alignment in cells, left/center/right or l/c/r for each column. With auto, columns of numbers are right-aligned and other columns are left-aligned".to_string(),
+                             r#type: PrimitiveArgType::String.into(),
+                         }, ArgInfo {
+                             name: "borders".to_string(),
+                             default: Some(Value::String("all".to_string())),
+                             description: "Which borders to draw".to_string(),
+                             r#type: ArgType::Enum(vec!["all".to_string(), "horizontal".to_string(), "vertical".to_string(), "outer".to_string(), "none".to_string()]),
+                         }, ArgInfo {
+                             name: "delimiter".to_string(),
+                             default: Some(Value::String("auto".to_string())),
+                             description: "The delimiter between cells, which is a single character or tab. With auto, .tsv and .tab files use tabs and other files use commas".to_string(),
+                             r#type: PrimitiveArgType::String.into(),
+                         }, ArgInfo {
+                             name: "columns".to_string(),
+                             default: Some(Value::String("".to_string())),
+                             description: "The columns to include, such as 1,3-4 or names in the heading. All columns are included by default".to_string(),
+                             r#type: PrimitiveArgType::String.into(),
+                         }, ArgInfo {
+                             name: "decimals".to_string(),
+                             default: Some(Value::String("".to_string())),
+                             description: "The number of decimals to show in numbers. Numbers are shown as they are written by default".to_string(),
+                             r#type: PrimitiveArgType::String.into(),
+                         }, ArgInfo {
+                             name: "thousands".to_string(),
+                             default: Some(Value::String("".to_string())),
+                             description: "The separator between groups of thousands in numbers, such as a comma or a space".to_string(),
+                             r#type: PrimitiveArgType::String.into(),
+                         },
+                     ],
+                     variables: HashMap::new(),
+                     unknown_content: false,
+                     evaluate_before_children: false,
+                     r#type: TransformType::Module,
                 }
             ],
+            binary_formats: vec![],
+            base_formats: HashMap::new(),
         };
 
         assert_eq!(info.as_ref(), &foo);
     }
+
+    type TestContext = Context<UnimplementedResolver, DefaultAccessManager>;
+
+    fn filter(from: &str, r#type: TransformType) -> Transform {
+        Transform {
+            from: from.to_string(),
+            to: vec![OutputFormat::Any],
+            description: None,
+            arguments: vec![],
+            variables: HashMap::new(),
+            unknown_content: false,
+            evaluate_before_children: false,
+            r#type,
+        }
+    }
+
+    #[test]
+    fn filter_order_test() {
+        let mut store = PackageStore::default();
+        let mut ids = vec![];
+        for (name, transforms) in [
+            ("a", vec!["a-first", "a-second"]),
+            ("b", vec!["b-first"]),
+            ("c", vec!["c-first"]),
+        ] {
+            let id = PackageID {
+                name: name.to_string(),
+                source: PackageSource::Standard,
+            };
+            let mut transforms: Vec<Transform> = transforms
+                .into_iter()
+                .map(|from| filter(from, TransformType::Filter))
+                .collect();
+            transforms.push(filter(
+                &format!("{name}-output"),
+                TransformType::OutputFilter,
+            ));
+            let info = PackageInfo {
+                name: name.to_string(),
+                version: "0.1".to_string(),
+                description: String::new(),
+                transforms,
+                binary_formats: vec![],
+                base_formats: HashMap::new(),
+            };
+            store
+                .standard_packages
+                .insert(id.clone(), Package::new_native(info).unwrap());
+            ids.push(id);
+        }
+
+        // Filters are run in import order, then by package name, and in manifest order within a
+        // package
+        store
+            .expose_transforms(ModuleImport::default(), &[ids[1].clone(), ids[0].clone()])
+            .unwrap();
+        let names = |filter_type| {
+            store
+                .find_filters(filter_type, &OutputFormat::new("html"))
+                .into_iter()
+                .map(|(transform, _)| transform.from)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(TransformType::Filter),
+            vec!["b-first", "a-first", "a-second", "c-first"]
+        );
+        assert_eq!(
+            names(TransformType::OutputFilter),
+            vec!["b-output", "a-output", "c-output"]
+        );
+    }
+
+    #[test]
+    fn filter_result_test() {
+        let document = Element::Parent {
+            name: "__document".to_string(),
+            args: HashMap::new(),
+            children: vec![],
+            id: GranularId::root(),
+        };
+
+        // A filter that doesn't return a list of elements fails
+        assert!(TestContext::document_from_filter(&document, "not json").is_none());
+        assert!(TestContext::document_from_filter(&document, r#"{"name": "raw"}"#).is_none());
+        assert!(TestContext::output_from_filter("not json").is_none());
+        assert!(
+            TestContext::output_from_filter(r#"[{"name": "__bold", "children": []}]"#).is_none()
+        );
+        assert_eq!(
+            TestContext::output_from_filter(r#"["a", {"name": "raw", "data": "b"}]"#),
+            Some("ab".to_string())
+        );
+
+        // The top-level elements get new ids, and keep the source lines that the filter passed on
+        let output = r#"[{"name": "__document", "children": [
+            {"name": "__paragraph", "children": [], "line": 1},
+            {"name": "raw", "data": "x"},
+            {"name": "raw", "data": "y", "line": 4}
+        ]}]"#;
+        let (filtered, source_lines) =
+            TestContext::document_from_filter(&document, output).unwrap();
+        let ids: Vec<GranularId> = GranularId::root().children().take(3).collect();
+        assert!(matches!(
+            &filtered,
+            Element::Parent { id, children, .. } if id == &GranularId::root() && children.len() == 3
+        ));
+        assert!(matches!(
+            filtered.get_by_id(ids[2].clone()),
+            Some(Element::Module { body, id, .. }) if body == "y" && id == ids[2]
+        ));
+        assert_eq!(
+            source_lines,
+            HashMap::from([(ids[0].clone(), 1), (ids[2].clone(), 4)])
+        );
+
+        let output = r#"[{"name": "raw", "data": "x", "line": 2}, "y"]"#;
+        let (filtered, source_lines) =
+            TestContext::document_from_filter(&document, output).unwrap();
+        assert!(matches!(filtered, Element::Compound(elements) if elements.len() == 2));
+        assert_eq!(source_lines, HashMap::from([(ids[0].clone(), 2)]));
+    }
+
+    #[test]
+    fn filter_source_lines_test() {
+        // A filter that returns its input unchanged keeps the source lines of the document
+        let mut ctx = Context::new(UnimplementedResolver, DefaultAccessManager).unwrap();
+        ctx.configure(None).unwrap();
+        ctx.track_source_lines = true;
+        let ids: Vec<GranularId> = GranularId::root().children().take(2).collect();
+        ctx.state.source_lines = HashMap::from([(ids[0].clone(), 1), (ids[1].clone(), 3)]);
+        let document = Element::Compound(
+            ids.iter()
+                .map(|id| Element::Module {
+                    name: "raw".to_string(),
+                    args: Default::default(),
+                    body: "text".to_string(),
+                    inline: false,
+                    id: id.clone(),
+                })
+                .collect(),
+        );
+
+        let format = OutputFormat::new("html");
+        let mut counter = 0..;
+        let serialized = ctx
+            .serialize_element(&document, &format, &mut || counter.next().unwrap())
+            .unwrap();
+        let (_, source_lines) = TestContext::document_from_filter(&document, &serialized).unwrap();
+        assert_eq!(source_lines, ctx.state.source_lines);
+    }
+
+    #[test]
+    fn macro_expansion_test() {
+        let mut ctx = Context::new(UnimplementedResolver, DefaultAccessManager).unwrap();
+        let format = OutputFormat::new("html");
+        let source = "[define name=warnbox params=\"title, kind=note\"]\n**{{title}}** ({{kind}}): {{body}}\n\n[warnbox title=Foo]\nHello\n";
+        let (ast, config) = parser::parse_with_config(source).unwrap();
+        let mut document = Element::try_from_ast(ast, GranularId::root()).unwrap();
+        ctx.configure(config).unwrap();
+        ctx.define_macros(&mut document, &format);
+
+        let Element::Parent { children, .. } = document else {
+            panic!("The document should be a parent")
+        };
+        let Element::Module { body, name, .. } = ctx.transform(&children[1], &format).unwrap()
+        else {
+            panic!("A macro should expand to a module")
+        };
+        assert_eq!(name, "block_content");
+        assert_eq!(body, "**Foo** (note): Hello\n");
+    }
+
+    #[test]
+    fn macro_definition_error_test() {
+        let mut ctx = Context::new(UnimplementedResolver, DefaultAccessManager).unwrap();
+        let format = OutputFormat::new("html");
+        let source = "[define name=inline_content]\nA\n\n[define name=warnbox]\nB\n\n[define name=warnbox]\nC\n\n[define name=\"not valid\"]\nD\n\n[define name=box params=\"a, a\"]\nE\n\n[define]\nF\n";
+        let (ast, config) = parser::parse_with_config(source).unwrap();
+        let mut document = Element::try_from_ast(ast, GranularId::root()).unwrap();
+        ctx.configure(config).unwrap();
+        ctx.define_macros(&mut document, &format);
+
+        // Every define that fails is replaced by an error, and the others are still defined
+        let Element::Parent { children, .. } = document else {
+            panic!("The document should be a parent")
+        };
+        let errors: Vec<Option<String>> = children
+            .iter()
+            .map(|child| match child {
+                Element::Module { name, body, .. } if name == "error" => Some(body.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                Some(CoreError::OccupiedMacroName("inline_content".to_string()).to_string()),
+                None,
+                Some(CoreError::OccupiedMacroName("warnbox".to_string()).to_string()),
+                Some(CoreError::InvalidMacroName("not valid".to_string()).to_string()),
+                Some(
+                    CoreError::InvalidMacroParameter("a".to_string(), "box".to_string())
+                        .to_string()
+                ),
+                Some(
+                    CoreError::MissingArgument("name".to_string(), "define".to_string())
+                        .to_string()
+                ),
+            ]
+        );
+        assert!(ctx.transform(&children[1], &format).is_ok());
+    }
+
+    #[test]
+    fn nested_macro_definition_test() {
+        let mut ctx = Context::new(UnimplementedResolver, DefaultAccessManager).unwrap();
+        let format = OutputFormat::new("html");
+        let source = "[block_content]\n[define name=inner]\nA\n";
+        let (ast, config) = parser::parse_with_config(source).unwrap();
+        let mut document = Element::try_from_ast(ast, GranularId::root()).unwrap();
+        ctx.configure(config).unwrap();
+        ctx.define_macros(&mut document, &format);
+
+        // The define is only parsed when the block_content is evaluated, so it is never collected
+        let Element::Parent { children, .. } = document else {
+            panic!("The document should be a parent")
+        };
+        let Element::Compound(content) = ctx.transform(&children[0], &format).unwrap() else {
+            panic!("block_content should output a compound")
+        };
+        let Element::Module { name, .. } = ctx.transform(&content[0], &format).unwrap() else {
+            panic!("The define should output an error module")
+        };
+        assert_eq!(name, "error");
+    }
 }
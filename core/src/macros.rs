@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::package::{ArgValue, PackageImplementation, PrimitiveArgType, TransformType};
+use crate::{ArgInfo, CoreError, Package, PackageInfo, Transform};
+
+/// The name of the parameter that is replaced by the body of the module that a macro is invoked
+/// with. It is always available, and may not be declared.
+static BODY_PARAMETER: &str = "body";
+
+/// How many times macros may be expanded in one compilation, so that a macro that uses itself is
+/// stopped instead of being expanded forever
+pub(crate) const MAX_MACRO_EXPANSIONS: usize = 10000;
+
+/// A macro defined in the document with `[define name=... params=...]`. The body of the define
+/// module is a ModMark template, in which `{{param}}` is replaced by the value of the argument
+/// `param`, and `{{body}}` is replaced by the body of the module that the macro is invoked with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Macro {
+    pub name: String,
+    /// The parameters of the macro in the order they are declared, with their default values
+    pub params: Vec<(String, Option<String>)>,
+    pub template: String,
+}
+
+impl Macro {
+    /// Creates a macro from the arguments and body of a define module. The parameters are
+    /// separated by commas, and a parameter may be given a default value with `param=default`,
+    /// which makes it optional.
+    pub fn new(name: &str, params: &str, template: &str) -> Result<Self, CoreError> {
+        if !is_valid_name(name) {
+            return Err(CoreError::InvalidMacroName(name.to_string()));
+        }
+
+        let mut parsed_params: Vec<(String, Option<String>)> = vec![];
+        for param in params.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (param_name, default) = match param.split_once('=') {
+                Some((param_name, default)) => {
+                    (param_name.trim(), Some(default.trim().to_string()))
+                }
+                None => (param, None),
+            };
+            if !is_valid_name(param_name)
+                || param_name == BODY_PARAMETER
+                || parsed_params.iter().any(|(other, _)| other == param_name)
+            {
+                return Err(CoreError::InvalidMacroParameter(
+                    param_name.to_string(),
+                    name.to_string(),
+                ));
+            }
+            parsed_params.push((param_name.to_string(), default));
+        }
+
+        Ok(Macro {
+            name: name.to_string(),
+            params: parsed_params,
+            template: template.to_string(),
+        })
+    }
+
+    /// Creates the package that the transform of this macro belongs to. A macro may be used as
+    /// any module, and its arguments are the parameters of the macro. Since a macro may expand to
+    /// any content, its content is unknown until it has been expanded.
+    pub(crate) fn package(&self) -> Package {
+        let arguments = self
+            .params
+            .iter()
+            .map(|(name, default)| ArgInfo {
+                name: name.clone(),
+                default: default.clone().map(Value::String),
+                description: format!("A parameter of the macro '{}'", self.name),
+                r#type: PrimitiveArgType::String.into(),
+            })
+            .collect();
+
+        Package {
+            info: Arc::new(PackageInfo {
+                name: "macros".to_string(),
+                version: "1".to_string(),
+                description: "Macros defined in the document".to_string(),
+                transforms: vec![Transform {
+                    from: self.name.clone(),
+                    to: vec![],
+                    description: Some(format!(
+                        "The macro '{}', defined in the document",
+                        self.name
+                    )),
+                    arguments,
+                    variables: HashMap::new(),
+                    unknown_content: true,
+                    evaluate_before_children: false,
+                    r#type: TransformType::Module,
+                }],
+                binary_formats: vec![],
+                base_formats: HashMap::new(),
+            }),
+            implementation: PackageImplementation::Macro(Arc::new(self.clone())),
+        }
+    }
+
+    /// Expands the template of this macro with the given arguments and body. Placeholders that
+    /// don't name a parameter are left as they are.
+    pub(crate) fn expand(&self, args: &HashMap<String, ArgValue>, body: &str) -> String {
+        let mut result = String::new();
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find("{{") {
+            let Some(length) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            let placeholder = rest[start + 2..start + 2 + length].trim();
+            let value = if placeholder == BODY_PARAMETER {
+                Some(body.to_string())
+            } else {
+                args.get(placeholder).map(|value| value.clone().into())
+            };
+
+            match value {
+                Some(value) => {
+                    result.push_str(&rest[..start]);
+                    result.push_str(&value);
+                    rest = &rest[start + 2 + length + 2..];
+                }
+                None => {
+                    result.push_str(&rest[..start + 2]);
+                    rest = &rest[start + 2..];
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+// Macro and parameter names follow the same rules as module names, so that a macro can be invoked
+// like any other module
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
@@ -8,6 +8,7 @@ use wasmer::Engine;
 use wasmer::{Instance, Module, RuntimeError, Store};
 use wasmer_wasi::{Pipe, WasiError, WasiState};
 
+use crate::macros::Macro;
 use crate::package::PackageImplementation::Native;
 use crate::variables::VarAccess;
 use crate::{error::CoreError, Element, OutputFormat};
@@ -41,9 +42,19 @@ pub enum TransformType {
     Parent,
     #[serde(alias = "*")]
     Any,
+    /// Transforms the whole document tree before it is evaluated
+    Filter,
+    /// Transforms the final output after the document is evaluated
+    OutputFilter,
 }
 
 impl TransformType {
+    /// Filters are not used to transform single elements, but are instead run on the whole
+    /// document (or output) in the order that their packages are imported
+    pub fn is_filter(&self) -> bool {
+        matches!(self, TransformType::Filter | TransformType::OutputFilter)
+    }
+
     pub fn verify_element_type(&self, element: &Element) -> Result<(), CoreError> {
         let Some(name) = element.name() else {
             unreachable!("Cannot transform compound element")
@@ -68,6 +79,7 @@ impl TransformType {
                 .then_some(())
                 .ok_or_else(|| ExpectedParent(name.to_string())),
             Any => Ok(()),
+            Filter | OutputFilter => unreachable!("Filters are never used on single elements"),
         }
     }
 }
@@ -96,6 +108,16 @@ pub struct PackageInfo {
     pub version: String,
     pub description: String,
     pub transforms: Vec<Transform>,
+    /// The output formats of this package that are binary. The output of a document in such a
+    /// format is encoded as base64, and should be decoded before it is saved.
+    #[serde(default)]
+    #[serde(rename = "binary-formats")]
+    pub binary_formats: Vec<OutputFormat>,
+    /// Output formats of this package that are based on another format, mapped to that format.
+    /// Elements without a transform to such a format are transformed to its base format instead.
+    #[serde(default)]
+    #[serde(rename = "base-formats")]
+    pub base_formats: HashMap<OutputFormat, OutputFormat>,
 }
 
 impl PackageInfo {
@@ -180,6 +202,7 @@ pub struct Package {
 pub enum PackageImplementation {
     Wasm(Module),
     Native,
+    Macro(Arc<Macro>),
 }
 
 // This is more or less just a wrapper to simplify writing enums like `type: [true, false]` without
@@ -413,12 +436,17 @@ impl ArgValue {
 
 impl PartialEq for PackageImplementation {
     /// Implements PartialEq for PackageImplementation in a way where two
-    /// `PackageImplementation::Native` gives `true` but any other combination gives `false`
+    /// `PackageImplementation::Native` or two equal `PackageImplementation::Macro` gives `true` but
+    /// any other combination gives `false`
     fn eq(&self, other: &Self) -> bool {
         match self {
             Native => match other {
                 Native => true,
-                PackageImplementation::Wasm(_) => false,
+                PackageImplementation::Wasm(_) | PackageImplementation::Macro(_) => false,
+            },
+            PackageImplementation::Macro(definition) => match other {
+                PackageImplementation::Macro(other_definition) => definition == other_definition,
+                Native | PackageImplementation::Wasm(_) => false,
             },
             PackageImplementation::Wasm(_) => false,
         }
@@ -13,7 +13,8 @@ use wasmer::Engine;
 use parser::config::{Config, Hide, Import};
 
 use crate::context::{ModuleImport, ModuleImportConfig, TransformVariant};
-use crate::package::PackageImplementation;
+use crate::macros::Macro;
+use crate::package::{PackageImplementation, TransformType};
 use crate::{std_packages, ArgInfo, CoreError, OutputFormat, Package, PackageInfo, Transform};
 
 // The package_new allows us to run Package::new(source, [engine]) by supplying the identifier to
@@ -42,6 +43,8 @@ pub struct PackageStore {
     pub(crate) new_packages: HashMap<PackageID, Vec<u8>>,
     pub(crate) package_task_failures: Vec<CoreError>,
     pub(crate) transforms: HashMap<String, TransformVariant>,
+    /// The filters that are exposed, in the order that they should be run
+    pub(crate) filters: Vec<(PackageID, Transform, Package)>,
 }
 
 impl PackageStore {
@@ -50,6 +53,7 @@ impl PackageStore {
     /// cache of the PackageStore
     pub fn clear_packages(&mut self) {
         self.transforms.clear();
+        self.filters.clear();
         self.external_packages.clear();
     }
 
@@ -202,18 +206,74 @@ impl PackageStore {
     }
 
     /// Gets the transform and package the transform is in, for a transform from a specific element
-    /// to a specific output format. Returns None if no such transform exists
+    /// to a specific output format. If there is no such transform, but the output format is based
+    /// on another format, the transform to the base format is used. Returns None if no such
+    /// transform exists
     pub fn find_transform(
         &self,
         element_name: &str,
         output_format: &OutputFormat,
     ) -> Option<(Transform, Package)> {
-        self.transforms
-            .get(element_name)
-            .and_then(|t| t.find_transform_to(output_format))
+        let variant = self.transforms.get(element_name)?;
+        variant
+            .find_transform_to(output_format)
+            .or_else(|| {
+                self.base_format(output_format)
+                    .and_then(|base| variant.find_transform_to(&base))
+            })
+            .cloned()
+    }
+
+    /// Gets the output format that a transform found by `find_transform` should be run with. This
+    /// is the base format if the transform doesn't support the output format itself.
+    pub(crate) fn transform_target(
+        &self,
+        transform: &Transform,
+        output_format: &OutputFormat,
+    ) -> OutputFormat {
+        if transform.to.contains(output_format) || transform.to.contains(&OutputFormat::Any) {
+            return output_format.clone();
+        }
+        self.base_format(output_format)
+            .unwrap_or_else(|| output_format.clone())
+    }
+
+    /// Gets the format that the output format is based on, if any loaded package declares one
+    pub fn base_format(&self, output_format: &OutputFormat) -> Option<OutputFormat> {
+        self.standard_packages
+            .values()
+            .chain(self.external_packages.values())
+            .find_map(|pkg| pkg.info.base_formats.get(output_format))
             .cloned()
     }
 
+    /// Gets all exposed filters of the given type (`Filter` or `OutputFilter`) to the given output
+    /// format, in the order that they should be run
+    pub(crate) fn find_filters(
+        &self,
+        filter_type: TransformType,
+        output_format: &OutputFormat,
+    ) -> Vec<(Transform, Package)> {
+        self.filters
+            .iter()
+            .filter(|(_, transform, _)| {
+                transform.r#type == filter_type
+                    && (transform.to.contains(output_format)
+                        || transform.to.contains(&OutputFormat::Any))
+            })
+            .map(|(_, transform, pkg)| (transform.clone(), pkg.clone()))
+            .collect()
+    }
+
+    /// Checks if any loaded package declares the output format as binary, in which case the
+    /// output of a document is encoded as base64
+    pub fn is_binary_format(&self, output_format: &OutputFormat) -> bool {
+        self.standard_packages
+            .values()
+            .chain(self.external_packages.values())
+            .any(|pkg| pkg.info.binary_formats.contains(output_format))
+    }
+
     pub(crate) fn generate_resolve_tasks(
         &mut self,
         arc_mutex: Arc<Mutex<Self>>,
@@ -254,12 +314,15 @@ impl PackageStore {
     }
 
     // This function makes sure the transforms that should be exposed according to the given
-    // ModuleImport is exposed, and that no other transforms are exposed.
+    // ModuleImport is exposed, and that no other transforms are exposed. Filters are ordered by
+    // the position of their package in `import_order`, and packages not in it come last
     pub(crate) fn expose_transforms(
         &mut self,
         mut config: ModuleImport,
+        import_order: &[PackageID],
     ) -> Result<(), Vec<CoreError>> {
         self.transforms.clear();
+        self.filters.clear();
 
         let mut errors = vec![];
 
@@ -298,7 +361,8 @@ impl PackageStore {
             };
             if let Err(e) = Self::insert_transforms(
                 &mut self.transforms,
-                pkg,
+                &mut self.filters,
+                (name, pkg),
                 include_entries,
                 include_list.as_slice(),
             ) {
@@ -322,7 +386,8 @@ impl PackageStore {
             };
             if let Err(e) = Self::insert_transforms(
                 &mut self.transforms,
-                pkg,
+                &mut self.filters,
+                (name, pkg),
                 include_entries,
                 include_list.as_slice(),
             ) {
@@ -330,6 +395,12 @@ impl PackageStore {
             }
         }
 
+        // Sorting is stable, so the filters of a package are run in the order of the manifest
+        self.filters.sort_by_key(|(id, _, _)| {
+            let position = import_order.iter().position(|imported| imported == id);
+            (position.unwrap_or(usize::MAX), id.name.clone())
+        });
+
         mem::take(&mut config.0)
             .into_keys()
             .map(|id| CoreError::UnusedConfig(id.name))
@@ -342,17 +413,38 @@ impl PackageStore {
         }
     }
 
+    /// Exposes a macro defined in the document as a transform for the current compilation. This
+    /// has to be done after `expose_transforms`, since a macro may not have the same name as any
+    /// other transform.
+    pub(crate) fn expose_macro(&mut self, definition: Macro) -> Result<(), CoreError> {
+        match self.transforms.entry(definition.name.clone()) {
+            Entry::Occupied(_) => Err(CoreError::OccupiedMacroName(definition.name)),
+            Entry::Vacant(entry) => {
+                let package = definition.package();
+                let transform = package.info.transforms[0].clone();
+                entry.insert(TransformVariant::Native((transform, package)));
+                Ok(())
+            }
+        }
+    }
+
     // This function was introduced to avoid repeated code. It takes a map and a package, and adds
     // all transforms in that package which either exists in the list (include_entries=true) or
-    // doesn't exist in the list (include_entries=false) into the map
+    // doesn't exist in the list (include_entries=false) into the map. Filters are added to the
+    // list of filters instead
     fn insert_transforms(
         map: &mut HashMap<String, TransformVariant>,
-        pkg: &Package,
+        filters: &mut Vec<(PackageID, Transform, Package)>,
+        (id, pkg): (&PackageID, &Package),
         include_entries: bool,
         include_list: &[String],
     ) -> Result<(), CoreError> {
         for transform @ Transform { from, to, .. } in &pkg.info.transforms {
             if include_entries == include_list.contains(from) {
+                if transform.r#type.is_filter() {
+                    filters.push((id.clone(), transform.clone(), pkg.clone()));
+                    continue;
+                }
                 for output_format in to {
                     match output_format {
                         OutputFormat::Any => {
@@ -22,6 +22,12 @@ define_standard_package_loader! {
     "table",
     "html",
     "latex",
+    "markdown",
+    "plaintext",
+    "docx",
+    "epub",
+    "typst",
+    "pandoc",
     "link",
     "list",
     "code",
@@ -32,6 +38,11 @@ define_standard_package_loader! {
     "bibliography",
     "plot",
     "structure",
+    "footnote",
+    "glossary",
+    "index",
+    "admonition",
+    "diagram",
 }
 
 // Here, all native packages are declared. The macro expands to two functions,
@@ -126,6 +137,32 @@ define_native_packages! {
             }
         ]
     }
+    "macros" => {
+        desc: "Define new modules in the document, without writing a package",
+        transforms: [
+            {
+                name: "define",
+                desc: "Defines a macro, which can then be used as a module with the given name anywhere in the document. The body is ModMark, in which {{param}} is replaced by the argument param and {{body}} by the body of the module.",
+                unknown_content: false,
+                vars: [],
+                args: vec![
+                    ArgInfo {
+                        name: "name".to_string(),
+                        default: None,
+                        description: "The name of the macro".to_string(),
+                        r#type: PrimitiveArgType::String.into()
+                    },
+                    ArgInfo {
+                        name: "params".to_string(),
+                        default: Some(Value::from("")),
+                        description: "The parameters of the macro, separated by commas. A parameter is optional if it is given a default value with 'param=default'".to_string(),
+                        r#type: PrimitiveArgType::String.into()
+                    }
+                ],
+                func: native_define
+            }
+        ]
+    }
     "variables" => {
         desc: "Read and write to environment variables.",
         transforms: [
@@ -292,6 +329,49 @@ pub fn native_block_content<T, U>(
     Ok(Element::Compound(elements))
 }
 
+/// Macros are collected from the document before it is evaluated, see `Context::define_macros`,
+/// so the define module itself doesn't output anything. A define that wasn't collected is in
+/// content that was parsed during evaluation, and its macro can't be used, so it is reported.
+pub fn native_define<T, U>(
+    ctx: &mut Context<T, U>,
+    _body: &str,
+    args: HashMap<String, ArgValue>,
+    inline: bool,
+    output_format: &OutputFormat,
+    id: &GranularId,
+) -> Result<Element, CoreError> {
+    if ctx.state.macro_definitions.contains(id) {
+        return Ok(Element::Compound(vec![]));
+    }
+
+    let name: String = args.get("name").cloned().unwrap().into();
+    Ok(define_error(
+        format!("Could not define the macro '{name}', since it is inside the body of another module. Macros can only be defined outside of the bodies of modules."),
+        inline,
+        id,
+        output_format,
+    ))
+}
+
+/// Creates the error module that replaces a define whose macro could not be defined
+pub(crate) fn define_error(
+    description: String,
+    inline: bool,
+    id: &GranularId,
+    format: &OutputFormat,
+) -> Element {
+    Element::Module {
+        name: "error".to_string(),
+        args: ModuleArguments::from([
+            ("source".to_string(), "define".to_string()),
+            ("target".to_string(), format.to_string()),
+        ]),
+        body: description,
+        inline,
+        id: id.clone(),
+    }
+}
+
 /// Helper function to create text elements
 fn text_element(contents: String, id: GranularId) -> Element {
     Element::Module {
@@ -47,7 +47,9 @@ macro_rules! define_native_packages {
                                     r#type: $crate::package::TransformType::Module
                                 }),
                             )*
-                        ]
+                        ],
+                        binary_formats: vec![],
+                        base_formats: HashMap::new(),
                     }),
                 )*
             ]
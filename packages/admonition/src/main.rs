@@ -0,0 +1,181 @@
+use std::env;
+use std::io::{self, Read};
+
+use serde_json::{json, Value};
+
+macro_rules! module {
+    ($name:expr, $data:expr $(,$($args:tt)*)?) => {json!({"name": $name $(,"arguments":$($args)*)*, "data": $data})}
+}
+
+macro_rules! import {
+    ($e:expr) => {module!("set-add", $e, {"name": "imports"})}
+}
+
+macro_rules! text {
+    ($e:expr) => {
+        module!("__text", $e)
+    };
+}
+
+/// The kinds of admonitions that have a module of their own, and their own colors
+const KINDS: [&str; 4] = ["note", "tip", "warning", "danger"];
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let action = &args[0];
+    match action.as_str() {
+        "manifest" => manifest(),
+        "transform" => transform(&args[1], &args[2]),
+        other => {
+            eprintln!("Invalid action {other}")
+        }
+    }
+}
+
+fn manifest() {
+    let mut transforms: Vec<Value> = KINDS
+        .iter()
+        .map(|kind| {
+            json!({
+                "from": kind,
+                "to": ["html", "latex"],
+                "arguments": [
+                    {"name": "title", "default": "", "description": format!("The title of the box. If this is empty, the title is {}", title_case(kind))},
+                    {"name": "collapsible", "default": "false", "type": ["true", "false"], "description": "If true, the body is hidden until the title is clicked. This is only supported in HTML"}
+                ],
+                "description": format!("A box that stands out from the text, for a {kind}. The body is block content, so it may contain paragraphs, lists and other modules"),
+                "variables": {
+                    "imports": {"type": "set", "access": "add"}
+                }
+            })
+        })
+        .collect();
+    transforms.push(json!({
+        "from": "callout",
+        "to": ["html", "latex"],
+        "arguments": [
+            {"name": "kind", "default": "note", "description": "The kind of the box, which decides its color. note, tip, warning and danger have colors of their own, and other kinds are gray"},
+            {"name": "title", "default": "", "description": "The title of the box. If this is empty, the title is the kind"},
+            {"name": "collapsible", "default": "false", "type": ["true", "false"], "description": "If true, the body is hidden until the title is clicked. This is only supported in HTML"}
+        ],
+        "description": "A box that stands out from the text, of any kind. The body is block content. Example: [callout kind=example title=\"Using callouts\"]",
+        "variables": {
+            "imports": {"type": "set", "access": "add"}
+        }
+    }));
+
+    print!(
+        "{}",
+        serde_json::to_string(&json!(
+            {
+            "name": "admonition",
+            "version": "0.1",
+            "description": "This package supports admonitions, which are boxes for notes, tips, warnings and other remarks that stand out from the text.",
+            "transforms": transforms
+            }
+        ))
+        .unwrap()
+    );
+}
+
+fn transform(from: &str, to: &str) {
+    let input: Value = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        serde_json::from_str(&buffer).unwrap()
+    };
+
+    match from {
+        "callout" => {
+            let kind = input["arguments"]["kind"].as_str().unwrap().trim();
+            transform_admonition(&input, kind, to);
+        }
+        kind if KINDS.contains(&kind) => transform_admonition(&input, kind, to),
+        other => {
+            eprintln!("Package does not support {other}");
+        }
+    }
+}
+
+fn transform_admonition(input: &Value, kind: &str, to: &str) {
+    let body = input["data"].as_str().unwrap();
+    let title = match input["arguments"]["title"].as_str().unwrap().trim() {
+        "" => title_case(kind),
+        title => title.to_string(),
+    };
+    let collapsible = input["arguments"]["collapsible"].as_str() == Some("true");
+
+    let output = match to {
+        "html" => {
+            let class = class_name(kind);
+            let mut output = vec![
+                import!(format!(
+                    "<style>\n{}</style>",
+                    include_str!("admonition.css")
+                )),
+                json!(format!("<aside class=\"admonition admonition-{class}\">\n")),
+            ];
+            if collapsible {
+                output.push(json!("<details>\n<summary class=\"admonition-title\">"));
+                output.push(text!(title));
+                output.push(json!("</summary>\n"));
+                output.push(module!("block_content", body));
+                output.push(json!("</details>\n"));
+            } else {
+                output.push(json!("<p class=\"admonition-title\">"));
+                output.push(text!(title));
+                output.push(json!("</p>\n"));
+                output.push(module!("block_content", body));
+            }
+            output.push(json!("</aside>\n"));
+            output
+        }
+        "latex" => {
+            let color = latex_color(kind);
+            vec![
+                import!("\\usepackage{tcolorbox}"),
+                json!(format!(
+                    "\n\\begin{{tcolorbox}}[colback={color}!5!white,colframe={color}!75!black,title={{"
+                )),
+                text!(title),
+                json!("}]\n"),
+                module!("block_content", body),
+                json!("\n\\end{tcolorbox}\n"),
+            ]
+        }
+        other => {
+            eprintln!("Cannot convert {kind} to {other}!");
+            return;
+        }
+    };
+
+    print!("{}", serde_json::to_string(&output).unwrap());
+}
+
+// The title of a box without a title of its own, which is its kind with an upper case first letter
+fn title_case(kind: &str) -> String {
+    let mut chars = kind.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// The kind of a box as a part of a CSS class name
+fn class_name(kind: &str) -> String {
+    kind.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+// The color of a box in LaTeX, which is one of the colors that xcolor always has
+fn latex_color(kind: &str) -> &'static str {
+    match kind.to_lowercase().as_str() {
+        "note" => "blue",
+        "tip" => "green",
+        "warning" => "orange",
+        "danger" => "red",
+        _ => "gray",
+    }
+}
@@ -0,0 +1,226 @@
+//! Rendering of citations and bibliographies with CSL styles, which is done by the CSL processor
+//! of hayagriva. The built-in styles are the CSL versions that hayagriva comes with, adjusted so
+//! that they render the bibliography as the package always has. The output is converted to
+//! `DisplayString`s, which keep the formatting that is displayed.
+
+use std::collections::HashSet;
+use std::fs;
+
+use hayagriva::archive::{locales, ArchivedStyle};
+use hayagriva::citationberg::taxonomy::Locator;
+use hayagriva::citationberg::{FontStyle, FontWeight, IndependentStyle, LocaleCode, Style};
+use hayagriva::lang::SentenceCase;
+use hayagriva::types::{FormatString, StringChunk};
+use hayagriva::{
+    BibliographyDriver, BibliographyRequest, CitationItem, CitationRequest, ElemChild,
+    ElemChildren, Entry, LocatorPayload, SpecificLocator,
+};
+
+use crate::{BibItem, DisplayString, Formatting, InlineCitation, Rendered};
+
+/// Renders the citations and the bibliography with the CSL style in the file `csl`, or with the
+/// CSL version of the built-in `style` if there is no file. The terms of the style are taken
+/// from `locale` if it is given. If the style can't be read, an error is printed to stderr and
+/// None is returned.
+pub fn render(
+    entries: &[Entry],
+    citations: &[String],
+    style: &str,
+    csl: &str,
+    locale: &str,
+    unused_keys_is_visible: bool,
+) -> Option<Rendered> {
+    let builtin = csl.is_empty();
+    let entries = if builtin {
+        builtin_entries(entries, style)
+    } else {
+        entries.to_vec()
+    };
+    let entries = entries.as_slice();
+    let style = load_style(style, csl)?;
+    let locale = (!locale.is_empty()).then(|| LocaleCode(locale.to_string()));
+    let locale_files = locales();
+
+    let mut driver = BibliographyDriver::new();
+
+    // The key of the entry that each [cite] refers to, if it exists
+    let mut cited_keys = vec![];
+    for citation_str in citations {
+        let citation: InlineCitation = serde_json::from_str(citation_str).unwrap();
+        let Some(entry) = entries.iter().find(|entry| entry.key() == citation.key) else {
+            cited_keys.push(None);
+            continue;
+        };
+
+        // Notes like "p. 10" are shown as they are written, so the style only marks where the
+        // note goes, and it is inserted there when the citation is converted
+        let locator = citation
+            .note
+            .map(|_| SpecificLocator(Locator::Custom, LocatorPayload::Transparent));
+        driver.citation(CitationRequest::new(
+            vec![CitationItem::new(entry, locator, None, false, None)],
+            &style,
+            locale.clone(),
+            &locale_files,
+            None,
+        ));
+        cited_keys.push(Some((entry.key().to_string(), citation.note)));
+    }
+
+    // Unused entries are cited by hidden citations, after all other citations so that they don't
+    // change the numbering of the cited entries
+    if unused_keys_is_visible {
+        for entry in entries {
+            driver.citation(CitationRequest::new(
+                vec![CitationItem::new(entry, None, None, true, None)],
+                &style,
+                locale.clone(),
+                &locale_files,
+                None,
+            ));
+        }
+    }
+
+    let rendered = driver.finish(BibliographyRequest {
+        style: &style,
+        locale,
+        locale_files: &locale_files,
+    });
+
+    let cited: HashSet<String> = cited_keys
+        .iter()
+        .flatten()
+        .map(|(key, _)| key.clone())
+        .collect();
+    let mut rendered_citations = rendered.citations.into_iter();
+    let citations = cited_keys
+        .into_iter()
+        .map(|cited| {
+            let (key, note) = cited?;
+            let citation = rendered_citations.next()?;
+            Some((key, to_display_string(&citation.citation, note)))
+        })
+        .collect();
+
+    let items = rendered
+        .bibliography
+        .map(|bibliography| {
+            bibliography
+                .items
+                .into_iter()
+                .map(|item| {
+                    // The built-in styles only number the entries that are cited
+                    let numbered = !builtin || cited.contains(&item.key);
+                    BibItem {
+                        prefix: item
+                            .first_field
+                            .filter(|_| numbered)
+                            .map(|field| to_display_string(&ElemChildren(vec![field]), None)),
+                        display: to_display_string(&item.content, None),
+                        key: item.key,
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(Rendered { citations, items })
+}
+
+/// The entries as the built-in style renders them. IEEE and APA write titles in sentence case,
+/// so the titles are converted before they are rendered, keeping the parts in braces as they are.
+fn builtin_entries(entries: &[Entry], style: &str) -> Vec<Entry> {
+    let mut entries = entries.to_vec();
+    if matches!(style, "IEEE" | "APA") {
+        let mut sentence_case = SentenceCase::new();
+        sentence_case.capitalize_words_with_caps_inside = false;
+        for entry in &mut entries {
+            if let Some(title) = entry.title() {
+                let title = title.format_sentence_case(sentence_case);
+                entry.set_title(FormatString::from(StringChunk::verbatim(title)));
+            }
+        }
+    }
+    entries
+}
+
+/// Loads the independent CSL style in the file `csl`, or the CSL version of the built-in style if
+/// `csl` is empty
+fn load_style(style: &str, csl: &str) -> Option<IndependentStyle> {
+    if !csl.is_empty() {
+        let Ok(xml) = fs::read_to_string(csl) else {
+            eprintln!("Could not read the CSL style {csl}");
+            return None;
+        };
+        return match IndependentStyle::from_xml(&xml) {
+            Ok(style) => Some(style),
+            Err(e) => {
+                eprintln!(
+                    "Could not parse the CSL style {csl}, which must be an independent style: {e}"
+                );
+                None
+            }
+        };
+    }
+
+    // The CSL version of IEEE leaves out the year of web pages, so the package has its own copy
+    if style == "IEEE" {
+        return Some(IndependentStyle::from_xml(include_str!("ieee.csl")).unwrap());
+    }
+
+    let name = match style {
+        "APA" => "apa",
+        "MLA" => "modern-language-association",
+        _ => "chicago-author-date",
+    };
+    match ArchivedStyle::by_name(name).map(|style| style.get()) {
+        Some(Style::Independent(style)) => Some(style),
+        _ => {
+            eprintln!("There is no CSL version of the style {style}");
+            None
+        }
+    }
+}
+
+/// Converts rendered CSL output to a `DisplayString`. Bold text, italic text and links are kept,
+/// and other formatting is left out. The note of a citation is inserted where the style puts it.
+fn to_display_string(children: &ElemChildren, note: Option<&str>) -> DisplayString {
+    let mut string = DisplayString::default();
+    push_children(&mut string, children, note);
+    string
+}
+
+fn push_children(string: &mut DisplayString, children: &ElemChildren, note: Option<&str>) {
+    for child in &children.0 {
+        match child {
+            ElemChild::Text(formatted) => {
+                let formatting = if matches!(formatted.formatting.font_weight, FontWeight::Bold) {
+                    Some(Formatting::Bold)
+                } else if matches!(formatted.formatting.font_style, FontStyle::Italic) {
+                    Some(Formatting::Italic)
+                } else {
+                    None
+                };
+                push_text(string, &formatted.text, formatting);
+            }
+            ElemChild::Link { text, url } => {
+                push_text(string, &text.text, Some(Formatting::Link(url.clone())));
+            }
+            ElemChild::Elem(elem) => push_children(string, &elem.children, note),
+            ElemChild::Markup(markup) => push_text(string, markup, None),
+            ElemChild::Transparent { .. } => push_text(string, note.unwrap_or_default(), None),
+        }
+    }
+}
+
+fn push_text(string: &mut DisplayString, text: &str, formatting: Option<Formatting>) {
+    let start = string.value.len();
+    string.value.push_str(text);
+    if let Some(formatting) = formatting {
+        if !text.is_empty() {
+            string
+                .formatting
+                .push((start..string.value.len(), formatting));
+        }
+    }
+}
@@ -1,17 +1,15 @@
-use std::borrow::Cow;
 use std::collections::HashSet;
+use std::fmt::Write;
 use std::io::{self, Read};
+use std::ops::Range;
 use std::{env, fs};
 
-use hayagriva::style::{
-    Apa, BibliographyStyle, ChicagoAuthorDate, Citation, CitationStyle, Database, DisplayReference,
-    DisplayString, Formatting, Ieee, Mla, Numerical,
-};
-use hayagriva::types::EntryType::{Article, Web};
 use hayagriva::Entry;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
+mod csl;
+
 fn main() {
     let args: Vec<String> = env::args().skip(1).collect();
     let action = &args[0];
@@ -65,7 +63,7 @@ macro_rules! text {
 macro_rules! text_or_reparse {
     ($e:expr, $reparse:expr) => {
         if $reparse {
-            module!("inline_content", $e.replace("[Online]", r"\[Online]"))
+            module!("inline_content", $e.replace('[', r"\["))
         } else {
             module!("__text", $e)
         }
@@ -114,13 +112,16 @@ fn manifest() {
                     from either the body of this module or a file passed in as the \"file\" argument.",
                     "arguments": [
                         {"name": "style", "default": "IEEE", "type": ["IEEE", "APA", "MLA", "Chicago"], "description": "The style to have the bibliography in"},
-                        {"name": "file", "default": "", "description": "A file containing BibLaTeX or Hayagriva YAML with the bibliography"},
+                        {"name": "file", "default": "", "description": "A file containing BibLaTeX or Hayagriva YAML with the bibliography. Several files may be given as a comma-separated list, and their entries are merged"},
+                        {"name": "csl", "default": "", "description": "A CSL 1.0 style file to use instead of the style argument. The style must be an independent style"},
+                        {"name": "locale", "default": "", "description": "The locale of the terms in citations and the bibliography, such as en-US or de-DE. The default locale of the style is used if this is empty"},
+                        {"name": "latex-output", "default": "rendered", "type": ["rendered", "biblatex"], "description": "For LaTeX, whether the bibliography is rendered by this module or left to BibLaTeX with \\addbibresource and \\cite. BibLaTeX can only be used with BibLaTeX databases"},
                         {"name": "visibility", "default": "visible", "type": ["visible", "hidden"], "description": "Whether the bibliography is 'visible' or 'hidden'. \
                         Note that a [bibliography] must exist for [cite]s to work, and if you don't want a bibliography in your document, you can set this argument to 'hidden'."},
                         {"name": "unused-entries", "default": "hidden", "type": ["visible", "hidden"], "description": "Whether unused entries in the database should be hidden or visible"},
                         {"name": "insertion-type", "default": "reparse", "type": ["reparse", "plain"], "description": "Whether to reparse the content in the bibliography using inline_content"},
                         {"name": "output", "default": "plain", "type": ["plain", "table"], "description": "Whether to output the result in plain text or in a [table]. Note that for a [table] to work, there must exist a [table] module with support for custom delimiters for the target language"},
-                        {"name": "specialization", "default": "enable", "type": ["enable", "disable"], "description": "Enabling specialization will render the result more nicely in HTML and LaTeX"},
+                        {"name": "specialization", "default": "enable", "type": ["enable", "disable"], "description": "Enabling specialization will render the result more nicely in HTML, LaTeX and Typst. Pandoc JSON output is always specialized, unless the output is a table"},
                         {"name": "styling", "default": "all", "description": "What styling options are available for the target language, as a comma-separated list, available: \
                         'bold', 'italic', 'url' (via the [link] module), 'target' (via [target]/[link]) to enable references within the document"}
                     ],
@@ -202,27 +203,8 @@ fn transform_cite_label(_to: &str, input: &Value) {
     }
 }
 
-macro_rules! clone_field {
-    ($name:expr, $from:expr, $to:expr) => {
-        if let Some(result) = $from.get($name) {
-            $to.set($name, result.clone())
-                .expect(concat!("Expected to be able to set the field ", $name));
-        }
-    };
-}
-
-macro_rules! clone_fields {
-    ($from:expr => $to:expr,) => {};
-    ($from:expr => $to:expr, $f1:expr, $($fs:expr,)*) => {{
-        clone_field!($f1, $from, $to);
-        clone_fields!($from => $to, $($fs,)*);
-    }}
-}
-
 /// Configuration of how styling should be applied
-struct StylingConfig<'a> {
-    bib_style: Box<dyn BibliographyStyle<'a>>,
-    cit_style: Box<dyn CitationStyle<'a>>,
+struct StylingConfig {
     bold: bool,
     italic: bool,
     url: bool,
@@ -230,17 +212,14 @@ struct StylingConfig<'a> {
     reparse: bool,
 }
 
-impl StylingConfig<'_> {
-    /// This parses a `StylingConfig` from the `style`, `styling` and `reparse` arguments.
-    fn from(style: &str, styling: &str, reparse: &str) -> Option<Self> {
-        let (bs, cs) = get_styles(style)?;
+impl StylingConfig {
+    /// This parses a `StylingConfig` from the `styling` and `reparse` arguments.
+    fn from(styling: &str, reparse: &str) -> Self {
         let reparse = &reparse.to_ascii_lowercase() == "reparse";
 
         let s = styling.to_ascii_lowercase();
-        let res = if s.trim() == "all" {
+        if s.trim() == "all" {
             Self {
-                bib_style: bs,
-                cit_style: cs,
                 bold: true,
                 italic: true,
                 url: true,
@@ -249,46 +228,57 @@ impl StylingConfig<'_> {
             }
         } else {
             Self {
-                bib_style: bs,
-                cit_style: cs,
                 bold: s.contains("bold"),
                 italic: s.contains("italic"),
                 url: s.contains("url"),
                 target: s.contains("target"),
                 reparse,
             }
-        };
-        Some(res)
+        }
     }
 }
 
+/// Text as it is rendered by a style, where some ranges of the text are bold, italic or links
+#[derive(Default)]
+struct DisplayString {
+    value: String,
+    formatting: Vec<(Range<usize>, Formatting)>,
+}
+
+impl DisplayString {
+    fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+}
+
+enum Formatting {
+    Bold,
+    Italic,
+    Link(String),
+}
+
+/// An entry of the bibliography as it is displayed, with its prefix (like [3]) if it has one
+struct BibItem {
+    key: String,
+    prefix: Option<DisplayString>,
+    display: DisplayString,
+}
+
+/// The citations and bibliography rendered by one of the styles. There is one citation for each
+/// [cite] in the document, which is None if its key is missing from the database
+struct Rendered {
+    citations: Vec<Option<(String, DisplayString)>>,
+    items: Vec<BibItem>,
+}
+
 fn transform_bibliography(to: &str, input: &Value) {
-    // Read the bibliography, and if it fails, read_bibliography() has already printed an error
-    let Some(mut bibliography) = read_bibliography(input) else { return; };
-
-    // Replace 'web' with 'article'
-    bibliography.iter_mut().for_each(|x| {
-        if x.kind() == Web {
-            let mut new = Entry::new(x.key(), Article);
-            clone_fields!(x => new,
-                "parent",
-                "title",
-                "location", "publisher" , "archive" , "archive-location",
-                "author", "editor",
-                "date",
-                "affiliated",
-                "organization" , "issn" , "isbn" , "doi" , "serial-number" , "note" ,
-                "issue" , "edition",
-                "volume" , "page-range",
-                "volume-total" , "page-total" ,
-                "time-range" ,
-                "runtime" ,
-                "url",
-                "language",
-            );
-            *x = new;
-        }
-    });
+    // Read the databases, and if it fails, read_sources() has already printed an error
+    let Some(sources) = read_sources(input) else {
+        return;
+    };
+    let Some(bibliography) = parse_bibliography(&sources) else {
+        return;
+    };
 
     // This is the citations (i.e [cite]s) used in the text
     let citations: Vec<String> = {
@@ -296,19 +286,15 @@ fn transform_bibliography(to: &str, input: &Value) {
         serde_json::from_str(&var).unwrap()
     };
 
-    // This is our database of all entries
-    let mut database = Database::from_entries(bibliography.iter());
-
     // Check what styling options are available for us
     // Style has bib/cite style (IEEE, APA etc)
     // Styling has the features available, like bold, italics etc
     // OK to unwrap since MMCore checks enum
-    let mut styling = StylingConfig::from(
-        input["arguments"]["style"].as_str().unwrap(),
+    let style = input["arguments"]["style"].as_str().unwrap();
+    let styling = StylingConfig::from(
         input["arguments"]["styling"].as_str().unwrap(),
         input["arguments"]["insertion-type"].as_str().unwrap(),
-    )
-    .unwrap();
+    );
 
     // This is true if we fall back to table
     let fallback_table = input["arguments"]["output"].as_str().unwrap() == "table";
@@ -319,55 +305,64 @@ fn transform_bibliography(to: &str, input: &Value) {
     let unused_keys_is_visible =
         input["arguments"]["unused-entries"].as_str().unwrap() == "visible";
 
-    let mut output: Vec<Value> = vec![];
-    let mut used_keys: HashSet<&str> = HashSet::new();
+    let is_visible = input["arguments"]["visibility"].as_str().unwrap() == "visible";
 
-    for citation_str in &citations {
-        // We parse the citation (which is a JSON obj)
-        let citation: InlineCitation = serde_json::from_str(citation_str).unwrap();
+    // LaTeX may leave the citations and the bibliography to BibLaTeX, which needs the databases
+    // in BibLaTeX format
+    if to == "latex" && input["arguments"]["latex-output"].as_str() == Some("biblatex") {
+        if let Some(output) = generate_biblatex(
+            &sources,
+            &bibliography,
+            &citations,
+            style,
+            is_visible,
+            unused_keys_is_visible,
+        ) {
+            println!("{}", Value::Array(output));
+            return;
+        }
+    }
 
-        // We find the record by the key
-        let entry = if let Some(record) = database.records.get(citation.key) {
-            if !unused_keys_is_visible {
-                // If unused keys isn't visible, we need to keep track of the used keys
-                // This if is used to not having to store all used keys if we aren't gonna use
-                // them later
-                used_keys.insert(citation.key);
-            }
-            record.entry
-        } else {
-            eprintln!(
-                "Missing citation key '{0}', consider adding it to your bibliography",
-                citation.key
-            );
+    // The citations and the bibliography are rendered by the CSL processor of hayagriva, with
+    // either the given CSL style or the CSL version of the chosen style
+    let csl = input["arguments"]["csl"].as_str().unwrap_or("").trim();
+    let locale = input["arguments"]["locale"].as_str().unwrap_or("").trim();
+    let Some(rendered) = csl::render(
+        &bibliography,
+        &citations,
+        style,
+        csl,
+        locale,
+        unused_keys_is_visible,
+    ) else {
+        return;
+    };
+
+    let mut output: Vec<Value> = vec![];
+
+    for (citation_str, citation) in citations.iter().zip(rendered.citations) {
+        let Some((key, display)) = citation else {
+            let key = serde_json::from_str::<InlineCitation>(citation_str)
+                .map(|citation| citation.key.to_string())
+                .unwrap_or_default();
+            eprintln!("Missing citation key '{key}', consider adding it to your bibliography");
             output.push(add_citation_label!(
                 citation_str,
-                format!(
-                    "[{}]",
-                    text!(format!("[Missing citation '{}']", citation.key))
-                )
+                format!("[{}]", text!(format!("[Missing citation '{key}']")))
             ));
             continue;
         };
 
-        // We construct a new citation (representing [cite] in Hayagravia)
-        let hayagriva_citation: Citation = Citation::new(entry, citation.note);
-
-        // We cite that entry from our db
-        let database_citation =
-            database.citation(styling.cit_style.as_mut(), &[hayagriva_citation]);
-
         // json_citation will be the thing that [cite-internal-do-not-use] will be turned into.
         // We either to it as a [link label=...] (if we support link/target) or
         // as just formatted text
         let json_citation = if styling.target {
-            let citation_content =
-                display_inline_content(&database_citation.display, &styling, true);
-            vec![module!("link", format!("bibentry:{}", entry.key()), {
+            let citation_content = display_inline_content(&display, &styling);
+            vec![module!("link", format!("bibentry:{key}"), {
                 "label": &citation_content
             })]
         } else {
-            display_to_ast(&database_citation.display, &styling, true)
+            display_to_ast(&display, &styling)
         };
 
         // Then, we are adding that citation as the label (that the [cite], now [cite-internal],
@@ -380,28 +375,24 @@ fn transform_bibliography(to: &str, input: &Value) {
         ));
     }
 
-    let is_visible = input["arguments"]["visibility"].as_str().unwrap() == "visible";
-
     // If the bibliography should be shown, show it!
     if is_visible {
-        // We filter out keys, if we should, and get the prefix and content
-        let entries: Vec<(Option<DisplayString>, DisplayReference)> = database
-            .bibliography(styling.bib_style.as_ref(), None)
-            .into_iter()
-            // Remove any unused keys if they shouldn't be visible
-            .filter(|entry| unused_keys_is_visible || used_keys.contains(&entry.entry.key()))
-            // Extract the prefix and content of the reference
-            .map(|entry| (entry.prefix.clone(), entry))
-            .collect();
+        let entries = rendered.items;
 
         // Now to generating the actual bib. We check what type of bib we want to generate,
-        // if we have specialization enabled and we target HTML or LaTeX, then generate
+        // if we have specialization enabled and we target HTML, LaTeX or Typst, then generate
         // specialized, otherwise check fallback_table arg to see the fallback
         if !entries.is_empty() {
             let mut bib_out = if specialization && to == "html" {
                 generate_specialized_html(&entries, &styling)
             } else if specialization && to == "latex" {
                 generate_specialized_latex(&entries, &styling)
+            } else if specialization && to == "typst" {
+                generate_specialized_typst(&entries, &styling)
+            } else if to == "pandoc-json" && (specialization || !fallback_table) {
+                // The plain output is inline content, which can't be placed among the blocks of
+                // the Pandoc AST, so it is always specialized unless a table is used
+                generate_specialized_pandoc(&entries, &styling)
             } else if fallback_table {
                 generate_table(&entries, &styling)
             } else {
@@ -414,60 +405,127 @@ fn transform_bibliography(to: &str, input: &Value) {
     println!("{}", Value::Array(output));
 }
 
-fn generate_plain(
-    entries: &[(Option<DisplayString>, DisplayReference)],
-    styling: &StylingConfig,
-) -> Vec<Value> {
+/// Generates the output for BibLaTeX, which makes the citations and the bibliography itself. The
+/// databases are added as resources, and a database in the body of the module is written to a file
+/// first. None is returned if some database isn't in the BibLaTeX format, since BibLaTeX can't read
+/// it, and the bibliography is rendered instead
+fn generate_biblatex(
+    sources: &[(String, String)],
+    bibliography: &[Entry],
+    citations: &[String],
+    style: &str,
+    is_visible: bool,
+    unused_keys_is_visible: bool,
+) -> Option<Vec<Value>> {
+    let is_biblatex = |text: &str| {
+        hayagriva::io::from_biblatex_str(&without_latex_comments(text))
+            .is_ok_and(|entries| !entries.is_empty() || text.trim().is_empty())
+    };
+    if !sources.iter().all(|(_, text)| is_biblatex(text)) {
+        eprintln!("BibLaTeX can only read databases in the BibLaTeX format, so the bibliography is rendered instead");
+        return None;
+    }
+
+    let biblatex_style = match style {
+        "IEEE" => "ieee",
+        "APA" => "apa",
+        "MLA" => "mla",
+        _ => "authoryear",
+    };
+    // The imports are a set without any order, so everything that must come after the package
+    // is in the same import
+    let mut preamble = format!("\\usepackage[backend=biber,style={biblatex_style}]{{biblatex}}");
+    for (name, text) in sources {
+        if name.is_empty() {
+            write!(
+                preamble,
+                "\n\\begin{{filecontents}}[overwrite]{{modmark-bibliography.bib}}\n{}\n\\end{{filecontents}}\n\\addbibresource{{modmark-bibliography.bib}}",
+                text.trim_end()
+            )
+            .unwrap();
+        } else {
+            write!(preamble, "\n\\addbibresource{{{name}}}").unwrap();
+        }
+    }
+    let mut output = vec![import!(preamble)];
+
+    for citation_str in citations {
+        let citation: InlineCitation = serde_json::from_str(citation_str).unwrap();
+        if !bibliography.iter().any(|entry| entry.key() == citation.key) {
+            eprintln!(
+                "Missing citation key '{0}', consider adding it to your bibliography",
+                citation.key
+            );
+        }
+        let cite = match citation.note {
+            Some(note) => format!("\\cite[{note}]{{{}}}", citation.key),
+            None => format!("\\cite{{{}}}", citation.key),
+        };
+        output.push(add_citation_label!(
+            citation_str,
+            serde_json::to_string(&[raw!(cite)]).unwrap()
+        ));
+    }
+
+    if is_visible {
+        if unused_keys_is_visible {
+            output.push(raw!("\n\\nocite{*}"));
+        }
+        output.push(raw!("\n\\printbibliography\n"));
+    }
+    Some(output)
+}
+
+fn generate_plain(entries: &[BibItem], styling: &StylingConfig) -> Vec<Value> {
     let mut rows: Vec<Value> = vec![];
-    for (prefix, entry) in entries {
+    for entry in entries {
+        let prefix = &entry.prefix;
         if let Some(x) = prefix {
-            rows.append(&mut display_to_ast(x, styling, true));
+            rows.append(&mut display_to_ast(x, styling));
             rows.push(text!(" "));
         }
         if styling.target {
             rows.push(module!(
                 "target",
-                display_inline_content(&entry.display, styling, false),
-                { "name": format!("bibentry:{}", entry.entry.key()) }
+                display_inline_content(&entry.display, styling),
+                { "name": format!("bibentry:{}", entry.key) }
             ));
         } else {
-            rows.append(&mut display_to_ast(&entry.display, styling, false));
+            rows.append(&mut display_to_ast(&entry.display, styling));
         }
         rows.push(module!("newline", ""));
     }
     rows
 }
 
-fn generate_table(
-    entries: &[(Option<DisplayString>, DisplayReference)],
-    styling: &StylingConfig,
-) -> Vec<Value> {
-    let has_prefix = entries.iter().any(|(x, _)| x.is_some());
+fn generate_table(entries: &[BibItem], styling: &StylingConfig) -> Vec<Value> {
+    let has_prefix = entries.iter().any(|entry| entry.prefix.is_some());
     let delimiter = "!!!!TaBlE_dElImItEr!!!!";
     let mut rows: Vec<String> = vec![];
     if has_prefix {
-        for (prefix, entry) in entries {
+        for entry in entries {
+            let prefix = &entry.prefix;
             let content = if styling.target {
                 format!(
                     "[target name=\"bibentry:{}\"]!{}!",
-                    entry.entry.key(),
-                    display_inline_content(&entry.display, styling, false)
+                    entry.key,
+                    display_inline_content(&entry.display, styling)
                 )
             } else {
-                display_inline_content(&entry.display, styling, false)
+                display_inline_content(&entry.display, styling)
             };
             if let Some(prefix) = prefix.as_ref() {
                 rows.push(format!(
                     r"{}{delimiter}{content}",
-                    display_inline_content(prefix, styling, true)
+                    display_inline_content(prefix, styling)
                 ));
             } else {
                 rows.push(format!(r"{delimiter}{content}"));
             }
         }
     } else {
-        for (_, entry) in entries {
-            rows.push(display_inline_content(&entry.display, styling, false))
+        for entry in entries {
+            rows.push(display_inline_content(&entry.display, styling))
         }
     }
 
@@ -477,27 +535,25 @@ fn generate_table(
     })]
 }
 
-fn generate_specialized_html(
-    entries: &[(Option<DisplayString>, DisplayReference)],
-    styling: &StylingConfig,
-) -> Vec<Value> {
+fn generate_specialized_html(entries: &[BibItem], styling: &StylingConfig) -> Vec<Value> {
     let mut using_prefix = false;
     let mut bibitems: Vec<Value> = entries
         .iter()
-        .flat_map(|(prefix, entry)| {
+        .flat_map(|entry| {
+            let prefix = &entry.prefix;
             let mut item = Vec::new();
             if let Some(prefix) = prefix.as_ref() {
                 using_prefix = true;
                 item.push(raw!(r#"<span class="modmark-bibliography-prefix">"#));
-                item.append(&mut display_to_ast(prefix, styling, true));
+                item.append(&mut display_to_ast(prefix, styling));
                 item.push(raw!("</span>"));
             }
 
             item.push(raw!(r#"<span class="modmark-bibliography-bibitem">"#));
             item.push(module!(
                 "target",
-                display_inline_content(&entry.display, styling, false),
-                { "name": format!("bibentry:{}", entry.entry.key()) }
+                display_inline_content(&entry.display, styling),
+                { "name": format!("bibentry:{}", entry.key) }
             ));
             item.push(raw!("</span>"));
             item.push(module!("newline", ""));
@@ -523,16 +579,14 @@ fn generate_specialized_html(
     output
 }
 
-fn generate_specialized_latex(
-    entries: &[(Option<DisplayString>, DisplayReference)],
-    styling: &StylingConfig,
-) -> Vec<Value> {
+fn generate_specialized_latex(entries: &[BibItem], styling: &StylingConfig) -> Vec<Value> {
     let default_ds = DisplayString::default();
 
     let mut longest_prefix = 0;
     let mut bibitems: Vec<Value> = entries
         .iter()
-        .flat_map(|(prefix, entry)| {
+        .flat_map(|entry| {
+            let prefix = &entry.prefix;
             longest_prefix = longest_prefix.max(prefix.as_ref().map_or(0, |x| x.value.len()));
             let mut item = vec![];
 
@@ -540,7 +594,6 @@ fn generate_specialized_latex(
             item.append(&mut display_to_ast(
                 prefix.as_ref().unwrap_or(&default_ds),
                 styling,
-                true,
             ));
             item.push(raw!("}]\n"));
 
@@ -548,11 +601,11 @@ fn generate_specialized_latex(
                 item.push(import!("\\usepackage[hidelinks]{hyperref}"));
                 item.push(module!(
                     "target",
-                    display_inline_content(&entry.display, styling, false),
-                    { "name": format!("bibentry:{}", entry.entry.key()) }
+                    display_inline_content(&entry.display, styling),
+                    { "name": format!("bibentry:{}", entry.key) }
                 ));
             } else {
-                item.append(&mut display_to_ast(&entry.display, styling, false));
+                item.append(&mut display_to_ast(&entry.display, styling));
             }
             item.push(raw!("\n"));
 
@@ -574,91 +627,172 @@ fn generate_specialized_latex(
     output
 }
 
-/// Gets the styles for the given key, as a pair of the bibliography style and citation style. If
-/// the key has no style mappings, None is returned. For the keys IEEE, APA, MLA and Chicago, Some is
-/// always returned, and for any other key, None is returned.
-fn get_styles<'a>(
-    key: &str,
-) -> Option<(Box<dyn BibliographyStyle<'a>>, Box<dyn CitationStyle<'a>>)> {
-    let bibliography_style: Box<dyn BibliographyStyle> = match key {
-        "IEEE" => Box::new({
-            let mut x = Ieee::new();
-            x.abbreviate_journals = false;
-            x
-        }),
-        "APA" => Box::new(Apa::new()),
-        "MLA" => Box::new(Mla::new()),
-        "Chicago" => Box::new(ChicagoAuthorDate::new()),
-        _ => return None,
-    };
+fn generate_specialized_typst(entries: &[BibItem], styling: &StylingConfig) -> Vec<Value> {
+    let using_prefix = entries.iter().any(|entry| entry.prefix.is_some());
+
+    let mut bibitems: Vec<Value> = entries
+        .iter()
+        .flat_map(|entry| {
+            let prefix = &entry.prefix;
+            let mut item = vec![raw!("  ")];
+
+            // Each entry is a row in the grid, with the prefix (like [3]) in its own column
+            if using_prefix {
+                item.push(raw!("["));
+                if let Some(prefix) = prefix.as_ref() {
+                    item.append(&mut display_to_ast(prefix, styling));
+                }
+                item.push(raw!("], "));
+            }
+
+            item.push(raw!("["));
+            if styling.target {
+                item.push(module!(
+                    "target",
+                    display_inline_content(&entry.display, styling),
+                    { "name": format!("bibentry:{}", entry.key) }
+                ));
+            } else {
+                item.append(&mut display_to_ast(&entry.display, styling));
+            }
+            item.push(raw!("],\n"));
+
+            item
+        })
+        .collect();
 
-    let citation_style: Box<dyn CitationStyle> = match key {
-        "IEEE" => Box::new(Numerical::new()),
-        "APA" => Box::new(ChicagoAuthorDate::new()),
-        "MLA" => Box::new(ChicagoAuthorDate::new()),
-        "Chicago" => Box::new(ChicagoAuthorDate::new()),
-        _ => return None,
+    let columns = if using_prefix {
+        "(auto, 1fr)"
+    } else {
+        "(1fr,)"
     };
+    let mut output = vec![raw!(format!(
+        "\n#grid(\n  columns: {columns},\n  column-gutter: 1em,\n  row-gutter: 0.65em,\n"
+    ))];
+    output.append(&mut bibitems);
+    output.push(raw!(")\n"));
+    output
+}
+
+fn generate_specialized_pandoc(entries: &[BibItem], styling: &StylingConfig) -> Vec<Value> {
+    // Each entry is a paragraph in a div, like the bibliographies that Pandoc makes itself. Every
+    // element is followed by a comma, which the pandoc package removes at the end of each list
+    let mut output = vec![raw!(r#"{"t":"Div","c":[["refs",["references"],[]],["#)];
+
+    for entry in entries {
+        let prefix = &entry.prefix;
+        output.push(raw!(r#"{"t":"Para","c":["#));
+        if let Some(prefix) = prefix.as_ref() {
+            output.append(&mut display_to_ast(prefix, styling));
+            output.push(raw!(r#"{"t":"Space"},"#));
+        }
+        if styling.target {
+            output.push(module!(
+                "target",
+                display_inline_content(&entry.display, styling),
+                { "name": format!("bibentry:{}", entry.key) }
+            ));
+        } else {
+            output.append(&mut display_to_ast(&entry.display, styling));
+        }
+        output.push(raw!("]},"));
+    }
 
-    Some((bibliography_style, citation_style))
+    output.push(raw!("]]},"));
+    output
 }
 
-/// This function reads the bibliography by first:
-/// * Checking the 'file' argument, and if it is non-empty, reads that file
-/// * Otherwise, checking the body of the module
+/// This function reads the databases of the bibliography by:
+/// * Checking the 'file' argument, and if it is non-empty, reading each of the comma-separated
+///   files in it
+/// * Otherwise, using the body of the module
 ///
-/// Then, it parses the bibliography by first:
-/// * Trying to parse it as a BibLaTeX file
+/// The databases are returned as pairs of the file name, which is empty for the body, and the
+/// contents. If some file can't be read, an error is printed to stderr and None is returned.
+fn read_sources(input: &Value) -> Option<Vec<(String, String)>> {
+    let files = input["arguments"]["file"].as_str().unwrap();
+    if files.trim().is_empty() {
+        return Some(vec![(
+            String::new(),
+            input["data"].as_str().unwrap().to_string(),
+        )]);
+    }
+
+    if !input["data"].as_str().unwrap().is_empty() {
+        eprintln!("Bibliography contains body text but it is ignored since the 'file' argument is present");
+    }
+
+    let mut sources = vec![];
+    for filename in files.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        let Ok(content) = fs::read_to_string(filename) else {
+            eprintln!("Could not read file {filename}");
+            return None;
+        };
+        sources.push((filename.to_string(), content));
+    }
+    Some(sources)
+}
+
+/// This function parses the databases of the bibliography by first:
+/// * Trying to parse each of them as a BibLaTeX file
 /// * If that doesn't work, try to parse it as a Hayagriva Yaml file
 ///
-/// If everything succeeds, the list of entries is returned, otherwise errors are
+/// If everything succeeds, the entries of all databases are returned, otherwise errors are
 /// printed to stderr and None is returned. Parsing errors will print both BibLaTeX
-/// and Yaml errors.
-fn read_bibliography(input: &Value) -> Option<Vec<Entry>> {
-    let filename = input["arguments"]["file"].as_str().unwrap();
-    let input_string: Cow<'_, str> = if filename.is_empty() {
-        Cow::Borrowed(input["data"].as_str().unwrap())
-    } else if let Ok(content) = fs::read_to_string(filename) {
-        if !input["data"].as_str().unwrap().is_empty() {
-            eprintln!("Bibliography contains body text but it is ignored since the 'file' argument is present");
-        }
-        Cow::Owned(content)
-    } else {
-        eprintln!("Could not read file {filename}");
-        return None;
-    };
+/// and Yaml errors. Entries with a key that an earlier entry already has are left out, with a
+/// warning.
+fn parse_bibliography(sources: &[(String, String)]) -> Option<Vec<Entry>> {
+    let mut entries: Vec<Entry> = vec![];
+    let mut keys: HashSet<String> = HashSet::new();
+
+    for (filename, text) in sources {
+        // Text that isn't BibLaTeX, such as Yaml, is read as BibLaTeX without any entries
+        let bibliography = match hayagriva::io::from_biblatex_str(&without_latex_comments(text)) {
+            Ok(b) if !b.is_empty() || text.trim().is_empty() => Ok(b),
+            biblatex => hayagriva::io::from_yaml_str(text)
+                .map_err(|e2| (biblatex.err().unwrap_or_default(), e2)),
+        };
 
-    let bibliography = hayagriva::io::from_biblatex_str(&without_latex_comments(&input_string))
-        .or_else(|e1| hayagriva::io::from_yaml_str(&input_string).map_err(|e2| (e1, e2)));
+        let bibliography = match bibliography {
+            Ok(b) => b,
+            Err((e1, e2)) => {
+                let source = if filename.is_empty() {
+                    String::from("bibliography")
+                } else {
+                    format!("bibliography in {filename}")
+                };
+                eprintln!(
+                    "Could not parse {source} as BibLaTeX: {}",
+                    e1.into_iter()
+                        .map(|e| e.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                eprintln!("Could not parse {source} as Yaml: {e2}");
+                return None;
+            }
+        };
 
-    match bibliography {
-        Ok(b) => Some(b),
-        Err((e1, e2)) => {
-            eprintln!(
-                "Could not parse bibliography as BibLaTeX: {}",
-                e1.into_iter()
-                    .map(|e| e.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            );
-            eprintln!("Could not parse bibliography as Yaml: {e2}");
-            None
+        for entry in bibliography {
+            if keys.insert(entry.key().to_string()) {
+                entries.push(entry);
+            } else {
+                eprintln!(
+                    "Duplicate key '{}' in the bibliography, only the first entry with this key is used",
+                    entry.key()
+                );
+            }
         }
     }
+    Some(entries)
 }
 
 /// This function displays a `DisplayString` by converting its formatting rules/spans
 /// to `ModMark` source representations that can be picked up by `[inline_content]` again. It
 /// returns the text, wrapped in ** and // for those rules, and uses `[link]` for URLs.
-/// Note that the uses of these are conditional, based on the `StylingConfig` passed
-/// If citation_brackets is set, and the string is non-empty, styling.cit_style.brackets() are
-/// added (if they exist). Special care is taken to intersperse link labels with `\` so nothing is
-/// re-parsed there.
-fn display_inline_content(
-    string: &DisplayString,
-    styling: &StylingConfig,
-    citation_brackets: bool,
-) -> String {
+/// Note that the uses of these are conditional, based on the `StylingConfig` passed.
+/// Special care is taken to intersperse link labels with `\` so nothing is re-parsed there.
+fn display_inline_content(string: &DisplayString, styling: &StylingConfig) -> String {
     if string.is_empty() {
         return "".to_string();
     }
@@ -667,9 +801,10 @@ fn display_inline_content(
     // intersperse with \, since foo = \f\o\o in mdm
     let escape_if_needed = |s: &str| {
         // If we are to reparse the text, we don't really want to escape stuff, but we manually
-        // escape [Online] since that is a common thing that we actually don't want reparsed
+        // escape brackets, since styles use them for things like [1] and [Online] that we
+        // actually don't want reparsed as modules
         if styling.reparse {
-            s.replace("[Online]", r"\[Online]")
+            s.replace('[', r"\[")
         } else {
             escape_by_interspersion(s)
         }
@@ -677,12 +812,6 @@ fn display_inline_content(
 
     let mut accumulated = String::new();
 
-    if citation_brackets {
-        if let Some(lb) = styling.cit_style.brackets().left() {
-            accumulated.push_str(&format!(r"\{lb}"));
-        }
-    }
-
     let mut last_unformatted = 0usize;
     for (range, rule) in &string.formatting {
         assert!(range.start >= last_unformatted);
@@ -706,12 +835,6 @@ fn display_inline_content(
     }
     accumulated.push_str(&escape_if_needed(&string.value[last_unformatted..]));
 
-    if citation_brackets {
-        if let Some(rb) = styling.cit_style.brackets().right() {
-            accumulated.push_str(&format!(r"\{rb}"));
-        }
-    }
-
     accumulated
 }
 
@@ -720,26 +843,14 @@ fn display_inline_content(
 /// __text for all text, wrapped in __bold and __italic for those rules, and uses
 /// the `[link]` package for URLs
 /// Note that the uses of these are conditional, based on the `StylingConfig` passed.
-/// If citation_brackets is set, and the string is non-empty, styling.cit_style.brackets() are
-/// added (if they exist). Special care is taken to intersperse link labels with `\` so nothing is
-/// re-parsed there.
-fn display_to_ast(
-    string: &DisplayString,
-    styling: &StylingConfig,
-    citation_brackets: bool,
-) -> Vec<Value> {
+/// Special care is taken to intersperse link labels with `\` so nothing is re-parsed there.
+fn display_to_ast(string: &DisplayString, styling: &StylingConfig) -> Vec<Value> {
     if string.is_empty() {
         return vec![];
     }
 
     let mut values = vec![];
 
-    if citation_brackets {
-        if let Some(lb) = styling.cit_style.brackets().left() {
-            values.push(text!(lb));
-        }
-    }
-
     let mut last_unformatted = 0usize;
     for (range, rule) in &string.formatting {
         assert!(range.start >= last_unformatted);
@@ -772,12 +883,6 @@ fn display_to_ast(
         styling.reparse
     ));
 
-    if citation_brackets {
-        if let Some(rb) = styling.cit_style.brackets().right() {
-            values.push(text!(rb));
-        }
-    }
-
     values
 }
 
@@ -1,5 +1,6 @@
 use std::env;
 use std::fmt::Write;
+use std::fs;
 use std::io::{self, Read};
 
 use serde_json::{json, Value};
@@ -12,6 +13,17 @@ const VERBATIM_OVERRIDE_LATEX: &str = r"\makeatletter
 \def\verbatim@nolig@list{\do\`\do\<\do\>\do\'\do\-}
 \makeatother";
 
+const LISTING_FLOAT_LATEX: &str = r"\newfloat{listing}{H}{lol}
+\floatname{listing}{Listing}";
+
+macro_rules! import {
+    ($e:expr) => {json!({"name": "set-add", "arguments": {"name": "imports"}, "data": $e})}
+}
+
+macro_rules! raw {
+    ($e:expr) => {json!({"name": "raw", "data": $e})}
+}
+
 fn main() {
     let args: Vec<String> = env::args().skip(1).collect();
     let action = &args[0];
@@ -33,8 +45,35 @@ fn manifest() {
         "transforms": [
             {
                 "from": "code",
-                "to": ["html", "latex"],
+                "to": ["html", "latex", "markdown", "plaintext", "docx", "typst", "pandoc-json"],
+                "arguments": [
+                    {"name": "lang", "default": "txt", "description":
+                        "The language to be highlighted. For available languages, see \
+                        https://github.com/sublimehq/Packages"},
+                    {"name": "font_size", "default": 12, "description": "The size of the font", "type": "uint"},
+                    {"name": "tab_size", "default": 4, "description": "The size tabs will be adjusted to", "type": "uint"},
+                    {"name": "theme", "default": "mocha", "description":
+                        "Theme of the code section.", "type": ["ocean_dark", "ocean_light", "mocha", "eighties", "github", "solar_dark", "solar_light"]},
+                    {"name": "bg", "default": "default", "description": "Background of the code section"},
+                    {"name": "line_numbers", "default": "false", "type": ["true", "false"], "description": "Whether the lines are numbered"},
+                    {"name": "highlight", "default": "", "description": "The lines to highlight, such as 3-5,9"},
+                    {"name": "caption", "default": "", "description": "The caption of the code block"},
+                    {"name": "label", "default": "", "description": "The label to use for the code block, to be able to refer to it from the document"},
+                    {"name": "start_line", "default": 1, "description": "The number of the first line", "type": "uint"},
+                ],
+                "variables": {
+                    "imports": {"type": "set", "access": "add"},
+                    "structure": {"type": "list", "access": "push"},
+                    "caption_style": {"type": "const", "access": "read"}
+                }
+            },
+            {
+                "from": "code-file",
+                "to": ["html", "latex", "markdown", "plaintext", "docx", "typst", "pandoc-json"],
+                "description": "Shows code from a file, which is highlighted like a [code] module. The lines are numbered from the first line that is included",
                 "arguments": [
+                    {"name": "path", "description": "The path to the file"},
+                    {"name": "lines", "default": "", "description": "The lines of the file to include, such as 10-40. All lines are included by default"},
                     {"name": "lang", "default": "txt", "description":
                         "The language to be highlighted. For available languages, see \
                         https://github.com/sublimehq/Packages"},
@@ -43,8 +82,16 @@ fn manifest() {
                     {"name": "theme", "default": "mocha", "description":
                         "Theme of the code section.", "type": ["ocean_dark", "ocean_light", "mocha", "eighties", "github", "solar_dark", "solar_light"]},
                     {"name": "bg", "default": "default", "description": "Background of the code section"},
+                    {"name": "line_numbers", "default": "false", "type": ["true", "false"], "description": "Whether the lines are numbered"},
+                    {"name": "highlight", "default": "", "description": "The lines to highlight, such as 3-5,9"},
+                    {"name": "caption", "default": "", "description": "The caption of the code block"},
+                    {"name": "label", "default": "", "description": "The label to use for the code block, to be able to refer to it from the document"},
                 ],
-                "variables": {"imports": {"type": "set", "access": "add"}}
+                "variables": {
+                    "imports": {"type": "set", "access": "add"},
+                    "structure": {"type": "list", "access": "push"},
+                    "caption_style": {"type": "const", "access": "read"}
+                }
             }
         ]
         }
@@ -54,14 +101,105 @@ fn manifest() {
 
 fn transform(from: &str, to: &str) {
     match from {
-        "code" => transform_code(to),
+        "code" => transform_code(to, false),
+        "code-file" => transform_code(to, true),
         other => {
             eprintln!("Package does not support {other}");
         }
     }
 }
 
-fn transform_code(to: &str) {
+/// The numbering, highlighting and caption of the lines of a code block
+struct Listing<'a> {
+    line_numbers: bool,
+    start_line: u64,
+    highlight: Vec<(u64, u64)>,
+    caption: Option<&'a str>,
+    label: Option<&'a str>,
+    key: String,
+}
+
+impl Listing<'_> {
+    /// Get the number of the line at some index of the code
+    fn number(&self, idx: usize) -> u64 {
+        self.start_line + idx as u64
+    }
+
+    fn is_highlighted(&self, idx: usize) -> bool {
+        let number = self.number(idx);
+        self.highlight
+            .iter()
+            .any(|(first, last)| (*first..=*last).contains(&number))
+    }
+
+    /// Listings with a caption or a label are numbered in the structure of the document
+    fn is_numbered(&self) -> bool {
+        self.caption.is_some() || self.label.is_some()
+    }
+
+    /// Get the ranges of highlighted lines, written like the argument
+    fn highlight_ranges(&self) -> String {
+        self.highlight
+            .iter()
+            .map(|(first, last)| {
+                if first == last {
+                    first.to_string()
+                } else {
+                    format!("{first}-{last}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Get the JSON that pushes this listing to the structure, if it is numbered
+    fn structure(&self) -> Option<Value> {
+        self.is_numbered().then(|| {
+            json!({
+                "name": "list-push",
+                "arguments": {"name": "structure"},
+                "data": json!({"element": "listing", "key": self.key}).to_string(),
+            })
+        })
+    }
+
+    /// Get the caption as inline content, with the number first if captions are numbered
+    fn caption(&self) -> Vec<Value> {
+        let mut caption = vec![];
+        if env::var("caption_style").unwrap_or_default() == "numbered" {
+            caption.push(json!({
+                "name": "inline_content",
+                "data": format!("**Listing [element-number]({}):** ", self.key)
+            }));
+        }
+        if let Some(text) = self.caption {
+            caption.push(json!({"name": "inline_content", "data": text}));
+        }
+        caption
+    }
+}
+
+/// Parse line ranges such as "3-5,9"
+fn parse_line_ranges(ranges: &str) -> Vec<(u64, u64)> {
+    let mut result = vec![];
+    for range in ranges
+        .split(',')
+        .map(str::trim)
+        .filter(|range| !range.is_empty())
+    {
+        let parsed = match range.split_once('-') {
+            Some((first, last)) => first.trim().parse().ok().zip(last.trim().parse().ok()),
+            None => range.parse().ok().map(|line| (line, line)),
+        };
+        match parsed {
+            Some((first, last)) if first <= last => result.push((first, last)),
+            _ => eprintln!("Invalid line range '{range}'"),
+        }
+    }
+    result
+}
+
+fn transform_code(to: &str, from_file: bool) {
     macro_rules! get_arg {
         ($input:expr, $arg:expr) => {
             if let Value::String(val) = &$input["arguments"][$arg] {
@@ -77,13 +215,62 @@ fn transform_code(to: &str) {
         serde_json::from_str(&buffer).unwrap()
     };
 
-    let code = input["data"].as_str().unwrap();
     let lang = get_arg!(input, "lang");
     let font_size = input["arguments"]["font_size"].as_u64().unwrap();
     let tab_size = input["arguments"]["tab_size"].as_u64().unwrap();
     let tm = get_arg!(input, "theme");
     let bg = get_arg!(input, "bg");
 
+    let optional_arg = |arg: &str| {
+        input["arguments"][arg]
+            .as_str()
+            .filter(|value| !value.is_empty())
+    };
+    let caption = optional_arg("caption");
+    let label = optional_arg("label");
+    let mut listing = Listing {
+        line_numbers: optional_arg("line_numbers") == Some("true"),
+        start_line: input["arguments"]["start_line"].as_u64().unwrap_or(1),
+        highlight: parse_line_ranges(optional_arg("highlight").unwrap_or_default()),
+        caption,
+        label,
+        key: label.map_or_else(
+            || format!("listing:{}", input["id"].as_u64().unwrap_or(0)),
+            str::to_string,
+        ),
+    };
+
+    // Snippets of files are numbered from the first line that is included
+    let contents;
+    let code = if from_file {
+        let path = get_arg!(input, "path");
+        let Ok(file) = fs::read_to_string(path) else {
+            eprintln!("File could not be accessed at {path}");
+            return;
+        };
+        let lines: Vec<&str> = file.lines().collect();
+        let (first, last) = match optional_arg("lines") {
+            Some(range) => match parse_line_ranges(range).as_slice() {
+                [(first, last)] => (*first, *last),
+                _ => {
+                    eprintln!("Expected lines to be one range, such as 10-40");
+                    return;
+                }
+            },
+            None => (1, lines.len() as u64),
+        };
+        let first = first.max(1);
+        let last = last.min(lines.len() as u64);
+        listing.start_line = first;
+        contents = lines
+            .get(first as usize - 1..last as usize)
+            .unwrap_or_default()
+            .join("\n");
+        contents.as_str()
+    } else {
+        input["data"].as_str().unwrap()
+    };
+
     let ss = SyntaxSet::load_defaults_newlines();
     let ts = ThemeSet::load_defaults();
     let theme = match tm.as_str() {
@@ -94,7 +281,7 @@ fn transform_code(to: &str) {
         "github" => &ts.themes["InspiredGitHub"],
         "solar_dark" => &ts.themes["Solarized (dark)"],
         "solar_light" => &ts.themes["Solarized (light)"],
-        _ => unreachable!()
+        _ => unreachable!(),
     };
 
     let syntax = ss.find_syntax_by_token(lang).unwrap_or_else(|| {
@@ -102,49 +289,250 @@ fn transform_code(to: &str) {
         ss.find_syntax_by_token("txt").unwrap()
     });
 
+    let Value::Bool(inline) = input["inline"] else {
+        return;
+    };
+    if inline && (listing.is_numbered() || listing.line_numbers || !listing.highlight.is_empty()) {
+        eprintln!("Line numbers, highlighted lines, captions and labels need a code block");
+        listing.line_numbers = false;
+        listing.highlight.clear();
+        listing.caption = None;
+        listing.label = None;
+    }
+
+    let mut result: Vec<Value> = listing.structure().into_iter().collect();
+
     match to {
         "html" => {
-            let (highlighted, default_bg) = get_highlighted_html(code, theme, syntax, &ss);
+            let (lines, default_bg) = get_highlighted_html(code, theme, syntax, &ss);
 
-            if let Value::Bool(inline) = &input["inline"] {
-                let style = get_style_html(inline, font_size, tab_size, bg, default_bg);
-                let html = if *inline {
-                    format!(r#"<code {style}>{highlighted}</code>"#)
-                } else {
-                    format!(r#"<pre {style}>{highlighted}</pre>"#)
-                };
-                print!("[{}]", json!({"name": "raw", "data": html}));
+            let style = get_style_html(&inline, font_size, tab_size, bg, default_bg);
+            let html = if inline {
+                format!(r#"<code {style}>{}</code>"#, lines.join("<br>"))
+            } else {
+                format!(
+                    r#"<pre {style}>{}</pre>"#,
+                    html_lines(lines, &listing, theme)
+                )
+            };
+            if listing.is_numbered() {
+                result.push(raw!(format!(
+                    r#"<figure id="{}">"#,
+                    listing.key.replace('"', "%22")
+                )));
+                if listing.caption.is_some() {
+                    result.push(raw!("<figcaption>"));
+                    result.extend(listing.caption());
+                    result.push(raw!("</figcaption>"));
+                }
+                result.push(raw!(html));
+                result.push(raw!("</figure>"));
+            } else {
+                result.push(raw!(html));
             }
         }
         "latex" => {
-            if let Value::Bool(inline) = &input["inline"] {
-                if *inline {
-                    print!("{}", latex_inline(code));
+            if inline {
+                result.extend(highlight_latex_inline(code, theme, syntax, &ss));
+            } else {
+                if font_size != 12 {
+                    eprintln!("Font size is not supported in LaTeX");
+                }
+                let highlighted = highlight_latex(code, tab_size, &listing, theme, syntax, &ss);
+                // LaTeX numbers the listings by itself, so the caption is only the text
+                if listing.is_numbered() {
+                    result.push(import!(r"\usepackage{float}"));
+                    result.push(import!(LISTING_FLOAT_LATEX));
+                    result.push(json!("\\begin{listing}[H]\n\\caption{"));
+                    if let Some(caption) = listing.caption {
+                        result.push(json!({"name": "inline_content", "data": caption}));
+                    }
+                    result.push(json!("}\n"));
+                    if let Some(label) = listing.label {
+                        result.push(json!(format!("\\label{{{label}}}\n")));
+                    }
+                    result.extend(highlighted);
+                    result.push(json!("\n\\end{listing}"));
                 } else {
-                    if font_size != 12 {
-                        eprintln!("Font size is not supported in LaTeX");
+                    result.extend(highlighted);
+                }
+            }
+        }
+        "markdown" => {
+            if inline {
+                result.push(json!(markdown_code_span(code)));
+            } else {
+                if listing.line_numbers || !listing.highlight.is_empty() {
+                    eprintln!("Line numbers and highlighted lines are not supported in Markdown");
+                }
+                if listing.is_numbered() {
+                    result.push(json!("\n"));
+                    if let Some(label) = listing.label {
+                        result.push(json!(format!(r#"<a id="{label}"></a>"#)));
+                    }
+                    if listing.caption.is_some() {
+                        result.push(json!("_"));
+                        result.extend(listing.caption());
+                        result.push(json!("_"));
+                    }
+                    result.push(json!("\n"));
+                }
+                // Markdown renderers do their own highlighting, so only the language is kept
+                let info = if syntax.name == "Plain Text" {
+                    ""
+                } else {
+                    lang
+                };
+                let fence = "`".repeat(longest_backtick_run(code).max(2) + 1);
+                result.push(json!(format!("\n{fence}{info}\n{code}\n{fence}\n")));
+            }
+        }
+        "plaintext" => {
+            if inline {
+                result.push(json!(code));
+            } else {
+                if listing.caption.is_some() {
+                    result.push(json!("\n\u{E000}\u{E001}"));
+                    result.extend(listing.caption());
+                    result.push(json!("\u{E002}\n"));
+                }
+                // Highlighted lines are marked in the margin
+                let width = listing
+                    .number(code.lines().count().saturating_sub(1))
+                    .to_string()
+                    .len();
+                let indented = code
+                    .lines()
+                    .enumerate()
+                    .map(|(idx, line)| {
+                        let margin = if listing.is_highlighted(idx) {
+                            "  > "
+                        } else {
+                            "    "
+                        };
+                        if listing.line_numbers {
+                            format!("{margin}{:>width$}  {line}", listing.number(idx))
+                        } else {
+                            format!("{margin}{line}")
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                result.push(json!(format!("\n{indented}\n")));
+            }
+        }
+        "docx" => {
+            let code = code.replace('\t', &" ".repeat(tab_size as usize));
+            let runs = highlight_docx(&code, font_size, bg, &listing, theme, syntax, &ss);
+            if inline {
+                result.push(raw!(runs));
+            } else {
+                if listing.caption.is_some() {
+                    result.push(raw!(r#"<w:p><w:pPr><w:pStyle w:val="Caption"/></w:pPr>"#));
+                    result.extend(listing.caption());
+                    result.push(raw!("</w:p>\n"));
+                }
+                let xml = format!(
+                    "<w:p><w:pPr><w:pStyle w:val=\"SourceCode\"/>{}</w:pPr>{runs}</w:p>\n",
+                    docx_shading(bg, theme)
+                );
+                result.push(raw!(xml));
+            }
+        }
+        "typst" => {
+            // Typst highlights raw text by itself, so only the language is kept
+            let mut raw = String::from("raw(");
+            if syntax.name != "Plain Text" {
+                raw.push_str(&format!("lang: {}, ", typst_string(lang)));
+            }
+            if !inline {
+                raw.push_str("block: true, ");
+            }
+            if code.contains('\t') {
+                raw.push_str(&format!("tab-size: {tab_size}, "));
+            }
+            raw.push_str(&format!("{})", typst_string(code)));
+            if font_size != 12 {
+                raw = format!("text(size: {font_size}pt, {raw})");
+            }
+            if inline {
+                result.push(json!(format!("#{raw}")));
+            } else {
+                let show_rule = typst_show_rule(code, &listing, theme);
+                if !show_rule.is_empty() {
+                    result.push(json!(format!("\n#[\n{show_rule}")));
+                }
+                if listing.is_numbered() {
+                    result.push(json!(format!("\n#figure(\n  {raw},\n")));
+                    if listing.caption.is_some() {
+                        result.push(json!("  caption: ["));
+                        result.extend(listing.caption());
+                        result.push(json!("],\n"));
                     }
-                    print!("{}", highlight_latex(code, tab_size, theme, syntax, &ss));
+                    result.push(json!("  kind: raw,\n  numbering: none,\n)"));
+                    if let Some(label) = listing.label {
+                        result.push(json!(format!(" <{label}>")));
+                    }
+                    result.push(json!("\n"));
+                } else {
+                    result.push(json!(format!("\n#{raw}\n")));
+                }
+                if !show_rule.is_empty() {
+                    result.push(json!("]\n"));
+                }
+            }
+        }
+        "pandoc-json" => {
+            // Pandoc highlights code by itself, so only the language is kept as a class
+            let mut classes = if syntax.name == "Plain Text" {
+                vec![]
+            } else {
+                vec![lang.as_str()]
+            };
+            let mut attributes = vec![];
+            if listing.line_numbers {
+                classes.push("numberLines");
+                if listing.start_line != 1 {
+                    attributes.push(json!(["startFrom", listing.start_line.to_string()]));
+                }
+            }
+            if !listing.highlight.is_empty() {
+                eprintln!("Highlighted lines are not supported in Pandoc");
+            }
+            let constructor = if inline { "Code" } else { "CodeBlock" };
+            let code = json!({"t": constructor, "c": [["", classes, attributes], code]});
+            if listing.is_numbered() {
+                result.push(json!(format!(
+                    r#"{{"t":"Figure","c":[[{},[],[]],[null,["#,
+                    json!(listing.key)
+                )));
+                if listing.caption.is_some() {
+                    result.push(json!(r#"{"t":"Plain","c":["#));
+                    result.extend(listing.caption());
+                    result.push(json!("]},"));
                 }
+                result.push(json!(format!("]],[{code}]]}},")));
+            } else {
+                result.push(json!(format!("{code},")));
             }
         }
         other => {
             eprintln!("Cannot convert code to {other}");
+            return;
         }
     }
+
+    print!("{}", serde_json::to_string(&result).unwrap());
 }
 
 fn highlight_latex(
     code: &str,
     tab_size: u64,
+    listing: &Listing,
     theme: &Theme,
     syntax: &SyntaxReference,
     ss: &SyntaxSet,
-) -> String {
-    macro_rules! import {
-        ($e:expr) => {json!({"name": "set-add", "arguments": {"name": "imports"}, "data": $e})}
-    }
-
+) -> Vec<Value> {
     let mut h = HighlightLines::new(syntax, theme);
     let mut result: Vec<String> = vec![];
     let background_color = theme.settings.background.unwrap();
@@ -157,8 +545,27 @@ fn highlight_latex(
     result.push(format!(
         "\\definecolor{{background}}{{RGB}}{{{r},{g},{b}}}\n"
     ));
+
+    let mut options = vec![r"commandchars=\\\{\}".to_string()];
+    if listing.line_numbers {
+        options.push("numbers=left".to_string());
+        if listing.start_line != 1 {
+            options.push(format!("firstnumber={}", listing.start_line));
+        }
+    }
+    // Highlighted lines need fvextra, which extends fancyvrb
+    if !listing.highlight.is_empty() {
+        let c = line_highlight(theme);
+        result.push(format!(
+            "\\definecolor{{highlight}}{{RGB}}{{{},{},{}}}\n",
+            c.r, c.g, c.b
+        ));
+        options.push(format!("highlightlines={{{}}}", listing.highlight_ranges()));
+        options.push("highlightcolor=highlight".to_string());
+    }
+
     result.push("\\begin{tcolorbox}[colback=background, frame empty]\n".to_string());
-    result.push("\\begin{Verbatim}[commandchars=\\\\\\{\\}]\n".to_string());
+    result.push(format!("\\begin{{Verbatim}}[{}]\n", options.join(", ")));
 
     for line in code.lines() {
         let regions = h.highlight_line(line, ss).unwrap();
@@ -183,9 +590,39 @@ fn highlight_latex(
         .collect::<Vec<_>>();
 
     json.push(import!(r"\usepackage{fancyvrb}"));
+    if !listing.highlight.is_empty() {
+        json.push(import!(r"\usepackage{fvextra}"));
+    }
     json.push(import!(r"\usepackage{tcolorbox}"));
     json.push(import!(VERBATIM_OVERRIDE_LATEX));
-    serde_json::to_string(&json).unwrap()
+    json
+}
+
+// Inline code is highlighted the same way as code blocks, on the background of the theme
+fn highlight_latex_inline(
+    code: &str,
+    theme: &Theme,
+    syntax: &SyntaxReference,
+    ss: &SyntaxSet,
+) -> Vec<Value> {
+    let mut h = HighlightLines::new(syntax, theme);
+    let mut latex = match theme.settings.background {
+        Some(c) => format!("\\colorbox[RGB]{{{},{},{}}}{{\\texttt{{", c.r, c.g, c.b),
+        None => "{\\texttt{".to_string(),
+    };
+    for (style, text) in h.highlight_line(code, ss).unwrap() {
+        let c = style.foreground;
+        let escaped = escape_latex_text(text.to_string());
+        write!(
+            latex,
+            "\\textcolor[RGB]{{{},{},{}}}{{{escaped}}}",
+            c.r, c.g, c.b
+        )
+        .unwrap();
+    }
+    latex.push_str("}}");
+
+    vec![raw!(latex), import!(r"\usepackage{xcolor}")]
 }
 
 fn escape_latex_text(text: String) -> String {
@@ -206,22 +643,121 @@ fn escape_latex_text(text: String) -> String {
     s
 }
 
-fn latex_inline(text: &str) -> String {
-    let mut result = String::new();
-    result.push('[');
-    write!(result, r#"{{"name": "raw", "data": "\\verb|"}},"#,).unwrap();
-    write!(
-        result,
-        "{},",
-        serde_json::to_string(&text.replace('|', "\\|")).unwrap()
-    )
-    .unwrap();
-    write!(result, r#"{{"name": "raw", "data": "|"}}"#,).unwrap();
-    result.push(']');
+/// Get the longest run of backticks in some text
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
 
+/// Wrap text in a Markdown code span, using more backticks than the text contains in a row
+fn markdown_code_span(text: &str) -> String {
+    let ticks = "`".repeat(longest_backtick_run(text) + 1);
+    if text.starts_with('`') || text.ends_with('`') {
+        format!("{ticks} {text} {ticks}")
+    } else {
+        format!("{ticks}{text}{ticks}")
+    }
+}
+
+/// Write text as a Typst string literal
+fn typst_string(text: &str) -> String {
+    let mut result = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
     result
 }
 
+/// Get the shading of a code block in a Word document, which is the background of the theme unless
+/// another background is given
+fn docx_shading(bg: &str, theme: &Theme) -> String {
+    let hex = match (bg, theme.settings.background) {
+        ("default", Some(c)) => format!("{:02X}{:02X}{:02X}", c.r, c.g, c.b),
+        ("default", None) => "FFFFFF".to_string(),
+        (bg, _) => bg.trim_start_matches('#').to_ascii_uppercase(),
+    };
+    format!(r#"<w:shd w:val="clear" w:color="auto" w:fill="{hex}"/>"#)
+}
+
+/// Highlight code as runs of a Word document, where each line is separated by a line break. The
+/// runs are shaded as well, so that inline code gets the background of the theme
+fn highlight_docx(
+    code: &str,
+    font_size: u64,
+    bg: &str,
+    listing: &Listing,
+    theme: &Theme,
+    syntax: &SyntaxReference,
+    ss: &SyntaxSet,
+) -> String {
+    let mut h = HighlightLines::new(syntax, theme);
+    let c = line_highlight(theme);
+    let highlight_shading = format!(
+        r#"<w:shd w:val="clear" w:color="auto" w:fill="{:02X}{:02X}{:02X}"/>"#,
+        c.r, c.g, c.b
+    );
+    // The font size is given in pixels, and Word uses half-points
+    let size = font_size * 3 / 2;
+    let width = listing
+        .number(code.lines().count().saturating_sub(1))
+        .to_string()
+        .len();
+    let mut runs = String::new();
+
+    for (idx, line) in code.lines().enumerate() {
+        if idx != 0 {
+            runs.push_str("<w:r><w:br/></w:r>");
+        }
+        let shading = if listing.is_highlighted(idx) {
+            highlight_shading.clone()
+        } else {
+            docx_shading(bg, theme)
+        };
+        let mut line = line.to_string();
+        if listing.line_numbers {
+            let c = gutter_foreground(theme);
+            write!(
+                runs,
+                "<w:r><w:rPr><w:rStyle w:val=\"VerbatimChar\"/>\
+                <w:color w:val=\"{:02X}{:02X}{:02X}\"/><w:sz w:val=\"{size}\"/>{shading}</w:rPr>\
+                <w:t xml:space=\"preserve\">{:>width$}  </w:t></w:r>",
+                c.r,
+                c.g,
+                c.b,
+                listing.number(idx)
+            )
+            .unwrap();
+        }
+        // Empty lines get a space, so that their highlighting is shown
+        if line.is_empty() && listing.is_highlighted(idx) {
+            line.push(' ');
+        }
+        for (style, text) in h.highlight_line(&line, ss).unwrap() {
+            let c = style.foreground;
+            let text = text
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;");
+            write!(
+                runs,
+                "<w:r><w:rPr><w:rStyle w:val=\"VerbatimChar\"/>\
+                <w:color w:val=\"{:02X}{:02X}{:02X}\"/><w:sz w:val=\"{size}\"/>{shading}</w:rPr>\
+                <w:t xml:space=\"preserve\">{text}</w:t></w:r>",
+                c.r, c.g, c.b
+            )
+            .unwrap();
+        }
+    }
+    runs
+}
+
 fn get_style_html(
     inline: &bool,
     font_size: u64,
@@ -253,7 +789,7 @@ fn get_highlighted_html(
     theme: &Theme,
     syntax: &SyntaxReference,
     ss: &SyntaxSet,
-) -> (String, Option<Color>) {
+) -> (Vec<String>, Option<Color>) {
     let mut h = HighlightLines::new(syntax, theme);
     let incl_bg = IncludeBackground::No;
     let mut html: Vec<String> = vec![];
@@ -263,5 +799,116 @@ fn get_highlighted_html(
         let regions = h.highlight_line(line, ss).unwrap();
         html.push(styled_line_to_highlighted_html(&regions[..], incl_bg).unwrap())
     }
-    (html.join("<br>"), theme.settings.background)
+    (html, theme.settings.background)
+}
+
+/// Join highlighted lines of HTML, with their numbers and the highlighting of the lines
+fn html_lines(lines: Vec<String>, listing: &Listing, theme: &Theme) -> String {
+    let width = listing
+        .number(lines.len().saturating_sub(1))
+        .to_string()
+        .len();
+    let gutter = gutter_foreground(theme);
+    let highlight = line_highlight(theme);
+
+    let mut html: Vec<String> = vec![];
+    for (idx, line) in lines.into_iter().enumerate() {
+        let mut line = if listing.line_numbers {
+            format!(
+                "<span style=\"display: inline-block; min-width: {width}ch; margin-right: 1em; \
+                text-align: right; user-select: none; color: #{:02x}{:02x}{:02x};\">{}</span>{line}",
+                gutter.r,
+                gutter.g,
+                gutter.b,
+                listing.number(idx)
+            )
+        } else {
+            line
+        };
+        if listing.is_highlighted(idx) {
+            line = format!(
+                "<span style=\"display: inline-block; width: 100%; \
+                background-color: #{:02x}{:02x}{:02x};\">{line}</span>",
+                highlight.r, highlight.g, highlight.b
+            );
+        }
+        html.push(line);
+    }
+    html.join("<br>")
+}
+
+/// Get a show rule for the lines of raw text in Typst, which numbers and highlights them
+fn typst_show_rule(code: &str, listing: &Listing, theme: &Theme) -> String {
+    if !listing.line_numbers && listing.highlight.is_empty() {
+        return String::new();
+    }
+
+    let mut rule = String::from("#show raw.line: it => {\n");
+    writeln!(
+        rule,
+        "  let number = it.number + {}",
+        listing.start_line - 1
+    )
+    .unwrap();
+    if listing.line_numbers {
+        let c = gutter_foreground(theme);
+        let width = listing
+            .number(code.lines().count().saturating_sub(1))
+            .to_string()
+            .len();
+        writeln!(
+            rule,
+            "  box(width: {:.1}em, align(right, text(fill: rgb(\"#{:02x}{:02x}{:02x}\"), str(number))))",
+            width as f64 * 0.6,
+            c.r,
+            c.g,
+            c.b
+        )
+        .unwrap();
+        rule.push_str("  h(1em)\n");
+    }
+    if listing.highlight.is_empty() {
+        rule.push_str("  it\n");
+    } else {
+        let c = line_highlight(theme);
+        let ranges = listing
+            .highlight
+            .iter()
+            .map(|(first, last)| format!("range({first}, {})", last + 1))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(
+            rule,
+            "  if ({ranges},).any(lines => number in lines) {{\n    \
+            highlight(fill: rgb(\"#{:02x}{:02x}{:02x}\"), it)\n  }} else {{\n    it\n  }}",
+            c.r, c.g, c.b
+        )
+        .unwrap();
+    }
+    rule.push_str("}\n");
+    rule
+}
+
+/// Get the background of highlighted lines in a theme
+fn line_highlight(theme: &Theme) -> Color {
+    theme.settings.line_highlight.unwrap_or(Color {
+        r: 128,
+        g: 128,
+        b: 128,
+        a: 0xFF,
+    })
+}
+
+/// Get the color of line numbers in a theme
+fn gutter_foreground(theme: &Theme) -> Color {
+    theme
+        .settings
+        .gutter_foreground
+        .or(theme.settings.foreground)
+        .unwrap_or(Color {
+            r: 128,
+            g: 128,
+            b: 128,
+            a: 0xFF,
+        })
 }
@@ -0,0 +1,523 @@
+//! Diagrams are laid out into a `Drawing`, which is a list of simple shapes with absolute
+//! coordinates. The drawing is then rendered to SVG for HTML, or to TikZ for LaTeX, so that both
+//! formats get the same layout without any external programs.
+
+use std::fmt::Write;
+
+pub const FONT_SIZE: f64 = 14.0;
+pub const LINE_HEIGHT: f64 = 18.0;
+
+/// Colors used in the drawings, as RGB
+pub const BLACK: (u8, u8, u8) = (0x33, 0x33, 0x33);
+pub const WHITE: (u8, u8, u8) = (0xff, 0xff, 0xff);
+pub const LIGHT_GRAY: (u8, u8, u8) = (0xf4, 0xf4, 0xf4);
+pub const GRAY: (u8, u8, u8) = (0x88, 0x88, 0x88);
+pub const YELLOW: (u8, u8, u8) = (0xff, 0xf8, 0xc4);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Arrow {
+    None,
+    Filled,
+    Open,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    Start,
+    Middle,
+}
+
+/// How the outline and the inside of a shape are painted. No stroke or no fill leaves it out.
+#[derive(Clone, Copy)]
+pub struct Style {
+    pub stroke: Option<(u8, u8, u8)>,
+    pub fill: Option<(u8, u8, u8)>,
+    pub dashed: bool,
+}
+
+impl Style {
+    pub fn outline(fill: (u8, u8, u8)) -> Self {
+        Style {
+            stroke: Some(BLACK),
+            fill: Some(fill),
+            dashed: false,
+        }
+    }
+
+    pub fn line(dashed: bool) -> Self {
+        Style {
+            stroke: Some(BLACK),
+            fill: None,
+            dashed,
+        }
+    }
+}
+
+pub enum Item {
+    Rect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        rounded: bool,
+        style: Style,
+    },
+    Ellipse {
+        cx: f64,
+        cy: f64,
+        rx: f64,
+        ry: f64,
+        style: Style,
+    },
+    Polygon {
+        points: Vec<(f64, f64)>,
+        style: Style,
+    },
+    Line {
+        points: Vec<(f64, f64)>,
+        style: Style,
+        arrow: Arrow,
+    },
+    /// A single line of text, where y is the vertical center of the line
+    Text {
+        x: f64,
+        y: f64,
+        text: String,
+        anchor: Anchor,
+        bold: bool,
+    },
+}
+
+#[derive(Default)]
+pub struct Drawing {
+    pub items: Vec<Item>,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Estimate the size of some text, which may have several lines. There are no fonts to measure
+/// with, so every character is assumed to be a bit wider than the average character of common
+/// sans-serif fonts.
+pub fn text_size(text: &str) -> (f64, f64) {
+    let lines: Vec<&str> = text.lines().collect();
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count() as f64 * FONT_SIZE * 0.6)
+        .fold(0.0, f64::max);
+    (width, lines.len().max(1) as f64 * LINE_HEIGHT)
+}
+
+impl Drawing {
+    /// Add text with its lines centered around y
+    pub fn text(&mut self, x: f64, y: f64, text: &str, anchor: Anchor, bold: bool) {
+        let lines: Vec<&str> = text.lines().collect();
+        let top = y - (lines.len() as f64 - 1.0) * LINE_HEIGHT / 2.0;
+        for (idx, line) in lines.into_iter().enumerate() {
+            self.items.push(Item::Text {
+                x,
+                y: top + idx as f64 * LINE_HEIGHT,
+                text: line.to_string(),
+                anchor,
+                bold,
+            });
+        }
+    }
+
+    /// Move everything so that the drawing starts at the margin, and set its size so that there
+    /// is a margin on all sides
+    pub fn fit(&mut self, margin: f64) {
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for item in &self.items {
+            let (x0, y0, x1, y1) = item.bounds();
+            min_x = min_x.min(x0);
+            min_y = min_y.min(y0);
+            max_x = max_x.max(x1);
+            max_y = max_y.max(y1);
+        }
+        if self.items.is_empty() {
+            (min_x, min_y, max_x, max_y) = (0.0, 0.0, 0.0, 0.0);
+        }
+
+        let (dx, dy) = (margin - min_x, margin - min_y);
+        for item in &mut self.items {
+            item.translate(dx, dy);
+        }
+        self.width = max_x - min_x + 2.0 * margin;
+        self.height = max_y - min_y + 2.0 * margin;
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"{FONT_SIZE}\">\n",
+            w = num(self.width),
+            h = num(self.height),
+        );
+        svg.push_str(concat!(
+            "<defs>\n",
+            "<marker id=\"arrow-filled\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto-start-reverse\">",
+            "<path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"#333333\"/></marker>\n",
+            "<marker id=\"arrow-open\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto-start-reverse\">",
+            "<path d=\"M 0 0 L 10 5 L 0 10\" fill=\"none\" stroke=\"#333333\"/></marker>\n",
+            "</defs>\n"
+        ));
+
+        for item in &self.items {
+            match item {
+                Item::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    rounded,
+                    style,
+                } => {
+                    let radius = if *rounded { " rx=\"6\"" } else { "" };
+                    writeln!(
+                        svg,
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{radius}{}/>",
+                        num(*x),
+                        num(*y),
+                        num(*width),
+                        num(*height),
+                        svg_style(style)
+                    )
+                    .unwrap();
+                }
+                Item::Ellipse {
+                    cx,
+                    cy,
+                    rx,
+                    ry,
+                    style,
+                } => {
+                    writeln!(
+                        svg,
+                        "<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\"{}/>",
+                        num(*cx),
+                        num(*cy),
+                        num(*rx),
+                        num(*ry),
+                        svg_style(style)
+                    )
+                    .unwrap();
+                }
+                Item::Polygon { points, style } => {
+                    writeln!(
+                        svg,
+                        "<polygon points=\"{}\"{}/>",
+                        svg_points(points),
+                        svg_style(style)
+                    )
+                    .unwrap();
+                }
+                Item::Line {
+                    points,
+                    style,
+                    arrow,
+                } => {
+                    let marker = match arrow {
+                        Arrow::None => "",
+                        Arrow::Filled => " marker-end=\"url(#arrow-filled)\"",
+                        Arrow::Open => " marker-end=\"url(#arrow-open)\"",
+                    };
+                    writeln!(
+                        svg,
+                        "<polyline points=\"{}\"{}{marker}/>",
+                        svg_points(points),
+                        svg_style(style)
+                    )
+                    .unwrap();
+                }
+                Item::Text {
+                    x,
+                    y,
+                    text,
+                    anchor,
+                    bold,
+                } => {
+                    let anchor = match anchor {
+                        Anchor::Start => "start",
+                        Anchor::Middle => "middle",
+                    };
+                    let weight = if *bold { " font-weight=\"bold\"" } else { "" };
+                    // The baseline is placed so that the text is vertically centered around y
+                    writeln!(
+                        svg,
+                        "<text x=\"{}\" y=\"{}\" text-anchor=\"{anchor}\"{weight} fill=\"#333333\">{}</text>",
+                        num(*x),
+                        num(y + FONT_SIZE * 0.35),
+                        escape_xml(text)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Render the drawing as a tikzpicture. The coordinates of the drawing are in pixels with y
+    /// pointing down, so the picture is scaled to points and flipped.
+    pub fn to_tikz(&self) -> String {
+        let mut tikz = String::from(
+            "\\begin{tikzpicture}[x=0.75pt,y=-0.75pt,every node/.style={inner sep=0pt,font=\\small}]\n",
+        );
+        // An invisible rectangle keeps the margins of the drawing
+        writeln!(
+            tikz,
+            "\\path (0,0) rectangle ({},{});",
+            num(self.width),
+            num(self.height)
+        )
+        .unwrap();
+
+        for item in &self.items {
+            match item {
+                Item::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    rounded,
+                    style,
+                } => {
+                    let mut options = tikz_style(style);
+                    if *rounded {
+                        options.push("rounded corners=4.5pt".to_string());
+                    }
+                    writeln!(
+                        tikz,
+                        "\\path[{}] ({},{}) rectangle ({},{});",
+                        options.join(","),
+                        num(*x),
+                        num(*y),
+                        num(x + width),
+                        num(y + height)
+                    )
+                    .unwrap();
+                }
+                Item::Ellipse {
+                    cx,
+                    cy,
+                    rx,
+                    ry,
+                    style,
+                } => {
+                    writeln!(
+                        tikz,
+                        "\\path[{}] ({},{}) ellipse [x radius={}, y radius={}];",
+                        tikz_style(style).join(","),
+                        num(*cx),
+                        num(*cy),
+                        num(*rx),
+                        num(*ry)
+                    )
+                    .unwrap();
+                }
+                Item::Polygon { points, style } => {
+                    writeln!(
+                        tikz,
+                        "\\path[{}] {} -- cycle;",
+                        tikz_style(style).join(","),
+                        tikz_points(points)
+                    )
+                    .unwrap();
+                }
+                Item::Line {
+                    points,
+                    style,
+                    arrow,
+                } => {
+                    let mut options = tikz_style(style);
+                    match arrow {
+                        Arrow::None => {}
+                        Arrow::Filled => options.push("-{Stealth[length=6pt]}".to_string()),
+                        Arrow::Open => options.push("-{Straight Barb[length=6pt]}".to_string()),
+                    }
+                    writeln!(
+                        tikz,
+                        "\\path[{}] {};",
+                        options.join(","),
+                        tikz_points(points)
+                    )
+                    .unwrap();
+                }
+                Item::Text {
+                    x,
+                    y,
+                    text,
+                    anchor,
+                    bold,
+                } => {
+                    let anchor = match anchor {
+                        Anchor::Start => "anchor=west",
+                        Anchor::Middle => "anchor=center",
+                    };
+                    let text = escape_latex(text);
+                    let text = if *bold {
+                        format!("\\textbf{{{text}}}")
+                    } else {
+                        text
+                    };
+                    writeln!(
+                        tikz,
+                        "\\node[{anchor}] at ({},{}) {{\\strut {text}}};",
+                        num(*x),
+                        num(*y)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        tikz.push_str("\\end{tikzpicture}");
+        tikz
+    }
+}
+
+impl Item {
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        match self {
+            Item::Rect {
+                x,
+                y,
+                width,
+                height,
+                ..
+            } => (*x, *y, x + width, y + height),
+            Item::Ellipse { cx, cy, rx, ry, .. } => (cx - rx, cy - ry, cx + rx, cy + ry),
+            Item::Polygon { points, .. } | Item::Line { points, .. } => points.iter().fold(
+                (
+                    f64::INFINITY,
+                    f64::INFINITY,
+                    f64::NEG_INFINITY,
+                    f64::NEG_INFINITY,
+                ),
+                |(x0, y0, x1, y1), (x, y)| (x0.min(*x), y0.min(*y), x1.max(*x), y1.max(*y)),
+            ),
+            Item::Text {
+                x, y, text, anchor, ..
+            } => {
+                let (width, height) = text_size(text);
+                let left = match anchor {
+                    Anchor::Start => *x,
+                    Anchor::Middle => x - width / 2.0,
+                };
+                (left, y - height / 2.0, left + width, y + height / 2.0)
+            }
+        }
+    }
+
+    fn translate(&mut self, dx: f64, dy: f64) {
+        match self {
+            Item::Rect { x, y, .. } | Item::Text { x, y, .. } => {
+                *x += dx;
+                *y += dy;
+            }
+            Item::Ellipse { cx, cy, .. } => {
+                *cx += dx;
+                *cy += dy;
+            }
+            Item::Polygon { points, .. } | Item::Line { points, .. } => {
+                for (x, y) in points {
+                    *x += dx;
+                    *y += dy;
+                }
+            }
+        }
+    }
+}
+
+// Format a coordinate with at most one decimal, to keep the output small
+fn num(value: f64) -> String {
+    let rounded = (value * 10.0).round() / 10.0;
+    if rounded == rounded.trunc() {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{rounded:.1}")
+    }
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn svg_style(style: &Style) -> String {
+    let mut attributes = String::new();
+    match style.fill {
+        Some(color) => write!(attributes, " fill=\"{}\"", hex(color)).unwrap(),
+        None => attributes.push_str(" fill=\"none\""),
+    }
+    if let Some(color) = style.stroke {
+        write!(
+            attributes,
+            " stroke=\"{}\" stroke-width=\"1.5\"",
+            hex(color)
+        )
+        .unwrap();
+    }
+    if style.dashed {
+        attributes.push_str(" stroke-dasharray=\"6 4\"");
+    }
+    attributes
+}
+
+fn svg_points(points: &[(f64, f64)]) -> String {
+    points
+        .iter()
+        .map(|(x, y)| format!("{},{}", num(*x), num(*y)))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn tikz_color((r, g, b): (u8, u8, u8)) -> String {
+    format!("{{rgb,255:red,{r};green,{g};blue,{b}}}")
+}
+
+fn tikz_style(style: &Style) -> Vec<String> {
+    let mut options = vec![];
+    if let Some(color) = style.stroke {
+        options.push(format!("draw={}", tikz_color(color)));
+        options.push("line width=1.1pt".to_string());
+    }
+    if let Some(color) = style.fill {
+        options.push(format!("fill={}", tikz_color(color)));
+    }
+    if style.dashed {
+        options.push("dashed".to_string());
+    }
+    options
+}
+
+fn tikz_points(points: &[(f64, f64)]) -> String {
+    points
+        .iter()
+        .map(|(x, y)| format!("({},{})", num(*x), num(*y)))
+        .collect::<Vec<String>>()
+        .join(" -- ")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_latex(text: &str) -> String {
+    text.split('\\')
+        .map(|t| t.replace('{', r"\{").replace('}', r"\}"))
+        .collect::<Vec<String>>()
+        .join(r"\textbackslash{}")
+        .replace('#', r"\#")
+        .replace('$', r"\$")
+        .replace('%', r"\%")
+        .replace('&', r"\&")
+        .replace('_', r"\_")
+        .replace('<', r"\textless{}")
+        .replace('>', r"\textgreater{}")
+        .replace('~', r"\textasciitilde{}")
+        .replace('^', r"\textasciicircum{}")
+}
@@ -0,0 +1,1069 @@
+//! Graphs are written in a subset of the DOT language of Graphviz. Nodes, edges, attributes,
+//! subgraphs and clusters are supported, but ports and HTML labels are not. They are laid out in
+//! layers, so that edges point in the direction given by rankdir:
+//!
+//! 1. Cycles are broken by reversing the edges that point back to a node being visited in a
+//!    depth-first search.
+//! 2. Each node is put in the layer after the last of its predecessors, and edges that span more
+//!    than one layer get an invisible node in each layer in between, so that they can bend around
+//!    the other nodes.
+//! 3. The nodes in each layer are ordered by the average position of their neighbours, sweeping
+//!    down and up a few times to reduce the number of crossing edges. Nodes in the same cluster are
+//!    kept together.
+//! 4. The nodes are placed as close to their neighbours as their order allows.
+
+use std::collections::HashMap;
+
+use crate::drawing::{text_size, Anchor, Arrow, Drawing, Item, Style, GRAY, LIGHT_GRAY, WHITE};
+
+const NODE_GAP: f64 = 30.0;
+const DUMMY_SIZE: f64 = 10.0;
+const RANK_GAP: f64 = 50.0;
+const CLUSTER_PADDING: f64 = 12.0;
+const SWEEPS: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum RankDir {
+    TopBottom,
+    BottomTop,
+    LeftRight,
+    RightLeft,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Shape {
+    Box,
+    Ellipse,
+    Circle,
+    Diamond,
+    Plain,
+}
+
+#[derive(Debug)]
+struct Node {
+    id: String,
+    attributes: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+struct Edge {
+    from: usize,
+    to: usize,
+    attributes: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+struct Cluster {
+    label: String,
+    nodes: Vec<usize>,
+    parent: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct Graph {
+    directed: bool,
+    rankdir: RankDir,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    clusters: Vec<Cluster>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Id(String),
+    Keyword(String),
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Equals,
+    Semicolon,
+    Comma,
+    EdgeOp,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '#' => while chars.next().is_some_and(|c| c != '\n') {},
+            '/' => {
+                chars.next();
+                match chars.next() {
+                    Some('/') => while chars.next().is_some_and(|c| c != '\n') {},
+                    Some('*') => {
+                        let mut last = ' ';
+                        loop {
+                            match chars.next() {
+                                Some('/') if last == '*' => break,
+                                Some(c) => last = c,
+                                None => return Err("Unterminated comment in graph".to_string()),
+                            }
+                        }
+                    }
+                    _ => return Err("Unexpected '/' in graph".to_string()),
+                }
+            }
+            '{' | '}' | '[' | ']' | '=' | ';' | ',' => {
+                chars.next();
+                tokens.push(match c {
+                    '{' => Token::LBrace,
+                    '}' => Token::RBrace,
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    '=' => Token::Equals,
+                    ';' => Token::Semicolon,
+                    _ => Token::Comma,
+                });
+            }
+            '-' if {
+                let mut lookahead = chars.clone();
+                lookahead.next();
+                matches!(lookahead.peek(), Some('>') | Some('-'))
+            } =>
+            {
+                chars.next();
+                chars.next();
+                tokens.push(Token::EdgeOp);
+            }
+            '"' => {
+                chars.next();
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') | Some('l') | Some('r') => string.push('\n'),
+                            Some(c) => string.push(c),
+                            None => break,
+                        },
+                        Some(c) => string.push(c),
+                        None => return Err("Unterminated string in graph".to_string()),
+                    }
+                }
+                tokens.push(Token::Id(string));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let mut id = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' || c == '.' {
+                        id.push(c);
+                        chars.next();
+                    } else if c == '-' && id.is_empty() {
+                        // Negative numbers are the only ids that may contain a dash
+                        id.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let keyword = id.to_lowercase();
+                if matches!(
+                    keyword.as_str(),
+                    "graph" | "digraph" | "subgraph" | "node" | "edge" | "strict"
+                ) {
+                    tokens.push(Token::Keyword(keyword));
+                } else {
+                    tokens.push(Token::Id(id));
+                }
+            }
+            other => return Err(format!("Unexpected '{other}' in graph")),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// The attributes that apply to new nodes and edges in a scope, which subgraphs inherit
+#[derive(Clone, Default)]
+struct Defaults {
+    node: HashMap<String, String>,
+    edge: HashMap<String, String>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    graph: Graph,
+    node_ids: HashMap<String, usize>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!(
+                "Expected {expected:?} but found {token:?} in graph"
+            )),
+            None => Err(format!("Expected {expected:?} at the end of the graph")),
+        }
+    }
+
+    fn id(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Id(id)) => Ok(id),
+            Some(token) => Err(format!("Expected a name but found {token:?} in graph")),
+            None => Err("Expected a name at the end of the graph".to_string()),
+        }
+    }
+
+    fn parse_graph(&mut self) -> Result<(), String> {
+        if self.peek() == Some(&Token::Keyword("strict".to_string())) {
+            self.next();
+        }
+        match self.next() {
+            Some(Token::Keyword(keyword)) if keyword == "digraph" => self.graph.directed = true,
+            Some(Token::Keyword(keyword)) if keyword == "graph" => self.graph.directed = false,
+            _ => return Err("A graph must start with 'digraph' or 'graph'".to_string()),
+        }
+        if matches!(self.peek(), Some(Token::Id(_))) {
+            self.next();
+        }
+        self.expect(Token::LBrace)?;
+        let mut defaults = Defaults::default();
+        let mut graph_attributes = HashMap::new();
+        self.parse_statements(&mut defaults, &mut graph_attributes, None)?;
+        if let Some(rankdir) = graph_attributes.get("rankdir") {
+            self.graph.rankdir = match rankdir.to_uppercase().as_str() {
+                "LR" => RankDir::LeftRight,
+                "RL" => RankDir::RightLeft,
+                "BT" => RankDir::BottomTop,
+                _ => RankDir::TopBottom,
+            };
+        }
+        if self.pos < self.tokens.len() {
+            return Err("Unexpected content after the end of the graph".to_string());
+        }
+        Ok(())
+    }
+
+    /// Parse statements until the closing brace of the current scope, and return the nodes that
+    /// were mentioned in it
+    fn parse_statements(
+        &mut self,
+        defaults: &mut Defaults,
+        attributes: &mut HashMap<String, String>,
+        cluster: Option<usize>,
+    ) -> Result<Vec<usize>, String> {
+        let mut members = vec![];
+        loop {
+            match self.peek() {
+                Some(Token::RBrace) => {
+                    self.next();
+                    return Ok(members);
+                }
+                Some(Token::Semicolon) => {
+                    self.next();
+                }
+                None => return Err("Missing '}' at the end of the graph".to_string()),
+                Some(Token::Keyword(keyword)) if keyword != "subgraph" => {
+                    let keyword = keyword.clone();
+                    self.next();
+                    let list = self.parse_attribute_lists()?;
+                    match keyword.as_str() {
+                        "node" => defaults.node.extend(list),
+                        "edge" => defaults.edge.extend(list),
+                        "graph" => attributes.extend(list),
+                        other => return Err(format!("Unexpected '{other}' in graph")),
+                    }
+                }
+                Some(Token::Id(_)) if self.tokens.get(self.pos + 1) == Some(&Token::Equals) => {
+                    let key = self.id()?;
+                    self.next();
+                    let value = self.id()?;
+                    attributes.insert(key.to_lowercase(), value);
+                }
+                _ => {
+                    let mut endpoints = self.parse_endpoint(defaults, cluster)?;
+                    members.extend(&endpoints);
+                    let mut edges = vec![];
+                    while self.peek() == Some(&Token::EdgeOp) {
+                        self.next();
+                        let next = self.parse_endpoint(defaults, cluster)?;
+                        members.extend(&next);
+                        for from in &endpoints {
+                            for to in &next {
+                                edges.push((*from, *to));
+                            }
+                        }
+                        endpoints = next;
+                    }
+
+                    let list = self.parse_attribute_lists()?;
+                    if edges.is_empty() {
+                        // A node statement only has one endpoint, which is a single node unless it
+                        // is a subgraph
+                        if let [node] = endpoints.as_slice() {
+                            self.graph.nodes[*node].attributes.extend(list);
+                        }
+                    } else {
+                        for (from, to) in edges {
+                            let mut attributes = defaults.edge.clone();
+                            attributes.extend(list.clone());
+                            self.graph.edges.push(Edge {
+                                from,
+                                to,
+                                attributes,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Parse one end of an edge, which is either a node or a subgraph with all of its nodes
+    fn parse_endpoint(
+        &mut self,
+        defaults: &Defaults,
+        cluster: Option<usize>,
+    ) -> Result<Vec<usize>, String> {
+        match self.peek() {
+            Some(Token::Keyword(keyword)) if keyword == "subgraph" => {
+                self.next();
+                let name = match self.peek() {
+                    Some(Token::Id(_)) => self.id()?,
+                    _ => String::new(),
+                };
+                self.expect(Token::LBrace)?;
+                self.parse_subgraph(&name, defaults, cluster)
+            }
+            Some(Token::LBrace) => {
+                self.next();
+                self.parse_subgraph("", defaults, cluster)
+            }
+            _ => {
+                let id = self.id()?;
+                Ok(vec![self.node(&id, defaults)])
+            }
+        }
+    }
+
+    // Like in Graphviz, only subgraphs with names starting with "cluster" are drawn in a box
+    fn parse_subgraph(
+        &mut self,
+        name: &str,
+        defaults: &Defaults,
+        cluster: Option<usize>,
+    ) -> Result<Vec<usize>, String> {
+        let mut inner_defaults = defaults.clone();
+        let mut attributes = HashMap::new();
+        let inner_cluster = if name.starts_with("cluster") {
+            self.graph.clusters.push(Cluster {
+                label: String::new(),
+                nodes: vec![],
+                parent: cluster,
+            });
+            Some(self.graph.clusters.len() - 1)
+        } else {
+            cluster
+        };
+
+        let members = self.parse_statements(&mut inner_defaults, &mut attributes, inner_cluster)?;
+        if let Some(idx) = inner_cluster.filter(|_| name.starts_with("cluster")) {
+            let cluster = &mut self.graph.clusters[idx];
+            cluster.label = attributes.get("label").cloned().unwrap_or_default();
+            for node in &members {
+                if !cluster.nodes.contains(node) {
+                    cluster.nodes.push(*node);
+                }
+            }
+        }
+        Ok(members)
+    }
+
+    fn parse_attribute_lists(&mut self) -> Result<HashMap<String, String>, String> {
+        let mut attributes = HashMap::new();
+        while self.peek() == Some(&Token::LBracket) {
+            self.next();
+            loop {
+                match self.peek() {
+                    Some(Token::RBracket) => {
+                        self.next();
+                        break;
+                    }
+                    Some(Token::Comma) | Some(Token::Semicolon) => {
+                        self.next();
+                    }
+                    _ => {
+                        let key = self.id()?;
+                        self.expect(Token::Equals)?;
+                        let value = self.id()?;
+                        attributes.insert(key.to_lowercase(), value);
+                    }
+                }
+            }
+        }
+        Ok(attributes)
+    }
+
+    /// Get the index of a node, and add it if it hasn't been mentioned before
+    fn node(&mut self, id: &str, defaults: &Defaults) -> usize {
+        if let Some(idx) = self.node_ids.get(id) {
+            return *idx;
+        }
+        let idx = self.graph.nodes.len();
+        self.graph.nodes.push(Node {
+            id: id.to_string(),
+            attributes: defaults.node.clone(),
+        });
+        self.node_ids.insert(id.to_string(), idx);
+        idx
+    }
+}
+
+pub fn parse(source: &str) -> Result<Graph, String> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+        graph: Graph {
+            directed: true,
+            rankdir: RankDir::TopBottom,
+            nodes: vec![],
+            edges: vec![],
+            clusters: vec![],
+        },
+        node_ids: HashMap::new(),
+    };
+    parser.parse_graph()?;
+    Ok(parser.graph)
+}
+
+/// A node in the layout, which is either a node of the graph or a point that an edge passes
+/// through
+struct LayoutNode {
+    /// The size across the layer and along the direction of the edges
+    breadth: f64,
+    depth: f64,
+    rank: usize,
+    pos: f64,
+    real: Option<usize>,
+}
+
+impl Node {
+    fn label(&self) -> &str {
+        self.attributes
+            .get("label")
+            .map(String::as_str)
+            .unwrap_or(&self.id)
+    }
+
+    fn shape(&self) -> Shape {
+        match self.attributes.get("shape").map(String::as_str) {
+            Some("box" | "rect" | "rectangle" | "square" | "record" | "Mrecord") => Shape::Box,
+            Some("circle" | "doublecircle" | "point") => Shape::Circle,
+            Some("diamond" | "Mdiamond") => Shape::Diamond,
+            Some("plaintext" | "plain" | "none" | "underline") => Shape::Plain,
+            _ => Shape::Ellipse,
+        }
+    }
+
+    fn style_contains(&self, style: &str) -> bool {
+        self.attributes
+            .get("style")
+            .is_some_and(|styles| styles.split(',').any(|s| s.trim() == style))
+    }
+
+    /// The width and height of the node, which fits its label
+    fn size(&self) -> (f64, f64) {
+        let (width, height) = text_size(self.label());
+        match self.shape() {
+            Shape::Box | Shape::Plain => ((width + 24.0).max(54.0), (height + 16.0).max(36.0)),
+            Shape::Ellipse => (
+                (width * 1.42 + 16.0).max(54.0),
+                (height * 1.42 + 8.0).max(36.0),
+            ),
+            Shape::Circle => {
+                let diameter = (width.max(height) * 1.42 + 12.0).max(36.0);
+                (diameter, diameter)
+            }
+            Shape::Diamond => (
+                (width * 2.0 + 24.0).max(54.0),
+                (height * 2.0 + 8.0).max(36.0),
+            ),
+        }
+    }
+}
+
+impl Graph {
+    pub fn layout(&self) -> Drawing {
+        let horizontal = matches!(self.rankdir, RankDir::LeftRight | RankDir::RightLeft);
+        let sizes: Vec<(f64, f64)> = self.nodes.iter().map(Node::size).collect();
+
+        // Self loops are drawn beside their node, and aren't a part of the layering
+        let edges: Vec<(usize, usize)> = self
+            .edges
+            .iter()
+            .filter(|edge| edge.from != edge.to)
+            .map(|edge| (edge.from, edge.to))
+            .collect();
+        let reversed = self.back_edges(&edges);
+        let acyclic: Vec<(usize, usize)> = edges
+            .iter()
+            .zip(&reversed)
+            .map(|(&(from, to), &rev)| if rev { (to, from) } else { (from, to) })
+            .collect();
+        let ranks = self.ranks(&acyclic);
+
+        let mut layout: Vec<LayoutNode> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(idx, _)| {
+                let (width, height) = sizes[idx];
+                let (breadth, depth) = if horizontal {
+                    (height, width)
+                } else {
+                    (width, height)
+                };
+                LayoutNode {
+                    breadth,
+                    depth,
+                    rank: ranks[idx],
+                    pos: 0.0,
+                    real: Some(idx),
+                }
+            })
+            .collect();
+
+        // Every edge becomes a chain of layout nodes, one in each layer
+        let has_labels = self
+            .edges
+            .iter()
+            .any(|edge| edge.attributes.contains_key("label"));
+        let mut chains: Vec<Vec<usize>> = vec![];
+        for &(from, to) in &acyclic {
+            let mut chain = vec![from];
+            for rank in ranks[from] + 1..ranks[to] {
+                layout.push(LayoutNode {
+                    breadth: DUMMY_SIZE,
+                    depth: 0.0,
+                    rank,
+                    pos: 0.0,
+                    real: None,
+                });
+                chain.push(layout.len() - 1);
+            }
+            chain.push(to);
+            chains.push(chain);
+        }
+
+        let rank_count = ranks.iter().max().map_or(0, |max| max + 1);
+        let mut layers: Vec<Vec<usize>> = vec![vec![]; rank_count];
+        for (idx, node) in layout.iter().enumerate() {
+            layers[node.rank].push(idx);
+        }
+
+        let mut predecessors: Vec<Vec<usize>> = vec![vec![]; layout.len()];
+        let mut successors: Vec<Vec<usize>> = vec![vec![]; layout.len()];
+        for chain in &chains {
+            for pair in chain.windows(2) {
+                successors[pair[0]].push(pair[1]);
+                predecessors[pair[1]].push(pair[0]);
+            }
+        }
+
+        self.order_layers(&mut layers, &layout, &predecessors, &successors);
+        place_layers(&layers, &mut layout, &predecessors, &successors);
+
+        // The position of each layer along the edges
+        let mut rank_gap = RANK_GAP;
+        if has_labels {
+            rank_gap += 20.0;
+        }
+        if !self.clusters.is_empty() {
+            rank_gap += 20.0;
+        }
+        let mut depths = vec![0.0; rank_count];
+        let mut depth = 0.0;
+        for (rank, layer) in layers.iter().enumerate() {
+            let size = layer
+                .iter()
+                .map(|idx| layout[*idx].depth)
+                .fold(0.0, f64::max);
+            depths[rank] = depth + size / 2.0;
+            depth += size + rank_gap;
+        }
+        let total_depth = depth;
+
+        let point = |idx: usize| -> (f64, f64) {
+            let node = &layout[idx];
+            let along = depths[node.rank];
+            match self.rankdir {
+                RankDir::TopBottom => (node.pos, along),
+                RankDir::BottomTop => (node.pos, total_depth - along),
+                RankDir::LeftRight => (along, node.pos),
+                RankDir::RightLeft => (total_depth - along, node.pos),
+            }
+        };
+        let centers: Vec<(f64, f64)> = (0..self.nodes.len()).map(point).collect();
+
+        let mut drawing = Drawing::default();
+        self.draw_clusters(&mut drawing, &centers, &sizes);
+
+        // Edges between the same two nodes are moved apart, so that they don't overlap
+        let mut parallel: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        for edge in &self.edges {
+            let pair = (edge.from.min(edge.to), edge.from.max(edge.to));
+            parallel.entry(pair).or_default().1 += 1;
+        }
+
+        let mut chain_iter = chains.iter();
+        for edge in &self.edges {
+            if edge.from == edge.to {
+                self.draw_self_loop(&mut drawing, edge, centers[edge.from], sizes[edge.from]);
+                continue;
+            }
+            let chain = chain_iter.next().unwrap();
+            let mut points: Vec<(f64, f64)> = chain.iter().map(|idx| point(*idx)).collect();
+            if layout[chain[0]].real != Some(edge.from) {
+                points.reverse();
+            }
+            let (index, count) = parallel
+                .get_mut(&(edge.from.min(edge.to), edge.from.max(edge.to)))
+                .unwrap();
+            let offset = (*index as f64 - (*count - 1) as f64 / 2.0) * 12.0;
+            *index += 1;
+            for (x, y) in &mut points {
+                if horizontal {
+                    *y += offset;
+                } else {
+                    *x += offset;
+                }
+            }
+            self.draw_edge(&mut drawing, edge, points);
+        }
+
+        for (idx, node) in self.nodes.iter().enumerate() {
+            draw_node(&mut drawing, node, centers[idx], sizes[idx]);
+        }
+
+        drawing.fit(10.0);
+        drawing
+    }
+
+    /// Find the edges that point back to a node on the path of a depth-first search, which would
+    /// make a cycle. Reversing them makes the graph acyclic.
+    fn back_edges(&self, edges: &[(usize, usize)]) -> Vec<bool> {
+        let mut outgoing: Vec<Vec<(usize, usize)>> = vec![vec![]; self.nodes.len()];
+        for (idx, &(from, to)) in edges.iter().enumerate() {
+            outgoing[from].push((to, idx));
+        }
+
+        // 0 is unvisited, 1 is on the current path and 2 is done
+        let mut state = vec![0; self.nodes.len()];
+        let mut reversed = vec![false; edges.len()];
+        for start in 0..self.nodes.len() {
+            if state[start] != 0 {
+                continue;
+            }
+            let mut stack = vec![(start, 0)];
+            state[start] = 1;
+            while let Some((node, next)) = stack.pop() {
+                if let Some(&(to, edge)) = outgoing[node].get(next) {
+                    stack.push((node, next + 1));
+                    match state[to] {
+                        0 => {
+                            state[to] = 1;
+                            stack.push((to, 0));
+                        }
+                        1 => reversed[edge] = true,
+                        _ => {}
+                    }
+                } else {
+                    state[node] = 2;
+                }
+            }
+        }
+        reversed
+    }
+
+    /// Put each node in the layer after the last of its predecessors
+    fn ranks(&self, edges: &[(usize, usize)]) -> Vec<usize> {
+        let mut incoming = vec![0; self.nodes.len()];
+        let mut outgoing: Vec<Vec<usize>> = vec![vec![]; self.nodes.len()];
+        for &(from, to) in edges {
+            incoming[to] += 1;
+            outgoing[from].push(to);
+        }
+
+        let mut ranks = vec![0; self.nodes.len()];
+        let mut queue: Vec<usize> = (0..self.nodes.len())
+            .filter(|idx| incoming[*idx] == 0)
+            .collect();
+        while let Some(node) = queue.pop() {
+            for &to in &outgoing[node] {
+                ranks[to] = ranks[to].max(ranks[node] + 1);
+                incoming[to] -= 1;
+                if incoming[to] == 0 {
+                    queue.push(to);
+                }
+            }
+        }
+        ranks
+    }
+
+    /// Order the nodes in each layer by the average position of their neighbours in the previous
+    /// layer, sweeping down and then up
+    fn order_layers(
+        &self,
+        layers: &mut [Vec<usize>],
+        layout: &[LayoutNode],
+        predecessors: &[Vec<usize>],
+        successors: &[Vec<usize>],
+    ) {
+        let mut position = vec![0.0; layout.len()];
+        for layer in layers.iter() {
+            for (pos, idx) in layer.iter().enumerate() {
+                position[*idx] = pos as f64;
+            }
+        }
+
+        for sweep in 0..SWEEPS {
+            let down = sweep.is_multiple_of(2);
+            let order: Vec<usize> = if down {
+                (1..layers.len()).collect()
+            } else {
+                (0..layers.len().saturating_sub(1)).rev().collect()
+            };
+            for rank in order {
+                let neighbours = if down { predecessors } else { successors };
+                let mut keys: HashMap<usize, f64> = HashMap::new();
+                for idx in &layers[rank] {
+                    let adjacent = &neighbours[*idx];
+                    let key = if adjacent.is_empty() {
+                        position[*idx]
+                    } else {
+                        adjacent.iter().map(|n| position[*n]).sum::<f64>() / adjacent.len() as f64
+                    };
+                    keys.insert(*idx, key);
+                }
+                self.sort_layer(&mut layers[rank], &keys, layout);
+                for (pos, idx) in layers[rank].iter().enumerate() {
+                    position[*idx] = pos as f64;
+                }
+            }
+        }
+    }
+
+    /// Sort a layer by the keys, but keep the nodes of each cluster next to each other, at the
+    /// average key of the cluster
+    fn sort_layer(&self, layer: &mut [usize], keys: &HashMap<usize, f64>, layout: &[LayoutNode]) {
+        let group = |idx: usize| -> Option<usize> {
+            let real = layout[idx].real?;
+            self.clusters
+                .iter()
+                .position(|cluster| cluster.parent.is_none() && self.in_cluster(real, cluster))
+        };
+
+        let mut group_keys: HashMap<usize, (f64, usize)> = HashMap::new();
+        for idx in layer.iter() {
+            if let Some(group) = group(*idx) {
+                let entry = group_keys.entry(group).or_insert((0.0, 0));
+                entry.0 += keys[idx];
+                entry.1 += 1;
+            }
+        }
+
+        layer.sort_by(|a, b| {
+            let key = |idx: &usize| match group(*idx) {
+                Some(group) => {
+                    let (sum, count) = group_keys[&group];
+                    (sum / count as f64, keys[idx])
+                }
+                None => (keys[idx], keys[idx]),
+            };
+            key(a).partial_cmp(&key(b)).unwrap()
+        });
+    }
+
+    fn in_cluster(&self, node: usize, cluster: &Cluster) -> bool {
+        cluster.nodes.contains(&node)
+    }
+
+    fn draw_clusters(&self, drawing: &mut Drawing, centers: &[(f64, f64)], sizes: &[(f64, f64)]) {
+        // Outer clusters are drawn first, and are a bit larger for each cluster inside them
+        let nesting = |mut idx: usize| {
+            let mut depth = 0;
+            while let Some(parent) = self.clusters[idx].parent {
+                depth += 1;
+                idx = parent;
+            }
+            depth
+        };
+        let max_nesting = (0..self.clusters.len()).map(nesting).max().unwrap_or(0);
+
+        let mut order: Vec<usize> = (0..self.clusters.len()).collect();
+        order.sort_by_key(|idx| nesting(*idx));
+        for idx in order {
+            let cluster = &self.clusters[idx];
+            if cluster.nodes.is_empty() {
+                continue;
+            }
+            let padding = CLUSTER_PADDING * (1 + max_nesting - nesting(idx)) as f64;
+            let (mut x0, mut y0) = (f64::INFINITY, f64::INFINITY);
+            let (mut x1, mut y1) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+            for node in &cluster.nodes {
+                let ((x, y), (width, height)) = (centers[*node], sizes[*node]);
+                x0 = x0.min(x - width / 2.0);
+                y0 = y0.min(y - height / 2.0);
+                x1 = x1.max(x + width / 2.0);
+                y1 = y1.max(y + height / 2.0);
+            }
+            let label_height = if cluster.label.is_empty() {
+                0.0
+            } else {
+                text_size(&cluster.label).1
+            };
+            let (x0, y0) = (x0 - padding, y0 - padding - label_height);
+            let (x1, y1) = (x1 + padding, y1 + padding);
+            let (label_width, _) = text_size(&cluster.label);
+            let x1 = x1.max(x0 + label_width + 2.0 * CLUSTER_PADDING);
+
+            drawing.items.push(Item::Rect {
+                x: x0,
+                y: y0,
+                width: x1 - x0,
+                height: y1 - y0,
+                rounded: true,
+                style: Style {
+                    stroke: Some(GRAY),
+                    fill: Some(LIGHT_GRAY),
+                    dashed: false,
+                },
+            });
+            if !cluster.label.is_empty() {
+                drawing.text(
+                    x0 + CLUSTER_PADDING,
+                    y0 + CLUSTER_PADDING / 2.0 + label_height / 2.0,
+                    &cluster.label,
+                    Anchor::Start,
+                    true,
+                );
+            }
+        }
+    }
+
+    fn draw_edge(&self, drawing: &mut Drawing, edge: &Edge, mut points: Vec<(f64, f64)>) {
+        let last = points.len() - 1;
+        points[0] = boundary(&self.nodes[edge.from], points[0], points[1]);
+        points[last] = boundary(&self.nodes[edge.to], points[last], points[last - 1]);
+
+        let dashed = edge
+            .attributes
+            .get("style")
+            .is_some_and(|style| style == "dashed" || style == "dotted");
+        let dir = edge.attributes.get("dir").map(String::as_str);
+        let arrow = match (self.directed, dir) {
+            (_, Some("none")) => Arrow::None,
+            (true, _) | (false, Some("forward" | "back" | "both")) => Arrow::Filled,
+            (false, _) => Arrow::None,
+        };
+        match dir {
+            Some("back") => points.reverse(),
+            Some("both") => {
+                // The arrow at the start is drawn as a separate line, pointing backwards
+                drawing.items.push(Item::Line {
+                    points: vec![points[1], points[0]],
+                    style: Style::line(dashed),
+                    arrow,
+                });
+            }
+            _ => {}
+        }
+
+        if let Some(label) = edge.attributes.get("label") {
+            // The label is put beside the middle of the edge
+            let middle = if points.len().is_multiple_of(2) {
+                let (a, b) = (points[points.len() / 2 - 1], points[points.len() / 2]);
+                ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0)
+            } else {
+                points[points.len() / 2]
+            };
+            let (width, height) = text_size(label);
+            drawing.items.push(Item::Rect {
+                x: middle.0 + 4.0,
+                y: middle.1 - height / 2.0,
+                width: width + 4.0,
+                height,
+                rounded: false,
+                style: Style {
+                    stroke: None,
+                    fill: Some(WHITE),
+                    dashed: false,
+                },
+            });
+            drawing.text(middle.0 + 6.0, middle.1, label, Anchor::Start, false);
+        }
+
+        drawing.items.push(Item::Line {
+            points,
+            style: Style::line(dashed),
+            arrow,
+        });
+    }
+
+    fn draw_self_loop(
+        &self,
+        drawing: &mut Drawing,
+        edge: &Edge,
+        (x, y): (f64, f64),
+        (width, height): (f64, f64),
+    ) {
+        let node = &self.nodes[edge.from];
+        let right = x + width / 2.0;
+        let spread = height / 4.0;
+        let arrow = if self.directed {
+            Arrow::Filled
+        } else {
+            Arrow::None
+        };
+        drawing.items.push(Item::Line {
+            points: vec![
+                boundary(node, (x, y), (right, y - spread)),
+                (right + 24.0, y - spread - 6.0),
+                (right + 24.0, y + spread + 6.0),
+                boundary(node, (x, y), (right, y + spread)),
+            ],
+            style: Style::line(false),
+            arrow,
+        });
+        if let Some(label) = edge.attributes.get("label") {
+            drawing.text(right + 30.0, y, label, Anchor::Start, false);
+        }
+    }
+}
+
+/// Place the nodes of each layer next to each other, and then move them towards the average
+/// position of their neighbours as far as the order of the layer allows
+fn place_layers(
+    layers: &[Vec<usize>],
+    layout: &mut [LayoutNode],
+    predecessors: &[Vec<usize>],
+    successors: &[Vec<usize>],
+) {
+    for layer in layers {
+        let mut pos = 0.0;
+        for idx in layer {
+            layout[*idx].pos = pos + layout[*idx].breadth / 2.0;
+            pos += layout[*idx].breadth + NODE_GAP;
+        }
+    }
+
+    for sweep in 0..SWEEPS {
+        let down = sweep.is_multiple_of(2);
+        let order: Vec<usize> = if down {
+            (0..layers.len()).collect()
+        } else {
+            (0..layers.len()).rev().collect()
+        };
+        for rank in order {
+            let neighbours = if down { predecessors } else { successors };
+            let layer = &layers[rank];
+            let desired: Vec<f64> = layer
+                .iter()
+                .map(|idx| {
+                    let adjacent = &neighbours[*idx];
+                    if adjacent.is_empty() {
+                        layout[*idx].pos
+                    } else {
+                        adjacent.iter().map(|n| layout[*n].pos).sum::<f64>() / adjacent.len() as f64
+                    }
+                })
+                .collect();
+
+            // Keep the order and the gaps, and then shift the layer so that the nodes are as
+            // close to where they want to be on average
+            let mut placed = desired.clone();
+            for i in 1..layer.len() {
+                let min = placed[i - 1]
+                    + (layout[layer[i - 1]].breadth + layout[layer[i]].breadth) / 2.0
+                    + NODE_GAP;
+                placed[i] = placed[i].max(min);
+            }
+            let shift = desired.iter().zip(&placed).map(|(d, p)| d - p).sum::<f64>()
+                / layer.len().max(1) as f64;
+            for (idx, pos) in layer.iter().zip(placed) {
+                layout[*idx].pos = pos + shift;
+            }
+        }
+    }
+}
+
+fn draw_node(drawing: &mut Drawing, node: &Node, (x, y): (f64, f64), (width, height): (f64, f64)) {
+    let style = Style {
+        dashed: node.style_contains("dashed"),
+        ..Style::outline(WHITE)
+    };
+    match node.shape() {
+        Shape::Box => drawing.items.push(Item::Rect {
+            x: x - width / 2.0,
+            y: y - height / 2.0,
+            width,
+            height,
+            rounded: node.style_contains("rounded"),
+            style,
+        }),
+        Shape::Ellipse | Shape::Circle => drawing.items.push(Item::Ellipse {
+            cx: x,
+            cy: y,
+            rx: width / 2.0,
+            ry: height / 2.0,
+            style,
+        }),
+        Shape::Diamond => drawing.items.push(Item::Polygon {
+            points: vec![
+                (x, y - height / 2.0),
+                (x + width / 2.0, y),
+                (x, y + height / 2.0),
+                (x - width / 2.0, y),
+            ],
+            style,
+        }),
+        Shape::Plain => {}
+    }
+    drawing.text(x, y, node.label(), Anchor::Middle, false);
+}
+
+/// The point where a line from the center of a node towards another point leaves the node
+fn boundary(node: &Node, center: (f64, f64), toward: (f64, f64)) -> (f64, f64) {
+    let (width, height) = node.size();
+    let (dx, dy) = (toward.0 - center.0, toward.1 - center.1);
+    if dx == 0.0 && dy == 0.0 {
+        return center;
+    }
+    let (a, b) = (width / 2.0, height / 2.0);
+    let t = match node.shape() {
+        Shape::Box | Shape::Plain => {
+            let tx = if dx == 0.0 {
+                f64::INFINITY
+            } else {
+                a / dx.abs()
+            };
+            let ty = if dy == 0.0 {
+                f64::INFINITY
+            } else {
+                b / dy.abs()
+            };
+            tx.min(ty)
+        }
+        Shape::Ellipse | Shape::Circle => 1.0 / ((dx / a).powi(2) + (dy / b).powi(2)).sqrt(),
+        Shape::Diamond => 1.0 / (dx.abs() / a + dy.abs() / b),
+    };
+    (center.0 + dx * t, center.1 + dy * t)
+}
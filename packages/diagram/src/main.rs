@@ -0,0 +1,220 @@
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read};
+
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{json, Value};
+
+use drawing::Drawing;
+
+mod drawing;
+mod graph;
+mod sequence;
+
+macro_rules! module {
+    ($name:expr, $data:expr $(,$($args:tt)*)?) => {json!({"name": $name $(,"arguments":$($args)*)*, "data": $data})}
+}
+
+macro_rules! import {
+    ($e:expr) => {module!("set-add", $e, {"name": "imports"})}
+}
+
+macro_rules! text {
+    ($e:expr) => {
+        module!("__text", $e)
+    };
+}
+
+// The imports are a set without any order, so the TikZ library is in the same import as TikZ
+const TIKZ_IMPORT: &str = "\\usepackage{tikz}\n\\usetikzlibrary{arrows.meta}";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let action = &args[0];
+    match action.as_str() {
+        "manifest" => manifest(),
+        "transform" => transform(&args[1], &args[2]),
+        other => {
+            eprintln!("Invalid action {other}")
+        }
+    }
+}
+
+fn manifest() {
+    let arguments = json!([
+        {"name": "caption", "default": "", "description": "The caption for the diagram."},
+        {"name": "label", "default": "", "description": "The label to use for the diagram, to be able to refer to it from the document."},
+        {"name": "width", "default": 1.0, "type": "f64", "description": "The largest width of the diagram, as a ratio to the width of the surrounding figure in HTML and of the text area in LaTeX. Diagrams that are narrower keep their own size."},
+        {"name": "latex-format", "default": "tikz", "type": ["tikz", "svg"], "description": "How the diagram is included in LaTeX. tikz draws it with TikZ, and svg embeds it as an SVG file, which needs Inkscape when the document is compiled."}
+    ]);
+    let variables = json!({
+        "imports": {"type": "set", "access": "add"},
+        "structure": {"type": "list", "access": "push"},
+        "caption_style": {"type": "const", "access": "read"}
+    });
+
+    print!(
+        "{}",
+        serde_json::to_string(&json!(
+            {
+            "name": "diagram",
+            "version": "0.1",
+            "description": "This package draws graphs and sequence diagrams from a textual description, without any external programs.",
+            "transforms": [
+                {
+                    "from": "graph",
+                    "to": ["html", "latex"],
+                    "type": "multiline-module",
+                    "arguments": arguments,
+                    "description": "Draws a graph written in the DOT language of Graphviz, with nodes, edges, labels, subgraphs and clusters. The attributes label, shape and style of nodes, label, style and dir of edges and rankdir of the graph are supported. Example: digraph { rankdir=LR; a -> b [label=\"next\"] }",
+                    "variables": variables
+                },
+                {
+                    "from": "sequence-diagram",
+                    "to": ["html", "latex"],
+                    "type": "multiline-module",
+                    "arguments": arguments,
+                    "description": "Draws a sequence diagram with one statement per line. Participants are declared with 'participant A as \"Label\"', messages are written 'A -> B: text', with --> for replies and ->> or -->> for asynchronous messages, and notes are written 'note left of A: text', 'note right of A: text' or 'note over A, B: text'.",
+                    "variables": variables
+                }
+            ]
+            }
+        ))
+        .unwrap()
+    );
+}
+
+fn transform(from: &str, to: &str) {
+    let input: Value = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        serde_json::from_str(&buffer).unwrap()
+    };
+    let source = input["data"].as_str().unwrap();
+
+    let drawing = match from {
+        "graph" => graph::parse(source).map(|graph| graph.layout()),
+        "sequence-diagram" => sequence::parse(source).map(|diagram| diagram.layout()),
+        other => {
+            eprintln!("Package does not support {other}");
+            return;
+        }
+    };
+    let drawing = match drawing {
+        Ok(drawing) => drawing,
+        Err(error) => {
+            eprintln!("{error}");
+            return;
+        }
+    };
+
+    match to {
+        "html" => print_html(&drawing, &input),
+        "latex" => print_latex(&drawing, &input),
+        other => eprintln!("Cannot convert {from} to {other}!"),
+    }
+}
+
+fn print_html(drawing: &Drawing, input: &Value) {
+    let caption = input["arguments"]["caption"].as_str().unwrap();
+    let label = input["arguments"]["label"].as_str().unwrap();
+    let width = input["arguments"]["width"]
+        .as_f64()
+        .unwrap()
+        .clamp(0.0, f64::MAX);
+
+    let encoded = general_purpose::STANDARD_NO_PAD.encode(drawing.to_svg());
+    let percentage = (width * 100.0).round() as i32;
+    let structure_data = json!({
+        "element": "figure",
+        "key": label,
+    })
+    .to_string();
+
+    let mut output = vec![
+        module!("list-push", structure_data, {"name": "structure"}),
+        json!("<figure>\n"),
+        json!(format!(
+            "<img src=\"data:image/svg+xml;base64,{encoded}\" style=\"max-width:{percentage}%\" "
+        )),
+    ];
+    if !label.is_empty() {
+        output.push(json!("id=\""));
+        output.push(text!(label));
+        output.push(json!("\" "));
+    }
+    output.push(json!("alt=\""));
+    output.push(text!(if caption.is_empty() {
+        "Diagram"
+    } else {
+        caption
+    }));
+    output.push(json!("\"/>\n"));
+    if !caption.is_empty() {
+        output.push(json!("<figcaption>"));
+        if env::var("caption_style").unwrap_or_default() == "numbered" {
+            output.push(module!(
+                "inline_content",
+                format!("**Figure [element-number]({label}):** ")
+            ));
+        }
+        output.push(module!("inline_content", caption));
+        output.push(json!("</figcaption>\n"));
+    }
+    output.push(json!("</figure>\n"));
+
+    print!("{}", Value::Array(output));
+}
+
+fn print_latex(drawing: &Drawing, input: &Value) {
+    let caption = input["arguments"]["caption"].as_str().unwrap();
+    let label = input["arguments"]["label"].as_str().unwrap();
+    let width = input["arguments"]["width"]
+        .as_f64()
+        .unwrap()
+        .clamp(0.0, f64::MAX);
+
+    let (imports, picture) = if input["arguments"]["latex-format"].as_str() == Some("svg") {
+        // The SVG is written to a file named after its contents when the document is compiled,
+        // like in the plot package
+        let svg = drawing.to_svg();
+        let content_hash = {
+            let mut hasher = DefaultHasher::new();
+            svg.hash(&mut hasher);
+            hasher.finish()
+        };
+        (
+            vec![
+                import!("\\usepackage{svg}"),
+                json!(format!(
+                    "\\begin{{filecontents}}[noheader]{{{content_hash}.svg}}\n{svg}\\end{{filecontents}}"
+                )),
+            ],
+            format!("\\includesvg[inkscapelatex=false]{{{content_hash}}}"),
+        )
+    } else {
+        (vec![import!(TIKZ_IMPORT)], drawing.to_tikz())
+    };
+
+    let mut output = imports;
+    output.push(import!("\\usepackage{float}"));
+    output.push(json!("\n\\begin{figure}[H]\n\\centering\n"));
+    // The diagram keeps its own size, unless it is wider than the largest width
+    output.push(json!(format!(
+        "\\resizebox{{\\ifdim\\width>{width}\\textwidth {width}\\textwidth\\else\\width\\fi}}{{!}}{{%\n{picture}\n}}\n"
+    )));
+    if !caption.is_empty() {
+        output.push(json!("\\caption{"));
+        output.push(module!("inline_content", caption));
+        output.push(json!("}\n"));
+    }
+    if !label.is_empty() {
+        output.push(json!("\\label{"));
+        output.push(module!("raw", label));
+        output.push(json!("}\n"));
+    }
+    output.push(json!("\\end{figure}\n"));
+
+    print!("{}", Value::Array(output));
+}
@@ -0,0 +1,380 @@
+//! Sequence diagrams are written one statement per line:
+//!
+//! ```text
+//! participant Client
+//! participant Server as "Web server"
+//! Client -> Server: GET /index.html
+//! Server --> Client: 200 OK
+//! note over Client, Server: The connection is kept open
+//! ```
+//!
+//! Messages use `->` for calls and `-->` for replies, and `->>` or `-->>` for asynchronous
+//! messages with an open arrow. Participants that aren't declared are added in the order they are
+//! first used, and `\n` starts a new line in any text.
+
+use crate::drawing::{text_size, Anchor, Arrow, Drawing, Item, Style, WHITE, YELLOW};
+
+const PARTICIPANT_GAP: f64 = 20.0;
+const MESSAGE_GAP: f64 = 14.0;
+const SELF_WIDTH: f64 = 30.0;
+const NOTE_PADDING: f64 = 8.0;
+
+#[derive(Debug)]
+struct Participant {
+    id: String,
+    label: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum NotePlacement {
+    LeftOf,
+    RightOf,
+    Over,
+}
+
+#[derive(Debug)]
+enum Statement {
+    Message {
+        from: usize,
+        to: usize,
+        text: String,
+        dashed: bool,
+        arrow: Arrow,
+    },
+    Note {
+        placement: NotePlacement,
+        first: usize,
+        last: usize,
+        text: String,
+    },
+}
+
+#[derive(Debug)]
+pub struct SequenceDiagram {
+    participants: Vec<Participant>,
+    statements: Vec<Statement>,
+}
+
+pub fn parse(source: &str) -> Result<SequenceDiagram, String> {
+    let mut diagram = SequenceDiagram {
+        participants: vec![],
+        statements: vec![],
+    };
+
+    for (idx, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = || {
+            format!(
+                "Could not understand line {} of the sequence diagram: {line}",
+                idx + 1
+            )
+        };
+
+        let (head, text) = match line.split_once(':') {
+            Some((head, text)) => (head.trim(), unescape(text.trim())),
+            None => (line, String::new()),
+        };
+
+        if let Some(rest) = line.strip_prefix("participant ") {
+            let (id, label) = match rest.split_once(" as ") {
+                Some((id, label)) => (id.trim(), unquote(label.trim())),
+                None => (rest.trim(), unquote(rest.trim())),
+            };
+            let id = unquote(id);
+            match diagram.participants.iter_mut().find(|p| p.id == id) {
+                Some(participant) => participant.label = unescape(&label),
+                None => diagram.participants.push(Participant {
+                    label: unescape(&label),
+                    id,
+                }),
+            }
+        } else if let Some(rest) = head.strip_prefix("note ") {
+            let rest = rest.trim_start();
+            let (placement, names) = if let Some(names) = rest.strip_prefix("left of ") {
+                (NotePlacement::LeftOf, names)
+            } else if let Some(names) = rest.strip_prefix("right of ") {
+                (NotePlacement::RightOf, names)
+            } else if let Some(names) = rest.strip_prefix("over ") {
+                (NotePlacement::Over, names)
+            } else {
+                return Err(error());
+            };
+            let mut indices: Vec<usize> = names
+                .split(',')
+                .map(|name| diagram.participant(&unquote(name.trim())))
+                .collect();
+            indices.sort();
+            diagram.statements.push(Statement::Note {
+                placement,
+                first: indices[0],
+                last: indices[indices.len() - 1],
+                text,
+            });
+        } else if let Some(arrow_start) = head.find("->") {
+            let (mut from, mut to) = (&head[..arrow_start], &head[arrow_start + 2..]);
+            let dashed = from.ends_with('-');
+            if dashed {
+                from = &from[..from.len() - 1];
+            }
+            let arrow = if let Some(rest) = to.strip_prefix('>') {
+                to = rest;
+                Arrow::Open
+            } else {
+                Arrow::Filled
+            };
+            let (from, to) = (unquote(from.trim()), unquote(to.trim()));
+            if from.is_empty() || to.is_empty() {
+                return Err(error());
+            }
+            let from = diagram.participant(&from);
+            let to = diagram.participant(&to);
+            diagram.statements.push(Statement::Message {
+                from,
+                to,
+                text,
+                dashed,
+                arrow,
+            });
+        } else {
+            return Err(error());
+        }
+    }
+
+    if diagram.participants.is_empty() {
+        return Err("A sequence diagram must have at least one participant".to_string());
+    }
+    Ok(diagram)
+}
+
+fn unquote(text: &str) -> String {
+    text.strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .unwrap_or(text)
+        .to_string()
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\n", "\n")
+}
+
+impl SequenceDiagram {
+    /// Get the index of a participant, and add it if it hasn't been used before
+    fn participant(&mut self, id: &str) -> usize {
+        if let Some(idx) = self.participants.iter().position(|p| p.id == id) {
+            return idx;
+        }
+        self.participants.push(Participant {
+            id: id.to_string(),
+            label: id.to_string(),
+        });
+        self.participants.len() - 1
+    }
+
+    pub fn layout(&self) -> Drawing {
+        let heads: Vec<(f64, f64)> = self
+            .participants
+            .iter()
+            .map(|p| {
+                let (width, height) = text_size(&p.label);
+                ((width + 20.0).max(80.0), height + 16.0)
+            })
+            .collect();
+        let head_height = heads.iter().map(|(_, height)| *height).fold(0.0, f64::max);
+        let centers = self.columns(&heads);
+
+        // The messages and notes are drawn on top of the lifelines, and text on top of everything
+        let mut rows = vec![];
+        let mut labels = Drawing::default();
+        let mut y = head_height + 20.0;
+        for statement in &self.statements {
+            match statement {
+                Statement::Message {
+                    from,
+                    to,
+                    text,
+                    dashed,
+                    arrow,
+                } => {
+                    let text_height = if text.is_empty() {
+                        0.0
+                    } else {
+                        text_size(text).1
+                    };
+                    if from == to {
+                        let x = centers[*from];
+                        let height = text_height.max(24.0);
+                        rows.push(Item::Line {
+                            points: vec![
+                                (x, y),
+                                (x + SELF_WIDTH, y),
+                                (x + SELF_WIDTH, y + height),
+                                (x, y + height),
+                            ],
+                            style: Style::line(*dashed),
+                            arrow: *arrow,
+                        });
+                        labels.text(
+                            x + SELF_WIDTH + 6.0,
+                            y + height / 2.0,
+                            text,
+                            Anchor::Start,
+                            false,
+                        );
+                        y += height + MESSAGE_GAP + 6.0;
+                    } else {
+                        let (x0, x1) = (centers[*from], centers[*to]);
+                        labels.text(
+                            (x0 + x1) / 2.0,
+                            y + text_height / 2.0,
+                            text,
+                            Anchor::Middle,
+                            false,
+                        );
+                        y += text_height + 4.0;
+                        rows.push(Item::Line {
+                            points: vec![(x0, y), (x1, y)],
+                            style: Style::line(*dashed),
+                            arrow: *arrow,
+                        });
+                        y += MESSAGE_GAP + 6.0;
+                    }
+                }
+                Statement::Note {
+                    placement,
+                    first,
+                    last,
+                    text,
+                } => {
+                    let (mut width, height) = note_size(text);
+                    let x = match placement {
+                        NotePlacement::LeftOf => centers[*first] - 10.0 - width,
+                        NotePlacement::RightOf => centers[*last] + 10.0,
+                        NotePlacement::Over => {
+                            // A note over several participants reaches a bit past their lifelines
+                            width = width.max(centers[*last] - centers[*first] + 40.0);
+                            (centers[*first] + centers[*last]) / 2.0 - width / 2.0
+                        }
+                    };
+                    rows.push(Item::Rect {
+                        x,
+                        y,
+                        width,
+                        height,
+                        rounded: false,
+                        style: Style::outline(YELLOW),
+                    });
+                    labels.text(
+                        x + width / 2.0,
+                        y + height / 2.0,
+                        text,
+                        Anchor::Middle,
+                        false,
+                    );
+                    y += height + MESSAGE_GAP;
+                }
+            }
+        }
+        let bottom = y + 6.0;
+
+        // Each participant has a lifeline with its box at the top and at the bottom
+        let mut drawing = Drawing::default();
+        for (idx, participant) in self.participants.iter().enumerate() {
+            let (x, (width, height)) = (centers[idx], heads[idx]);
+            drawing.items.push(Item::Line {
+                points: vec![(x, head_height), (x, bottom)],
+                style: Style::line(true),
+                arrow: Arrow::None,
+            });
+            for top in [head_height - height, bottom] {
+                drawing.items.push(Item::Rect {
+                    x: x - width / 2.0,
+                    y: top,
+                    width,
+                    height,
+                    rounded: false,
+                    style: Style::outline(WHITE),
+                });
+                labels.text(
+                    x,
+                    top + height / 2.0,
+                    &participant.label,
+                    Anchor::Middle,
+                    false,
+                );
+            }
+        }
+        drawing.items.extend(rows);
+        drawing.items.extend(labels.items);
+
+        drawing.fit(10.0);
+        drawing
+    }
+
+    /// Place the participants from left to right, so that there is room for their boxes and for
+    /// the text of the messages and notes between them
+    fn columns(&self, heads: &[(f64, f64)]) -> Vec<f64> {
+        let count = self.participants.len();
+        let mut gaps: Vec<f64> = heads
+            .windows(2)
+            .map(|pair| (pair[0].0 + pair[1].0) / 2.0 + PARTICIPANT_GAP)
+            .collect();
+
+        // Each constraint is a minimum distance between two participants
+        let mut constraints: Vec<(usize, usize, f64)> = vec![];
+        for statement in &self.statements {
+            match statement {
+                Statement::Message { from, to, text, .. } if from == to => {
+                    if *from + 1 < count {
+                        let width = SELF_WIDTH + 16.0 + text_size(text).0;
+                        let next_head = heads[*from + 1].0 / 2.0;
+                        constraints.push((*from, *from + 1, width + next_head));
+                    }
+                }
+                Statement::Message { from, to, text, .. } => {
+                    let (first, last) = ((*from).min(*to), (*from).max(*to));
+                    constraints.push((first, last, text_size(text).0 + 24.0));
+                }
+                Statement::Note {
+                    placement,
+                    first,
+                    last,
+                    text,
+                } => {
+                    let (width, _) = note_size(text);
+                    match placement {
+                        NotePlacement::LeftOf if *first > 0 => {
+                            constraints.push((*first - 1, *first, width + 30.0));
+                        }
+                        NotePlacement::RightOf if *last + 1 < count => {
+                            constraints.push((*last, *last + 1, width + 30.0));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        // Shorter spans are handled first, so that the extra room is put where it is needed most
+        constraints.sort_by_key(|(first, last, _)| last - first);
+        for (first, last, distance) in constraints {
+            let current: f64 = gaps[first..last].iter().sum();
+            if current < distance {
+                gaps[last - 1] += distance - current;
+            }
+        }
+
+        let mut centers = vec![heads[0].0 / 2.0];
+        for gap in gaps {
+            centers.push(centers[centers.len() - 1] + gap);
+        }
+        centers
+    }
+}
+
+fn note_size(text: &str) -> (f64, f64) {
+    let (width, height) = text_size(text);
+    (width + 2.0 * NOTE_PADDING, height + NOTE_PADDING)
+}
@@ -0,0 +1,671 @@
+//! A docx file is a zip archive of XML parts, where the contents of the document are in
+//! `word/document.xml`. Transforms to docx output WordprocessingML for the body of that part, and
+//! the "docx-archive" output filter packs the body together with the other parts. Since parents
+//! are transformed before their children, transforms can't know what they will contain, so the
+//! filter also fixes up the body:
+//!
+//! * Paragraphs in paragraphs are split up. A paragraph that is nested in an empty paragraph
+//!   (such as a paragraph in a list item) takes over the properties of the outer one, and any
+//!   later paragraphs in it get the same properties except for the numbering.
+//! * Runs outside of paragraphs, and text outside of runs, are put in paragraphs and runs.
+//! * Table cells that don't end with a paragraph get an empty one, as Word requires.
+//!
+//! The filter also handles a few elements with the `mm` prefix, which are removed from the
+//! output:
+//!
+//! * `<mm:b>`, `<mm:i>`, `<mm:u>`, `<mm:strike>`, `<mm:sup>` and `<mm:sub>` add their run
+//!   properties to all runs within them, together with the run properties of the run itself. A
+//!   property of the run itself takes precedence over the same property of a tag.
+//! * `<mm:num id="…" format="…" start="…" text="…"/>` defines a numbering, where format is a
+//!   `w:numFmt` such as `decimal` or `bullet` and text is the text of bullets. Paragraphs use it
+//!   by its id in `<w:numId w:val="…"/>`, and its levels are indented by `w:ilvl`.
+//! * `<mm:media id="…" name="…" data="…"/>` adds an image, given as base64, to the media of the
+//!   document with the relationship id that is used in `r:embed` of the drawing.
+
+use std::io::{Cursor, Write};
+
+use base64::{engine::general_purpose, Engine as _};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+const WORD_NAMESPACES: &str = concat!(
+    r#"xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" "#,
+    r#"xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" "#,
+    r#"xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" "#,
+    r#"xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" "#,
+    r#"xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture""#,
+);
+
+const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n";
+
+const STYLES: &str = include_str!("styles.xml");
+
+/// Pack the body of a document into a docx file, encoded as base64
+pub fn archive(body: &str) -> Result<String, String> {
+    let document = Document::from_body(body);
+    document
+        .to_zip()
+        .map(|bytes| general_purpose::STANDARD.encode(bytes))
+        .map_err(|error| format!("Could not create the docx file: {error}"))
+}
+
+struct Numbering {
+    id: String,
+    format: String,
+    start: u32,
+    text: String,
+}
+
+struct Media {
+    id: String,
+    name: String,
+    data: Vec<u8>,
+}
+
+struct Document {
+    body: String,
+    numberings: Vec<Numbering>,
+    media: Vec<Media>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// The paragraph hasn't been written yet, since nothing has been added to it
+    Pending,
+    Open,
+    /// The paragraph has been closed since a block was nested in it. It is continued if more
+    /// content is added to it.
+    Suspended,
+}
+
+struct Paragraph {
+    properties: String,
+    state: State,
+}
+
+/// Something that contains paragraphs, which is either the body or a table cell
+#[derive(Default)]
+struct Container {
+    paragraphs: Vec<Paragraph>,
+    ends_with_paragraph: bool,
+}
+
+#[derive(Default)]
+struct Normalizer {
+    output: String,
+    containers: Vec<Container>,
+    run_properties: Vec<String>,
+    numberings: Vec<Numbering>,
+    media: Vec<Media>,
+}
+
+enum Token<'a> {
+    Tag(&'a str),
+    Text(&'a str),
+}
+
+/// Split XML into tags and the text between them
+fn tokenize(xml: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut rest = xml;
+    while !rest.is_empty() {
+        if rest.starts_with('<') {
+            let end = rest.find('>').map(|idx| idx + 1).unwrap_or(rest.len());
+            tokens.push(Token::Tag(&rest[..end]));
+            rest = &rest[end..];
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            tokens.push(Token::Text(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+    tokens
+}
+
+/// Get the name of the element of a tag
+fn tag_name(tag: &str) -> &str {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .next()
+        .unwrap_or("")
+}
+
+fn is_closing(tag: &str) -> bool {
+    tag.starts_with("</")
+}
+
+fn is_self_closing(tag: &str) -> bool {
+    tag.ends_with("/>")
+}
+
+/// Get the value of an attribute of a tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!(" {name}=\""))? + name.len() + 3;
+    let end = tag[start..].find('"')? + start;
+    Some(&tag[start..end])
+}
+
+/// Copy an element, starting at its opening tag, and return the number of tokens it spans
+fn copy_element(tokens: &[Token], output: &mut String) -> usize {
+    let Some(Token::Tag(opening)) = tokens.first() else {
+        return 1;
+    };
+    let name = tag_name(opening);
+    let mut depth = 0;
+    for (idx, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Tag(tag) => {
+                output.push_str(tag);
+                if tag_name(tag) != name || is_self_closing(tag) {
+                    if idx == 0 {
+                        return 1;
+                    }
+                } else if is_closing(tag) {
+                    depth -= 1;
+                    if depth == 0 {
+                        return idx + 1;
+                    }
+                } else {
+                    depth += 1;
+                }
+            }
+            Token::Text(text) => output.push_str(text),
+        }
+    }
+    tokens.len()
+}
+
+/// Get the contents of an element, without its own tags
+fn inner_xml(element: &str) -> &str {
+    let start = element.find('>').map(|idx| idx + 1).unwrap_or(0);
+    let end = element.rfind("</").unwrap_or(element.len());
+    &element[start.min(end)..end]
+}
+
+/// Get the properties of a paragraph that continues another paragraph after a nested block, which
+/// shouldn't be numbered again
+fn continued(properties: &str) -> String {
+    match (properties.find("<w:numPr>"), properties.find("</w:numPr>")) {
+        (Some(start), Some(end)) => {
+            format!("{}{}", &properties[..start], &properties[end + "</w:numPr>".len()..])
+        }
+        _ => properties.to_string(),
+    }
+}
+
+/// Elements that contain the properties of tables, rows and cells, which are copied as they are
+const TABLE_PROPERTIES: [&str; 4] = ["w:tblPr", "w:tblGrid", "w:trPr", "w:tcPr"];
+
+/// Elements that may be both in paragraphs and directly in the body
+const NEUTRAL: [&str; 3] = ["w:bookmarkStart", "w:bookmarkEnd", "w:tr"];
+
+/// The run properties that tags may add
+const RUN_PROPERTIES: [(&str, &str); 6] = [
+    ("b", "<w:b/>"),
+    ("i", "<w:i/>"),
+    ("strike", "<w:strike/>"),
+    ("u", "<w:u w:val=\"single\"/>"),
+    ("sup", "<w:vertAlign w:val=\"superscript\"/>"),
+    ("sub", "<w:vertAlign w:val=\"subscript\"/>"),
+];
+
+/// The order of run properties in the schema of WordprocessingML
+const RUN_PROPERTY_ORDER: [&str; 32] = [
+    "w:rStyle",
+    "w:rFonts",
+    "w:b",
+    "w:bCs",
+    "w:i",
+    "w:iCs",
+    "w:caps",
+    "w:smallCaps",
+    "w:strike",
+    "w:dstrike",
+    "w:outline",
+    "w:shadow",
+    "w:emboss",
+    "w:imprint",
+    "w:noProof",
+    "w:snapToGrid",
+    "w:vanish",
+    "w:webHidden",
+    "w:color",
+    "w:spacing",
+    "w:w",
+    "w:kern",
+    "w:position",
+    "w:sz",
+    "w:szCs",
+    "w:highlight",
+    "w:u",
+    "w:effect",
+    "w:bdr",
+    "w:shd",
+    "w:fitText",
+    "w:vertAlign",
+];
+
+impl Normalizer {
+    fn normalize(mut self, body: &str) -> Document {
+        let tokens = tokenize(body);
+        self.containers.push(Container::default());
+
+        let mut idx = 0;
+        while idx < tokens.len() {
+            match tokens[idx] {
+                Token::Text(text) if text.trim().is_empty() => self.output.push_str(text),
+                Token::Text(text) => {
+                    self.open_paragraph();
+                    let properties = self.run_properties_xml("");
+                    let text = text.trim_matches('\n');
+                    self.output.push_str(&format!(
+                        "<w:r>{properties}<w:t xml:space=\"preserve\">{text}</w:t></w:r>"
+                    ));
+                }
+                Token::Tag(tag) => {
+                    let name = tag_name(tag);
+                    match name {
+                        "w:p" if !is_closing(tag) => {
+                            // The paragraph properties are kept with the paragraph, since it
+                            // may be written later or be continued
+                            let mut properties = String::new();
+                            if is_self_closing(tag) {
+                                self.start_paragraph(properties);
+                                self.end_paragraph();
+                            } else {
+                                if matches!(tokens.get(idx + 1), Some(Token::Tag(t)) if tag_name(t) == "w:pPr")
+                                {
+                                    idx += 1;
+                                    idx += copy_element(&tokens[idx..], &mut properties) - 1;
+                                }
+                                self.start_paragraph(properties);
+                            }
+                        }
+                        "w:p" => self.end_paragraph(),
+                        "w:tbl" if !is_closing(tag) => {
+                            self.suspend_paragraph();
+                            self.output.push_str(tag);
+                        }
+                        "w:tbl" => {
+                            self.output.push_str(tag);
+                            self.containers.last_mut().unwrap().ends_with_paragraph = false;
+                        }
+                        "w:tc" if !is_closing(tag) => {
+                            self.output.push_str(tag);
+                            self.containers.push(Container::default());
+                        }
+                        "w:tc" => {
+                            self.end_container();
+                            self.output.push_str(tag);
+                        }
+                        _ if TABLE_PROPERTIES.contains(&name) => {
+                            idx += copy_element(&tokens[idx..], &mut self.output) - 1;
+                        }
+                        _ if NEUTRAL.contains(&name) => self.output.push_str(tag),
+                        "w:r" if !is_closing(tag) && !is_self_closing(tag) => {
+                            self.open_paragraph();
+                            self.output.push_str(tag);
+                            // The run properties of tags are merged with those of the run
+                            let mut own = String::new();
+                            if matches!(tokens.get(idx + 1), Some(Token::Tag(t)) if tag_name(t) == "w:rPr")
+                            {
+                                idx += 1;
+                                idx += copy_element(&tokens[idx..], &mut own) - 1;
+                            }
+                            let properties = self.run_properties_xml(inner_xml(&own));
+                            self.output.push_str(&properties);
+                        }
+                        _ if name.starts_with("mm:") => self.handle_marker(tag),
+                        "w:t" | "w:instrText" if !is_closing(tag) && !is_self_closing(tag) => {
+                            // The text of runs is copied as it is
+                            self.open_paragraph();
+                            self.output.push_str(tag);
+                            while let Some(Token::Text(text)) = tokens.get(idx + 1) {
+                                self.output.push_str(text);
+                                idx += 1;
+                            }
+                        }
+                        _ => {
+                            self.open_paragraph();
+                            self.output.push_str(tag);
+                        }
+                    }
+                }
+            }
+            idx += 1;
+        }
+
+        while !self.containers.is_empty() {
+            self.end_container();
+        }
+
+        // The numberings are referred to by their index, in the order they were defined
+        let mut body = self.output;
+        for (idx, numbering) in self.numberings.iter().enumerate() {
+            body = body.replace(
+                &format!("<w:numId w:val=\"{}\"/>", numbering.id),
+                &format!("<w:numId w:val=\"{}\"/>", idx + 1),
+            );
+        }
+
+        Document {
+            body,
+            numberings: self.numberings,
+            media: self.media,
+        }
+    }
+
+    fn container(&mut self) -> &mut Container {
+        self.containers.last_mut().unwrap()
+    }
+
+    fn start_paragraph(&mut self, mut properties: String) {
+        let mut output = String::new();
+        let container = self.container();
+        if let Some(parent) = container.paragraphs.last_mut() {
+            match parent.state {
+                State::Open => {
+                    output.push_str("</w:p>");
+                    parent.state = State::Suspended;
+                    container.ends_with_paragraph = true;
+                }
+                State::Pending => {
+                    if properties.is_empty() {
+                        properties = parent.properties.clone();
+                    }
+                    parent.state = State::Suspended;
+                }
+                State::Suspended => {
+                    if properties.is_empty() {
+                        properties = continued(&parent.properties);
+                    }
+                }
+            }
+        }
+        container.paragraphs.push(Paragraph {
+            properties,
+            state: State::Pending,
+        });
+        self.output.push_str(&output);
+    }
+
+    fn end_paragraph(&mut self) {
+        let mut output = String::new();
+        let container = self.container();
+        if let Some(paragraph) = container.paragraphs.pop() {
+            match paragraph.state {
+                State::Open => output.push_str("</w:p>"),
+                State::Pending => {
+                    output.push_str(&format!("<w:p>{}</w:p>", paragraph.properties));
+                }
+                State::Suspended => {}
+            }
+            if paragraph.state != State::Suspended {
+                container.ends_with_paragraph = true;
+            }
+        }
+        self.output.push_str(&output);
+    }
+
+    /// Make sure that the current paragraph is open, so that runs can be added to it
+    fn open_paragraph(&mut self) {
+        let mut output = String::new();
+        let container = self.container();
+        if container.paragraphs.is_empty() {
+            container.paragraphs.push(Paragraph {
+                properties: String::new(),
+                state: State::Pending,
+            });
+        }
+        let paragraph = container.paragraphs.last_mut().unwrap();
+        match paragraph.state {
+            State::Open => {}
+            State::Pending => output.push_str(&format!("<w:p>{}", paragraph.properties)),
+            State::Suspended => {
+                output.push_str(&format!("<w:p>{}", continued(&paragraph.properties)));
+            }
+        }
+        paragraph.state = State::Open;
+        container.ends_with_paragraph = false;
+        self.output.push_str(&output);
+    }
+
+    /// Close the current paragraph before a block that can't be in it, such as a table
+    fn suspend_paragraph(&mut self) {
+        let mut output = String::new();
+        let container = self.container();
+        if let Some(paragraph) = container.paragraphs.last_mut() {
+            match paragraph.state {
+                State::Open => output.push_str("</w:p>"),
+                State::Pending => {
+                    output.push_str(&format!("<w:p>{}</w:p>", paragraph.properties));
+                }
+                State::Suspended => {}
+            }
+            paragraph.state = State::Suspended;
+        }
+        self.output.push_str(&output);
+    }
+
+    fn end_container(&mut self) {
+        while !self.container().paragraphs.is_empty() {
+            self.end_paragraph();
+        }
+        let container = self.containers.pop().unwrap();
+        // Table cells must end with a paragraph
+        if !container.ends_with_paragraph && !self.containers.is_empty() {
+            self.output.push_str("<w:p/>");
+        }
+    }
+
+    fn handle_marker(&mut self, tag: &str) {
+        let name = &tag_name(tag)["mm:".len()..];
+        match name {
+            "num" => self.numberings.push(Numbering {
+                id: attribute(tag, "id").unwrap_or_default().to_string(),
+                format: attribute(tag, "format").unwrap_or("decimal").to_string(),
+                start: attribute(tag, "start")
+                    .and_then(|start| start.parse().ok())
+                    .unwrap_or(1),
+                text: attribute(tag, "text").unwrap_or("\u{2022}").to_string(),
+            }),
+            "media" => {
+                let data = attribute(tag, "data").unwrap_or_default();
+                match general_purpose::STANDARD.decode(data) {
+                    Ok(data) => self.media.push(Media {
+                        id: attribute(tag, "id").unwrap_or_default().to_string(),
+                        name: attribute(tag, "name").unwrap_or_default().to_string(),
+                        data,
+                    }),
+                    Err(_) => eprintln!("Could not decode the image data of a docx document"),
+                }
+            }
+            _ if is_closing(tag) => {
+                if let Some(idx) = self.run_properties.iter().rposition(|p| p == name) {
+                    self.run_properties.remove(idx);
+                }
+            }
+            _ => self.run_properties.push(name.to_string()),
+        }
+    }
+
+    /// Get the run properties of a run, given the contents of its own `w:rPr`
+    fn run_properties_xml(&self, own: &str) -> String {
+        let mut properties: Vec<(&str, &str)> = tokenize(own)
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Tag(tag) => Some((tag_name(tag), tag)),
+                Token::Text(_) => None,
+            })
+            .collect();
+
+        // Only the innermost of superscript and subscript is used
+        let vertical_alignment = self
+            .run_properties
+            .iter()
+            .rev()
+            .find(|p| *p == "sub" || *p == "sup");
+        for (name, xml) in RUN_PROPERTIES {
+            let active = if name == "sub" || name == "sup" {
+                vertical_alignment.map(String::as_str) == Some(name)
+            } else {
+                self.run_properties.iter().any(|p| p == name)
+            };
+            if active && !properties.iter().any(|(own, _)| *own == tag_name(xml)) {
+                properties.push((tag_name(xml), xml));
+            }
+        }
+
+        if properties.is_empty() {
+            return String::new();
+        }
+        // Word requires the properties to be in the order of the schema
+        properties.sort_by_key(|(name, _)| {
+            RUN_PROPERTY_ORDER
+                .iter()
+                .position(|n| n == name)
+                .unwrap_or(RUN_PROPERTY_ORDER.len())
+        });
+        let properties: String = properties.into_iter().map(|(_, xml)| xml).collect();
+        format!("<w:rPr>{properties}</w:rPr>")
+    }
+}
+
+impl Document {
+    fn from_body(body: &str) -> Self {
+        Normalizer::default().normalize(body)
+    }
+
+    fn to_zip(&self) -> zip::result::ZipResult<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        zip.start_file("[Content_Types].xml", options)?;
+        zip.write_all(self.content_types().as_bytes())?;
+        zip.start_file("_rels/.rels", options)?;
+        zip.write_all(PACKAGE_RELATIONSHIPS.as_bytes())?;
+        zip.start_file("word/document.xml", options)?;
+        zip.write_all(self.document().as_bytes())?;
+        zip.start_file("word/_rels/document.xml.rels", options)?;
+        zip.write_all(self.relationships().as_bytes())?;
+        zip.start_file("word/styles.xml", options)?;
+        zip.write_all(STYLES.as_bytes())?;
+        zip.start_file("word/numbering.xml", options)?;
+        zip.write_all(self.numbering().as_bytes())?;
+        for media in &self.media {
+            zip.start_file(format!("word/media/{}", media.name), options)?;
+            zip.write_all(&media.data)?;
+        }
+
+        Ok(zip.finish()?.into_inner())
+    }
+
+    fn content_types(&self) -> String {
+        let mut extensions: Vec<&str> = self
+            .media
+            .iter()
+            .filter_map(|media| media.name.rsplit_once('.').map(|(_, ext)| ext))
+            .collect();
+        extensions.sort_unstable();
+        extensions.dedup();
+
+        let defaults: String = extensions
+            .into_iter()
+            .map(|ext| {
+                let content_type = match ext {
+                    "jpg" | "jpeg" => "image/jpeg",
+                    "gif" => "image/gif",
+                    _ => "image/png",
+                };
+                format!("<Default Extension=\"{ext}\" ContentType=\"{content_type}\"/>")
+            })
+            .collect();
+
+        let main = "application/vnd.openxmlformats-officedocument.wordprocessingml";
+        format!(
+            "{XML_DECLARATION}<Types \
+            xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+            <Default Extension=\"rels\" \
+            ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+            <Default Extension=\"xml\" ContentType=\"application/xml\"/>{defaults}\
+            <Override PartName=\"/word/document.xml\" \
+            ContentType=\"{main}.document.main+xml\"/>\
+            <Override PartName=\"/word/styles.xml\" ContentType=\"{main}.styles+xml\"/>\
+            <Override PartName=\"/word/numbering.xml\" ContentType=\"{main}.numbering+xml\"/>\
+            </Types>"
+        )
+    }
+
+    fn document(&self) -> String {
+        format!(
+            "{XML_DECLARATION}<w:document {WORD_NAMESPACES}><w:body>\n{}\n\
+            <w:sectPr><w:pgSz w:w=\"11906\" w:h=\"16838\"/>\
+            <w:pgMar w:top=\"1440\" w:right=\"1440\" w:bottom=\"1440\" w:left=\"1440\" \
+            w:header=\"708\" w:footer=\"708\" w:gutter=\"0\"/></w:sectPr>\
+            </w:body></w:document>",
+            self.body.trim()
+        )
+    }
+
+    fn relationships(&self) -> String {
+        let base = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+        let media: String = self
+            .media
+            .iter()
+            .map(|media| {
+                format!(
+                    "<Relationship Id=\"{}\" Type=\"{base}/image\" Target=\"media/{}\"/>",
+                    media.id, media.name
+                )
+            })
+            .collect();
+        format!(
+            "{XML_DECLARATION}<Relationships \
+            xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+            <Relationship Id=\"rIdStyles\" Type=\"{base}/styles\" Target=\"styles.xml\"/>\
+            <Relationship Id=\"rIdNumbering\" Type=\"{base}/numbering\" \
+            Target=\"numbering.xml\"/>{media}</Relationships>"
+        )
+    }
+
+    fn numbering(&self) -> String {
+        let mut abstract_nums = String::new();
+        let mut nums = String::new();
+        for (idx, numbering) in self.numberings.iter().enumerate() {
+            abstract_nums.push_str(&format!(
+                "<w:abstractNum w:abstractNumId=\"{idx}\">\
+                <w:multiLevelType w:val=\"multilevel\"/>"
+            ));
+            for level in 0..9 {
+                let text = if numbering.format == "bullet" {
+                    numbering.text.clone()
+                } else {
+                    format!("%{}.", level + 1)
+                };
+                abstract_nums.push_str(&format!(
+                    "<w:lvl w:ilvl=\"{level}\"><w:start w:val=\"{}\"/>\
+                    <w:numFmt w:val=\"{}\"/><w:lvlText w:val=\"{text}\"/>\
+                    <w:lvlJc w:val=\"left\"/><w:pPr>\
+                    <w:ind w:left=\"{}\" w:hanging=\"360\"/></w:pPr></w:lvl>",
+                    numbering.start,
+                    numbering.format,
+                    720 * (level + 1)
+                ));
+            }
+            abstract_nums.push_str("</w:abstractNum>");
+            nums.push_str(&format!(
+                "<w:num w:numId=\"{}\"><w:abstractNumId w:val=\"{idx}\"/></w:num>",
+                idx + 1
+            ));
+        }
+        format!("{XML_DECLARATION}<w:numbering {WORD_NAMESPACES}>{abstract_nums}{nums}</w:numbering>")
+    }
+}
+
+const PACKAGE_RELATIONSHIPS: &str = concat!(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n",
+    "<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">",
+    "<Relationship Id=\"rIdDocument\" ",
+    "Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" ",
+    "Target=\"word/document.xml\"/></Relationships>"
+);
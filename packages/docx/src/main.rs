@@ -0,0 +1,420 @@
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, Read},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, json, to_value, Value};
+
+mod archive;
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum JsonEntry {
+    ParentNode {
+        name: String,
+        arguments: HashMap<String, Value>,
+        children: Vec<Self>,
+        id: u64,
+    },
+    Module {
+        name: String,
+        #[serde(default)]
+        data: String,
+        #[serde(default)]
+        arguments: HashMap<String, Value>,
+        #[serde(default = "default_inline")]
+        inline: bool,
+    },
+    Compound(Vec<Self>),
+    Raw(String),
+}
+
+/// This is just a helper to ensure that omitted "inline" fields
+/// default to true.
+fn default_inline() -> bool {
+    true
+}
+
+macro_rules! inline_content {
+    ($expr:expr) => {
+        json!({
+            "name": "inline_content",
+            "data": $expr
+        })
+    }
+}
+
+macro_rules! block_content {
+    ($expr:expr) => {
+        json!({
+            "name": "block_content",
+            "data": $expr
+        })
+    }
+}
+
+macro_rules! dynamic_content {
+    ($cond:expr, $expr:expr) => {
+        if $cond {
+            block_content!($expr)
+        } else {
+            inline_content!($expr)
+        }
+    };
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let Some(action) = args.first() else {
+        eprintln!("No action was provided.");
+        return;
+    };
+
+    match action.as_str() {
+        "manifest" => print!("{}", &manifest()),
+        "transform" => {
+            let from = args.get(1).unwrap();
+            let format = args.get(2).unwrap();
+
+            if "docx" != format {
+                eprintln!("Output format not supported");
+                return;
+            }
+
+            print!("{}", transform(from));
+        }
+        other => eprintln!("Invalid action '{other}'"),
+    }
+}
+
+fn transform(from: &str) -> String {
+    let input: JsonEntry = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        from_str(&buffer).unwrap()
+    };
+
+    // The run properties of tags are applied to all runs within them by the archive filter, since
+    // runs can't be nested
+    match from {
+        "__bold" => transform_tag(input, "b"),
+        "__italic" => transform_tag(input, "i"),
+        "__superscript" => transform_tag(input, "sup"),
+        "__subscript" => transform_tag(input, "sub"),
+        "__underlined" => transform_tag(input, "u"),
+        "__strikethrough" => transform_tag(input, "strike"),
+        "__verbatim" => transform_verbatim(input),
+        "__paragraph" => transform_paragraph(to_value(input).unwrap()),
+        "__document" => transform_document(to_value(input).unwrap()),
+        "__math" => transform_math(to_value(input).unwrap()),
+        "__text" => escape_text(to_value(input).unwrap()),
+        "__heading" => transform_heading(to_value(input).unwrap()),
+        "docx-archive" => transform_archive(to_value(input).unwrap()),
+        _ => panic!("element not supported"),
+    }
+}
+
+fn transform_paragraph(paragraph: Value) -> String {
+    let mut result = vec![json!("<w:p>")];
+    if let Value::Array(children) = &paragraph["children"] {
+        result.extend(children.iter().cloned());
+    }
+    result.push(json!("</w:p>\n"));
+
+    serde_json::to_string(&result).unwrap()
+}
+
+fn transform_tag(node: JsonEntry, property: &str) -> String {
+    let mut result: Vec<Value> = vec![];
+    result.push(Value::from(format!("<mm:{property}>")));
+
+    match node {
+        JsonEntry::ParentNode { children, .. } => {
+            result.extend(children.into_iter().map(|x| to_value(x).unwrap()));
+        }
+        JsonEntry::Module { data, inline, .. } => {
+            result.push(dynamic_content!(inline, data));
+        }
+        _ => {}
+    }
+
+    result.push(Value::from(format!("</mm:{property}>")));
+    serde_json::to_string(&result).unwrap()
+}
+
+fn transform_verbatim(node: JsonEntry) -> String {
+    let result = match node {
+        JsonEntry::Module { data, inline, .. } => {
+            if inline {
+                vec![Value::from(code_run(&data))]
+            } else {
+                vec![Value::from(format!(
+                    "<w:p><w:pPr><w:pStyle w:val=\"SourceCode\"/></w:pPr>{}</w:p>\n",
+                    code_run(&data)
+                ))]
+            }
+        }
+        JsonEntry::ParentNode { children, .. } => {
+            // Verbatim is evaluated before its children, so all text is collected into one run
+            let mut content = String::new();
+            for child in children {
+                match child {
+                    JsonEntry::Module { name, data, .. } if name == "__text" => {
+                        content.push_str(&data);
+                    }
+                    JsonEntry::Module { name, .. } | JsonEntry::ParentNode { name, .. } => {
+                        eprintln!("Modules are not allowed in verbatim tags; found module {name}");
+                    }
+                    _ => {}
+                }
+            }
+            vec![Value::from(code_run(&content))]
+        }
+        _ => vec![],
+    };
+    serde_json::to_string(&result).unwrap()
+}
+
+/// Make a run with monospace text, where each line of the text is separated by a line break
+fn code_run(text: &str) -> String {
+    let lines = text
+        .lines()
+        .map(|line| format!("<w:t xml:space=\"preserve\">{}</w:t>", escape_xml(line)))
+        .collect::<Vec<_>>()
+        .join("<w:br/>");
+    format!("<w:r><w:rPr><w:rStyle w:val=\"VerbatimChar\"/></w:rPr>{lines}</w:r>")
+}
+
+fn transform_heading(heading: Value) -> String {
+    let mut vec = vec![];
+
+    let Value::String(s) = &heading["arguments"]["level"] else {
+        panic!();
+    };
+    let level = s.parse::<u8>().unwrap();
+    if level > 6 {
+        eprintln!("Only headings up to level 6 are styled in Word documents");
+    }
+    let level = level.clamp(1, 6);
+    let id = heading["id"].as_u64().unwrap();
+    let key = format!("heading:{id}");
+    let heading_style = env::var("heading_style").unwrap_or_default();
+
+    vec.push(json!(format!(
+        "<w:p><w:pPr><w:pStyle w:val=\"Heading{level}\"/></w:pPr>"
+    )));
+
+    let element = if heading_style == "numbered" {
+        vec.push(inline_content!(format!("[element-number]({key}) ")));
+        "numbered-heading"
+    } else {
+        "unnumbered-heading"
+    };
+
+    if let Value::Array(children) = &heading["children"] {
+        for child in children {
+            vec.push(child.clone());
+        }
+    }
+
+    vec.push(json!("</w:p>\n"));
+
+    let structure_data = json!({
+        "element": element,
+        "level": level,
+        "key": key,
+        "contents": heading["children"],
+    })
+    .to_string();
+
+    vec.push(json!(
+        {
+            "name": "list-push",
+            "arguments": {"name": "structure"},
+            "data": structure_data,
+        }
+    ));
+
+    serde_json::to_string(&vec).unwrap()
+}
+
+fn transform_math(node: Value) -> String {
+    // We know that the math tag is a non-recursively parsed tag, which means that it may only
+    // contain __text and modules. For now, we collect all __text nodes and
+    if let Value::Array(children) = &node["children"] {
+        let mut content = String::new();
+        for child in children {
+            let name = child["name"].as_str().unwrap();
+            if name == "__text" {
+                content.push_str(child["data"].as_str().unwrap());
+            } else {
+                eprintln!("Modules are not allowed in math tags; found module {name}");
+            }
+        }
+        if content.is_empty() {
+            format!("{}", json!([]))
+        } else {
+            format!(
+                "{}",
+                json!([
+                    {
+                      "name": "math",
+                      "data": content,
+                      "arguments": {},
+                      "inline": true
+                    }
+                ])
+            )
+        }
+    } else {
+        eprintln!("Unexpected __math structure");
+        String::new()
+    }
+}
+
+fn transform_document(mut doc: Value) -> String {
+    let mut result: Vec<Value> = vec![];
+
+    if let Some(vec) = doc.get_mut("children").and_then(Value::as_array_mut) {
+        result.append(vec);
+    }
+
+    serde_json::to_string(&result).unwrap()
+}
+
+fn escape_text(module: Value) -> String {
+    if let Value::String(s) = &module["data"] {
+        // Line breaks within paragraphs are just whitespace, like in HTML
+        let s = escape_xml(&s.replace('\n', " "));
+        let run = format!("<w:r><w:t xml:space=\"preserve\">{s}</w:t></w:r>");
+        format!("{}", json! {[{"name":"raw","data":run}]})
+    } else {
+        panic!("Malformed text module");
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn transform_archive(output: Value) -> String {
+    let body = output["data"].as_str().unwrap();
+    match archive::archive(body) {
+        Ok(archive) => serde_json::to_string(&json!([archive])).unwrap(),
+        Err(error) => {
+            // Nothing is printed to stdout, so that the filter fails and the error is reported
+            eprintln!("{error}");
+            String::new()
+        }
+    }
+}
+
+fn manifest() -> String {
+    serde_json::to_string(&json!(
+        {
+            "version": "0.1",
+            "name": "docx",
+            "description": "This packages provides Word document (docx) support for the basic Modmark features.",
+            "binary-formats": ["docx"],
+            "transforms": [
+                {
+                    "from": "__bold",
+                    "to": ["docx"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__italic",
+                    "to": ["docx"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__superscript",
+                    "to": ["docx"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__subscript",
+                    "to": ["docx"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__strikethrough",
+                    "to": ["docx"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__underlined",
+                    "to": ["docx"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__math",
+                    "to": ["docx"],
+                    "arguments": [],
+                    "evaluate-before-children": true,
+                    "type": "parent"
+                },
+                {
+                    "from": "__document",
+                    "to": ["docx"],
+                    "arguments": [],
+                    "type": "parent"
+                },
+                {
+                    "from": "__text",
+                    "to": ["docx"],
+                    "arguments": []
+                },
+                {
+                    "from": "__paragraph",
+                    "to": ["docx"],
+                    "arguments": [],
+                    "type": "parent"
+                },
+                {
+                    "from": "__verbatim",
+                    "to": ["docx"],
+                    "arguments": [],
+                    "evaluate-before-children": true,
+                    "type": "any"
+                },
+                {
+                    "from": "__heading",
+                    "to": ["docx"],
+                    "arguments": [
+                        {
+                            "name": "level",
+                            "description": "The level of the heading",
+                            "default": "1"
+                        }
+                    ],
+                    "type": "parent",
+                    "variables": {
+                        "structure": {"type": "list", "access": "push"},
+                        "heading_style": {"type": "const", "access": "read"},
+                    },
+                },
+                {
+                    "from": "docx-archive",
+                    "to": ["docx"],
+                    "arguments": [],
+                    "description": "Packs the document body together with its styles, numbering definitions and media into a docx file, which is output as base64",
+                    "type": "output-filter",
+                },
+            ]
+        }
+    ))
+    .unwrap()
+}
@@ -0,0 +1,571 @@
+//! An epub file is a zip archive of XHTML files together with a package document that lists them.
+//! The "epub-archive" output filter takes the output of the HTML transforms, which is used for the
+//! contents of the book, and turns it into such an archive:
+//!
+//! * The document is split into chapters at each level 1 heading, and links to elements in other
+//!   chapters are changed to point to the file of that chapter.
+//! * The HTML is turned into XHTML by closing empty elements such as `<br>`. Scripts are removed,
+//!   since reading systems don't have to run them.
+//! * Images are read and added to the archive, and images that can't be read are replaced by
+//!   their alt text.
+//! * The table of contents within `<mm:nav>` is used for both the navigation document and the
+//!   NCX, which older reading systems use. If it is empty, each chapter is listed instead.
+
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{Cursor, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose, Engine as _};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+<rootfiles>
+<rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+</rootfiles>
+</container>
+"#;
+
+const STYLE: &str = include_str!("style.css");
+
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Pack the output of a document into an epub file, encoded as base64
+pub fn archive(body: &str) -> Result<String, String> {
+    let book = Book::from_body(body);
+    book.to_zip()
+        .map(|bytes| general_purpose::STANDARD.encode(bytes))
+        .map_err(|error| format!("Could not create the epub file: {error}"))
+}
+
+struct Chapter {
+    file: String,
+    title: String,
+    content: String,
+}
+
+struct Image {
+    file: String,
+    media_type: &'static str,
+    data: Vec<u8>,
+}
+
+struct Book {
+    /// The title of the book, as XML text
+    title: String,
+    identifier: String,
+    chapters: Vec<Chapter>,
+    images: Vec<Image>,
+    navigation: String,
+}
+
+enum Token<'a> {
+    Tag(&'a str),
+    Text(&'a str),
+}
+
+/// Split HTML into tags and the text between them
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut rest = html;
+    while !rest.is_empty() {
+        if rest.starts_with('<') {
+            let end = rest.find('>').map(|idx| idx + 1).unwrap_or(rest.len());
+            tokens.push(Token::Tag(&rest[..end]));
+            rest = &rest[end..];
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            tokens.push(Token::Text(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+    tokens
+}
+
+/// Get the name of the element of a tag
+fn tag_name(tag: &str) -> &str {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .next()
+        .unwrap_or("")
+}
+
+fn is_closing(tag: &str) -> bool {
+    tag.starts_with("</")
+}
+
+/// Check if a tag doesn't need to be closed, which is the case for comments, empty elements and
+/// elements that are closed by the tag itself
+fn is_standalone(tag: &str) -> bool {
+    tag.starts_with("<!") || tag.ends_with("/>") || VOID_ELEMENTS.contains(&tag_name(tag))
+}
+
+/// Get the value of an attribute of a tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!(" {name}=\""))? + name.len() + 3;
+    let end = tag[start..].find('"')? + start;
+    Some(&tag[start..end])
+}
+
+/// Get the text of some HTML, without its tags
+fn text_content(html: &str) -> String {
+    tokenize(html)
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Text(text) => Some(text),
+            Token::Tag(_) => None,
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Get the media type of an image from its contents
+fn media_type(data: &[u8]) -> Option<(&'static str, &'static str)> {
+    if data.starts_with(b"\x89PNG") {
+        Some(("image/png", "png"))
+    } else if data.starts_with(&[0xFF, 0xD8]) {
+        Some(("image/jpeg", "jpg"))
+    } else if data.starts_with(b"GIF8") {
+        Some(("image/gif", "gif"))
+    } else if String::from_utf8_lossy(&data[..data.len().min(1024)]).contains("<svg") {
+        Some(("image/svg+xml", "svg"))
+    } else {
+        None
+    }
+}
+
+/// Make a name-based identifier for the book from its contents, in the form of a UUID
+fn identifier(body: &str) -> String {
+    // 64-bit FNV-1a, with two different offsets to get 128 bits
+    let hash = |offset: u64| {
+        body.bytes().fold(offset, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+    };
+    let high = (hash(0xcbf29ce484222325) & !0xF000) | 0x5000;
+    let low = (hash(0x84222325cbf29ce4) & !(0xC << 60)) | (0x8 << 60);
+    format!(
+        "urn:uuid:{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xFFFF,
+        high & 0xFFFF,
+        low >> 48,
+        low & 0xFFFF_FFFF_FFFF
+    )
+}
+
+/// Get the current time in the format that is used for the modification date of the book
+fn modified() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let (days, time) = (seconds / 86400, seconds % 86400);
+
+    // Convert the days since 1970-01-01 to a date in the Gregorian calendar
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+impl Book {
+    fn from_body(body: &str) -> Self {
+        let (body, navigation) = match (body.find("<mm:nav>"), body.find("</mm:nav>")) {
+            (Some(start), Some(end)) if start < end => (
+                format!("{}{}", &body[..start], &body[end + "</mm:nav>".len()..]),
+                body[start + "<mm:nav>".len()..end].to_string(),
+            ),
+            _ => (body.to_string(), String::new()),
+        };
+
+        let mut book = Book {
+            title: String::new(),
+            identifier: identifier(&body),
+            chapters: vec![],
+            images: vec![],
+            navigation: String::new(),
+        };
+        let mut contents = vec![String::new()];
+        let mut depth = 0_usize;
+        let mut warned_script = false;
+        let tokens = tokenize(&body);
+        let mut tokens = tokens.iter();
+
+        while let Some(token) = tokens.next() {
+            let tag = match token {
+                Token::Text(text) => {
+                    contents
+                        .last_mut()
+                        .unwrap()
+                        .push_str(&text.replace("&nbsp;", "&#160;"));
+                    continue;
+                }
+                Token::Tag(tag) => *tag,
+            };
+
+            let name = tag_name(tag);
+            if name == "script" && !is_closing(tag) {
+                if !warned_script {
+                    eprintln!("Scripts are not included in epub documents");
+                    warned_script = true;
+                }
+                if !tag.ends_with("/>") {
+                    for token in tokens.by_ref() {
+                        if matches!(token, Token::Tag(tag) if tag.starts_with("</script")) {
+                            break;
+                        }
+                    }
+                }
+                continue;
+            }
+
+            if name == "h1" && !is_closing(tag) && depth == 0 {
+                contents.push(String::new());
+            }
+            if !is_standalone(tag) {
+                if is_closing(tag) {
+                    depth = depth.saturating_sub(1);
+                } else {
+                    depth += 1;
+                }
+            }
+
+            let content = contents.last_mut().unwrap();
+            if name == "img" {
+                book.push_image(tag, content);
+            } else if VOID_ELEMENTS.contains(&name) && !tag.ends_with("/>") {
+                content.push_str(tag.trim_end_matches('>'));
+                content.push_str("/>");
+            } else {
+                content.push_str(tag);
+            }
+        }
+
+        // The content before the first heading is only a chapter if there is something in it
+        if contents.len() > 1 && text_content(&contents[0]).is_empty() && !contents[0].contains('<')
+        {
+            contents.remove(0);
+        }
+
+        for (idx, content) in contents.into_iter().enumerate() {
+            let title = match (content.find("<h1"), content.find("</h1>")) {
+                (Some(start), Some(end)) if start < end => text_content(&content[start..end]),
+                _ => String::new(),
+            };
+            if book.title.is_empty() {
+                book.title = title.clone();
+            }
+            book.chapters.push(Chapter {
+                file: format!("chapter{}.xhtml", idx + 1),
+                title,
+                content,
+            });
+        }
+        if book.title.is_empty() {
+            book.title = "Document".to_string();
+        }
+
+        book.resolve_links();
+        book.navigation = book.resolve_navigation(&navigation);
+        book
+    }
+
+    /// Add the image of an `img` tag to the book, and write the tag with the source changed to
+    /// the file in the book. If the image can't be read, its alt text is written instead
+    fn push_image(&mut self, tag: &str, output: &mut String) {
+        let src = attribute(tag, "src").unwrap_or("");
+        let alt = attribute(tag, "alt").unwrap_or("");
+
+        let data = if let Some(encoded) = src.strip_prefix("data:") {
+            encoded.split_once("base64,").and_then(|(_, data)| {
+                general_purpose::STANDARD_NO_PAD
+                    .decode(data.trim_end_matches('='))
+                    .ok()
+            })
+        } else if src.starts_with("http://") || src.starts_with("https://") {
+            eprintln!("Remote images can't be included in epub documents: {src}");
+            None
+        } else {
+            fs::read(src)
+                .map_err(|_| eprintln!("Image could not be accessed at {src}"))
+                .ok()
+        };
+        let Some((data, (media_type, ext))) =
+            data.and_then(|data| media_type(&data).map(|media_type| (data, media_type)))
+        else {
+            write!(output, "<span class=\"missing-image\">{alt}</span>").unwrap();
+            return;
+        };
+
+        let file = match self.images.iter().find(|image| image.data == data) {
+            Some(image) => image.file.clone(),
+            None => {
+                let file = format!("images/image{}.{ext}", self.images.len() + 1);
+                self.images.push(Image {
+                    file: file.clone(),
+                    media_type,
+                    data,
+                });
+                file
+            }
+        };
+
+        let tag = tag.replacen(&format!(" src=\"{src}\""), &format!(" src=\"{file}\""), 1);
+        if tag.ends_with("/>") {
+            output.push_str(&tag);
+        } else {
+            output.push_str(tag.trim_end_matches('>'));
+            output.push_str("/>");
+        }
+    }
+
+    /// Find the chapter that an element with the given id is in
+    fn chapter_of(&self, id: &str) -> Option<usize> {
+        let attribute = format!(" id=\"{id}\"");
+        self.chapters
+            .iter()
+            .position(|chapter| chapter.content.contains(&attribute))
+    }
+
+    /// Change links to elements in other chapters to point to the file of that chapter
+    fn resolve_links(&mut self) {
+        for idx in 0..self.chapters.len() {
+            let content = &self.chapters[idx].content;
+            let mut resolved = String::with_capacity(content.len());
+            let mut rest = content.as_str();
+            while let Some(start) = rest.find(" href=\"#") {
+                let start = start + " href=\"".len();
+                resolved.push_str(&rest[..start]);
+                rest = &rest[start..];
+                let end = rest.find('"').unwrap_or(rest.len());
+                match self.chapter_of(&rest[1..end]) {
+                    Some(target) if target != idx => {
+                        resolved.push_str(&self.chapters[target].file);
+                    }
+                    _ => {}
+                }
+            }
+            resolved.push_str(rest);
+            self.chapters[idx].content = resolved;
+        }
+    }
+
+    /// Make the list of the navigation document, where links point to the files of the chapters
+    fn resolve_navigation(&self, navigation: &str) -> String {
+        if !navigation.contains("<li") {
+            let mut list = String::from("<ol>");
+            for chapter in &self.chapters {
+                let title = if chapter.title.is_empty() {
+                    &self.title
+                } else {
+                    &chapter.title
+                };
+                write!(list, "<li><a href=\"{}\">{title}</a></li>", chapter.file).unwrap();
+            }
+            list.push_str("</ol>");
+            return list;
+        }
+
+        let mut resolved = String::new();
+        let mut rest = navigation;
+        while let Some(start) = rest.find(" href=\"#") {
+            let start = start + " href=\"".len();
+            resolved.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest.find('"').unwrap_or(rest.len());
+            match self.chapter_of(&rest[1..end]) {
+                Some(target) => resolved.push_str(&self.chapters[target].file),
+                None => {
+                    // Link to the start of the book rather than an element that doesn't exist
+                    resolved.push_str(&self.chapters[0].file);
+                    rest = &rest[end..];
+                }
+            }
+        }
+        resolved.push_str(rest);
+        resolved
+    }
+
+    fn to_zip(&self) -> zip::result::ZipResult<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+        let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        // The mimetype must be the first file, and must not be compressed
+        zip.start_file("mimetype", stored)?;
+        zip.write_all(b"application/epub+zip")?;
+        zip.start_file("META-INF/container.xml", options)?;
+        zip.write_all(CONTAINER.as_bytes())?;
+        zip.start_file("OEBPS/content.opf", options)?;
+        zip.write_all(self.package_document().as_bytes())?;
+        zip.start_file("OEBPS/nav.xhtml", options)?;
+        zip.write_all(self.navigation_document().as_bytes())?;
+        zip.start_file("OEBPS/toc.ncx", options)?;
+        zip.write_all(self.ncx().as_bytes())?;
+        zip.start_file("OEBPS/style.css", options)?;
+        zip.write_all(STYLE.as_bytes())?;
+        for chapter in &self.chapters {
+            zip.start_file(format!("OEBPS/{}", chapter.file), options)?;
+            zip.write_all(self.xhtml(&chapter.title, &chapter.content).as_bytes())?;
+        }
+        for image in &self.images {
+            zip.start_file(format!("OEBPS/{}", image.file), options)?;
+            zip.write_all(&image.data)?;
+        }
+
+        Ok(zip.finish()?.into_inner())
+    }
+
+    fn xhtml(&self, title: &str, content: &str) -> String {
+        let title = if title.is_empty() { &self.title } else { title };
+        format!(
+            "{XML_DECLARATION}<!DOCTYPE html>\n\
+            <html xmlns=\"http://www.w3.org/1999/xhtml\" \
+            xmlns:epub=\"http://www.idpf.org/2007/ops\" xml:lang=\"en\" lang=\"en\">\n\
+            <head>\n<meta charset=\"UTF-8\"/>\n<title>{title}</title>\n\
+            <link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n\
+            <body>\n<section>\n{content}\n</section>\n</body>\n</html>\n"
+        )
+    }
+
+    fn navigation_document(&self) -> String {
+        let navigation = format!(
+            "<nav epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n{}\n</nav>",
+            self.navigation
+        );
+        self.xhtml("Contents", &navigation)
+    }
+
+    fn package_document(&self) -> String {
+        let mut manifest = String::from(
+            "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" \
+            properties=\"nav\"/>\n\
+            <item id=\"ncx\" href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\"/>\n\
+            <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n",
+        );
+        let mut spine = String::new();
+
+        for (idx, chapter) in self.chapters.iter().enumerate() {
+            let mut properties = vec![];
+            if chapter.content.contains("<math") {
+                properties.push("mathml");
+            }
+            if chapter.content.contains("<svg") {
+                properties.push("svg");
+            }
+            let properties = if properties.is_empty() {
+                String::new()
+            } else {
+                format!(" properties=\"{}\"", properties.join(" "))
+            };
+            writeln!(
+                manifest,
+                "<item id=\"chapter{}\" href=\"{}\" media-type=\"application/xhtml+xml\"\
+                {properties}/>",
+                idx + 1,
+                chapter.file
+            )
+            .unwrap();
+            writeln!(spine, "<itemref idref=\"chapter{}\"/>", idx + 1).unwrap();
+        }
+        for (idx, image) in self.images.iter().enumerate() {
+            writeln!(
+                manifest,
+                "<item id=\"image{}\" href=\"{}\" media-type=\"{}\"/>",
+                idx + 1,
+                image.file,
+                image.media_type
+            )
+            .unwrap();
+        }
+
+        format!(
+            "{XML_DECLARATION}<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" \
+            unique-identifier=\"book-id\" xml:lang=\"en\">\n\
+            <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n\
+            <dc:identifier id=\"book-id\">{}</dc:identifier>\n\
+            <dc:title>{}</dc:title>\n\
+            <dc:language>en</dc:language>\n\
+            <meta property=\"dcterms:modified\">{}</meta>\n\
+            </metadata>\n<manifest>\n{manifest}</manifest>\n\
+            <spine toc=\"ncx\">\n{spine}</spine>\n</package>\n",
+            self.identifier,
+            self.title,
+            modified()
+        )
+    }
+
+    /// Make the NCX from the list of the navigation document, where each list item is a point
+    fn ncx(&self) -> String {
+        let mut points = String::new();
+        let mut count = 0;
+        let mut depth = 0;
+        let mut max_depth = 1;
+        let mut link: Option<(&str, String)> = None;
+
+        for token in tokenize(&self.navigation) {
+            match (token, link.as_mut()) {
+                (Token::Text(text), Some((_, label))) => label.push_str(text),
+                (Token::Tag(tag), Some(_)) if tag_name(tag) == "a" && is_closing(tag) => {
+                    let (href, label) = link.take().unwrap();
+                    count += 1;
+                    writeln!(
+                        points,
+                        "<navPoint id=\"point{count}\" playOrder=\"{count}\">\
+                        <navLabel><text>{}</text></navLabel><content src=\"{href}\"/>",
+                        label.split_whitespace().collect::<Vec<_>>().join(" ")
+                    )
+                    .unwrap();
+                }
+                (Token::Tag(tag), None) if tag_name(tag) == "a" && !is_closing(tag) => {
+                    link = Some((attribute(tag, "href").unwrap_or(""), String::new()));
+                }
+                (Token::Tag(tag), None) if tag_name(tag) == "li" => {
+                    if is_closing(tag) {
+                        points.push_str("</navPoint>\n");
+                        depth -= 1;
+                    } else {
+                        depth += 1;
+                        max_depth = max_depth.max(depth);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        format!(
+            "{XML_DECLARATION}<ncx xmlns=\"http://www.daisy.org/z3986/2005/ncx/\" \
+            version=\"2005-1\">\n\
+            <head>\n<meta name=\"dtb:uid\" content=\"{}\"/>\n\
+            <meta name=\"dtb:depth\" content=\"{max_depth}\"/>\n\
+            <meta name=\"dtb:totalPageCount\" content=\"0\"/>\n\
+            <meta name=\"dtb:maxPageNumber\" content=\"0\"/>\n</head>\n\
+            <docTitle><text>{}</text></docTitle>\n<navMap>\n{points}</navMap>\n</ncx>\n",
+            self.identifier, self.title
+        )
+    }
+}
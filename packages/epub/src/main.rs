@@ -0,0 +1,189 @@
+use std::{
+    env,
+    io::{self, Read},
+};
+
+use serde_json::{from_str, json, Value};
+
+mod archive;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let Some(action) = args.first() else {
+        eprintln!("No action was provided.");
+        return;
+    };
+
+    match action.as_str() {
+        "manifest" => print!("{}", &manifest()),
+        "transform" => {
+            let from = args.get(1).unwrap();
+            let format = args.get(2).unwrap();
+
+            if "epub" != format {
+                eprintln!("Output format not supported");
+                return;
+            }
+
+            print!("{}", transform(from));
+        }
+        other => eprintln!("Invalid action '{other}'"),
+    }
+}
+
+fn transform(from: &str) -> String {
+    let input: Value = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        from_str(&buffer).unwrap()
+    };
+
+    // All other elements are transformed by their HTML transforms, since epub is based on html
+    match from {
+        "__document" => transform_document(input),
+        "epub-navigation" => transform_navigation(),
+        "epub-archive" => transform_archive(input),
+        _ => panic!("element not supported"),
+    }
+}
+
+fn transform_document(mut doc: Value) -> String {
+    let mut result: Vec<Value> = vec![];
+
+    if let Some(vec) = doc.get_mut("children").and_then(Value::as_array_mut) {
+        result.append(vec);
+    }
+
+    // The navigation is evaluated after all headings have been added to the structure, and the
+    // archive filter moves it to its own file
+    result.push(json!({
+        "name": "epub-navigation",
+        "arguments": {},
+        "data": "",
+        "inline": false,
+    }));
+
+    serde_json::to_string(&result).unwrap()
+}
+
+/// Make the table of contents of the book from the headings in the structure list. Entries that
+/// skip a level are nested in the closest entry above them, since each level of the navigation
+/// must be a list of links.
+fn transform_navigation() -> String {
+    let mut result = vec![json!("<mm:nav><ol>")];
+    let mut counters: Vec<u64> = vec![];
+    // The levels of the list items that are open, and whether they have an open sublist
+    let mut open: Vec<(u64, bool)> = vec![];
+
+    for entry in structure_list() {
+        let numbered = match entry["element"].as_str() {
+            Some("numbered-heading") => true,
+            Some("unnumbered-heading") => false,
+            _ => continue,
+        };
+        let level = entry["level"].as_u64().unwrap_or(1).max(1);
+        let key = entry["key"].as_str().unwrap_or("");
+
+        while let Some(&(open_level, has_list)) = open.last() {
+            if open_level < level {
+                break;
+            }
+            if has_list {
+                result.push(json!("</ol>"));
+            }
+            result.push(json!("</li>"));
+            open.pop();
+        }
+        if let Some((_, has_list)) = open.last_mut() {
+            if !*has_list {
+                result.push(json!("<ol>"));
+                *has_list = true;
+            }
+        }
+
+        result.push(json!(format!("<li><a href=\"#{key}\">")));
+        if numbered {
+            let level = level as usize;
+            counters.resize(level.max(counters.len()), 0);
+            counters[level - 1] += 1;
+            counters.truncate(level);
+            let number: Vec<String> = counters.iter().map(u64::to_string).collect();
+            result.push(json!(format!("{} ", number.join("."))));
+        }
+        result.push(entry["contents"].clone());
+        result.push(json!("</a>"));
+        open.push((level, false));
+    }
+
+    while let Some((_, has_list)) = open.pop() {
+        if has_list {
+            result.push(json!("</ol>"));
+        }
+        result.push(json!("</li>"));
+    }
+    result.push(json!("</ol></mm:nav>"));
+
+    serde_json::to_string(&result).unwrap()
+}
+
+/// Get the entries of the "structure" list, which are pushed as JSON strings by headings, figures
+/// and tables
+fn structure_list() -> Vec<Value> {
+    let var = env::var("structure").unwrap_or("[]".to_string());
+    from_str::<Vec<String>>(&var)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| from_str(entry).ok())
+        .collect()
+}
+
+fn transform_archive(output: Value) -> String {
+    let body = output["data"].as_str().unwrap();
+    match archive::archive(body) {
+        Ok(archive) => serde_json::to_string(&json!([archive])).unwrap(),
+        Err(error) => {
+            // Nothing is printed to stdout, so that the filter fails and the error is reported
+            eprintln!("{error}");
+            String::new()
+        }
+    }
+}
+
+fn manifest() -> String {
+    serde_json::to_string(&json!(
+        {
+            "version": "0.1",
+            "name": "epub",
+            "description": "This package provides EPUB 3 e-book support. All elements without an epub transform use their HTML transform.",
+            "binary-formats": ["epub"],
+            "base-formats": {"epub": "html"},
+            "transforms": [
+                {
+                    "from": "__document",
+                    "to": ["epub"],
+                    "arguments": [],
+                    "type": "parent"
+                },
+                {
+                    "from": "epub-navigation",
+                    "to": ["epub"],
+                    "arguments": [],
+                    "description": "The table of contents of the book, which is added to the end of the document and moved to the navigation document by the archive filter",
+                    "type": "module",
+                    "variables": {
+                        "structure": {"type": "list", "access": "read"}
+                    }
+                },
+                {
+                    "from": "epub-archive",
+                    "to": ["epub"],
+                    "arguments": [],
+                    "description": "Splits the document into chapters at level 1 headings, and packs them together with the navigation, the package document and images into an epub file, which is output as base64",
+                    "type": "output-filter"
+                }
+            ]
+        }
+    ))
+    .unwrap()
+}
@@ -43,12 +43,12 @@ fn manifest() {
             "transforms": [
                 {
                     "from": "textfile",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "markdown", "plaintext", "docx", "typst", "pandoc-json"],
                     "arguments": [],
                 },
                 {
                     "from": "image",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "markdown", "plaintext", "docx", "typst", "pandoc-json"],
                     "type": "multiline-module",
                     "arguments": [
                         {"name": "alt", "default": "", "description": "Alternative text for the image"},
@@ -70,7 +70,10 @@ fn manifest() {
                                 "\
                                 The width of the image resulting image. \
                                 For LaTeX this is ratio to the document's text area width. \
-                                For HTML this is ratio to the width of the surrounding figure tag (created automatically).\
+                                For HTML this is ratio to the width of the surrounding figure tag (created automatically). \
+                                For docx this is ratio to the width of the page's text area. \
+                                For Typst this is ratio to the width of the surrounding container. \
+                                For Pandoc JSON this is ratio to the width of the page's text area.\
                                 "
                         },
                         {
@@ -123,7 +126,7 @@ fn transform(from: &str, to: &str) {
 
 fn transform_text(input: Value, to: &str) {
     match to {
-        "html" | "latex" => {
+        "html" | "latex" | "markdown" | "plaintext" | "docx" | "typst" | "pandoc-json" => {
             let path = input["data"].as_str().unwrap().trim();
             if let Ok(contents) = fs::read_to_string(path) {
                 let text = json!([{"name": "__text", "data": contents}]);
@@ -176,30 +179,8 @@ fn transform_image(input: Value, to: &str) {
             })
             .to_string();
 
-            let img_src = if embed == "false" {
-                String::from(path)
-            } else {
-                let read_res = fs::read(path);
-                let ext_opt = Path::new(path).extension().and_then(OsStr::to_str);
-                if let Ok(contents) = read_res {
-                    let encoded: String = general_purpose::STANDARD_NO_PAD.encode(contents);
-                    if let Some(ext) = ext_opt {
-                        match ext {
-                            "svg" => format!("data:image/svg+xml;base64,{encoded}"),
-                            "jpg" | "jpeg" | "png" => format!("data:image/png;base64,{encoded}"),
-                            _ => {
-                                eprintln!("Unexpected file extension.");
-                                format!("data:image/png;base64,{encoded}")
-                            }
-                        }
-                    } else {
-                        eprintln!("File type could not be inferred from path.");
-                        format!("data:image/png;base64,{encoded}")
-                    }
-                } else {
-                    eprintln!("File could not be accessed at {path}.");
-                    return;
-                }
+            let Some(img_src) = image_source(path, embed) else {
+                return;
             };
 
             let fig_str = format!("<figure {style}>");
@@ -213,7 +194,7 @@ fn transform_image(input: Value, to: &str) {
             if !caption.is_empty() {
                 let caption_str = format!("<figcaption style=\"text-align: {cap_align}\">");
                 v.push(json!(caption_str));
-                if env::var("caption_style").unwrap_or(String::new()) == "numbered" {
+                if env::var("caption_style").unwrap_or_default() == "numbered" {
                     v.push(inline_content!(format!("**Figure [element-number]({label}):** ")));
                 }
                 v.push(json!({"name": "inline_content", "data": caption}));
@@ -277,12 +258,381 @@ fn transform_image(input: Value, to: &str) {
 
             print!("{}", json!(v));
         }
+        "markdown" => {
+            let path = input["data"].as_str().unwrap().trim();
+            let alt = input["arguments"]["alt"].as_str().unwrap();
+            if alt.is_empty() {
+                eprintln!("Missing alt text");
+            }
+            let caption = input["arguments"]["caption"].as_str().unwrap();
+            let label = input["arguments"]["label"].as_str().unwrap();
+            let embed = input["arguments"]["embed"].as_str().unwrap();
+            if input["arguments"]["width"].as_f64() != Some(1.0) {
+                eprintln!("Width is not supported in Markdown");
+            }
+
+            let Some(img_src) = image_source(path, embed) else {
+                return;
+            };
+            // Paths with spaces or parentheses have to be enclosed in angle brackets
+            let img_src = if img_src.contains(|c: char| c.is_whitespace() || c == '(' || c == ')')
+            {
+                format!("<{img_src}>")
+            } else {
+                img_src
+            };
+
+            let structure_data = json!({
+                "element": "figure",
+                "key": label,
+            })
+            .to_string();
+
+            let mut v = vec![];
+
+            v.push(json!("\n"));
+            if !label.is_empty() {
+                v.push(json!(format!("<a id=\"{label}\"></a>")));
+            }
+            v.push(json!("!["));
+            v.push(json!({"name": "__text", "data": alt}));
+            v.push(json!(format!("]({img_src})\n")));
+            if !caption.is_empty() {
+                v.push(json!("\n_"));
+                if env::var("caption_style").unwrap_or_default() == "numbered" {
+                    v.push(inline_content!(format!("**Figure [element-number]({label}):** ")));
+                }
+                v.push(json!({"name": "inline_content", "data": caption}));
+                v.push(json!("_\n"));
+            }
+            v.push(json!(
+                {
+                    "name": "list-push",
+                    "arguments": {"name": "structure"},
+                    "data": structure_data,
+                }
+            ));
+            print!("{}", json!(v));
+        }
+        "plaintext" => {
+            let alt = input["arguments"]["alt"].as_str().unwrap();
+            let caption = input["arguments"]["caption"].as_str().unwrap();
+            let label = input["arguments"]["label"].as_str().unwrap();
+
+            let structure_data = json!({
+                "element": "figure",
+                "key": label,
+            })
+            .to_string();
+
+            // The image can't be shown, so it is replaced by its alt text. The markers are used
+            // by the plaintext package to wrap the text
+            let mut v = vec![];
+            v.push(json!("\n\u{E000}\u{E001}[Image: "));
+            if alt.is_empty() {
+                eprintln!("Missing alt text");
+                v.push(json!({"name": "__text", "data": input["data"].as_str().unwrap().trim()}));
+            } else {
+                v.push(json!({"name": "__text", "data": alt}));
+            }
+            v.push(json!("]\u{E002}\n"));
+            if !caption.is_empty() {
+                v.push(json!("\n\u{E000}\u{E001}"));
+                if env::var("caption_style").unwrap_or_default() == "numbered" {
+                    v.push(inline_content!(format!("**Figure [element-number]({label}):** ")));
+                }
+                v.push(json!({"name": "inline_content", "data": caption}));
+                v.push(json!("\u{E002}\n"));
+            }
+            v.push(json!(
+                {
+                    "name": "list-push",
+                    "arguments": {"name": "structure"},
+                    "data": structure_data,
+                }
+            ));
+            print!("{}", json!(v));
+        }
+        "typst" => {
+            let path = input["data"].as_str().unwrap().trim();
+            let alt = input["arguments"]["alt"].as_str().unwrap();
+            if alt.is_empty() {
+                eprintln!("Missing alt text");
+            }
+            let width = input["arguments"]["width"]
+                .as_f64()
+                .unwrap()
+                .clamp(0.0, f64::MAX);
+            let caption = input["arguments"]["caption"].as_str().unwrap();
+            let cap_align = input["arguments"]["caption-alignment"].as_str().unwrap();
+            let label = input["arguments"]["label"].as_str().unwrap();
+
+            let percentage = (width * 100.0).round() as i32;
+            let structure_data = json!({
+                "element": "figure",
+                "key": label,
+            })
+            .to_string();
+
+            let mut v = vec![];
+
+            // Captions are centered by default, and the show rule only applies within the block
+            let aligned = cap_align != "center";
+            if aligned {
+                v.push(json!(format!("\n#[#show figure.caption: set align({cap_align})")));
+            }
+            v.push(json!(format!(
+                "\n#figure(\n  image({}, width: {percentage}%",
+                typst_string(path)
+            )));
+            if !alt.is_empty() {
+                v.push(json!(format!(", alt: {}", typst_string(alt))));
+            }
+            v.push(json!("),\n"));
+            if !caption.is_empty() {
+                v.push(json!("  caption: ["));
+                if env::var("caption_style").unwrap_or_default() == "numbered" {
+                    v.push(inline_content!(format!("**Figure [element-number]({label}):** ")));
+                }
+                v.push(json!({"name": "inline_content", "data": caption}));
+                v.push(json!("],\n"));
+            }
+            // The numbering comes from the structure, like for the other output formats
+            v.push(json!("  numbering: none,\n)"));
+            if !label.is_empty() {
+                v.push(json!(format!(" <{label}>")));
+            }
+            v.push(json!(if aligned { "\n]\n" } else { "\n" }));
+            v.push(json!(
+                {
+                    "name": "list-push",
+                    "arguments": {"name": "structure"},
+                    "data": structure_data,
+                }
+            ));
+            print!("{}", json!(v));
+        }
+        "pandoc-json" => {
+            let path = input["data"].as_str().unwrap().trim();
+            let alt = input["arguments"]["alt"].as_str().unwrap();
+            if alt.is_empty() {
+                eprintln!("Missing alt text");
+            }
+            let width = input["arguments"]["width"]
+                .as_f64()
+                .unwrap()
+                .clamp(0.0, f64::MAX);
+            let caption = input["arguments"]["caption"].as_str().unwrap();
+            let label = input["arguments"]["label"].as_str().unwrap();
+
+            let percentage = (width * 100.0).round() as i32;
+            let structure_data = json!({
+                "element": "figure",
+                "key": label,
+            })
+            .to_string();
+
+            // The label is the identifier of the figure, and the caption is a plain block. Every
+            // element is followed by a comma, which the pandoc package removes at the end of
+            // each list
+            let mut v = vec![];
+            v.push(json!(format!(r#"{{"t":"Figure","c":[[{},[],[]],[null,["#, json!(label))));
+            if !caption.is_empty() {
+                v.push(json!(r#"{"t":"Plain","c":["#));
+                if env::var("caption_style").unwrap_or_default() == "numbered" {
+                    v.push(inline_content!(format!("**Figure [element-number]({label}):** ")));
+                }
+                v.push(json!({"name": "inline_content", "data": caption}));
+                v.push(json!("]},"));
+            }
+            v.push(json!(format!(
+                r#"]],[{{"t":"Plain","c":[{{"t":"Image","c":[["",[],[["width","{percentage}%"]]],["#
+            )));
+            v.push(json!({"name": "__text", "data": alt}));
+            v.push(json!(format!(r#"],[{},""]]}}]}}]]}},"#, json!(path))));
+            v.push(json!(
+                {
+                    "name": "list-push",
+                    "arguments": {"name": "structure"},
+                    "data": structure_data,
+                }
+            ));
+            print!("{}", json!(v));
+        }
+        "docx" => {
+            let path = input["data"].as_str().unwrap().trim();
+            let alt = input["arguments"]["alt"].as_str().unwrap();
+            if alt.is_empty() {
+                eprintln!("Missing alt text");
+            }
+            let width = input["arguments"]["width"]
+                .as_f64()
+                .unwrap()
+                .clamp(0.0, f64::MAX);
+            let caption = input["arguments"]["caption"].as_str().unwrap();
+            let label = input["arguments"]["label"].as_str().unwrap();
+            let id = input["id"].as_u64().unwrap_or_default();
+
+            let structure_data = json!({
+                "element": "figure",
+                "key": label,
+            })
+            .to_string();
+
+            let mut v = vec![];
+            match docx_image(path, id) {
+                Some((media, name, size)) => {
+                    // Word measures drawings in EMU, and the text area of an A4 page is 5731510
+                    // EMU wide
+                    let cx = (width * 5731510.0).round() as u64;
+                    let cy = (cx as f64 * size.1 as f64 / size.0 as f64).round() as u64;
+                    let alt = alt.replace('&', "&amp;").replace('"', "&quot;");
+                    v.push(json!(media));
+                    v.push(json!(format!(
+                        "<w:p><w:pPr><w:pStyle w:val=\"Figure\"/></w:pPr><w:r><w:drawing>\
+                        <wp:inline><wp:extent cx=\"{cx}\" cy=\"{cy}\"/>\
+                        <wp:docPr id=\"{id}\" name=\"{name}\" descr=\"{alt}\"/>\
+                        <a:graphic><a:graphicData \
+                        uri=\"http://schemas.openxmlformats.org/drawingml/2006/picture\">\
+                        <pic:pic><pic:nvPicPr><pic:cNvPr id=\"{id}\" name=\"{name}\"/>\
+                        <pic:cNvPicPr/></pic:nvPicPr><pic:blipFill>\
+                        <a:blip r:embed=\"rIdImage{id}\"/><a:stretch><a:fillRect/></a:stretch>\
+                        </pic:blipFill><pic:spPr><a:xfrm><a:off x=\"0\" y=\"0\"/>\
+                        <a:ext cx=\"{cx}\" cy=\"{cy}\"/></a:xfrm>\
+                        <a:prstGeom prst=\"rect\"><a:avLst/></a:prstGeom></pic:spPr></pic:pic>\
+                        </a:graphicData></a:graphic></wp:inline></w:drawing></w:r></w:p>\n"
+                    )));
+                }
+                None => {
+                    // The image can't be included, so it is replaced by its alt text
+                    v.push(json!("<w:p><w:pPr><w:pStyle w:val=\"Figure\"/></w:pPr>"));
+                    v.push(json!("<w:r><w:t xml:space=\"preserve\">[Image: </w:t></w:r>"));
+                    if alt.is_empty() {
+                        v.push(json!({"name": "__text", "data": path}));
+                    } else {
+                        v.push(json!({"name": "__text", "data": alt}));
+                    }
+                    v.push(json!("<w:r><w:t>]</w:t></w:r></w:p>\n"));
+                }
+            }
+            if !caption.is_empty() {
+                v.push(json!("<w:p><w:pPr><w:pStyle w:val=\"Caption\"/></w:pPr>"));
+                if env::var("caption_style").unwrap_or_default() == "numbered" {
+                    v.push(inline_content!(format!("**Figure [element-number]({label}):** ")));
+                }
+                v.push(json!({"name": "inline_content", "data": caption}));
+                v.push(json!("</w:p>\n"));
+            }
+            v.push(json!(
+                {
+                    "name": "list-push",
+                    "arguments": {"name": "structure"},
+                    "data": structure_data,
+                }
+            ));
+            print!("{}", json!(v));
+        }
         other => {
             eprintln!("Cannot convert file to {other}");
         }
     }
 }
 
+// Read an image to be included in a docx document. The media element that adds it to the
+// document is returned, together with the name of the image and its size in pixels. None is
+// returned if the image can't be read or isn't in a format that Word supports
+fn docx_image(path: &str, id: u64) -> Option<(String, String, (u32, u32))> {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase);
+    let ext = match ext.as_deref() {
+        Some(ext @ ("png" | "jpg" | "jpeg" | "gif")) => ext.to_string(),
+        _ => {
+            eprintln!("Only PNG, JPEG and GIF images can be included in docx documents");
+            return None;
+        }
+    };
+    let Ok(contents) = fs::read(path) else {
+        eprintln!("File could not be accessed at {path}.");
+        return None;
+    };
+    let size = image_size(&contents).unwrap_or_else(|| {
+        eprintln!("The size of the image at {path} could not be read");
+        (4, 3)
+    });
+
+    let name = format!("image{id}.{ext}");
+    let encoded = general_purpose::STANDARD.encode(contents);
+    let media = format!("<mm:media id=\"rIdImage{id}\" name=\"{name}\" data=\"{encoded}\"/>");
+    Some((media, name, size))
+}
+
+// Write text as a Typst string literal
+fn typst_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Read the width and height of a PNG, JPEG or GIF image from its header
+fn image_size(data: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |i: usize| Some(u16::from_be_bytes(data.get(i..i + 2)?.try_into().ok()?) as u32);
+    let be32 = |i: usize| Some(u32::from_be_bytes(data.get(i..i + 4)?.try_into().ok()?));
+    let le16 = |i: usize| Some(u16::from_le_bytes(data.get(i..i + 2)?.try_into().ok()?) as u32);
+
+    let size = if data.starts_with(b"\x89PNG") {
+        (be32(16)?, be32(20)?)
+    } else if data.starts_with(b"GIF8") {
+        (le16(6)?, le16(8)?)
+    } else if data.starts_with(&[0xFF, 0xD8]) {
+        // Look for the start of frame segment, which contains the size
+        let mut i = 2;
+        loop {
+            if *data.get(i)? != 0xFF {
+                return None;
+            }
+            let marker = *data.get(i + 1)?;
+            if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+                break (be16(i + 7)?, be16(i + 5)?);
+            }
+            i += 2 + be16(i + 2)? as usize;
+        }
+    } else {
+        return None;
+    };
+    (size.0 > 0 && size.1 > 0).then_some(size)
+}
+
+// Get the source of an image, which is the image itself encoded as a data URL if it should be
+// embedded. None is returned if the image should be embedded but can't be read
+fn image_source(path: &str, embed: &str) -> Option<String> {
+    if embed == "false" {
+        return Some(String::from(path));
+    }
+
+    let read_res = fs::read(path);
+    let ext_opt = Path::new(path).extension().and_then(OsStr::to_str);
+    if let Ok(contents) = read_res {
+        let encoded: String = general_purpose::STANDARD_NO_PAD.encode(contents);
+        let src = if let Some(ext) = ext_opt {
+            match ext {
+                "svg" => format!("data:image/svg+xml;base64,{encoded}"),
+                "jpg" | "jpeg" | "png" => format!("data:image/png;base64,{encoded}"),
+                _ => {
+                    eprintln!("Unexpected file extension.");
+                    format!("data:image/png;base64,{encoded}")
+                }
+            }
+        } else {
+            eprintln!("File type could not be inferred from path.");
+            format!("data:image/png;base64,{encoded}")
+        };
+        Some(src)
+    } else {
+        eprintln!("File could not be accessed at {path}.");
+        None
+    }
+}
+
 // Because everything inside is reparsed, we do not match against output format
 fn transform_include(input: Value) {
     let path = input["data"].as_str().unwrap().trim();
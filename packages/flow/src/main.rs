@@ -173,6 +173,108 @@ fn manifest() {
                         "$list": {"type": "list", "access": "read"}
                     },
                     "unknown-content": true
+                },
+                {
+                    "from": "for-each",
+                    "to": ["any"],
+                    "description": "Repeats the content for every item in a list, with {{item}} replaced by the item and {{index}} by its position. Example: [for-each authors separator=\", \" last-separator=\" and \"]<{{item}}>",
+                    "arguments": [
+                        {
+                            "name": "list",
+                            "description": "The list to repeat the content for"
+                        },
+                        {
+                            "name": "var",
+                            "description": "The name of the placeholder that is replaced by the item, which is written as {{name}} in the content",
+                            "default": "item"
+                        },
+                        {
+                            "name": "index-var",
+                            "description": "The name of the placeholder that is replaced by the position of the item, starting at 1",
+                            "default": "index"
+                        },
+                        {
+                            "name": "separator",
+                            "description": "Text to put between the items. In a multiline module, every item is on a new line, and the separator is put at the end of every line but the last.",
+                            "default": ""
+                        },
+                        {
+                            "name": "last-separator",
+                            "description": "Text to put between the last two items instead of the separator, such as ' and '. If it is empty, the separator is used.",
+                            "default": ""
+                        },
+                        {
+                            "name": "before-first",
+                            "description": "Text to put before the first item, which is left out if there are no items. In a multiline module, it is on a line of its own.",
+                            "default": ""
+                        },
+                        {
+                            "name": "after-last",
+                            "description": "Text to put after the last item, which is left out if there are no items. In a multiline module, it is on a line of its own.",
+                            "default": ""
+                        },
+                        {
+                            "name": "sort",
+                            "description": "Specifies the order of the items. none keeps the order that the items were added to the list in.",
+                            "default": "none",
+                            "type": ["none", "ascending", "descending"]
+                        }
+                    ],
+                    "variables": {
+                        "$list": {"type": "list", "access": "read"}
+                    },
+                    "unknown-content": true
+                },
+                {
+                    "from": "for-each-set",
+                    "to": ["any"],
+                    "description": "Repeats the content for every item in a set, with {{item}} replaced by the item. Example: [for-each-set keywords separator=\", \"]<*{{item}}*>",
+                    "arguments": [
+                        {
+                            "name": "set",
+                            "description": "The set to repeat the content for"
+                        },
+                        {
+                            "name": "var",
+                            "description": "The name of the placeholder that is replaced by the item, which is written as {{name}} in the content",
+                            "default": "item"
+                        },
+                        {
+                            "name": "index-var",
+                            "description": "The name of the placeholder that is replaced by the position of the item, starting at 1",
+                            "default": "index"
+                        },
+                        {
+                            "name": "separator",
+                            "description": "Text to put between the items. In a multiline module, every item is on a new line, and the separator is put at the end of every line but the last.",
+                            "default": ""
+                        },
+                        {
+                            "name": "last-separator",
+                            "description": "Text to put between the last two items instead of the separator, such as ' and '. If it is empty, the separator is used.",
+                            "default": ""
+                        },
+                        {
+                            "name": "before-first",
+                            "description": "Text to put before the first item, which is left out if there are no items. In a multiline module, it is on a line of its own.",
+                            "default": ""
+                        },
+                        {
+                            "name": "after-last",
+                            "description": "Text to put after the last item, which is left out if there are no items. In a multiline module, it is on a line of its own.",
+                            "default": ""
+                        },
+                        {
+                            "name": "sort",
+                            "description": "Specifies the order of the items. Sets have no order of their own, so they are always sorted.",
+                            "default": "ascending",
+                            "type": ["ascending", "descending"]
+                        }
+                    ],
+                    "variables": {
+                        "$set": {"type": "set", "access": "read"}
+                    },
+                    "unknown-content": true
                 }
             ]
             }
@@ -193,6 +295,8 @@ fn transform(from: &str, to: &str) {
         "if-const" => transform_if_const(input),
         "if-set" => transform_if_collection(input, true),
         "if-list" => transform_if_collection(input, false),
+        "for-each" => transform_for_each(input, false),
+        "for-each-set" => transform_for_each(input, true),
         other => {
             eprintln!("Package does not support {other}");
         }
@@ -302,6 +406,84 @@ fn transform_if_collection(input: Value, is_set: bool) {
     }
 }
 
+fn transform_for_each(input: Value, is_set: bool) {
+    let key = input["arguments"][if is_set { "set" } else { "list" }]
+        .as_str()
+        .unwrap();
+    let mut items: Vec<String> =
+        serde_json::from_str(&env::var(key).unwrap_or("[]".to_string())).unwrap();
+
+    // Items that only differ in case are ordered by case as well, so that the order doesn't depend
+    // on the order of the set
+    let sort = input["arguments"]["sort"].as_str().unwrap();
+    if sort != "none" {
+        items.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()).then(a.cmp(b)));
+    }
+    if sort == "descending" {
+        items.reverse();
+    }
+
+    if items.is_empty() {
+        print!("[]");
+        return;
+    }
+
+    let inline = input["inline"].as_bool().unwrap();
+    let body = input["data"].as_str().unwrap();
+    let placeholder = format!("{{{{{}}}}}", input["arguments"]["var"].as_str().unwrap());
+    let index_placeholder = format!(
+        "{{{{{}}}}}",
+        input["arguments"]["index-var"].as_str().unwrap()
+    );
+    let separator = input["arguments"]["separator"].as_str().unwrap();
+    let last_separator = match input["arguments"]["last-separator"].as_str().unwrap() {
+        "" => separator,
+        last_separator => last_separator,
+    };
+
+    let before_first = input["arguments"]["before-first"].as_str().unwrap();
+    let after_last = input["arguments"]["after-last"].as_str().unwrap();
+
+    let mut content = String::new();
+    if !before_first.is_empty() {
+        content.push_str(before_first);
+        if !inline {
+            content.push('\n');
+        }
+    }
+    for (idx, item) in items.iter().enumerate() {
+        if idx > 0 {
+            content.push_str(if idx == items.len() - 1 {
+                last_separator
+            } else {
+                separator
+            });
+            if !inline {
+                content.push('\n');
+            }
+        }
+        content.push_str(
+            &body
+                .trim_end_matches('\n')
+                .replace(&placeholder, item)
+                .replace(&index_placeholder, &(idx + 1).to_string()),
+        );
+    }
+    if !after_last.is_empty() {
+        if !inline {
+            content.push('\n');
+        }
+        content.push_str(after_last);
+    }
+
+    let json = if inline {
+        json!({"name": "inline_content", "data": content}).to_string()
+    } else {
+        json!({"name": "block_content", "data": content}).to_string()
+    };
+    print!("[{json}]")
+}
+
 fn transform_if(output_format: &str, input: Value) {
     let cmp_format = input["arguments"]["format"]
         .as_str()
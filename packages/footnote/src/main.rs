@@ -0,0 +1,247 @@
+use std::env;
+use std::io::{self, Read};
+
+use serde_json::{from_str, json, Value};
+
+macro_rules! module {
+    ($name:expr, $data:expr $(,$($args:tt)*)?) => {json!({"name": $name $(,"arguments":$($args)*)*, "data": $data})}
+}
+
+macro_rules! push_footnote {
+    ($e:expr) => {module!("list-push", $e, {"name": "footnotes"})}
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let action = &args[0];
+    match action.as_str() {
+        "manifest" => manifest(),
+        "transform" => transform(&args[1], &args[2]),
+        other => {
+            eprintln!("Invalid action {other}")
+        }
+    }
+}
+
+fn manifest() {
+    print!(
+        "{}",
+        serde_json::to_string(&json!(
+            {
+            "name": "footnote",
+            "version": "0.1",
+            "description": "This package supports footnotes. In HTML and EPUB, they are numbered throughout the document and collected at each [endnotes] or at the end of the document, and in LaTeX, they are placed at the bottom of the page.",
+            "transforms": [
+                {
+                    "from": "footnote",
+                    "to": ["html", "epub", "latex"],
+                    "arguments": [],
+                    "description": "Adds a footnote with the body as its text. Example: [footnote](This is a footnote)",
+                    "variables": {
+                        "footnotes": {"type": "list", "access": "push"}
+                    },
+                    "type": "inline-module"
+                },
+                {
+                    "from": "endnotes",
+                    "to": ["html", "epub", "latex"],
+                    "arguments": [],
+                    "description": "Collects all footnotes since the previous [endnotes] here, instead of at the end of the document. This is useful to put the notes at the end of each chapter. In LaTeX, footnotes are always at the bottom of the page, so this does nothing",
+                    "variables": {
+                        "footnotes": {"type": "list", "access": "push"}
+                    }
+                },
+                {
+                    "from": "footnote-number",
+                    "to": ["html", "epub"],
+                    "arguments": [],
+                    "variables": {
+                        "footnotes": {"type": "list", "access": "read"}
+                    }
+                },
+                {
+                    "from": "endnotes-internal-do-not-use",
+                    "to": ["html", "epub"],
+                    "arguments": [],
+                    "description": "Do not use this module",
+                    "variables": {
+                        "footnotes": {"type": "list", "access": "read"}
+                    }
+                },
+                {
+                    "from": "trailing-endnotes",
+                    "to": ["html", "epub"],
+                    "arguments": [],
+                    "description": "Adds an [endnotes] at the end of the document, so that footnotes after the last [endnotes] are listed there",
+                    "type": "filter"
+                }
+            ]
+            }
+        ))
+        .unwrap()
+    );
+}
+
+fn transform(from: &str, to: &str) {
+    let input: Value = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        from_str(&buffer).unwrap()
+    };
+
+    match from {
+        "footnote" => transform_footnote(input, to),
+        "endnotes" => transform_endnotes(input, to),
+        "footnote-number" => transform_footnote_number(input),
+        "endnotes-internal-do-not-use" => transform_endnotes_list(input),
+        "trailing-endnotes" => transform_trailing_endnotes(input),
+        other => {
+            eprintln!("Package does not support {other}");
+        }
+    }
+}
+
+fn transform_footnote(input: Value, to: &str) {
+    let contents = input["data"].as_str().unwrap();
+
+    match to {
+        "html" | "epub" => {
+            let id = input["id"].as_u64().unwrap();
+            let entry = json!({
+                "element": "footnote",
+                "key": format!("footnote:{id}"),
+                "contents": module!("inline_content", contents),
+            })
+            .to_string();
+
+            // The reference and the note link to each other, so that the reader can go back to
+            // where they were in the text
+            let output = json!([
+                format!("<sup class=\"footnote-ref\"><a id=\"footnote-ref:{id}\" href=\"#footnote:{id}\">"),
+                module!("footnote-number", format!("footnote:{id}")),
+                "</a></sup>",
+                push_footnote!(entry),
+            ]);
+            print!("{output}");
+        }
+        "latex" => {
+            let output = json!(["\\footnote{", module!("inline_content", contents), "}"]);
+            print!("{output}");
+        }
+        other => eprintln!("Cannot convert footnote to {other}!"),
+    }
+}
+
+fn transform_endnotes(input: Value, to: &str) {
+    match to {
+        "html" | "epub" => {
+            let id = input["id"].as_u64().unwrap();
+            let key = format!("endnotes:{id}");
+            let entry = json!({"element": "endnotes", "key": key}).to_string();
+
+            // The notes are listed by another module, since this one can't read the list that it
+            // pushes to
+            let output = json!([
+                push_footnote!(entry),
+                module!("endnotes-internal-do-not-use", key),
+            ]);
+            print!("{output}");
+        }
+        "latex" => print!("[]"),
+        other => eprintln!("Cannot convert endnotes to {other}!"),
+    }
+}
+
+// The filter is given the whole document, which is a list if the document is a compound, and the
+// [endnotes] is added as its last element. Core gives the document its id, so only the name,
+// arguments and children are output. If the document already ends with an [endnotes], the
+// added one has no footnotes to list and outputs nothing.
+fn transform_trailing_endnotes(mut document: Value) {
+    let endnotes = json!({"name": "endnotes", "arguments": {}, "data": "", "inline": false});
+    let output = match &mut document {
+        Value::Array(elements) => {
+            elements.push(endnotes);
+            document
+        }
+        Value::Object(node) => {
+            let mut children = match node.remove("children") {
+                Some(Value::Array(children)) => children,
+                _ => vec![],
+            };
+            children.push(endnotes);
+            json!([{"name": node["name"], "arguments": node["arguments"], "children": children}])
+        }
+        _ => {
+            eprintln!("Cannot add endnotes to the document");
+            return;
+        }
+    };
+    print!("{output}");
+}
+
+// Footnotes are numbered throughout the document, so the number is the position of the footnote
+// among all footnotes in the list
+fn transform_footnote_number(input: Value) {
+    let key = input["data"].as_str().unwrap();
+    let position = get_footnote_list()
+        .iter()
+        .filter(|entry| entry["element"].as_str() == Some("footnote"))
+        .position(|entry| entry["key"].as_str() == Some(key));
+
+    match position {
+        Some(index) => print!("{}", json!([(index + 1).to_string()])),
+        None => eprintln!("Cannot find footnote with key={key}"),
+    }
+}
+
+// List the footnotes between the previous [endnotes] and the one with the given key. If there are
+// no such footnotes, nothing is output.
+fn transform_endnotes_list(input: Value) {
+    let key = input["data"].as_str().unwrap();
+    let mut number = 0;
+    let mut notes: Vec<Value> = vec![];
+
+    for entry in get_footnote_list() {
+        match entry["element"].as_str() {
+            Some("footnote") => {
+                number += 1;
+                notes.push(entry);
+            }
+            Some("endnotes") if entry["key"].as_str() == Some(key) => break,
+            Some("endnotes") => notes.clear(),
+            _ => {}
+        }
+    }
+
+    if notes.is_empty() {
+        print!("[]");
+        return;
+    }
+
+    // The list starts at the number of the first note, so that the numbers match the references
+    let start = number - notes.len() + 1;
+    let mut output = vec![json!(format!(
+        "<section class=\"footnotes\">\n<hr>\n<ol start=\"{start}\">\n"
+    ))];
+    for note in notes {
+        let note_key = note["key"].as_str().unwrap_or_default();
+        let ref_key = note_key.replacen("footnote:", "footnote-ref:", 1);
+        output.push(json!(format!("<li id=\"{note_key}\">")));
+        output.push(note["contents"].clone());
+        output.push(json!(format!(" <a href=\"#{ref_key}\">↩</a></li>\n")));
+    }
+    output.push(json!("</ol>\n</section>\n"));
+
+    print!("{}", serde_json::to_string(&output).unwrap());
+}
+
+// Get a list of Values from the "footnotes" environment variable, which contains both the
+// footnotes and the [endnotes] in the order they appear in the document
+fn get_footnote_list() -> Vec<Value> {
+    let var = env::var("footnotes").unwrap_or("[]".to_string());
+    from_str::<Vec<String>>(&var)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| from_str(entry).ok())
+        .collect()
+}
@@ -0,0 +1,382 @@
+use std::env;
+use std::io::{self, Read};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, json, Value};
+
+macro_rules! module {
+    ($name:expr, $data:expr $(,$($args:tt)*)?) => {json!({"name": $name $(,"arguments":$($args)*)*, "data": $data})}
+}
+
+macro_rules! import {
+    ($e:expr) => {module!("set-add", $e, {"name": "imports"})}
+}
+
+macro_rules! text {
+    ($e:expr) => {
+        module!("__text", $e)
+    };
+}
+
+/// A term defined by [define-term], which is added to the set glossary_terms as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Term {
+    key: String,
+    long: String,
+    description: String,
+}
+
+/// A use of a term by [term], which is pushed to the list glossary_uses as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TermUse {
+    key: String,
+    id: u64,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let action = &args[0];
+    match action.as_str() {
+        "manifest" => manifest(),
+        "transform" => transform(&args[1], &args[2]),
+        other => {
+            eprintln!("Invalid action {other}")
+        }
+    }
+}
+
+fn manifest() {
+    print!(
+        "{}",
+        serde_json::to_string(&json!(
+            {
+            "name": "glossary",
+            "version": "0.1",
+            "description": "This package supports glossaries and acronyms. Terms are defined with [define-term], used with [term] and listed with [glossary].",
+            "transforms": [
+                {
+                    "from": "define-term",
+                    "to": ["any"],
+                    "arguments": [
+                        {"name": "key", "type": "string", "description": "The short form of the term, which is also used to refer to it. Example: API"},
+                        {"name": "long", "type": "string", "description": "The long form of the term. Example: Application Programming Interface"}
+                    ],
+                    "description": "Defines a term for the glossary. The body is an optional description of the term. Example: [define-term key=API long=\"Application Programming Interface\"]",
+                    "variables": {
+                        "glossary_terms": {"type": "set", "access": "add"}
+                    }
+                },
+                {
+                    "from": "term",
+                    "to": ["html", "latex", "markdown", "plaintext"],
+                    "arguments": [],
+                    "description": "Uses a term defined by [define-term]. The first use shows the long form followed by the short form, and later uses only show the short form. In HTML and Markdown, the term links to its entry in the glossary. In LaTeX, a [glossary] is needed for the terms to be defined. Example: [term] API",
+                    "variables": {
+                        "glossary_uses": {"type": "list", "access": "push"}
+                    }
+                },
+                {
+                    "from": "term-internal-do-not-use",
+                    "to": ["html", "markdown", "plaintext"],
+                    "arguments": [],
+                    "description": "Do not use this module",
+                    "variables": {
+                        "glossary_terms": {"type": "set", "access": "read"},
+                        "glossary_uses": {"type": "list", "access": "read"}
+                    }
+                },
+                {
+                    "from": "glossary",
+                    "to": ["html", "latex", "markdown", "plaintext"],
+                    "arguments": [
+                        {"name": "visibility", "default": "visible", "type": ["visible", "hidden"], "description": "Whether the glossary is shown. In LaTeX, the [glossary] defines the terms, so one is needed even if the list isn't shown"}
+                    ],
+                    "description": "Lists all defined terms, sorted by their short form. In LaTeX, this uses the glossaries package, and makeglossaries must be run before the document is compiled again",
+                    "variables": {
+                        "glossary_terms": {"type": "set", "access": "read"},
+                        "imports": {"type": "set", "access": "add"}
+                    }
+                }
+            ]
+            }
+        ))
+        .unwrap()
+    );
+}
+
+fn transform(from: &str, to: &str) {
+    let input: Value = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        from_str(&buffer).unwrap()
+    };
+
+    match from {
+        "define-term" => transform_define_term(input),
+        "term" => transform_term(input, to),
+        "term-internal-do-not-use" => transform_term_label(input, to),
+        "glossary" => transform_glossary(input, to),
+        other => {
+            eprintln!("Package does not support {other}");
+        }
+    }
+}
+
+fn transform_define_term(input: Value) {
+    let key = input["arguments"]["key"].as_str().unwrap().trim();
+    let long = input["arguments"]["long"].as_str().unwrap().trim();
+    let description = input["data"].as_str().unwrap().trim();
+
+    if key.is_empty() {
+        eprintln!("A term must have a key");
+        return;
+    }
+
+    let term = Term {
+        key: key.to_string(),
+        long: long.to_string(),
+        description: description.to_string(),
+    };
+    let entry = serde_json::to_string(&term).unwrap();
+
+    print!(
+        "{}",
+        json!([module!("set-add", entry, {"name": "glossary_terms"})])
+    );
+}
+
+fn transform_term(input: Value, to: &str) {
+    let key = input["data"].as_str().unwrap().trim();
+
+    // LaTeX keeps track of the first use by itself
+    if to == "latex" {
+        print!("{}", json!([format!("\\gls{{{}}}", label(key))]));
+        return;
+    }
+
+    let id = input["id"].as_u64().unwrap();
+    let term_use = serde_json::to_string(&TermUse {
+        key: key.to_string(),
+        id,
+    })
+    .unwrap();
+
+    // Whether this is the first use can only be known when all uses have been pushed, so it is
+    // decided by another module
+    let output = json!([
+        module!("list-push", &term_use, {"name": "glossary_uses"}),
+        module!("term-internal-do-not-use", &term_use),
+    ]);
+    print!("{output}");
+}
+
+fn transform_term_label(input: Value, to: &str) {
+    let term_use: TermUse = from_str(input["data"].as_str().unwrap()).unwrap();
+
+    let Some(term) = get_terms(false)
+        .into_iter()
+        .find(|term| term.key == term_use.key)
+    else {
+        eprintln!(
+            "The term '{}' is not defined, consider adding it with [define-term]",
+            term_use.key
+        );
+        print!("{}", json!([text!(term_use.key)]));
+        return;
+    };
+
+    let first_use = get_uses()
+        .iter()
+        .find(|other| other.key == term_use.key)
+        .is_none_or(|other| other.id == term_use.id);
+    let text = if first_use {
+        format!("{} ({})", term.long, term.key)
+    } else {
+        term.key.clone()
+    };
+
+    let output = match to {
+        "html" => {
+            let link = format!("<a href=\"#{}\">", anchor(&term.key));
+            if first_use {
+                json!([link, text!(text), "</a>"])
+            } else {
+                json!([
+                    format!("{link}<abbr title=\"{}\">", escape_html(&term.long)),
+                    text!(text),
+                    "</abbr></a>"
+                ])
+            }
+        }
+        "markdown" => json!(["[", text!(text), format!("](#{})", anchor(&term.key))]),
+        _ => json!([text!(text)]),
+    };
+    print!("{output}");
+}
+
+fn transform_glossary(input: Value, to: &str) {
+    let is_visible = input["arguments"]["visibility"].as_str() == Some("visible");
+    let terms = get_terms(true);
+
+    let mut output = vec![];
+    if to == "latex" {
+        // The imports are a set without any order, so the definitions are in the same import as
+        // the package that they need
+        let mut preamble = String::from("\\usepackage[acronym]{glossaries}\n\\makeglossaries");
+        for term in &terms {
+            let description = if term.description.is_empty() {
+                String::new()
+            } else {
+                format!("[description={{{}}}]", escape_latex(&term.description))
+            };
+            preamble.push_str(&format!(
+                "\n\\newacronym{description}{{{}}}{{{}}}{{{}}}",
+                label(&term.key),
+                escape_latex(&term.key),
+                escape_latex(&term.long)
+            ));
+        }
+        output.push(import!(preamble));
+    }
+
+    if !is_visible || terms.is_empty() {
+        print!("{}", Value::Array(output));
+        return;
+    }
+
+    match to {
+        "html" => {
+            output.push(json!("<dl class=\"glossary\">\n"));
+            for term in terms {
+                output.push(json!(format!("<dt id=\"{}\">", anchor(&term.key))));
+                output.push(text!(term.key));
+                output.push(json!("</dt>\n<dd>"));
+                output.push(text!(term.long));
+                output.push(json!("</dd>\n"));
+                if !term.description.is_empty() {
+                    output.push(json!("<dd>"));
+                    output.push(module!("inline_content", term.description));
+                    output.push(json!("</dd>\n"));
+                }
+            }
+            output.push(json!("</dl>\n"));
+        }
+        "latex" => output.push(json!("\n\\printglossary[type=\\acronymtype]\n")),
+        "markdown" => {
+            output.push(json!("\n"));
+            for term in terms {
+                output.push(json!(format!("- <a id=\"{}\"></a>**", anchor(&term.key))));
+                output.push(text!(term.key));
+                output.push(json!("**: "));
+                output.push(text!(term.long));
+                if !term.description.is_empty() {
+                    output.push(json!(". "));
+                    output.push(module!("inline_content", term.description));
+                }
+                output.push(json!("\n"));
+            }
+            output.push(json!("\n"));
+        }
+        "plaintext" => {
+            output.push(json!("\n"));
+            for term in terms {
+                output.push(json!(format!("\u{E000}{}: \u{E001}", term.key)));
+                output.push(text!(term.long));
+                if !term.description.is_empty() {
+                    output.push(json!(". "));
+                    output.push(module!("inline_content", term.description));
+                }
+                output.push(json!("\u{E002}\n"));
+            }
+            output.push(json!("\n"));
+        }
+        other => {
+            eprintln!("Cannot convert glossary to {other}!");
+            return;
+        }
+    }
+
+    print!("{}", Value::Array(output));
+}
+
+// Get the terms in the "glossary_terms" set, sorted by their short form. If a key is defined more
+// than once with different forms, only one of the definitions is kept, and a warning is printed if
+// warn_duplicates is set.
+fn get_terms(warn_duplicates: bool) -> Vec<Term> {
+    let var = env::var("glossary_terms").unwrap_or("[]".to_string());
+    let mut terms: Vec<Term> = from_str::<Vec<String>>(&var)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| from_str(entry).ok())
+        .collect();
+
+    terms.sort_by(|a, b| {
+        (a.key.to_lowercase(), &a.key, &a.long, &a.description).cmp(&(
+            b.key.to_lowercase(),
+            &b.key,
+            &b.long,
+            &b.description,
+        ))
+    });
+    terms.dedup_by(|a, b| {
+        let duplicate = a.key == b.key;
+        if duplicate && warn_duplicates {
+            eprintln!("The term '{}' is defined more than once", a.key);
+        }
+        duplicate
+    });
+    terms
+}
+
+// Get the uses of terms in the "glossary_uses" list, in the order they appear in the document
+fn get_uses() -> Vec<TermUse> {
+    let var = env::var("glossary_uses").unwrap_or("[]".to_string());
+    from_str::<Vec<String>>(&var)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| from_str(entry).ok())
+        .collect()
+}
+
+// The label of a term, which only contains characters that are safe to use in ids and LaTeX labels.
+// Every other byte, including '-', is written as '-' and its hex value, so that different keys
+// such as "C++" and "C--" don't get the same label
+fn label(key: &str) -> String {
+    key.bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() {
+                (b as char).to_string()
+            } else {
+                format!("-{b:02X}")
+            }
+        })
+        .collect()
+}
+
+// The id of the entry of a term in the glossary, which terms link to
+fn anchor(key: &str) -> String {
+    format!("glossary:{}", label(key))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_latex(text: &str) -> String {
+    text.split('\\')
+        .map(|t| t.replace('{', r"\{").replace('}', r"\}"))
+        .collect::<Vec<String>>()
+        .join(r"\textbackslash{}")
+        .replace('#', r"\#")
+        .replace('$', r"\$")
+        .replace('%', r"\%")
+        .replace('&', r"\&")
+        .replace('_', r"\_")
+        .replace('<', r"\textless{}")
+        .replace('>', r"\textgreater{}")
+        .replace('~', r"\textasciitilde{}")
+        .replace('^', r"\textasciicircum{}")
+}
@@ -2,6 +2,7 @@ use std::{
     collections::HashMap,
     env,
     fmt::Write,
+    fs,
     io::{self, Read},
 };
 
@@ -16,6 +17,8 @@ enum JsonEntry {
         arguments: HashMap<String, Value>,
         children: Vec<Self>,
         id: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        line: Option<u64>,
     },
     Module {
         name: String,
@@ -25,6 +28,8 @@ enum JsonEntry {
         arguments: HashMap<String, Value>,
         #[serde(default = "default_inline")]
         inline: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        line: Option<u64>,
     },
     Compound(Vec<Self>),
     Raw(String),
@@ -123,16 +128,35 @@ fn transform(from: &str) -> String {
     }
 }
 
+// Get a constant set in the [config] module, if it is set and not empty
+fn constant(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
 fn transform_document(mut doc: Value) -> String {
-    let mut result = vec![raw!(
-        r#"
-<!DOCTYPE html>
-<html>
-<head>
-<title>Document</title>
-<meta charset="UTF-8">
-"#
-    )];
+    // A fragment is only the article, for embedding in existing pages which have their own head
+    // and styling
+    let fragment = constant("html_fragment").as_deref() == Some("true");
+
+    let mut result = vec![];
+    if !fragment {
+        let lang = constant("lang")
+            .map(|lang| format!(" lang=\"{}\"", escape(&lang)))
+            .unwrap_or_default();
+        let title = constant("title").unwrap_or("Document".to_string());
+        result.push(raw!(format!(
+            "\n<!DOCTYPE html>\n<html{lang}>\n<head>\n<title>{}</title>\n<meta charset=\"UTF-8\">\n",
+            escape(&title)
+        )));
+        for name in ["author", "description"] {
+            if let Some(content) = constant(name) {
+                result.push(raw!(format!(
+                    "<meta name=\"{name}\" content=\"{}\">\n",
+                    escape(&content)
+                )));
+            }
+        }
+    }
 
     // Add imports
     let mut imports = {
@@ -141,26 +165,90 @@ fn transform_document(mut doc: Value) -> String {
     };
     result.append(&mut imports);
 
-    result.push(raw!("<style>"));
-    result.push(raw!(include_str!("templates/html.css")));
-    result.push(raw!(
-        r#"
-</style>
-</head>
-<body>
-<article>
-"#
-    ));
+    let mut head_end = String::new();
+    if !fragment {
+        let theme = constant("html_theme").unwrap_or("default".to_string());
+        let mut body_class = "";
+        let css = match theme.as_str() {
+            "default" => Some(include_str!("templates/html.css").to_string()),
+            "dark" => Some(format!(
+                "{}{}",
+                include_str!("templates/html.css"),
+                include_str!("templates/dark.css")
+            )),
+            "auto" => Some(format!(
+                "{}@media (prefers-color-scheme: dark) {{\n{}}}\n",
+                include_str!("templates/html.css"),
+                include_str!("templates/dark.css")
+            )),
+            "latex" | "latex-dark" | "latex-auto" => {
+                body_class = match theme.as_str() {
+                    "latex-dark" => " class=\"latex-dark\"",
+                    "latex-auto" => " class=\"latex-dark-auto\"",
+                    _ => "",
+                };
+                Some(include_str!("templates/latex.css").to_string())
+            }
+            "none" => None,
+            other => {
+                eprintln!(
+                    "Unknown theme '{other}', expected one of default, dark, auto, latex, \
+                    latex-dark, latex-auto or none"
+                );
+                Some(include_str!("templates/html.css").to_string())
+            }
+        };
+        if let Some(css) = css {
+            result.push(raw!("<style>"));
+            result.push(raw!(css));
+            head_end.push_str("\n</style>\n");
+        }
+
+        // The stylesheet of the user comes after the theme, so that it can override it
+        if let Some(path) = constant("stylesheet") {
+            if constant("stylesheet_embed").as_deref() == Some("true") {
+                match fs::read_to_string(&path) {
+                    Ok(css) => write!(head_end, "<style>\n{css}\n</style>\n").unwrap(),
+                    Err(_) => eprintln!("Stylesheet could not be accessed at {path}"),
+                }
+            } else {
+                writeln!(
+                    head_end,
+                    "<link rel=\"stylesheet\" href=\"{}\">",
+                    escape(&path)
+                )
+                .unwrap();
+            }
+        }
+
+        write!(head_end, "</head>\n<body{body_class}>\n").unwrap();
+    }
+    head_end.push_str("<article>\n");
+    result.push(raw!(head_end));
 
     if let Some(children) = doc.get_mut("children") {
         if let Value::Array(ref mut children) = children {
-            result.append(children);
+            for child in children.drain(..) {
+                // Blocks that know their line in the source are wrapped, so that the live
+                // preview can map them back to the source
+                if let Some(line) = child.get("line").and_then(Value::as_u64) {
+                    result.push(raw!(format!("<div data-source-line=\"{line}\">")));
+                    result.push(child);
+                    result.push(raw!("</div>"));
+                } else {
+                    result.push(child);
+                }
+            }
         } else {
             unreachable!("Children is not a list");
         }
     }
 
-    result.push(raw!("</article></body></html>"));
+    result.push(raw!(if fragment {
+        "</article>"
+    } else {
+        "</article></body></html>"
+    }));
 
     serde_json::to_string(&result).unwrap()
 }
@@ -188,7 +276,8 @@ fn transform_heading(heading: Value) -> String {
         "level": level,
         "key": key,
         "contents": heading["children"],
-    }).to_string();
+    })
+    .to_string();
 
     if let Value::Array(children) = &heading["children"] {
         for child in children {
@@ -206,7 +295,6 @@ fn transform_heading(heading: Value) -> String {
     ));
 
     serde_json::to_string(&json).unwrap()
-
 }
 
 fn transform_error(error: Value) -> String {
@@ -399,9 +487,18 @@ fn manifest() -> String {
                     "from": "__document",
                     "to": ["html"],
                     "arguments": [],
+                    "description": "Reads the constants title, author, lang and description for the metadata of the document. The constant html_theme chooses the bundled styling: default, dark, auto (follows the system), latex, latex-dark, latex-auto or none. A CSS file given by the constant stylesheet is linked, or inlined if stylesheet_embed is true. If html_fragment is true, only the article is output, for embedding in an existing page",
                     "type": "parent",
                     "variables": {
-                        "imports": {"type": "set", "access": "read"}
+                        "imports": {"type": "set", "access": "read"},
+                        "title": {"type": "const", "access": "read"},
+                        "author": {"type": "const", "access": "read"},
+                        "lang": {"type": "const", "access": "read"},
+                        "description": {"type": "const", "access": "read"},
+                        "stylesheet": {"type": "const", "access": "read"},
+                        "stylesheet_embed": {"type": "const", "access": "read"},
+                        "html_theme": {"type": "const", "access": "read"},
+                        "html_fragment": {"type": "const", "access": "read"}
                     },
                 },
                 {
@@ -0,0 +1,298 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::io::{self, Read};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, json, Value};
+
+macro_rules! module {
+    ($name:expr, $data:expr $(,$($args:tt)*)?) => {json!({"name": $name $(,"arguments":$($args)*)*, "data": $data})}
+}
+
+macro_rules! import {
+    ($e:expr) => {module!("set-add", $e, {"name": "imports"})}
+}
+
+macro_rules! text {
+    ($e:expr) => {
+        module!("__text", $e)
+    };
+}
+
+// The imports are a set without any order, so \makeindex is in the same import as the package
+const LATEX_IMPORT: &str = "\\usepackage{makeidx}\n\\makeindex";
+
+/// An entry marked by [idx], which is pushed to the list index_entries as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    key: String,
+    path: Vec<String>,
+    see: String,
+    see_also: String,
+}
+
+/// A term in the index, with the links to where it occurs and its subterms
+#[derive(Default)]
+struct IndexNode {
+    keys: Vec<String>,
+    see: Vec<String>,
+    see_also: Vec<String>,
+    children: BTreeMap<(bool, String, String), IndexNode>,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let action = &args[0];
+    match action.as_str() {
+        "manifest" => manifest(),
+        "transform" => transform(&args[1], &args[2]),
+        other => {
+            eprintln!("Invalid action {other}")
+        }
+    }
+}
+
+fn manifest() {
+    print!(
+        "{}",
+        serde_json::to_string(&json!(
+            {
+            "name": "index",
+            "version": "0.1",
+            "description": "This package supports back-of-book indexes. Entries are marked with [idx] and listed with [print-index].",
+            "transforms": [
+                {
+                    "from": "idx",
+                    "to": ["html", "latex"],
+                    "arguments": [
+                        {"name": "see", "default": "", "description": "Another term to refer to instead of this place in the text. Example: [idx see=fruit](apple)"},
+                        {"name": "see-also", "default": "", "description": "Another term to refer to in addition to this place in the text"}
+                    ],
+                    "description": "Marks an entry for the index at this place in the text. Subterms are separated by !, like term!subterm. The body is not shown in the text. Example: [idx](fruit!apple)",
+                    "variables": {
+                        "index_entries": {"type": "list", "access": "push"},
+                        "imports": {"type": "set", "access": "add"}
+                    },
+                    "type": "inline-module"
+                },
+                {
+                    "from": "print-index",
+                    "to": ["html", "latex"],
+                    "arguments": [],
+                    "description": "Lists all entries marked with [idx], sorted alphabetically and grouped by their first letter. In HTML, each entry links to every place it occurs. In LaTeX, this uses makeidx, and makeindex must be run before the document is compiled again",
+                    "variables": {
+                        "index_entries": {"type": "list", "access": "read"},
+                        "imports": {"type": "set", "access": "add"}
+                    }
+                }
+            ]
+            }
+        ))
+        .unwrap()
+    );
+}
+
+fn transform(from: &str, to: &str) {
+    let input: Value = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        from_str(&buffer).unwrap()
+    };
+
+    match from {
+        "idx" => transform_idx(input, to),
+        "print-index" => transform_print_index(to),
+        other => {
+            eprintln!("Package does not support {other}");
+        }
+    }
+}
+
+fn transform_idx(input: Value, to: &str) {
+    let path: Vec<String> = input["data"]
+        .as_str()
+        .unwrap()
+        .split('!')
+        .map(str::trim)
+        .filter(|term| !term.is_empty())
+        .map(str::to_string)
+        .collect();
+    let see = input["arguments"]["see"].as_str().unwrap().trim();
+    let see_also = input["arguments"]["see-also"].as_str().unwrap().trim();
+
+    if path.is_empty() {
+        eprintln!("An index entry must have a term");
+        return;
+    }
+
+    match to {
+        "html" => {
+            // The anchor is based on the id of the module, so that it is the same every time the
+            // document is compiled
+            let id = input["id"].as_u64().unwrap();
+            let entry = IndexEntry {
+                key: format!("idx:{id}"),
+                path,
+                see: see.to_string(),
+                see_also: see_also.to_string(),
+            };
+            let output = json!([
+                format!("<span id=\"idx:{id}\"></span>"),
+                module!("list-push", serde_json::to_string(&entry).unwrap(), {"name": "index_entries"}),
+            ]);
+            print!("{output}");
+        }
+        "latex" => {
+            let mut entry = path
+                .iter()
+                .map(|term| escape_makeindex(term))
+                .collect::<Vec<String>>()
+                .join("!");
+            if !see.is_empty() {
+                entry.push_str(&format!("|see{{{}}}", escape_latex(see)));
+            } else if !see_also.is_empty() {
+                entry.push_str(&format!("|seealso{{{}}}", escape_latex(see_also)));
+            }
+            let output = json!([format!("\\index{{{entry}}}"), import!(LATEX_IMPORT)]);
+            print!("{output}");
+        }
+        other => eprintln!("Cannot convert idx to {other}!"),
+    }
+}
+
+fn transform_print_index(to: &str) {
+    match to {
+        "html" => {
+            let index = build_index();
+            if index.children.is_empty() {
+                print!("[]");
+                return;
+            }
+
+            let mut output = vec![json!("<div class=\"index\">\n")];
+            let mut group: Option<String> = None;
+            for ((_, _, term), node) in &index.children {
+                let letter = group_name(term);
+                if group.as_ref() != Some(&letter) {
+                    if group.is_some() {
+                        output.push(json!("</ul>\n"));
+                    }
+                    output.push(json!(format!("<h3>{letter}</h3>\n<ul>\n")));
+                    group = Some(letter);
+                }
+                node_to_html(term, node, &mut output);
+            }
+            output.push(json!("</ul>\n</div>\n"));
+            print!("{}", serde_json::to_string(&output).unwrap());
+        }
+        "latex" => print!("{}", json!(["\n\\printindex\n", import!(LATEX_IMPORT)])),
+        other => eprintln!("Cannot convert print-index to {other}!"),
+    }
+}
+
+// Build the tree of terms from the entries in the "index_entries" list. The terms are sorted
+// without regard to case, after the terms that don't start with a letter, and the links of each
+// term are in the order they occur in the document.
+fn build_index() -> IndexNode {
+    let mut index = IndexNode::default();
+    for entry in get_index_entries() {
+        let mut node = &mut index;
+        for term in &entry.path {
+            node = node
+                .children
+                .entry((starts_with_letter(term), term.to_lowercase(), term.clone()))
+                .or_default();
+        }
+
+        if !entry.see.is_empty() {
+            if !node.see.contains(&entry.see) {
+                node.see.push(entry.see);
+            }
+        } else {
+            node.keys.push(entry.key);
+            if !entry.see_also.is_empty() && !node.see_also.contains(&entry.see_also) {
+                node.see_also.push(entry.see_also);
+            }
+        }
+    }
+    index
+}
+
+// Each term is a list item with numbered links to where it occurs, followed by its references and
+// a nested list of its subterms
+fn node_to_html(term: &str, node: &IndexNode, output: &mut Vec<Value>) {
+    output.push(json!("<li>"));
+    output.push(text!(term));
+    for (idx, key) in node.keys.iter().enumerate() {
+        output.push(json!(format!(", <a href=\"#{key}\">{}</a>", idx + 1)));
+    }
+    for see in &node.see {
+        output.push(json!(", <i>see</i> "));
+        output.push(text!(see));
+    }
+    for see_also in &node.see_also {
+        output.push(json!(", <i>see also</i> "));
+        output.push(text!(see_also));
+    }
+    if !node.children.is_empty() {
+        output.push(json!("\n<ul>\n"));
+        for ((_, _, child_term), child) in &node.children {
+            node_to_html(child_term, child, output);
+        }
+        output.push(json!("</ul>\n"));
+    }
+    output.push(json!("</li>\n"));
+}
+
+// The group of a term is its first letter in upper case. Terms that don't start with a letter are
+// grouped together before the letters.
+fn group_name(term: &str) -> String {
+    if starts_with_letter(term) {
+        term.chars().next().unwrap().to_uppercase().collect()
+    } else {
+        "Symbols".to_string()
+    }
+}
+
+fn starts_with_letter(term: &str) -> bool {
+    term.chars().next().is_some_and(char::is_alphabetic)
+}
+
+// Get a list of entries from the "index_entries" environment variable
+fn get_index_entries() -> Vec<IndexEntry> {
+    let var = env::var("index_entries").unwrap_or("[]".to_string());
+    from_str::<Vec<String>>(&var)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| from_str(entry).ok())
+        .collect()
+}
+
+// Escape a term for \index. The characters that makeindex uses for subterms, page formats and
+// sort keys are quoted with ", and the rest is escaped like other LaTeX text.
+fn escape_makeindex(text: &str) -> String {
+    let mut result = String::new();
+    for c in escape_latex(text).chars() {
+        if matches!(c, '!' | '@' | '|' | '"') {
+            result.push('"');
+        }
+        result.push(c);
+    }
+    result
+}
+
+fn escape_latex(text: &str) -> String {
+    text.split('\\')
+        .map(|t| t.replace('{', r"\{").replace('}', r"\}"))
+        .collect::<Vec<String>>()
+        .join(r"\textbackslash{}")
+        .replace('#', r"\#")
+        .replace('$', r"\$")
+        .replace('%', r"\%")
+        .replace('&', r"\&")
+        .replace('_', r"\_")
+        .replace('<', r"\textless{}")
+        .replace('>', r"\textgreater{}")
+        .replace('~', r"\textasciitilde{}")
+        .replace('^', r"\textasciicircum{}")
+}
@@ -2,6 +2,7 @@ use std::{
     collections::HashMap,
     env,
     fmt::Write,
+    fs,
     io::{self, Read},
 };
 
@@ -216,21 +217,17 @@ fn transform_heading(heading: Value) -> String {
         panic!();
     };
     let level = s.parse::<u8>().unwrap();
-    if level > 3 {
-        eprintln!("Latex only supports headings up to level 3");
-    }
-    let clamped_level = level.clamp(1, 3); //latex only supports 1-3
-    let mut subs = String::new();
-    if clamped_level > 1 {
-        subs.push_str(&"sub".repeat((clamped_level - 1) as usize));
+    let (command, max_level) = sectioning_command(level);
+    if level > max_level {
+        eprintln!("Latex only supports headings up to level {max_level}");
     }
 
     let heading_style = env::var("heading_style").unwrap_or(String::new());
 
     if heading_style == "unnumbered" {
-        vec.push(json!(format!("\n\\{subs}section*{{")));
+        vec.push(json!(format!("\n\\{command}*{{")));
     } else {
-        vec.push(json!(format!("\n\\{subs}section{{")));
+        vec.push(json!(format!("\n\\{command}{{")));
     }
 
     if let Value::Array(children) = &heading["children"] {
@@ -242,7 +239,7 @@ fn transform_heading(heading: Value) -> String {
     vec.push(json!("}\n"));
 
     if heading_style == "unnumbered" {
-        vec.push(json!(format!("\\addcontentsline{{toc}}{{{subs}section}}{{")));
+        vec.push(json!(format!("\\addcontentsline{{toc}}{{{command}}}{{")));
         if let Value::Array(children) = &heading["children"] {
             for child in children {
                 vec.push(child.clone());
@@ -288,10 +285,57 @@ fn transform_math(node: Value) -> String {
     }
 }
 
+// Get a constant set in the [config] module, if it is set and not empty
+fn constant(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+// Classes like book and report have chapters above sections, which level 1 headings become.
+// Returns the sectioning command of a heading, and the deepest level that has one
+fn sectioning_command(level: u8) -> (String, u8) {
+    let class = constant("document_class").unwrap_or_default();
+    if matches!(
+        class.as_str(),
+        "book" | "report" | "scrbook" | "scrreprt" | "memoir"
+    ) {
+        let level = level.clamp(1, 4);
+        let command = if level == 1 {
+            "chapter".to_string()
+        } else {
+            format!("{}section", "sub".repeat((level - 2) as usize))
+        };
+        (command, 4)
+    } else {
+        let level = level.clamp(1, 3);
+        (format!("{}section", "sub".repeat((level - 1) as usize)), 3)
+    }
+}
+
 fn transform_document(mut doc: Value) -> String {
     let mut result: Vec<Value> = vec![];
 
-    result.push(Value::from(r"\documentclass{article}"));
+    let class = constant("document_class").unwrap_or("article".to_string());
+    let mut options: Vec<String> = constant("class_options")
+        .map(|options| options.split(',').map(|s| s.trim().to_string()).collect())
+        .unwrap_or_default();
+    if let Some(paper) = constant("paper_size") {
+        // Both "a4" and "a4paper" are accepted
+        if paper.ends_with("paper") {
+            options.push(paper);
+        } else {
+            options.push(format!("{paper}paper"));
+        }
+    }
+    options.retain(|option| !option.is_empty());
+
+    if options.is_empty() {
+        result.push(Value::from(format!(r"\documentclass{{{class}}}")));
+    } else {
+        result.push(Value::from(format!(
+            r"\documentclass[{}]{{{class}}}",
+            options.join(",")
+        )));
+    }
 
     let imports_var = env::var("imports").unwrap_or("[]".to_string());
     let imports: Vec<String> = serde_json::from_str(&imports_var).unwrap();
@@ -303,8 +347,49 @@ fn transform_document(mut doc: Value) -> String {
         }
     }
 
+    // The preamble of the user comes after the imports, so that it can change what they set up
+    if let Some(path) = constant("preamble") {
+        match fs::read_to_string(&path) {
+            Ok(preamble) => result.push(Value::from(format!("\n\n{}", preamble.trim_end()))),
+            Err(_) => eprintln!("Preamble could not be accessed at {path}"),
+        }
+    }
+
+    let title = constant("title");
+    let mut metadata = String::new();
+    for (command, value) in [
+        ("title", &title),
+        ("author", &constant("author")),
+        ("date", &constant("date")),
+    ] {
+        if let Some(value) = value {
+            write!(metadata, "\n\\{command}{{{}}}", escape(value)).unwrap();
+        }
+    }
+    if !metadata.is_empty() {
+        result.push(Value::from(format!("\n{metadata}")));
+    }
+
     result.push(Value::from("\n\n\\begin{document}\n\n"));
 
+    // The title is made by default when there is one, but it can be turned off
+    let maketitle = match constant("maketitle").as_deref() {
+        Some("true") => true,
+        Some("false") => false,
+        Some(other) => {
+            eprintln!("Expected maketitle to be true or false, got '{other}'");
+            title.is_some()
+        }
+        None => title.is_some(),
+    };
+    if maketitle {
+        if class == "beamer" {
+            result.push(Value::from("\\frame{\\titlepage}\n\n"));
+        } else {
+            result.push(Value::from("\\maketitle\n\n"));
+        }
+    }
+
     if let Some(vec) = doc.get_mut("children").and_then(Value::as_array_mut) {
         result.append(vec);
     }
@@ -313,23 +398,25 @@ fn transform_document(mut doc: Value) -> String {
     serde_json::to_string(&result).unwrap()
 }
 
+fn escape(text: &str) -> String {
+    text.split('\\')
+        .map(|t| t.replace('{', r"\{").replace('}', r"\}"))
+        .collect::<Vec<String>>()
+        .join(r"\textbackslash{}")
+        .replace('#', r"\#")
+        .replace('$', r"\$")
+        .replace('%', r"\%")
+        .replace('&', r"\&")
+        .replace('_', r"\_")
+        .replace('<', r"\textless{}")
+        .replace('>', r"\textgreater{}")
+        .replace('~', r"\textasciitilde{}")
+        .replace('^', r"\textasciicircum{}")
+}
+
 fn escape_text(module: Value) -> String {
     if let Value::String(s) = &module["data"] {
-        let s = s
-            .split('\\')
-            .map(|t| t.replace('{', r"\{").replace('}', r"\}"))
-            .collect::<Vec<String>>()
-            .join(r"\textbackslash{}")
-            .replace('#', r"\#")
-            .replace('$', r"\$")
-            .replace('%', r"\%")
-            .replace('&', r"\&")
-            .replace('_', r"\_")
-            .replace('<', r"\textless{}")
-            .replace('>', r"\textgreater{}")
-            .replace('~', r"\textasciitilde{}")
-            .replace('^', r"\textasciicircum{}");
-        format!("{}", json! {[{"name":"raw","data":s}]})
+        format!("{}", json! {[{"name":"raw","data":escape(s)}]})
     } else {
         panic!("Malformed text module");
     }
@@ -392,8 +479,17 @@ fn manifest() -> String {
                     "from": "__document",
                     "to": ["latex"],
                     "arguments": [],
+                    "description": "Reads the constants document_class (article by default), class_options (separated by commas), paper_size, title, author and date. A title is made if maketitle is true, which it is by default when there is a title. A file of LaTeX given by the constant preamble is included in the preamble",
                     "variables": {
-                        "imports": {"type": "set", "access": "read"}
+                        "imports": {"type": "set", "access": "read"},
+                        "document_class": {"type": "const", "access": "read"},
+                        "class_options": {"type": "const", "access": "read"},
+                        "paper_size": {"type": "const", "access": "read"},
+                        "title": {"type": "const", "access": "read"},
+                        "author": {"type": "const", "access": "read"},
+                        "date": {"type": "const", "access": "read"},
+                        "maketitle": {"type": "const", "access": "read"},
+                        "preamble": {"type": "const", "access": "read"}
                     },
                     "type": "parent"
                 },
@@ -428,6 +524,7 @@ fn manifest() -> String {
                     "type": "parent",
                     "variables": {
                         "heading_style": {"type": "const", "access": "read"},
+                        "document_class": {"type": "const", "access": "read"},
                     },
                 },
 
@@ -28,12 +28,12 @@ fn manifest() {
             "transforms": [
                 {
                     "from": "newline",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "plaintext", "pandoc-json"],
                     "arguments": [],
                 },
                 {
                     "from": "newpage",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "plaintext"],
                     "arguments": [],
                 },
                 {
@@ -58,7 +58,7 @@ fn manifest() {
                 },
                 {
                     "from": "center",
-                    "to": ["html"],
+                    "to": ["html", "plaintext"],
                     "arguments": [
                         {"name": "separator", "default": ",", "description": "The pattern used to separate items in the input content." },
                         {"name": "gap", "default": "10", "description":
@@ -109,6 +109,9 @@ fn transform_newline(to: &str, input: Value) {
     match to {
         "latex" => println!("[{}]", json!({"name": "raw", "data": "\\\\{}"})),
         "html" => println!("[{}]", json!({"name": "raw", "data": "<br/>"})),
+        // A line break marker for the layout filter of the plaintext package
+        "plaintext" => println!("[{}]", json!({"name": "raw", "data": "\u{E003}"})),
+        "pandoc-json" => println!("[{}]", json!({"name": "raw", "data": r#"{"t":"LineBreak"},"#})),
         other => eprintln!("Cannot convert to '{other}' format."),
     }
 }
@@ -126,6 +129,7 @@ fn transform_newpage(to: &str, input: Value) {
             "[{}]",
             json!({"name": "raw", "data": r#"<div style="break-after: page;"></div>"#})
         ),
+        "plaintext" => println!("[{}]", json!({"name": "raw", "data": "\n\u{0C}\n"})),
         other => eprintln!("Cannot convert to '{other}' format."),
     }
 }
@@ -165,6 +169,23 @@ fn transform_flex(from: &str, to: &str, input: Value) {
 
             print!("[{open},{items},{close}]");
         }
+        "plaintext" if from == "center" => {
+            // Each item is put on its own line, and all lines are centered by the layout filter of
+            // the plaintext package
+            let content = input["data"].as_str().unwrap();
+            let separator = get_arg!(input, "separator");
+
+            let mut result = vec![json!("\n\u{E005}")];
+            for (idx, item) in content.split(separator).enumerate() {
+                if idx != 0 {
+                    result.push(json!("\u{E003}"));
+                }
+                result.push(json!({"name": "inline_content", "data": item.trim()}));
+            }
+            result.push(json!("\u{E002}\n"));
+
+            print!("{}", json!(result));
+        }
         other => {
             eprintln!("Cannot convert {from} to {other}");
         }
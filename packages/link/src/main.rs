@@ -38,7 +38,7 @@ fn manifest() {
             "transforms": [
                 {
                     "from": "link",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "markdown", "plaintext", "typst", "pandoc-json"],
                     "arguments": [
                         {"name": "label", "default": "", "description": "Label for link"}
                     ],
@@ -50,7 +50,7 @@ fn manifest() {
                 },
                 {
                     "from": "label",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "markdown", "plaintext", "typst", "pandoc-json"],
                     "arguments": [],
                     "variables": {
                         "structure": {"type": "list", "access": "push"}
@@ -58,15 +58,16 @@ fn manifest() {
                 },
                 {
                     "from": "reference",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "markdown", "plaintext", "typst", "pandoc-json"],
                     "arguments": [],
                     "variables": {
-                        "structure": {"type": "list", "access": "read"}
+                        "structure": {"type": "list", "access": "read"},
+                        "imports": {"type": "set", "access": "add"}
                     },
                 },
                 {
                     "from": "target",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "markdown", "plaintext", "typst", "pandoc-json"],
                     "arguments": [
                         {"name": "name", "type": "string", "description": "The name used to refer to a target later on"}
                     ],
@@ -123,8 +124,33 @@ fn transform_target(input: Value, to: &str) {
         res.push(module!("inline_content", body));
         res.push(Value::from("</span>"));
         res
+    } else if to == "markdown" {
+        let mut res = vec![];
+        res.push(inline_target!(name));
+        res.push(Value::String(format!("<a id=\"inlinetarget{}\"></a>", name)));
+        res.push(module!("inline_content", body));
+        res
+    } else if to == "plaintext" {
+        vec![inline_target!(name), module!("inline_content", body)]
+    } else if to == "typst" {
+        let mut res = vec![];
+        res.push(inline_target!(name));
+        res.push(Value::from("#["));
+        res.push(module!("inline_content", body));
+        res.push(Value::String(format!("]<inlinetarget{}>", name)));
+        res
+    } else if to == "pandoc-json" {
+        let mut res = vec![];
+        res.push(inline_target!(name));
+        res.push(Value::String(format!(
+            r#"{{"t":"Span","c":[[{},[],[]],["#,
+            json!(format!("inlinetarget{}", name))
+        )));
+        res.push(module!("inline_content", body));
+        res.push(Value::from("]]},"));
+        res
     } else {
-        panic!("[target] only supports HTML and LaTeX");
+        panic!("[target] only supports HTML, LaTeX, Markdown, plain text, Typst and Pandoc JSON");
     };
     println!("{}", Value::Array(result));
 }
@@ -181,6 +207,89 @@ fn transform_link(input: Value, to: &str) {
             ]);
             print!("{output}");
         }
+        "markdown" => {
+            let label = input["arguments"]
+                .get("label")
+                .map(|val| val.as_str().unwrap())
+                .unwrap_or_else(|| "");
+            let link = input["data"].as_str().unwrap();
+
+            let text = if label.is_empty() { link } else { label };
+            let destination = if is_target {
+                format!("#inlinetarget{}", link)
+            } else {
+                markdown_destination(link)
+            };
+
+            let output = json!([
+                "[",
+                {"name": "inline_content", "data": text},
+                format!("]({destination})"),
+            ]);
+            print!("{output}");
+        }
+        "plaintext" => {
+            let label = input["arguments"]
+                .get("label")
+                .map(|val| val.as_str().unwrap())
+                .unwrap_or_else(|| "");
+            let link = input["data"].as_str().unwrap();
+
+            // Links to other documents are listed at the end of the document by the layout of
+            // the plaintext package, unless the URL is already shown
+            let output = if is_target || label.is_empty() {
+                let text = if label.is_empty() { link } else { label };
+                json!([{"name": "inline_content", "data": text}])
+            } else {
+                json!([
+                    {"name": "inline_content", "data": label},
+                    format!("\u{E006}{link}\u{E007}"),
+                ])
+            };
+            print!("{output}");
+        }
+        "typst" => {
+            let label = input["arguments"]
+                .get("label")
+                .map(|val| val.as_str().unwrap())
+                .unwrap_or_else(|| "");
+            let link = input["data"].as_str().unwrap();
+
+            let text = if label.is_empty() { link } else { label };
+            let destination = if is_target {
+                format!("<inlinetarget{}>", link)
+            } else {
+                typst_string(link)
+            };
+
+            let output = json!([
+                format!("#link({destination})["),
+                {"name": "inline_content", "data": text},
+                "]",
+            ]);
+            print!("{output}");
+        }
+        "pandoc-json" => {
+            let label = input["arguments"]
+                .get("label")
+                .map(|val| val.as_str().unwrap())
+                .unwrap_or_else(|| "");
+            let link = input["data"].as_str().unwrap();
+
+            let text = if label.is_empty() { link } else { label };
+            let destination = if is_target {
+                format!("#inlinetarget{}", link)
+            } else {
+                link.to_string()
+            };
+
+            let output = json!([
+                r#"{"t":"Link","c":[["",[],[]],["#,
+                {"name": "inline_content", "data": text},
+                format!(r#"],[{},""]]}},"#, json!(destination)),
+            ]);
+            print!("{output}");
+        }
         other => {
             eprintln!("Cannot convert link to {other}");
         }
@@ -218,6 +327,67 @@ fn transform_label(input: Value, to: &str) {
 
             print!("{output}");
         }
+        "markdown" => {
+            let label = input["data"].as_str().unwrap();
+            let escaped_label = label.replace('"', "%22");
+            let structure_data = json!({"element": "label", "key": label}).to_string();
+
+            let output = json!([
+                format!(r#"<a id="{escaped_label}"></a>"#),
+                {
+                    "name": "list-push",
+                    "arguments":{"name": "structure"},
+                    "data": structure_data,
+                }
+            ]);
+
+            print!("{output}");
+        }
+        "plaintext" => {
+            let label = input["data"].as_str().unwrap();
+            let structure_data = json!({"element": "label", "key": label}).to_string();
+
+            let output = json!([
+                {
+                    "name": "list-push",
+                    "arguments":{"name": "structure"},
+                    "data": structure_data,
+                }
+            ]);
+
+            print!("{output}");
+        }
+        "typst" => {
+            let label = input["data"].as_str().unwrap();
+            let structure_data = json!({"element": "label", "key": label}).to_string();
+
+            // A label has to be attached to an element, so an invisible one is placed here
+            let output = json!([
+                format!("#metadata(none) <{label}>"),
+                {
+                    "name": "list-push",
+                    "arguments":{"name": "structure"},
+                    "data": structure_data,
+                }
+            ]);
+
+            print!("{output}");
+        }
+        "pandoc-json" => {
+            let label = input["data"].as_str().unwrap();
+            let structure_data = json!({"element": "label", "key": label}).to_string();
+
+            let output = json!([
+                format!(r#"{{"t":"Span","c":[[{},[],[]],[]]}},"#, json!(label)),
+                {
+                    "name": "list-push",
+                    "arguments":{"name": "structure"},
+                    "data": structure_data,
+                }
+            ]);
+
+            print!("{output}");
+        }
         other => {
             eprintln!("Cannot convert label to {other}");
         }
@@ -243,9 +413,64 @@ fn transform_reference(input: Value, to: &str) {
             let label = input["data"].as_str().unwrap();
             let escaped_label = label.replace('"', "%22");
 
-            let label_tag = format!(r#"\ref{{{}}}"#, escaped_label);
+            // Equations are referred to with their number in parentheses
+            let output = if is_equation(label) {
+                json!([
+                    import!(r"\usepackage{amsmath}"),
+                    format!(r#"\eqref{{{}}}"#, escaped_label)
+                ])
+            } else {
+                json!(format!(r#"\ref{{{}}}"#, escaped_label))
+            };
 
-            let output = json!(label_tag);
+            print!("{output}");
+        }
+        "markdown" => {
+            let label = input["data"].as_str().unwrap();
+
+            let output = json!([
+                "[",
+                {"name": "inline_content", "data": format!("[element-number]({label})")},
+                "](#",
+                {"name": "inline_content", "data": format!("[label-to-key]({label})")},
+                ")",
+            ]);
+
+            print!("{output}");
+        }
+        "plaintext" => {
+            let label = input["data"].as_str().unwrap();
+
+            let output = json!([
+                {"name": "inline_content", "data": format!("[element-number]({label})")},
+            ]);
+
+            print!("{output}");
+        }
+        "typst" => {
+            let label = input["data"].as_str().unwrap();
+
+            let output = json!([
+                "#link(<",
+                {"name": "inline_content", "data": format!("[label-to-key]({label})")},
+                ">)[",
+                {"name": "inline_content", "data": format!("[element-number]({label})")},
+                "]",
+            ]);
+
+            print!("{output}");
+        }
+        "pandoc-json" => {
+            let label = input["data"].as_str().unwrap();
+
+            // The key is inserted into a JSON string, so it can't contain quotes
+            let output = json!([
+                r#"{"t":"Link","c":[["",[],[]],["#,
+                {"name": "inline_content", "data": format!("[element-number]({label})")},
+                r##"],["#"##,
+                {"name": "inline_content", "data": format!("[label-to-key]({label})")},
+                r#"",""]]},"#,
+            ]);
 
             print!("{output}");
         }
@@ -254,3 +479,27 @@ fn transform_reference(input: Value, to: &str) {
         }
     }
 }
+
+// Check if a label belongs to an equation in the structure of the document
+fn is_equation(label: &str) -> bool {
+    let structure: Vec<String> =
+        serde_json::from_str(&env::var("structure").unwrap_or("[]".to_string())).unwrap_or_default();
+    structure
+        .iter()
+        .filter_map(|entry| serde_json::from_str::<Value>(entry).ok())
+        .any(|entry| entry["element"] == "equation" && entry["key"] == label)
+}
+
+// Link destinations with spaces or parentheses have to be enclosed in angle brackets in Markdown
+fn markdown_destination(link: &str) -> String {
+    if link.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+        format!("<{}>", link.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        link.to_string()
+    }
+}
+
+// Write a link as a Typst string literal
+fn typst_string(link: &str) -> String {
+    format!("\"{}\"", link.replace('\\', "\\\\").replace('"', "\\\""))
+}
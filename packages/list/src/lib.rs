@@ -264,6 +264,266 @@ impl List {
         json_vec
     }
 
+    fn has_non_decimal_numbering(&self) -> bool {
+        let non_decimal = matches!(
+            self.list_type,
+            ListType::OrderedList(_, ordered_type) if ordered_type != OrderedType::Decimal
+        );
+        non_decimal
+            || self.items.iter().any(|item| match item {
+                ListItem::Content(_) => false,
+                ListItem::List(sub_list) => sub_list.has_non_decimal_numbering(),
+            })
+    }
+
+    pub fn to_markdown(&self) -> String {
+        if self.has_non_decimal_numbering() {
+            eprintln!("Markdown only supports decimal numbering, so it is used for all lists");
+        }
+        let mut json_vec = vec![Value::from("\n")];
+        json_vec.extend(self.to_markdown_vec(""));
+        json_vec.push(Value::from("\n"));
+        json!(json_vec).to_string()
+    }
+
+    // Nested lists are indented to line up with the content of the item before them, since that
+    // is what makes them belong to that item in Markdown
+    fn to_markdown_vec(&self, indent: &str) -> Vec<Value> {
+        let mut json_vec: Vec<Value> = vec![];
+        let mut number = match self.list_type {
+            ListType::OrderedList(start, _) => start,
+            ListType::UnorderedList => 0,
+        };
+        let mut nested_indent = format!("{indent}  ");
+
+        for item in &self.items {
+            match item {
+                ListItem::Content(content) => {
+                    let marker = match self.list_type {
+                        ListType::OrderedList(_, _) => {
+                            number += 1;
+                            format!("{}. ", number - 1)
+                        }
+                        ListType::UnorderedList => "- ".to_string(),
+                    };
+                    nested_indent = format!("{indent}{}", " ".repeat(marker.len()));
+                    json_vec.push(Value::String(format!("{indent}{marker}")));
+                    json_vec.push(inline_content!(content));
+                    json_vec.push(Value::from("\n"));
+                }
+                ListItem::List(sub_list) => {
+                    json_vec.extend(sub_list.to_markdown_vec(&nested_indent));
+                }
+            }
+        }
+        json_vec
+    }
+
+    pub fn to_plaintext(&self) -> String {
+        let mut json_vec = vec![Value::from("\n")];
+        json_vec.extend(self.to_plaintext_vec(""));
+        json_vec.push(Value::from("\n"));
+        json!(json_vec).to_string()
+    }
+
+    // Each item is a block in the layout of the plaintext package, which starts with the marker
+    // and indents the rest of the lines to line up with the content
+    fn to_plaintext_vec(&self, indent: &str) -> Vec<Value> {
+        use OrderedType::*;
+        let mut json_vec: Vec<Value> = vec![];
+        let mut number = match self.list_type {
+            ListType::OrderedList(start, _) => start,
+            ListType::UnorderedList => 0,
+        };
+        let mut nested_indent = format!("{indent}  ");
+
+        for item in &self.items {
+            match item {
+                ListItem::Content(content) => {
+                    let marker = match self.list_type {
+                        ListType::OrderedList(_, ordered_type) => {
+                            number += 1;
+                            let label = match ordered_type {
+                                Decimal => (number - 1).to_string(),
+                                LowerAlpha => start_to_alpha(number - 1).to_ascii_lowercase(),
+                                UpperAlpha => start_to_alpha(number - 1),
+                                LowerRoman => start_to_roman(number - 1).to_ascii_lowercase(),
+                                UpperRoman => start_to_roman(number - 1),
+                            };
+                            format!("{label}. ")
+                        }
+                        ListType::UnorderedList => "- ".to_string(),
+                    };
+                    nested_indent = format!("{indent}{}", " ".repeat(marker.len()));
+                    json_vec.push(Value::String(format!("\u{E000}{indent}{marker}\u{E001}")));
+                    json_vec.push(inline_content!(content));
+                    json_vec.push(Value::from("\u{E002}\n"));
+                }
+                ListItem::List(sub_list) => {
+                    json_vec.extend(sub_list.to_plaintext_vec(&nested_indent));
+                }
+            }
+        }
+        json_vec
+    }
+
+    pub fn to_docx(&self, id: u64) -> String {
+        let mut count = 0;
+        json!(self.to_docx_vec(id, 0, &mut count)).to_string()
+    }
+
+    // Each list gets its own numbering, which is defined right before the list by a marker that
+    // the docx package turns into a numbering definition. The numberings are unique within the
+    // document since the id of the list module is a part of their keys
+    fn to_docx_vec(&self, id: u64, level: usize, count: &mut usize) -> Vec<Value> {
+        use OrderedType::*;
+        let key = format!("list-{id}-{count}");
+        *count += 1;
+
+        let (format, start) = match self.list_type {
+            ListType::OrderedList(start, ordered_type) => {
+                let format = match ordered_type {
+                    Decimal => "decimal",
+                    LowerAlpha => "lowerLetter",
+                    UpperAlpha => "upperLetter",
+                    LowerRoman => "lowerRoman",
+                    UpperRoman => "upperRoman",
+                };
+                (format, start)
+            }
+            ListType::UnorderedList => ("bullet", 1),
+        };
+        let bullet = ["\u{2022}", "\u{25E6}", "\u{25AA}"][level % 3];
+
+        let mut json_vec: Vec<Value> = vec![Value::String(format!(
+            r#"<mm:num id="{key}" format="{format}" start="{start}" text="{bullet}"/>"#
+        ))];
+        for item in &self.items {
+            match item {
+                ListItem::Content(content) => {
+                    json_vec.push(Value::String(format!(
+                        "<w:p><w:pPr><w:pStyle w:val=\"ListParagraph\"/><w:numPr>\
+                        <w:ilvl w:val=\"{level}\"/><w:numId w:val=\"{key}\"/></w:numPr></w:pPr>"
+                    )));
+                    json_vec.push(inline_content!(content));
+                    json_vec.push(Value::from("</w:p>\n"));
+                }
+                ListItem::List(sub_list) => {
+                    json_vec.extend(sub_list.to_docx_vec(id, level + 1, count));
+                }
+            }
+        }
+        json_vec
+    }
+
+    pub fn to_typst(&self) -> String {
+        let mut json_vec = vec![Value::from("\n#")];
+        json_vec.extend(self.to_typst_vec(""));
+        json_vec.push(Value::from("\n"));
+        json!(json_vec).to_string()
+    }
+
+    // Lists are written as calls to the list and enum functions rather than as markup, since that
+    // is the only way to give each list its own start and numbering. Nested lists are put in the
+    // content of the item before them
+    fn to_typst_vec(&self, indent: &str) -> Vec<Value> {
+        use OrderedType::*;
+        let mut json_vec: Vec<Value> = vec![Value::String(match self.list_type {
+            ListType::OrderedList(start, ordered_type) => {
+                let numbering = match ordered_type {
+                    Decimal => "1.",
+                    LowerAlpha => "a.",
+                    UpperAlpha => "A.",
+                    LowerRoman => "i.",
+                    UpperRoman => "I.",
+                };
+                format!("enum(numbering: \"{numbering}\", start: {start},\n")
+            }
+            ListType::UnorderedList => "list(\n".to_string(),
+        })];
+        let nested_indent = format!("{indent}  ");
+
+        let mut items = self.items.iter().peekable();
+        while let Some(item) = items.next() {
+            json_vec.push(Value::String(format!("{nested_indent}[")));
+            let sub_list = match item {
+                ListItem::Content(content) => {
+                    json_vec.push(inline_content!(content));
+                    match items.peek() {
+                        Some(ListItem::List(sub_list)) => {
+                            items.next();
+                            json_vec.push(Value::from(" "));
+                            Some(sub_list)
+                        }
+                        _ => None,
+                    }
+                }
+                ListItem::List(sub_list) => Some(sub_list),
+            };
+            if let Some(sub_list) = sub_list {
+                json_vec.push(Value::from("#"));
+                json_vec.extend(sub_list.to_typst_vec(&nested_indent));
+            }
+            json_vec.push(Value::from("],\n"));
+        }
+        json_vec.push(Value::String(format!("{indent})")));
+        json_vec
+    }
+
+    pub fn to_pandoc(&self) -> String {
+        json!(self.to_pandoc_vec()).to_string()
+    }
+
+    // Each item is a list of blocks, which is its content as a plain block followed by the lists
+    // nested in it. Every element is followed by a comma, which the pandoc package removes at the
+    // end of each list
+    fn to_pandoc_vec(&self) -> Vec<Value> {
+        use OrderedType::*;
+        let mut json_vec: Vec<Value> = vec![Value::String(match self.list_type {
+            ListType::OrderedList(start, ordered_type) => {
+                let style = match ordered_type {
+                    Decimal => "Decimal",
+                    LowerAlpha => "LowerAlpha",
+                    UpperAlpha => "UpperAlpha",
+                    LowerRoman => "LowerRoman",
+                    UpperRoman => "UpperRoman",
+                };
+                format!(r#"{{"t":"OrderedList","c":[[{start},{{"t":"{style}"}},{{"t":"Period"}}],["#)
+            }
+            ListType::UnorderedList => r#"{"t":"BulletList","c":["#.to_string(),
+        })];
+
+        let mut open_item = false;
+        for item in &self.items {
+            match item {
+                ListItem::Content(content) => {
+                    if open_item {
+                        json_vec.push(Value::from("],"));
+                    }
+                    json_vec.push(Value::from(r#"[{"t":"Plain","c":["#));
+                    json_vec.push(inline_content!(content));
+                    json_vec.push(Value::from("]},"));
+                    open_item = true;
+                }
+                ListItem::List(sub_list) => {
+                    if !open_item {
+                        json_vec.push(Value::from("["));
+                        open_item = true;
+                    }
+                    json_vec.extend(sub_list.to_pandoc_vec());
+                }
+            }
+        }
+        if open_item {
+            json_vec.push(Value::from("],"));
+        }
+        json_vec.push(Value::from(match self.list_type {
+            ListType::OrderedList(_, _) => "]]},",
+            ListType::UnorderedList => "]},",
+        }));
+        json_vec
+    }
+
     pub fn from_str(s: &str, spaces_per_indent: u64) -> Result<Self, InvalidListError> {
         if s.lines().count() == 0 || s.lines().next().unwrap().parse::<ListType>().is_err() {
             return Err(InvalidListError);
@@ -349,6 +609,27 @@ impl List {
     }
 }
 
+// Gets the uppercase letters for a number, continuing with AA after Z like spreadsheet columns
+fn start_to_alpha(start: u32) -> String {
+    let mut letters = vec![];
+    let mut n = start;
+    while n > 0 {
+        n -= 1;
+        letters.push((b'A' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    letters.into_iter().rev().collect()
+}
+
+// Gets the uppercase roman numeral for a number, falling back to decimal for numbers that have no
+// roman numeral
+fn start_to_roman(start: u32) -> String {
+    i32::try_from(start)
+        .ok()
+        .and_then(roman::to)
+        .unwrap_or_else(|| start.to_string())
+}
+
 fn alpha_to_start(c: char) -> Option<u32> {
     let lowercase = c.is_ascii_lowercase().then_some(c as u8 - b'a' + 1);
     let uppercase = c.is_ascii_uppercase().then_some(c as u8 - b'A' + 1);
@@ -27,7 +27,7 @@ fn manifest() {
             "transforms": [
                 {
                     "from": "list",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "markdown", "plaintext", "docx", "typst", "pandoc-json"],
                     "arguments": [
                         {
                             "name": "indent",
@@ -90,6 +90,87 @@ fn transform_list(to: &str) {
                 eprintln!("Module block does not start with a list")
             }
         }
+        "markdown" => {
+            let input: Value = {
+                let mut buffer = String::new();
+                io::stdin().read_to_string(&mut buffer).unwrap();
+                serde_json::from_str(&buffer).unwrap()
+            };
+
+            let body = input["data"].as_str().unwrap();
+            let indent = input["arguments"]["indent"].as_u64().unwrap();
+
+            if let Ok(list) = List::from_str(body, indent) {
+                print!("{}", list.to_markdown())
+            } else {
+                eprintln!("Module block does not start with a list")
+            }
+        }
+        "plaintext" => {
+            let input: Value = {
+                let mut buffer = String::new();
+                io::stdin().read_to_string(&mut buffer).unwrap();
+                serde_json::from_str(&buffer).unwrap()
+            };
+
+            let body = input["data"].as_str().unwrap();
+            let indent = input["arguments"]["indent"].as_u64().unwrap();
+
+            if let Ok(list) = List::from_str(body, indent) {
+                print!("{}", list.to_plaintext())
+            } else {
+                eprintln!("Module block does not start with a list")
+            }
+        }
+        "docx" => {
+            let input: Value = {
+                let mut buffer = String::new();
+                io::stdin().read_to_string(&mut buffer).unwrap();
+                serde_json::from_str(&buffer).unwrap()
+            };
+
+            let body = input["data"].as_str().unwrap();
+            let indent = input["arguments"]["indent"].as_u64().unwrap();
+            let id = input["id"].as_u64().unwrap_or(0);
+
+            if let Ok(list) = List::from_str(body, indent) {
+                print!("{}", list.to_docx(id))
+            } else {
+                eprintln!("Module block does not start with a list")
+            }
+        }
+        "typst" => {
+            let input: Value = {
+                let mut buffer = String::new();
+                io::stdin().read_to_string(&mut buffer).unwrap();
+                serde_json::from_str(&buffer).unwrap()
+            };
+
+            let body = input["data"].as_str().unwrap();
+            let indent = input["arguments"]["indent"].as_u64().unwrap();
+
+            if let Ok(list) = List::from_str(body, indent) {
+                print!("{}", list.to_typst())
+            } else {
+                eprintln!("Module block does not start with a list")
+            }
+        }
+        "pandoc-json" => {
+            let input: Value = {
+                let mut buffer = String::new();
+                io::stdin().read_to_string(&mut buffer).unwrap();
+                serde_json::from_str(&buffer).unwrap()
+            };
+
+            let body = input["data"].as_str().unwrap();
+            let indent = input["arguments"]["indent"].as_u64().unwrap();
+
+            if let Ok(list) = List::from_str(body, indent) {
+                print!("{}", list.to_pandoc())
+            } else {
+                eprintln!("Module block does not start with a list")
+            }
+        }
         other => {
             eprintln!("Cannot convert list to {other}");
         }
@@ -0,0 +1,440 @@
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, Read},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, json, to_value, Value};
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum JsonEntry {
+    ParentNode {
+        name: String,
+        arguments: HashMap<String, Value>,
+        children: Vec<Self>,
+        id: u64,
+    },
+    Module {
+        name: String,
+        #[serde(default)]
+        data: String,
+        #[serde(default)]
+        arguments: HashMap<String, Value>,
+        #[serde(default = "default_inline")]
+        inline: bool,
+    },
+    Compound(Vec<Self>),
+    Raw(String),
+}
+
+/// This is just a helper to ensure that omitted "inline" fields
+/// default to true.
+fn default_inline() -> bool {
+    true
+}
+
+macro_rules! inline_content {
+    ($expr:expr) => {
+        json!({
+            "name": "inline_content",
+            "data": $expr
+        })
+    }
+}
+
+macro_rules! block_content {
+    ($expr:expr) => {
+        json!({
+            "name": "block_content",
+            "data": $expr
+        })
+    }
+}
+
+macro_rules! dynamic_content {
+    ($cond:expr, $expr:expr) => {
+        if $cond {
+            block_content!($expr)
+        } else {
+            inline_content!($expr)
+        }
+    };
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let Some(action) = args.first() else {
+        eprintln!("No action was provided.");
+        return;
+    };
+
+    match action.as_str() {
+        "manifest" => print!("{}", &manifest()),
+        "transform" => {
+            let from = args.get(1).unwrap();
+            let format = args.get(2).unwrap();
+
+            if "markdown" != format {
+                eprintln!("Output format not supported");
+                return;
+            }
+
+            print!("{}", transform(from));
+        }
+        other => eprintln!("Invalid action '{other}'"),
+    }
+}
+
+fn transform(from: &str) -> String {
+    let input: JsonEntry = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        from_str(&buffer).unwrap()
+    };
+
+    // Markdown has no syntax for superscript, subscript and underlined text, but most renderers
+    // allow inline HTML so we use that instead
+    match from {
+        "__bold" => transform_tag(input, "**", "**"),
+        "__italic" => transform_tag(input, "*", "*"),
+        "__superscript" => transform_tag(input, "<sup>", "</sup>"),
+        "__subscript" => transform_tag(input, "<sub>", "</sub>"),
+        "__underlined" => transform_tag(input, "<ins>", "</ins>"),
+        "__strikethrough" => transform_tag(input, "~~", "~~"),
+        "__verbatim" => transform_verbatim(input),
+        "__paragraph" => transform_paragraph(to_value(input).unwrap()),
+        "__document" => transform_document(to_value(input).unwrap()),
+        "__math" => transform_math(to_value(input).unwrap()),
+        "__text" => escape_text(to_value(input).unwrap()),
+        "__heading" => transform_heading(to_value(input).unwrap()),
+        _ => panic!("element not supported"),
+    }
+}
+
+fn transform_paragraph(paragraph: Value) -> String {
+    let mut result = vec![json!("\n")];
+    if let Value::Array(children) = &paragraph["children"] {
+        result.extend(children.iter().cloned());
+    }
+    result.push(json!("\n"));
+
+    serde_json::to_string(&result).unwrap()
+}
+
+fn transform_tag(node: JsonEntry, opening: &str, closing: &str) -> String {
+    let mut result: Vec<Value> = vec![];
+    result.push(Value::from(opening));
+
+    match node {
+        JsonEntry::ParentNode { children, .. } => {
+            result.extend(children.into_iter().map(|x| to_value(x).unwrap()));
+        }
+        JsonEntry::Module { data, inline, .. } => {
+            result.push(dynamic_content!(inline, data));
+        }
+        _ => {}
+    }
+
+    result.push(Value::from(closing));
+    serde_json::to_string(&result).unwrap()
+}
+
+fn transform_verbatim(node: JsonEntry) -> String {
+    let result = match node {
+        JsonEntry::Module { data, inline, .. } => {
+            if inline {
+                vec![Value::from(code_span(&data))]
+            } else {
+                let fence = code_fence(&data);
+                vec![Value::from(format!("\n{fence}\n{data}\n{fence}\n"))]
+            }
+        }
+        JsonEntry::ParentNode { children, .. } => {
+            // Verbatim is evaluated before its children, so all text is collected into one code
+            // span since the delimiters depend on the contents
+            let mut content = String::new();
+            for child in children {
+                match child {
+                    JsonEntry::Module { name, data, .. } if name == "__text" => {
+                        content.push_str(&data);
+                    }
+                    JsonEntry::Module { name, .. } | JsonEntry::ParentNode { name, .. } => {
+                        eprintln!("Modules are not allowed in verbatim tags; found module {name}");
+                    }
+                    _ => {}
+                }
+            }
+            vec![Value::from(code_span(&content))]
+        }
+        _ => vec![],
+    };
+    serde_json::to_string(&result).unwrap()
+}
+
+/// Get the longest run of backticks in some text
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+/// Wrap text in a code span, using more backticks than the text contains in a row
+fn code_span(text: &str) -> String {
+    let ticks = "`".repeat(longest_backtick_run(text) + 1);
+    if text.starts_with('`') || text.ends_with('`') {
+        format!("{ticks} {text} {ticks}")
+    } else {
+        format!("{ticks}{text}{ticks}")
+    }
+}
+
+/// Get a fence for a fenced code block that can't be closed by the code itself
+fn code_fence(text: &str) -> String {
+    "`".repeat(longest_backtick_run(text).max(2) + 1)
+}
+
+fn transform_heading(heading: Value) -> String {
+    let mut vec = vec![];
+
+    let Value::String(s) = &heading["arguments"]["level"] else {
+        panic!();
+    };
+    let level = s.parse::<u8>().unwrap();
+    if level > 6 {
+        eprintln!("Markdown only supports headings up to level 6");
+    }
+    let level = level.clamp(1, 6);
+    let id = heading["id"].as_u64().unwrap();
+    let key = format!("heading:{id}");
+    let heading_style = env::var("heading_style").unwrap_or_default();
+
+    vec.push(json!(format!("\n{} ", "#".repeat(level as usize))));
+
+    let element = if heading_style == "numbered" {
+        vec.push(inline_content!(format!("[element-number]({key}) ")));
+        "numbered-heading"
+    } else {
+        "unnumbered-heading"
+    };
+
+    if let Value::Array(children) = &heading["children"] {
+        for child in children {
+            vec.push(child.clone());
+        }
+    }
+
+    // The anchor is placed inside of the heading, since a line with only an HTML tag would start
+    // an HTML block that swallows the heading
+    vec.push(json!(format!(" <a id=\"{key}\"></a>\n")));
+
+    let structure_data = json!({
+        "element": element,
+        "level": level,
+        "key": key,
+        "contents": heading["children"],
+    })
+    .to_string();
+
+    vec.push(json!(
+        {
+            "name": "list-push",
+            "arguments": {"name": "structure"},
+            "data": structure_data,
+        }
+    ));
+
+    serde_json::to_string(&vec).unwrap()
+}
+
+fn transform_math(node: Value) -> String {
+    // We know that the math tag is a non-recursively parsed tag, which means that it may only
+    // contain __text and modules. For now, we collect all __text nodes and
+    if let Value::Array(children) = &node["children"] {
+        let mut content = String::new();
+        for child in children {
+            let name = child["name"].as_str().unwrap();
+            if name == "__text" {
+                content.push_str(child["data"].as_str().unwrap());
+            } else {
+                eprintln!("Modules are not allowed in math tags; found module {name}");
+            }
+        }
+        if content.is_empty() {
+            format!("{}", json!([]))
+        } else {
+            format!(
+                "{}",
+                json!([
+                    {
+                      "name": "math",
+                      "data": content,
+                      "arguments": {},
+                      "inline": true
+                    }
+                ])
+            )
+        }
+    } else {
+        eprintln!("Unexpected __math structure");
+        String::new()
+    }
+}
+
+fn transform_document(mut doc: Value) -> String {
+    let mut result: Vec<Value> = vec![];
+
+    if let Some(vec) = doc.get_mut("children").and_then(Value::as_array_mut) {
+        result.append(vec);
+    }
+
+    serde_json::to_string(&result).unwrap()
+}
+
+fn escape_text(module: Value) -> String {
+    if let Value::String(s) = &module["data"] {
+        let s = s
+            .split('\n')
+            .map(escape_line)
+            .collect::<Vec<String>>()
+            .join("\n");
+        format!("{}", json! {[{"name":"raw","data":s}]})
+    } else {
+        panic!("Malformed text module");
+    }
+}
+
+/// Escape one line of text. Since we can't know what comes before a text node, the start of
+/// the text is treated as the start of a line, which at worst adds a redundant backslash.
+fn escape_line(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let indent = line.len() - line.trim_start().len();
+    result.push_str(&line[..indent]);
+    let line = &line[indent..];
+
+    // Characters that only have a meaning at the start of a line, such as list markers
+    let mut chars = line.chars().peekable();
+    match chars.peek() {
+        Some('-' | '+' | '=') => {
+            result.push('\\');
+            result.push(chars.next().unwrap());
+        }
+        Some(c) if c.is_ascii_digit() => {
+            while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                result.push(c);
+            }
+            if let Some(c) = chars.next_if(|c| *c == '.' || *c == ')') {
+                result.push('\\');
+                result.push(c);
+            }
+        }
+        _ => {}
+    }
+
+    for c in chars {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~' | '$' | '&'
+        ) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+fn manifest() -> String {
+    serde_json::to_string(&json!(
+        {
+            "version": "0.1",
+            "name": "markdown",
+            "description": "This packages provides Markdown support for the basic Modmark features.",
+            "transforms": [
+                {
+                    "from": "__bold",
+                    "to": ["markdown"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__italic",
+                    "to": ["markdown"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__superscript",
+                    "to": ["markdown"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__subscript",
+                    "to": ["markdown"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__strikethrough",
+                    "to": ["markdown"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__underlined",
+                    "to": ["markdown"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__math",
+                    "to": ["markdown"],
+                    "arguments": [],
+                    "evaluate-before-children": true,
+                    "type": "parent"
+                },
+                {
+                    "from": "__document",
+                    "to": ["markdown"],
+                    "arguments": [],
+                    "type": "parent"
+                },
+                {
+                    "from": "__text",
+                    "to": ["markdown"],
+                    "arguments": []
+                },
+                {
+                    "from": "__paragraph",
+                    "to": ["markdown"],
+                    "arguments": [],
+                    "type": "parent"
+                },
+                {
+                    "from": "__verbatim",
+                    "to": ["markdown"],
+                    "arguments": [],
+                    "evaluate-before-children": true,
+                    "type": "any"
+                },
+                {
+                    "from": "__heading",
+                    "to": ["markdown"],
+                    "arguments": [
+                        {
+                            "name": "level",
+                            "description": "The level of the heading",
+                            "default": "1"
+                        }
+                    ],
+                    "type": "parent",
+                    "variables": {
+                        "structure": {"type": "list", "access": "push"},
+                        "heading_style": {"type": "const", "access": "read"},
+                    },
+                },
+            ]
+        }
+    ))
+    .unwrap()
+}
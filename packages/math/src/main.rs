@@ -4,6 +4,8 @@ use std::{env, io};
 use latex2mathml::{latex_to_mathml, DisplayStyle};
 use serde_json::{json, Value};
 
+mod typst;
+
 macro_rules! raw {
     ($expr:expr) => {
         json!({
@@ -32,11 +34,11 @@ fn manifest() {
             {
             "name": "math",
             "version": "0.1",
-            "description": "This package provides inline and multiline [math] modules",
+            "description": "This package provides inline and multiline [math] modules, and numbered [equation] and [align] modules",
             "transforms": [
                 {
                     "from": "math",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "markdown", "plaintext", "typst", "pandoc-json"],
                     "arguments": [
                         {
                             "name": "import",
@@ -45,6 +47,30 @@ fn manifest() {
                             "description": r#"If set to "true", a Mathjax import will be added to HTML outputs for maximum compatibility"#
                         }
                     ],
+                },
+                {
+                    "from": "equation",
+                    "to": ["html", "latex", "markdown", "plaintext", "typst", "pandoc-json"],
+                    "arguments": [
+                        {"name": "label", "default": "", "description": "The label to use for the equation, to be able to refer to it from the document"}
+                    ],
+                    "description": "A numbered equation in display style",
+                    "variables": {
+                        "structure": {"type": "list", "access": "push"},
+                        "imports": {"type": "set", "access": "add"}
+                    }
+                },
+                {
+                    "from": "align",
+                    "to": ["html", "latex", "markdown", "plaintext", "typst", "pandoc-json"],
+                    "arguments": [
+                        {"name": "label", "default": "", "description": "The label to use for the equations, to be able to refer to them from the document"}
+                    ],
+                    "description": "Multiple lines of equations, which are separated by \\\\ and aligned at &. They are numbered as one equation",
+                    "variables": {
+                        "structure": {"type": "list", "access": "push"},
+                        "imports": {"type": "set", "access": "add"}
+                    }
                 }
             ]
             }
@@ -56,6 +82,8 @@ fn manifest() {
 fn transform(from: &str, to: &str) {
     match from {
         "math" => transform_math(to),
+        "equation" => transform_equation(to, false),
+        "align" => transform_equation(to, true),
         other => {
             eprintln!("Package does not support transforming from {other}");
         }
@@ -72,6 +100,10 @@ fn transform_math(to: &str) {
     match to {
         "html" => math_to_html(&json),
         "latex" => math_to_latex(&json),
+        "markdown" => math_to_markdown(&json),
+        "plaintext" => math_to_plaintext(&json),
+        "typst" => math_to_typst(&json),
+        "pandoc-json" => math_to_pandoc(&json),
         other => {
             eprintln!("Package does not support transforming math to {other}");
         }
@@ -146,3 +178,241 @@ fn math_to_latex(json: &Value) {
         );
     }
 }
+
+// Uses the dollar sign syntax that GitHub and most other Markdown renderers support
+fn math_to_markdown(json: &Value) {
+    let body = json["data"].as_str().expect("Data as string");
+    if json["inline"].as_bool().expect("Inline as bool") {
+        println!(
+            "{}",
+            json! {[
+                raw!(format!("${}$", body.trim()))
+            ]}
+        );
+    } else {
+        println!(
+            "{}",
+            json! {[
+                raw!(format!("\n$$\n{}\n$$\n", body.trim()))
+            ]}
+        );
+    }
+}
+
+// The LaTeX source is the most readable plain text version of the math, so it is kept as is
+fn math_to_plaintext(json: &Value) {
+    let body = json["data"].as_str().expect("Data as string");
+    if json["inline"].as_bool().expect("Inline as bool") {
+        println!(
+            "{}",
+            json! {[
+                raw!(body.trim())
+            ]}
+        );
+    } else {
+        let indented = body
+            .trim()
+            .lines()
+            .map(|line| format!("    {}", line.trim()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        println!(
+            "{}",
+            json! {[
+                raw!(format!("\n{indented}\n"))
+            ]}
+        );
+    }
+}
+
+// Display math is written with spaces inside of the dollar signs, which is how Typst tells it apart
+// from inline math
+fn math_to_typst(json: &Value) {
+    let body = json["data"].as_str().expect("Data as string");
+    let math = typst::latex_to_typst(body);
+    if json["inline"].as_bool().expect("Inline as bool") {
+        println!(
+            "{}",
+            json! {[
+                raw!(format!("${math}$"))
+            ]}
+        );
+    } else {
+        println!(
+            "{}",
+            json! {[
+                raw!(format!("\n$ {math} $\n"))
+            ]}
+        );
+    }
+}
+
+// Pandoc reads the LaTeX source of math itself. Display math is an inline in Pandoc, so a block of
+// math is put in a paragraph of its own
+fn math_to_pandoc(json: &Value) {
+    let body = json["data"].as_str().expect("Data as string");
+    if json["inline"].as_bool().expect("Inline as bool") {
+        let math = json!({"t": "Math", "c": [{"t": "InlineMath"}, body.trim()]});
+        println!("{}", json! {[raw!(format!("{math},"))]});
+    } else {
+        let math = json!({"t": "Math", "c": [{"t": "DisplayMath"}, body.trim()]});
+        println!("{}", json! {[raw!(format!("{{\"t\":\"Para\",\"c\":[{math}]}},"))]});
+    }
+}
+
+fn transform_equation(to: &str, align: bool) {
+    let json: Value = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        serde_json::from_str(&buffer).unwrap()
+    };
+
+    let body = json["data"].as_str().expect("Data as string").trim();
+    let label = json["arguments"]["label"].as_str().unwrap_or("");
+    // Equations without a label are numbered too, so they get a key of their own
+    let key = if label.is_empty() {
+        format!("equation:{}", json["id"].as_u64().unwrap_or(0))
+    } else {
+        label.to_string()
+    };
+    let number = json!({"name": "inline_content", "data": format!("[element-number]({key})")});
+
+    let structure_data = json!({"element": "equation", "key": key}).to_string();
+    let mut result = vec![json!({
+        "name": "list-push",
+        "arguments": {"name": "structure"},
+        "data": structure_data,
+    })];
+
+    match to {
+        "html" => {
+            let mathml = if align {
+                align_to_mathml(body)
+            } else {
+                latex_to_mathml(body, DisplayStyle::Block).map_err(|e| e.to_string())
+            };
+            let mathml = match mathml {
+                Ok(mathml) => mathml,
+                Err(e) => {
+                    eprintln!("Failed to parse latex: {e}");
+                    return;
+                }
+            };
+            result.push(raw!(format!(
+                r#"<div class="equation" id="{}" style="display: flex; align-items: center;">"#,
+                key.replace('"', "%22")
+            )));
+            result.push(raw!(format!(
+                r#"<div style="flex: 1;">{mathml}</div><span class="equation-number">"#
+            )));
+            result.push(number);
+            result.push(raw!("</span></div>"));
+        }
+        "latex" => {
+            result.push(json!({
+                "name": "set-add",
+                "arguments": {"name": "imports"},
+                "data": r"\usepackage{amsmath}"
+            }));
+            let mut equation = String::from("\\begin{equation}\n");
+            if !label.is_empty() {
+                equation.push_str(&format!("\\label{{{label}}}\n"));
+            }
+            if align {
+                equation.push_str(&format!("\\begin{{aligned}}\n{body}\n\\end{{aligned}}\n"));
+            } else {
+                equation.push_str(&format!("{body}\n"));
+            }
+            equation.push_str("\\end{equation}");
+            result.push(raw!(equation));
+        }
+        "markdown" => {
+            // \tag* is used since the number already has parentheses
+            let math = if align {
+                format!("\\begin{{aligned}}\n{body}\n\\end{{aligned}}")
+            } else {
+                body.to_string()
+            };
+            result.push(raw!(format!(
+                "\n<a id=\"{}\"></a>\n\n$$\n{math}\n\\tag*{{",
+                key.replace('"', "%22")
+            )));
+            result.push(number);
+            result.push(raw!("}\n$$\n"));
+        }
+        "plaintext" => {
+            // The lines of aligned equations are separated by \\, and the & are left out
+            let lines: Vec<String> = if align {
+                body.split(r"\\")
+                    .map(|line| line.replace('&', ""))
+                    .filter(|line| !line.trim().is_empty())
+                    .collect()
+            } else {
+                body.lines().map(str::to_string).collect()
+            };
+            let indented = lines
+                .iter()
+                .map(|line| format!("    {}", line.trim()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            result.push(raw!(format!("\n{indented}    ")));
+            result.push(number);
+            result.push(raw!("\n"));
+        }
+        "typst" => {
+            // The numbering comes from the structure, so the number is given as the numbering
+            let math = typst::latex_to_typst(body);
+            result.push(raw!("\n#math.equation(block: true, numbering: _ => ["));
+            result.push(number);
+            result.push(raw!(format!("], $ {math} $) <{key}>\n")));
+        }
+        "pandoc-json" => {
+            let math = if align {
+                format!("\\begin{{aligned}}\n{body}\n\\end{{aligned}}")
+            } else {
+                body.to_string()
+            };
+            let math = json!({"t": "Math", "c": [{"t": "DisplayMath"}, math]});
+            let attributes = json!([key, ["equation"], []]);
+            result.push(raw!(format!(
+                r#"{{"t":"Para","c":[{{"t":"Span","c":[{attributes},[{math}]]}},{{"t":"Space"}},"#
+            )));
+            result.push(number);
+            result.push(raw!("]},"));
+        }
+        other => {
+            eprintln!("Package does not support transforming equations to {other}");
+            return;
+        }
+    }
+
+    println!("{}", json!(result));
+}
+
+// Lines are separated by \\ and columns by &. Every other column is aligned to the right, so
+// that the columns line up at the & like they do in LaTeX
+fn align_to_mathml(body: &str) -> Result<String, String> {
+    let mut rows = String::new();
+    let mut columns = 0;
+    for line in body.split(r"\\").filter(|line| !line.trim().is_empty()) {
+        let cells: Vec<&str> = line.split('&').collect();
+        columns = columns.max(cells.len());
+        rows.push_str("<mtr>");
+        for cell in cells {
+            let mathml = latex_to_mathml(cell.trim(), DisplayStyle::Inline)
+                .map_err(|e| e.to_string())?;
+            // Only the contents of the <math> element are kept
+            let start = mathml.find('>').map_or(0, |i| i + 1);
+            let end = mathml.rfind("</math>").unwrap_or(mathml.len());
+            rows.push_str(&format!("<mtd>{}</mtd>", &mathml[start..end]));
+        }
+        rows.push_str("</mtr>");
+    }
+    let alignment = (0..columns)
+        .map(|i| if i % 2 == 0 { "right" } else { "left" })
+        .collect::<Vec<_>>()
+        .join(" ");
+    Ok(format!(
+        r#"<math xmlns="http://www.w3.org/1998/Math/MathML" display="block"><mtable columnalign="{alignment}" displaystyle="true">{rows}</mtable></math>"#
+    ))
+}
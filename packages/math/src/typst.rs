@@ -0,0 +1,492 @@
+//! Conversion of LaTeX math to Typst math. Typst has counterparts for most commands that are
+//! used in math, but a few things work differently:
+//!
+//! * Consecutive letters are one identifier in Typst, so letters are separated by spaces.
+//! * Grouping is done with parentheses, which are removed when they are the argument of a
+//!   function, a fraction or an attachment such as a superscript.
+//! * Functions take their arguments in parentheses and separated by commas, so commas and
+//!   semicolons in arguments are escaped.
+//!
+//! Commands that have no counterpart are kept as text, with a warning.
+
+const GREEK: [&str; 36] = [
+    "alpha", "beta", "gamma", "delta", "zeta", "eta", "theta", "iota", "kappa", "lambda", "mu",
+    "nu", "xi", "pi", "rho", "sigma", "tau", "upsilon", "chi", "psi", "omega", "Gamma", "Delta",
+    "Theta", "Lambda", "Xi", "Pi", "Sigma", "Upsilon", "Phi", "Psi", "Omega", "omicron", "ell",
+    "aleph", "nabla",
+];
+
+const OPERATORS: [&str; 32] = [
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh",
+    "tanh", "coth", "log", "ln", "lg", "exp", "lim", "liminf", "limsup", "sup", "inf", "max",
+    "min", "det", "gcd", "deg", "dim", "ker", "arg", "Pr", "hom",
+];
+
+/// Get the Typst symbol for a LaTeX command that doesn't take arguments
+fn symbol(command: &str) -> Option<&'static str> {
+    let symbol = match command {
+        "epsilon" => "epsilon.alt",
+        "varepsilon" => "epsilon",
+        "vartheta" => "theta.alt",
+        "varpi" => "pi.alt",
+        "varrho" => "rho.alt",
+        "varsigma" => "sigma.alt",
+        "phi" => "phi.alt",
+        "varphi" => "phi",
+        "cdot" | "cdotp" => "dot.op",
+        "times" => "times",
+        "div" => "div",
+        "pm" => "plus.minus",
+        "mp" => "minus.plus",
+        "leq" | "le" => "<=",
+        "geq" | "ge" => ">=",
+        "neq" | "ne" => "!=",
+        "ll" => "<<",
+        "gg" => ">>",
+        "approx" => "approx",
+        "equiv" => "equiv",
+        "sim" => "tilde.op",
+        "simeq" => "tilde.eq",
+        "cong" => "tilde.equiv",
+        "propto" => "prop",
+        "infty" => "infinity",
+        "partial" => "diff",
+        "sum" => "sum",
+        "prod" => "product",
+        "coprod" => "product.co",
+        "int" => "integral",
+        "iint" => "integral.double",
+        "iiint" => "integral.triple",
+        "oint" => "integral.cont",
+        "to" | "rightarrow" => "->",
+        "gets" | "leftarrow" => "<-",
+        "Rightarrow" => "=>",
+        "Leftarrow" => "arrow.l.double",
+        "leftrightarrow" => "<->",
+        "Leftrightarrow" | "iff" => "<=>",
+        "implies" => "==>",
+        "mapsto" => "|->",
+        "uparrow" => "arrow.t",
+        "downarrow" => "arrow.b",
+        "in" => "in",
+        "notin" => "in.not",
+        "ni" => "in.rev",
+        "subset" => "subset",
+        "subseteq" => "subset.eq",
+        "supset" => "supset",
+        "supseteq" => "supset.eq",
+        "cup" => "union",
+        "cap" => "sect",
+        "bigcup" => "union.big",
+        "bigcap" => "sect.big",
+        "setminus" => "without",
+        "emptyset" | "varnothing" => "emptyset",
+        "forall" => "forall",
+        "exists" => "exists",
+        "nexists" => "exists.not",
+        "neg" | "lnot" => "not",
+        "land" | "wedge" => "and",
+        "lor" | "vee" => "or",
+        "oplus" => "plus.circle",
+        "otimes" => "times.circle",
+        "ldots" | "dots" => "dots.h",
+        "cdots" => "dots.c",
+        "vdots" => "dots.v",
+        "ddots" => "dots.down",
+        "circ" => "compose",
+        "bullet" => "bullet",
+        "star" => "star",
+        "ast" => "ast",
+        "hbar" => "planck.reduce",
+        "Re" => "Re",
+        "Im" => "Im",
+        "angle" => "angle",
+        "perp" => "perp",
+        "parallel" => "parallel",
+        "mid" => "divides",
+        "langle" => "angle.l",
+        "rangle" => "angle.r",
+        "lfloor" => "floor.l",
+        "rfloor" => "floor.r",
+        "lceil" => "ceil.l",
+        "rceil" => "ceil.r",
+        "prime" => "prime",
+        "triangle" => "triangle",
+        "square" => "square",
+        "quad" => "quad",
+        "qquad" => "wide",
+        "," => "thin",
+        ":" | ">" => "med",
+        ";" => "thick",
+        " " => "space",
+        "!" => "",
+        "{" => "\\{",
+        "}" => "\\}",
+        "|" => "||",
+        "%" => "%",
+        "$" => "\\$",
+        "#" => "\\#",
+        "&" => "\\&",
+        "_" => "\\_",
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+/// Get the Typst function for a LaTeX command that takes one argument
+fn function(command: &str) -> Option<&'static str> {
+    let function = match command {
+        "hat" | "widehat" => "hat",
+        "bar" => "macron",
+        "overline" => "overline",
+        "underline" => "underline",
+        "vec" | "overrightarrow" => "arrow",
+        "dot" => "dot",
+        "ddot" => "dot.double",
+        "tilde" | "widetilde" => "tilde",
+        "overbrace" => "overbrace",
+        "underbrace" => "underbrace",
+        "cancel" => "cancel",
+        "mathbf" | "boldsymbol" | "bm" => "bold",
+        "mathrm" => "upright",
+        "mathit" => "italic",
+        "mathbb" => "bb",
+        "mathcal" => "cal",
+        "mathfrak" => "frak",
+        "mathsf" => "sans",
+        "mathtt" => "mono",
+        _ => return None,
+    };
+    Some(function)
+}
+
+/// Get the delimiter of a matrix environment, as a Typst value
+fn matrix_delimiter(environment: &str) -> Option<&'static str> {
+    let delimiter = match environment {
+        "matrix" | "smallmatrix" | "array" => "#none",
+        "pmatrix" => "\"(\"",
+        "bmatrix" => "\"[\"",
+        "Bmatrix" => "\"{\"",
+        "vmatrix" => "\"|\"",
+        "Vmatrix" => "\"||\"",
+        _ => return None,
+    };
+    Some(delimiter)
+}
+
+/// Make a Typst string literal, which is shown as upright text in math
+fn string_literal(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Convert LaTeX math to Typst math
+pub fn latex_to_typst(latex: &str) -> String {
+    let mut converter = Converter {
+        chars: latex.chars().collect(),
+        pos: 0,
+        in_arguments: false,
+    };
+    converter.convert_until(None).trim().to_string()
+}
+
+struct Converter {
+    chars: Vec<char>,
+    pos: usize,
+    /// Whether the content is an argument of a function, where commas and semicolons separate the
+    /// arguments
+    in_arguments: bool,
+}
+
+/// Add an atom to Typst math, with a space in between if they would otherwise be joined into
+/// one identifier or number
+fn push_atom(output: &mut String, atom: &str) {
+    let joins = |c: char| c.is_alphanumeric() || c == '.' || c == '"' || c == ')';
+    if let (Some(last), Some(first)) = (output.chars().last(), atom.chars().next()) {
+        if joins(last) && (first.is_alphanumeric() || first == '"') {
+            output.push(' ');
+        }
+    }
+    output.push_str(atom);
+}
+
+impl Converter {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map_or(false, char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Read the name of a command, after its backslash
+    fn command_name(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().map_or(false, |c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        if self.pos == start && self.peek().is_some() {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Read the raw text of a group, such as the name of an environment
+    fn raw_group(&mut self) -> String {
+        self.skip_whitespace();
+        if self.peek() != Some('{') {
+            return String::new();
+        }
+        self.pos += 1;
+        let start = self.pos;
+        let mut depth = 1;
+        while let Some(c) = self.peek() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        let text = self.chars[start..self.pos].iter().collect();
+        self.pos = (self.pos + 1).min(self.chars.len());
+        text
+    }
+
+    /// Convert the argument of a command or an attachment, which is either a group or one token
+    fn argument(&mut self, in_arguments: bool) -> String {
+        self.skip_whitespace();
+        let outer = self.in_arguments;
+        self.in_arguments = in_arguments;
+        let result = match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                self.convert_until(Some('}'))
+            }
+            Some(_) => {
+                let mut output = String::new();
+                self.convert_token(&mut output);
+                output
+            }
+            None => String::new(),
+        };
+        self.in_arguments = outer;
+        result.trim().to_string()
+    }
+
+    /// Convert an optional argument in square brackets, if there is one
+    fn optional_argument(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        if self.peek() != Some('[') {
+            return None;
+        }
+        self.pos += 1;
+        let outer = self.in_arguments;
+        self.in_arguments = true;
+        let result = self.convert_until(Some(']'));
+        self.in_arguments = outer;
+        Some(result.trim().to_string())
+    }
+
+    /// Convert until the end of the input or the given closing character, which is consumed
+    fn convert_until(&mut self, end: Option<char>) -> String {
+        let mut output = String::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => break,
+                Some(c) if Some(c) == end => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(_) => self.convert_token(&mut output),
+            }
+        }
+        output
+    }
+
+    /// Convert the contents of an environment, until its `\end`
+    fn convert_environment(&mut self) -> String {
+        let mut output = String::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => break,
+                Some('\\') if self.chars[self.pos + 1..].starts_with(&['e', 'n', 'd']) => {
+                    self.pos += 4;
+                    self.raw_group();
+                    break;
+                }
+                Some(_) => self.convert_token(&mut output),
+            }
+        }
+        output
+    }
+
+    fn convert_token(&mut self, output: &mut String) {
+        let Some(c) = self.peek() else {
+            return;
+        };
+        self.pos += 1;
+        match c {
+            '\\' => self.convert_command(output),
+            '{' => {
+                let group = self.convert_until(Some('}'));
+                push_atom(output, group.trim());
+            }
+            '^' | '_' => {
+                let argument = self.argument(false);
+                output.truncate(output.trim_end().len());
+                let simple = argument.chars().all(char::is_alphanumeric)
+                    || argument.chars().count() == 1;
+                if simple && !argument.is_empty() {
+                    output.push_str(&format!("{c}{argument}"));
+                } else {
+                    output.push_str(&format!("{c}({argument})"));
+                }
+            }
+            c if c.is_ascii_digit() => {
+                let mut number = String::from(c);
+                while let Some(c) = self.peek().filter(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                    self.pos += 1;
+                }
+                push_atom(output, &number);
+            }
+            '/' => output.push_str("\\/"),
+            '"' => output.push_str("\\\""),
+            '#' => output.push_str("\\#"),
+            '$' => output.push_str("\\$"),
+            '~' => push_atom(output, " space.nobreak "),
+            ',' | ';' if self.in_arguments => {
+                output.push('\\');
+                output.push(c);
+                output.push(' ');
+            }
+            ',' | ';' => {
+                output.push(c);
+                output.push(' ');
+            }
+            '&' => output.push_str(" & "),
+            '=' | '+' | '-' | '<' | '>' => {
+                output.push(' ');
+                output.push(c);
+                output.push(' ');
+            }
+            c => push_atom(output, &c.to_string()),
+        }
+    }
+
+    fn convert_command(&mut self, output: &mut String) {
+        let command = self.command_name();
+        let command = command.as_str();
+
+        if GREEK.contains(&command) || OPERATORS.contains(&command) {
+            push_atom(output, command);
+        } else if let Some(symbol) = symbol(command) {
+            push_atom(output, symbol);
+        } else if let Some(function) = function(command) {
+            let argument = self.argument(true);
+            push_atom(output, &format!("{function}({argument})"));
+        } else {
+            match command {
+                "frac" | "dfrac" | "tfrac" | "cfrac" | "binom" | "dbinom" | "tbinom" => {
+                    let first = self.argument(true);
+                    let second = self.argument(true);
+                    let function = if command.contains("binom") {
+                        "binom"
+                    } else {
+                        "frac"
+                    };
+                    push_atom(output, &format!("{function}({first}, {second})"));
+                }
+                "sqrt" => {
+                    let index = self.optional_argument();
+                    let argument = self.argument(true);
+                    match index {
+                        Some(index) => push_atom(output, &format!("root({index}, {argument})")),
+                        None => push_atom(output, &format!("sqrt({argument})")),
+                    }
+                }
+                "text" | "textrm" | "textit" | "textbf" | "mbox" | "mathnormal" => {
+                    let text = self.raw_group();
+                    push_atom(output, &string_literal(&text));
+                }
+                "operatorname" => {
+                    let name = self.raw_group();
+                    push_atom(output, &format!("op({})", string_literal(&name)));
+                }
+                "left" | "right" | "big" | "Big" | "bigg" | "Bigg" | "bigl" | "bigr" | "Bigl"
+                | "Bigr" => {
+                    // Typst scales matching delimiters by itself
+                    self.skip_whitespace();
+                    if self.peek() == Some('.') {
+                        self.pos += 1;
+                    } else {
+                        self.convert_token(output);
+                    }
+                }
+                "\\" | "newline" => {
+                    self.optional_argument();
+                    output.push_str(" \\ ");
+                }
+                "begin" => self.convert_begin(output),
+                "displaystyle" | "textstyle" | "limits" | "nolimits" | "nonumber" | "notag" => {}
+                other => {
+                    eprintln!("The LaTeX command \\{other} is not supported in Typst math");
+                    push_atom(output, &string_literal(&format!("\\{other}")));
+                }
+            }
+        }
+    }
+
+    fn convert_begin(&mut self, output: &mut String) {
+        let environment = self.raw_group();
+        if environment == "array" {
+            // The column specification is not used
+            self.raw_group();
+        }
+        let outer = self.in_arguments;
+
+        if let Some(delimiter) = matrix_delimiter(&environment) {
+            self.in_arguments = true;
+            let contents = self.convert_environment();
+            let rows = contents
+                .split(" \\ ")
+                .filter(|row| !row.trim().is_empty())
+                .map(|row| {
+                    row.split(" & ")
+                        .map(str::trim)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .collect::<Vec<_>>()
+                .join("; ");
+            push_atom(output, &format!("mat(delim: {delimiter}, {rows})"));
+        } else if environment == "cases" {
+            self.in_arguments = true;
+            let contents = self.convert_environment();
+            let rows = contents
+                .split(" \\ ")
+                .map(str::trim)
+                .filter(|row| !row.is_empty())
+                .collect::<Vec<_>>()
+                .join(", ");
+            push_atom(output, &format!("cases({rows})"));
+        } else {
+            // Environments such as align and gathered have the same syntax for alignment and line
+            // breaks in Typst
+            let contents = self.convert_environment();
+            output.push_str(contents.trim());
+        }
+
+        self.in_arguments = outer;
+    }
+}
+
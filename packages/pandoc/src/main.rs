@@ -0,0 +1,438 @@
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, Read},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, json, to_value, Value};
+
+/// The version of the Pandoc AST that is output, which is the one used by Pandoc 3
+const PANDOC_API_VERSION: [u32; 3] = [1, 23, 1];
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum JsonEntry {
+    ParentNode {
+        name: String,
+        arguments: HashMap<String, Value>,
+        children: Vec<Self>,
+        id: u64,
+    },
+    Module {
+        name: String,
+        #[serde(default)]
+        data: String,
+        #[serde(default)]
+        arguments: HashMap<String, Value>,
+        #[serde(default = "default_inline")]
+        inline: bool,
+    },
+    Compound(Vec<Self>),
+    Raw(String),
+}
+
+/// This is just a helper to ensure that omitted "inline" fields
+/// default to true.
+fn default_inline() -> bool {
+    true
+}
+
+macro_rules! inline_content {
+    ($expr:expr) => {
+        json!({
+            "name": "inline_content",
+            "data": $expr
+        })
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let Some(action) = args.first() else {
+        eprintln!("No action was provided.");
+        return;
+    };
+
+    match action.as_str() {
+        "manifest" => print!("{}", &manifest()),
+        "transform" => {
+            let from = args.get(1).unwrap();
+            let format = args.get(2).unwrap();
+
+            if "pandoc-json" != format {
+                eprintln!("Output format not supported");
+                return;
+            }
+
+            print!("{}", transform(from));
+        }
+        other => eprintln!("Invalid action '{other}'"),
+    }
+}
+
+fn transform(from: &str) -> String {
+    let input: JsonEntry = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        from_str(&buffer).unwrap()
+    };
+
+    // Every block and inline is followed by a comma, since an element can't know if it is the
+    // last one in its list. The separator filter removes the commas that end a list
+    match from {
+        "__bold" => transform_tag(input, "Strong"),
+        "__italic" => transform_tag(input, "Emph"),
+        "__superscript" => transform_tag(input, "Superscript"),
+        "__subscript" => transform_tag(input, "Subscript"),
+        "__underlined" => transform_tag(input, "Underline"),
+        "__strikethrough" => transform_tag(input, "Strikeout"),
+        "__verbatim" => transform_verbatim(input),
+        "__paragraph" => transform_paragraph(to_value(input).unwrap()),
+        "__document" => transform_document(to_value(input).unwrap()),
+        "__math" => transform_math(to_value(input).unwrap()),
+        "__text" => transform_text(to_value(input).unwrap()),
+        "__heading" => transform_heading(to_value(input).unwrap()),
+        "pandoc-json-separators" => transform_separators(to_value(input).unwrap()),
+        _ => panic!("element not supported"),
+    }
+}
+
+fn transform_paragraph(paragraph: Value) -> String {
+    let mut result = vec![json!(r#"{"t":"Para","c":["#)];
+    if let Value::Array(children) = &paragraph["children"] {
+        result.extend(children.iter().cloned());
+    }
+    result.push(json!("]},"));
+
+    serde_json::to_string(&result).unwrap()
+}
+
+fn transform_tag(node: JsonEntry, constructor: &str) -> String {
+    let mut result: Vec<Value> = vec![];
+    result.push(Value::from(format!(r#"{{"t":"{constructor}","c":["#)));
+
+    match node {
+        JsonEntry::ParentNode { children, .. } => {
+            result.extend(children.into_iter().map(|x| to_value(x).unwrap()));
+        }
+        JsonEntry::Module { data, .. } => {
+            result.push(inline_content!(data));
+        }
+        _ => {}
+    }
+
+    result.push(Value::from("]},"));
+    serde_json::to_string(&result).unwrap()
+}
+
+fn transform_verbatim(node: JsonEntry) -> String {
+    let result = match node {
+        JsonEntry::Module { data, inline, .. } => {
+            let constructor = if inline { "Code" } else { "CodeBlock" };
+            let code = json!({"t": constructor, "c": [["", [], []], data]});
+            vec![Value::from(format!("{code},"))]
+        }
+        JsonEntry::ParentNode { children, .. } => {
+            // Verbatim is evaluated before its children, so all text is collected into one string
+            let mut content = String::new();
+            for child in children {
+                match child {
+                    JsonEntry::Module { name, data, .. } if name == "__text" => {
+                        content.push_str(&data);
+                    }
+                    JsonEntry::Module { name, .. } | JsonEntry::ParentNode { name, .. } => {
+                        eprintln!("Modules are not allowed in verbatim tags; found module {name}");
+                    }
+                    _ => {}
+                }
+            }
+            let code = json!({"t": "Code", "c": [["", [], []], content]});
+            vec![Value::from(format!("{code},"))]
+        }
+        _ => vec![],
+    };
+    serde_json::to_string(&result).unwrap()
+}
+
+fn transform_heading(heading: Value) -> String {
+    let mut vec = vec![];
+
+    let Value::String(s) = &heading["arguments"]["level"] else {
+        panic!();
+    };
+    let level = s.parse::<u8>().unwrap().max(1);
+    let id = heading["id"].as_u64().unwrap();
+    let key = format!("heading:{id}");
+    let heading_style = env::var("heading_style").unwrap_or_default();
+
+    // The key is the identifier of the heading, so that links to it work. The numbers come from
+    // the structure, so Pandoc is told not to number the heading
+    vec.push(json!(format!(
+        r#"{{"t":"Header","c":[{level},[{},["unnumbered"],[]],["#,
+        json!(key)
+    )));
+
+    let element = if heading_style == "numbered" {
+        vec.push(inline_content!(format!("[element-number]({key}) ")));
+        "numbered-heading"
+    } else {
+        "unnumbered-heading"
+    };
+
+    if let Value::Array(children) = &heading["children"] {
+        for child in children {
+            vec.push(child.clone());
+        }
+    }
+
+    vec.push(json!("]]},"));
+
+    let structure_data = json!({
+        "element": element,
+        "level": level,
+        "key": key,
+        "contents": heading["children"],
+    })
+    .to_string();
+
+    vec.push(json!(
+        {
+            "name": "list-push",
+            "arguments": {"name": "structure"},
+            "data": structure_data,
+        }
+    ));
+
+    serde_json::to_string(&vec).unwrap()
+}
+
+fn transform_math(node: Value) -> String {
+    // We know that the math tag is a non-recursively parsed tag, which means that it may only
+    // contain __text and modules. For now, we collect all __text nodes and
+    if let Value::Array(children) = &node["children"] {
+        let mut content = String::new();
+        for child in children {
+            let name = child["name"].as_str().unwrap();
+            if name == "__text" {
+                content.push_str(child["data"].as_str().unwrap());
+            } else {
+                eprintln!("Modules are not allowed in math tags; found module {name}");
+            }
+        }
+        if content.is_empty() {
+            format!("{}", json!([]))
+        } else {
+            format!(
+                "{}",
+                json!([
+                    {
+                      "name": "math",
+                      "data": content,
+                      "arguments": {},
+                      "inline": true
+                    }
+                ])
+            )
+        }
+    } else {
+        eprintln!("Unexpected __math structure");
+        String::new()
+    }
+}
+
+fn transform_document(mut doc: Value) -> String {
+    let mut result: Vec<Value> = vec![json!(format!(
+        r#"{{"pandoc-api-version":{},"meta":{{}},"blocks":["#,
+        json!(PANDOC_API_VERSION)
+    ))];
+
+    if let Some(vec) = doc.get_mut("children").and_then(Value::as_array_mut) {
+        result.append(vec);
+    }
+
+    result.push(json!("]}\n"));
+
+    serde_json::to_string(&result).unwrap()
+}
+
+/// Split text into words, spaces and line breaks, which are separate inlines in Pandoc
+fn transform_text(module: Value) -> String {
+    let Value::String(s) = &module["data"] else {
+        panic!("Malformed text module");
+    };
+
+    let mut inlines = String::new();
+    for (idx, line) in s.split('\n').enumerate() {
+        if idx != 0 {
+            inlines.push_str(r#"{"t":"SoftBreak"},"#);
+        }
+        let mut words = line.split(' ').peekable();
+        while let Some(word) = words.next() {
+            if !word.is_empty() {
+                inlines.push_str(&format!(r#"{{"t":"Str","c":{}}},"#, json!(word)));
+            }
+            // Runs of spaces are a single space in Pandoc
+            if words.peek().is_some() && !inlines.ends_with(r#"{"t":"Space"},"#) {
+                inlines.push_str(r#"{"t":"Space"},"#);
+            }
+        }
+    }
+
+    format!("{}", json!([{"name": "raw", "data": inlines}]))
+}
+
+/// Remove the commas that come right before the end of a list or an object, since JSON doesn't
+/// allow trailing commas. Commas within strings are left as they are
+fn transform_separators(output: Value) -> String {
+    let body = output["data"].as_str().unwrap();
+    let mut result = String::with_capacity(body.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    // A comma that hasn't been written yet, and the whitespace that follows it
+    let mut pending: Option<String> = None;
+
+    for c in body.chars() {
+        if in_string {
+            result.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        if let Some(whitespace) = pending.as_mut() {
+            if c.is_whitespace() {
+                whitespace.push(c);
+                continue;
+            }
+            let whitespace = pending.take().unwrap();
+            if c != ']' && c != '}' {
+                result.push(',');
+            }
+            result.push_str(&whitespace);
+        }
+
+        match c {
+            ',' => pending = Some(String::new()),
+            '"' => {
+                in_string = true;
+                result.push(c);
+            }
+            c => result.push(c),
+        }
+    }
+    if let Some(whitespace) = pending {
+        result.push_str(&whitespace);
+    }
+
+    serde_json::to_string(&json!([{"name": "raw", "data": result}])).unwrap()
+}
+
+fn manifest() -> String {
+    serde_json::to_string(&json!(
+        {
+            "version": "0.1",
+            "name": "pandoc",
+            "description": "This packages provides output as the JSON representation of the Pandoc AST, which can be read by Pandoc with 'pandoc -f json'.",
+            "transforms": [
+                {
+                    "from": "__bold",
+                    "to": ["pandoc-json"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__italic",
+                    "to": ["pandoc-json"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__superscript",
+                    "to": ["pandoc-json"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__subscript",
+                    "to": ["pandoc-json"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__strikethrough",
+                    "to": ["pandoc-json"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__underlined",
+                    "to": ["pandoc-json"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__math",
+                    "to": ["pandoc-json"],
+                    "arguments": [],
+                    "evaluate-before-children": true,
+                    "type": "parent"
+                },
+                {
+                    "from": "__document",
+                    "to": ["pandoc-json"],
+                    "arguments": [],
+                    "type": "parent"
+                },
+                {
+                    "from": "__text",
+                    "to": ["pandoc-json"],
+                    "arguments": []
+                },
+                {
+                    "from": "__paragraph",
+                    "to": ["pandoc-json"],
+                    "arguments": [],
+                    "type": "parent"
+                },
+                {
+                    "from": "__verbatim",
+                    "to": ["pandoc-json"],
+                    "arguments": [],
+                    "evaluate-before-children": true,
+                    "type": "any"
+                },
+                {
+                    "from": "__heading",
+                    "to": ["pandoc-json"],
+                    "arguments": [
+                        {
+                            "name": "level",
+                            "description": "The level of the heading",
+                            "default": "1"
+                        }
+                    ],
+                    "type": "parent",
+                    "variables": {
+                        "structure": {"type": "list", "access": "push"},
+                        "heading_style": {"type": "const", "access": "read"},
+                    },
+                },
+                {
+                    "from": "pandoc-json-separators",
+                    "to": ["pandoc-json"],
+                    "arguments": [],
+                    "description": "Removes the separators after the last element of each list, which every element is followed by",
+                    "type": "output-filter"
+                },
+            ]
+        }
+    ))
+    .unwrap()
+}
@@ -0,0 +1,353 @@
+//! Plain text can't be laid out while elements are transformed, since parents are transformed
+//! before their children and the width of the contents isn't known yet. Instead, transforms to
+//! plaintext mark up their output with characters from the Unicode private use area, and the
+//! "plaintext-layout" output filter lays out the final document from those markers:
+//!
+//! * `BLOCK prefix SEPARATOR contents END` wraps the contents to the text width. The first line
+//!   starts with the prefix, and the other lines are indented to line up with the contents.
+//! * `HEADING underline SEPARATOR contents END` wraps the contents and underlines them with the
+//!   underline character.
+//! * `CENTER contents END` wraps the contents and centers every line.
+//! * `LINK url LINK_END` is replaced by a reference like `[1]`, and the url is listed at the
+//!   end of the document.
+//! * `TABLE spec SEPARATOR rows END` draws a table, where the cells of each row are separated by
+//!   `CELL` and each row ends with `ROW`. The spec is one alignment character (`l`, `c` or `r`)
+//!   for each column, followed by `:` and the borders to draw: `h` between rows, `v` between
+//!   columns, `o` around the table and `H` below the header row.
+//!
+//! `BREAK` is a hard line break within wrapped contents.
+
+use unicode_width::UnicodeWidthStr;
+
+pub const BLOCK: char = '\u{E000}';
+pub const SEPARATOR: char = '\u{E001}';
+pub const END: char = '\u{E002}';
+pub const BREAK: char = '\u{E003}';
+pub const HEADING: char = '\u{E004}';
+pub const CENTER: char = '\u{E005}';
+pub const LINK: char = '\u{E006}';
+pub const LINK_END: char = '\u{E007}';
+pub const TABLE: char = '\u{E008}';
+pub const CELL: char = '\u{E009}';
+pub const ROW: char = '\u{E00A}';
+
+/// Check if a character is one of the markers, and thus may not occur in the text of a document
+pub fn is_marker(c: char) -> bool {
+    ('\u{E000}'..='\u{E00F}').contains(&c)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BoxStyle {
+    Unicode,
+    Ascii,
+}
+
+/// Lay out a whole document, which ends with a list of the links in it
+pub fn layout(output: &str, width: usize, style: BoxStyle) -> String {
+    let (text, links) = extract_links(output);
+    let mut result = collapse_blank_lines(&render(&text, width, style));
+
+    if !links.is_empty() {
+        result.push_str("\n\n");
+        for (idx, link) in links.iter().enumerate() {
+            result.push_str(&format!("[{}] {link}\n", idx + 1));
+        }
+    } else if !result.is_empty() {
+        result.push('\n');
+    }
+    result
+}
+
+/// Replace all links with references to a list of unique links
+fn extract_links(text: &str) -> (String, Vec<String>) {
+    let mut links: Vec<String> = vec![];
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(LINK) {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + LINK.len_utf8()..];
+        let end = after.find(LINK_END).unwrap_or(after.len());
+        let link = after[..end].to_string();
+
+        let number = match links.iter().position(|l| *l == link) {
+            Some(idx) => idx + 1,
+            None => {
+                links.push(link);
+                links.len()
+            }
+        };
+        result.push_str(&format!(" [{number}]"));
+        rest = after.get(end + LINK_END.len_utf8()..).unwrap_or("");
+    }
+    result.push_str(rest);
+
+    (result, links)
+}
+
+/// Render all regions in some text, starting with the innermost ones
+fn render(text: &str, width: usize, style: BoxStyle) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(is_opening) {
+        result.push_str(&rest[..start]);
+        let opening = rest[start..].chars().next().unwrap();
+        let after = &rest[start + opening.len_utf8()..];
+        let end = matching_end(after);
+        let contents = render(&after[..end], width, style);
+
+        result.push_str(&render_region(opening, &contents, width, style));
+        rest = after.get(end + END.len_utf8()..).unwrap_or("");
+    }
+    result.push_str(rest);
+
+    result
+}
+
+fn is_opening(c: char) -> bool {
+    matches!(c, BLOCK | HEADING | CENTER | TABLE)
+}
+
+/// Find the end of a region, skipping over any regions nested in it
+fn matching_end(text: &str) -> usize {
+    let mut depth = 0;
+    for (idx, c) in text.char_indices() {
+        if is_opening(c) {
+            depth += 1;
+        } else if c == END {
+            if depth == 0 {
+                return idx;
+            }
+            depth -= 1;
+        }
+    }
+    text.len()
+}
+
+fn render_region(opening: char, contents: &str, width: usize, style: BoxStyle) -> String {
+    let (head, body) = contents.split_once(SEPARATOR).unwrap_or(("", contents));
+    match opening {
+        BLOCK => wrap(body, head, width),
+        HEADING => {
+            let text = wrap(body, "", width);
+            let longest = text.lines().map(UnicodeWidthStr::width).max().unwrap_or(0);
+            let underline = head.chars().next().unwrap_or('=');
+            format!("{text}\n{}", underline.to_string().repeat(longest))
+        }
+        CENTER => wrap(contents, "", width)
+            .lines()
+            .map(|line| {
+                let padding = width.saturating_sub(line.width()) / 2;
+                format!("{}{line}", " ".repeat(padding))
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        TABLE => render_table(head, body, width, style),
+        _ => unreachable!(),
+    }
+}
+
+/// Wrap text to a width, with a prefix on the first line and the following lines indented to
+/// line up with the text after the prefix. Any whitespace in the text is collapsed.
+pub fn wrap(text: &str, prefix: &str, width: usize) -> String {
+    let indent = " ".repeat(prefix.width());
+    let mut lines = vec![];
+
+    for hard_line in text.split(BREAK) {
+        let mut line = if lines.is_empty() {
+            prefix.to_string()
+        } else {
+            indent.clone()
+        };
+        let mut is_empty = true;
+
+        for word in hard_line.split_whitespace() {
+            if !is_empty && line.width() + 1 + word.width() > width {
+                lines.push(line);
+                line = indent.clone();
+                is_empty = true;
+            }
+            if !is_empty {
+                line.push(' ');
+            }
+            line.push_str(word);
+            is_empty = false;
+        }
+        lines.push(line.trim_end().to_string());
+    }
+
+    lines.join("\n")
+}
+
+/// Turn runs of more than one blank line into a single blank line, and remove blank lines at the
+/// start and end of the document. Form feeds (page breaks) are kept.
+fn collapse_blank_lines(text: &str) -> String {
+    let mut result: Vec<&str> = vec![];
+    let mut blank = false;
+    for line in text.lines().map(|line| line.trim_end_matches([' ', '\t', '\r'])) {
+        if line.is_empty() {
+            blank = !result.is_empty();
+        } else {
+            if blank {
+                result.push("");
+            }
+            result.push(line);
+            blank = false;
+        }
+    }
+    result.join("\n")
+}
+
+struct Borders {
+    horizontal: bool,
+    vertical: bool,
+    outer: bool,
+    header: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Rule {
+    Top,
+    Middle,
+    Header,
+    Bottom,
+}
+
+impl BoxStyle {
+    // The characters to use for a rule, as (left, fill, junction, right)
+    fn rule_chars(&self, rule: Rule) -> (char, char, char, char) {
+        match (self, rule) {
+            (BoxStyle::Unicode, Rule::Top) => ('┌', '─', '┬', '┐'),
+            (BoxStyle::Unicode, Rule::Middle) => ('├', '─', '┼', '┤'),
+            (BoxStyle::Unicode, Rule::Header) => ('╞', '═', '╪', '╡'),
+            (BoxStyle::Unicode, Rule::Bottom) => ('└', '─', '┴', '┘'),
+            (BoxStyle::Ascii, Rule::Header) => ('+', '=', '+', '+'),
+            (BoxStyle::Ascii, _) => ('+', '-', '+', '+'),
+        }
+    }
+
+    fn vertical(&self) -> char {
+        match self {
+            BoxStyle::Unicode => '│',
+            BoxStyle::Ascii => '|',
+        }
+    }
+}
+
+fn render_table(spec: &str, body: &str, width: usize, style: BoxStyle) -> String {
+    let (alignment, borders) = spec.split_once(':').unwrap_or((spec, ""));
+    let alignment: Vec<char> = alignment.chars().collect();
+    let borders = Borders {
+        horizontal: borders.contains('h'),
+        vertical: borders.contains('v'),
+        outer: borders.contains('o'),
+        header: borders.contains('H'),
+    };
+
+    // Every cell is split into its lines, since it may contain line breaks
+    let rows: Vec<Vec<Vec<String>>> = body
+        .split(ROW)
+        .filter(|row| !row.trim().is_empty())
+        .map(|row| {
+            row.split(CELL)
+                .map(|cell| {
+                    wrap(cell, "", width)
+                        .lines()
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        })
+        .collect();
+
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+    let widths: Vec<usize> = (0..columns)
+        .map(|col| {
+            rows.iter()
+                .filter_map(|row| row.get(col))
+                .flat_map(|cell| cell.iter().map(|line| line.width()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let rule = |rule: Rule| {
+        let (left, fill, junction, right) = style.rule_chars(rule);
+        let mut line = String::new();
+        if borders.outer {
+            line.push(left);
+        }
+        for (idx, width) in widths.iter().enumerate() {
+            if idx != 0 {
+                line.push(if borders.vertical { junction } else { fill });
+            }
+            line.push_str(&fill.to_string().repeat(width + 2));
+        }
+        if borders.outer {
+            line.push(right);
+        }
+        line
+    };
+
+    let mut lines = vec![];
+    if borders.outer {
+        lines.push(rule(Rule::Top));
+    }
+    for (row_idx, row) in rows.iter().enumerate() {
+        let height = row.iter().map(Vec::len).max().unwrap_or(1).max(1);
+        for line_idx in 0..height {
+            let mut line = String::new();
+            if borders.outer {
+                line.push(style.vertical());
+            }
+            for (col, width) in widths.iter().enumerate() {
+                if col != 0 {
+                    line.push(if borders.vertical { style.vertical() } else { ' ' });
+                }
+                let text = row
+                    .get(col)
+                    .and_then(|cell| cell.get(line_idx))
+                    .map(String::as_str)
+                    .unwrap_or("");
+                let align = alignment.get(col).copied().unwrap_or('l');
+                line.push(' ');
+                line.push_str(&align_text(text, *width, align));
+                line.push(' ');
+            }
+            if borders.outer {
+                line.push(style.vertical());
+            }
+            lines.push(line.trim_end().to_string());
+        }
+
+        if row_idx + 1 != rows.len() {
+            if row_idx == 0 && borders.header {
+                lines.push(rule(Rule::Header));
+            } else if borders.horizontal {
+                lines.push(rule(Rule::Middle));
+            }
+        }
+    }
+    if borders.outer {
+        lines.push(rule(Rule::Bottom));
+    }
+
+    lines.join("\n")
+}
+
+fn align_text(text: &str, width: usize, alignment: char) -> String {
+    let padding = width.saturating_sub(text.width());
+    match alignment {
+        'r' => format!("{}{text}", " ".repeat(padding)),
+        'c' => format!(
+            "{}{text}{}",
+            " ".repeat(padding / 2),
+            " ".repeat(padding - padding / 2)
+        ),
+        _ => format!("{text}{}", " ".repeat(padding)),
+    }
+}
@@ -0,0 +1,413 @@
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, Read},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, json, to_value, Value};
+
+use layout::{BoxStyle, BLOCK, END, HEADING, SEPARATOR};
+
+mod layout;
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum JsonEntry {
+    ParentNode {
+        name: String,
+        arguments: HashMap<String, Value>,
+        children: Vec<Self>,
+        id: u64,
+    },
+    Module {
+        name: String,
+        #[serde(default)]
+        data: String,
+        #[serde(default)]
+        arguments: HashMap<String, Value>,
+        #[serde(default = "default_inline")]
+        inline: bool,
+    },
+    Compound(Vec<Self>),
+    Raw(String),
+}
+
+/// This is just a helper to ensure that omitted "inline" fields
+/// default to true.
+fn default_inline() -> bool {
+    true
+}
+
+macro_rules! inline_content {
+    ($expr:expr) => {
+        json!({
+            "name": "inline_content",
+            "data": $expr
+        })
+    }
+}
+
+macro_rules! block_content {
+    ($expr:expr) => {
+        json!({
+            "name": "block_content",
+            "data": $expr
+        })
+    }
+}
+
+macro_rules! dynamic_content {
+    ($cond:expr, $expr:expr) => {
+        if $cond {
+            block_content!($expr)
+        } else {
+            inline_content!($expr)
+        }
+    };
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let Some(action) = args.first() else {
+        eprintln!("No action was provided.");
+        return;
+    };
+
+    match action.as_str() {
+        "manifest" => print!("{}", &manifest()),
+        "transform" => {
+            let from = args.get(1).unwrap();
+            let format = args.get(2).unwrap();
+
+            if "plaintext" != format {
+                eprintln!("Output format not supported");
+                return;
+            }
+
+            print!("{}", transform(from));
+        }
+        other => eprintln!("Invalid action '{other}'"),
+    }
+}
+
+fn transform(from: &str) -> String {
+    let input: JsonEntry = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        from_str(&buffer).unwrap()
+    };
+
+    match from {
+        "__bold" => transform_tag(input, "*", "*"),
+        "__italic" => transform_tag(input, "/", "/"),
+        "__superscript" => transform_tag(input, "^(", ")"),
+        "__subscript" => transform_tag(input, "_(", ")"),
+        "__underlined" => transform_tag(input, "_", "_"),
+        "__strikethrough" => transform_tag(input, "~", "~"),
+        "__verbatim" => transform_verbatim(input),
+        "__paragraph" => transform_paragraph(to_value(input).unwrap()),
+        "__document" => transform_document(to_value(input).unwrap()),
+        "__math" => transform_math(to_value(input).unwrap()),
+        "__text" => escape_text(to_value(input).unwrap()),
+        "__heading" => transform_heading(to_value(input).unwrap()),
+        "plaintext-layout" => transform_layout(to_value(input).unwrap()),
+        _ => panic!("element not supported"),
+    }
+}
+
+fn transform_paragraph(paragraph: Value) -> String {
+    let mut result = vec![json!(format!("\n{BLOCK}{SEPARATOR}"))];
+    if let Value::Array(children) = &paragraph["children"] {
+        result.extend(children.iter().cloned());
+    }
+    result.push(json!(format!("{END}\n")));
+
+    serde_json::to_string(&result).unwrap()
+}
+
+fn transform_tag(node: JsonEntry, opening: &str, closing: &str) -> String {
+    let mut result: Vec<Value> = vec![];
+    result.push(Value::from(opening));
+
+    match node {
+        JsonEntry::ParentNode { children, .. } => {
+            result.extend(children.into_iter().map(|x| to_value(x).unwrap()));
+        }
+        JsonEntry::Module { data, inline, .. } => {
+            result.push(dynamic_content!(inline, data));
+        }
+        _ => {}
+    }
+
+    result.push(Value::from(closing));
+    serde_json::to_string(&result).unwrap()
+}
+
+fn transform_verbatim(node: JsonEntry) -> String {
+    let result = match node {
+        JsonEntry::Module { data, inline, .. } => {
+            let data: String = data.chars().filter(|c| !layout::is_marker(*c)).collect();
+            if inline {
+                vec![Value::from(data)]
+            } else {
+                vec![Value::from(format!("\n{}\n", indent_lines(&data, 4)))]
+            }
+        }
+        JsonEntry::ParentNode { children, .. } => children
+            .into_iter()
+            .map(|child| match child {
+                JsonEntry::Module { name, data, .. } if name == "__text" => {
+                    let data: String = data.chars().filter(|c| !layout::is_marker(*c)).collect();
+                    Value::from(data)
+                }
+                other => to_value(other).unwrap(),
+            })
+            .collect(),
+        _ => vec![],
+    };
+    serde_json::to_string(&result).unwrap()
+}
+
+/// Indent all lines of some text by a number of spaces
+fn indent_lines(text: &str, spaces: usize) -> String {
+    text.lines()
+        .map(|line| format!("{}{line}", " ".repeat(spaces)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn transform_heading(heading: Value) -> String {
+    let mut vec = vec![];
+
+    let Value::String(s) = &heading["arguments"]["level"] else {
+        panic!();
+    };
+    let level = s.parse::<u8>().unwrap();
+    let id = heading["id"].as_u64().unwrap();
+    let key = format!("heading:{id}");
+    let heading_style = env::var("heading_style").unwrap_or_default();
+    let underline = match level {
+        0 | 1 => '=',
+        2 => '-',
+        _ => '~',
+    };
+
+    vec.push(json!(format!("\n{HEADING}{underline}{SEPARATOR}")));
+
+    let element = if heading_style == "numbered" {
+        vec.push(inline_content!(format!("[element-number]({key}) ")));
+        "numbered-heading"
+    } else {
+        "unnumbered-heading"
+    };
+
+    if let Value::Array(children) = &heading["children"] {
+        for child in children {
+            vec.push(child.clone());
+        }
+    }
+
+    vec.push(json!(format!("{END}\n")));
+
+    let structure_data = json!({
+        "element": element,
+        "level": level,
+        "key": key,
+        "contents": heading["children"],
+    })
+    .to_string();
+
+    vec.push(json!(
+        {
+            "name": "list-push",
+            "arguments": {"name": "structure"},
+            "data": structure_data,
+        }
+    ));
+
+    serde_json::to_string(&vec).unwrap()
+}
+
+fn transform_math(node: Value) -> String {
+    // We know that the math tag is a non-recursively parsed tag, which means that it may only
+    // contain __text and modules. For now, we collect all __text nodes and
+    if let Value::Array(children) = &node["children"] {
+        let mut content = String::new();
+        for child in children {
+            let name = child["name"].as_str().unwrap();
+            if name == "__text" {
+                content.push_str(child["data"].as_str().unwrap());
+            } else {
+                eprintln!("Modules are not allowed in math tags; found module {name}");
+            }
+        }
+        if content.is_empty() {
+            format!("{}", json!([]))
+        } else {
+            format!(
+                "{}",
+                json!([
+                    {
+                      "name": "math",
+                      "data": content,
+                      "arguments": {},
+                      "inline": true
+                    }
+                ])
+            )
+        }
+    } else {
+        eprintln!("Unexpected __math structure");
+        String::new()
+    }
+}
+
+fn transform_document(mut doc: Value) -> String {
+    let mut result: Vec<Value> = vec![];
+
+    if let Some(vec) = doc.get_mut("children").and_then(Value::as_array_mut) {
+        result.append(vec);
+    }
+
+    serde_json::to_string(&result).unwrap()
+}
+
+fn escape_text(module: Value) -> String {
+    if let Value::String(s) = &module["data"] {
+        // The markers used for the layout may not be part of the text itself
+        let s: String = s.chars().filter(|c| !layout::is_marker(*c)).collect();
+        format!("{}", json! {[{"name":"raw","data":s}]})
+    } else {
+        panic!("Malformed text module");
+    }
+}
+
+fn transform_layout(output: Value) -> String {
+    let width = match env::var("text_width") {
+        Ok(width) => width.trim().parse::<usize>().unwrap_or_else(|_| {
+            eprintln!("Invalid text_width '{width}', using 80 instead");
+            80
+        }),
+        Err(_) => 80,
+    };
+    let style = match env::var("table_style").unwrap_or_default().as_str() {
+        "" | "unicode" => BoxStyle::Unicode,
+        "ascii" => BoxStyle::Ascii,
+        other => {
+            eprintln!("Invalid table_style '{other}', choose one of unicode/ascii");
+            BoxStyle::Unicode
+        }
+    };
+
+    let text = output["data"].as_str().unwrap();
+    serde_json::to_string(&json!([layout::layout(text, width, style)])).unwrap()
+}
+
+fn manifest() -> String {
+    serde_json::to_string(&json!(
+        {
+            "version": "0.1",
+            "name": "plaintext",
+            "description": "This packages provides plain text support for the basic Modmark features.",
+            "transforms": [
+                {
+                    "from": "__bold",
+                    "to": ["plaintext"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__italic",
+                    "to": ["plaintext"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__superscript",
+                    "to": ["plaintext"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__subscript",
+                    "to": ["plaintext"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__strikethrough",
+                    "to": ["plaintext"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__underlined",
+                    "to": ["plaintext"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__math",
+                    "to": ["plaintext"],
+                    "arguments": [],
+                    "evaluate-before-children": true,
+                    "type": "parent"
+                },
+                {
+                    "from": "__document",
+                    "to": ["plaintext"],
+                    "arguments": [],
+                    "type": "parent"
+                },
+                {
+                    "from": "__text",
+                    "to": ["plaintext"],
+                    "arguments": []
+                },
+                {
+                    "from": "__paragraph",
+                    "to": ["plaintext"],
+                    "arguments": [],
+                    "type": "parent"
+                },
+                {
+                    "from": "__verbatim",
+                    "to": ["plaintext"],
+                    "arguments": [],
+                    "evaluate-before-children": true,
+                    "type": "any"
+                },
+                {
+                    "from": "__heading",
+                    "to": ["plaintext"],
+                    "arguments": [
+                        {
+                            "name": "level",
+                            "description": "The level of the heading",
+                            "default": "1"
+                        }
+                    ],
+                    "type": "parent",
+                    "variables": {
+                        "structure": {"type": "list", "access": "push"},
+                        "heading_style": {"type": "const", "access": "read"},
+                    },
+                },
+                {
+                    "from": "plaintext-layout",
+                    "to": ["plaintext"],
+                    "arguments": [],
+                    "description": "Wraps the text of the document to text_width (80 by default) columns and draws the tables, using either unicode or ascii characters depending on table_style",
+                    "type": "output-filter",
+                    "variables": {
+                        "text_width": {"type": "const", "access": "read"},
+                        "table_style": {"type": "const", "access": "read"},
+                    },
+                },
+            ]
+        }
+    ))
+    .unwrap()
+}
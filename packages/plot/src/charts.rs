@@ -0,0 +1,564 @@
+use plotters::prelude::*;
+use serde_json::Value;
+use std::fs;
+use std::ops::Range;
+
+use crate::utils::*;
+
+// The options of the charts that are drawn from tables of data, such as CSV files
+pub struct ChartOptions {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    x_column: usize,
+    y_columns: Vec<usize>,
+    y_given: bool,
+    x_label: String,
+    y_label: String,
+    legend: Option<SeriesLabelPosition>,
+    x_log: bool,
+    y_log: bool,
+    x_ticks: usize,
+    y_ticks: usize,
+    tick_decimals: Option<usize>,
+    stacked: bool,
+    bins: usize,
+}
+
+impl ChartOptions {
+    pub fn new(input: &Value, data: &str) -> Result<Self, String> {
+        let arg = |name: &str| input["arguments"][name].as_str().unwrap_or("").trim();
+
+        // The data is read from the file if there is one, and from the module otherwise
+        let text = match arg("path") {
+            "" => data.to_string(),
+            path => fs::read_to_string(path)
+                .map_err(|_| format!("File could not be accessed at {path}"))?,
+        };
+        let delimiter = match arg("delimiter") {
+            "tab" | "\\t" => b'\t',
+            "" => {
+                if arg("path").ends_with(".tsv") {
+                    b'\t'
+                } else {
+                    b','
+                }
+            }
+            s if s.len() == 1 && s != "\"" => s.as_bytes()[0],
+            s => {
+                return Err(format!(
+                    "Invalid delimiter '{s}', use a single ASCII character or tab."
+                ))
+            }
+        };
+
+        let mut rows = parse_csv(&text, delimiter)?;
+        if rows.is_empty() {
+            return Err(String::from("There is no data to plot."));
+        }
+        let header = rows.remove(0);
+
+        let x_column = match arg("x") {
+            "" => 0,
+            x => find_column(x, &header)?,
+        };
+        let y_columns = match arg("y") {
+            "" => (0..header.len()).filter(|&idx| idx != x_column).collect(),
+            y => y
+                .split(',')
+                .map(|column| find_column(column.trim(), &header))
+                .collect::<Result<_, _>>()?,
+        };
+
+        let legend = match arg("legend") {
+            "none" => None,
+            "upper-left" => Some(SeriesLabelPosition::UpperLeft),
+            "lower-left" => Some(SeriesLabelPosition::LowerLeft),
+            "lower-right" => Some(SeriesLabelPosition::LowerRight),
+            "upper-right" | "" => Some(SeriesLabelPosition::UpperRight),
+            other => {
+                return Err(format!(
+                    "Invalid legend '{other}', use upper-left, upper-right, lower-left, lower-right or none."
+                ))
+            }
+        };
+
+        let tick_decimals = match arg("tick_decimals") {
+            "" => None,
+            s => Some(
+                s.parse::<usize>()
+                    .map_err(|_| String::from("The number of decimals should be a number."))?,
+            ),
+        };
+
+        Ok(Self {
+            header,
+            rows,
+            x_column,
+            y_columns,
+            y_given: !arg("y").is_empty(),
+            x_label: arg("x_label").to_string(),
+            y_label: arg("y_label").to_string(),
+            legend,
+            x_log: arg("x_scale") == "log",
+            y_log: arg("y_scale") == "log",
+            x_ticks: input["arguments"]["x_ticks"].as_u64().unwrap_or(10) as usize,
+            y_ticks: input["arguments"]["y_ticks"].as_u64().unwrap_or(10) as usize,
+            tick_decimals,
+            stacked: arg("mode") == "stacked",
+            bins: input["arguments"]["bins"].as_u64().unwrap_or(10).max(1) as usize,
+        })
+    }
+
+    // Gets the numbers in a column, leaving out the cells that aren't numbers
+    fn numbers(&self, column: usize) -> Vec<Option<f64>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                row.get(column)
+                    .and_then(|cell| cell.trim().parse::<f64>().ok())
+            })
+            .collect()
+    }
+
+    fn name(&self, column: usize) -> String {
+        self.header.get(column).cloned().unwrap_or_default()
+    }
+}
+
+// Finds a column by its name in the header, or by its number starting at 1
+fn find_column(column: &str, header: &[String]) -> Result<usize, String> {
+    if let Some(idx) = header.iter().position(|name| name.trim() == column) {
+        return Ok(idx);
+    }
+    match column.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= header.len() => Ok(n - 1),
+        _ => Err(format!("There is no column '{column}' in the data.")),
+    }
+}
+
+// Parses comma-separated values as described in RFC 4180, with some other delimiter if wanted.
+// Rows may have different numbers of fields, and rows without any values are left out
+fn parse_csv(text: &str, delimiter: u8) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = vec![];
+    for record in csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(text.as_bytes())
+        .records()
+    {
+        let record = record.map_err(|e| format!("The data could not be read as CSV: {e}"))?;
+        if record.iter().any(|cell| !cell.trim().is_empty()) {
+            rows.push(record.iter().map(str::to_string).collect());
+        }
+    }
+    Ok(rows)
+}
+
+// What is drawn for one series of data
+enum Shape {
+    Bars(Vec<[(f64, f64); 2]>),
+    Points(Vec<(f64, f64)>),
+    Line(Vec<(f64, f64)>),
+}
+
+struct Series {
+    name: Option<String>,
+    shape: Shape,
+}
+
+// The ranges of the axes. Log scales are drawn as the logarithms of the values on linear axes,
+// and the labels of the ticks show the values themselves
+struct Frame {
+    x: Range<f64>,
+    y: Range<f64>,
+    categories: Option<Vec<String>>,
+}
+
+// Gets the range that contains all values, with some space around them
+fn padded_range(values: impl Iterator<Item = f64>, include_zero: bool) -> Range<f64> {
+    let (mut min, mut max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+        (min.min(v), max.max(v))
+    });
+    if include_zero {
+        min = min.min(0.0);
+        max = max.max(0.0);
+    }
+    if !min.is_finite() || !max.is_finite() {
+        return 0.0..1.0;
+    }
+    if min == max {
+        return (min - 1.0)..(max + 1.0);
+    }
+    let padding = (max - min) * 0.05;
+    let min = if include_zero && min == 0.0 {
+        0.0
+    } else {
+        min - padding
+    };
+    let max = if include_zero && max == 0.0 {
+        0.0
+    } else {
+        max + padding
+    };
+    min..max
+}
+
+// Gets a value on an axis, which is its logarithm on log scales. Values that can't be shown on a
+// log scale are left out
+fn scale(value: f64, log: bool) -> Option<f64> {
+    match (log, value) {
+        (false, v) if v.is_finite() => Some(v),
+        (true, v) if v > 0.0 && v.is_finite() => Some(v.log10()),
+        _ => None,
+    }
+}
+
+fn format_tick(value: f64, log: bool, decimals: Option<usize>) -> String {
+    let value = if log { 10f64.powf(value) } else { value };
+    let formatted = match decimals {
+        Some(decimals) => format!("{value:.decimals$}"),
+        None => {
+            let s = format!("{value:.4}");
+            s.trim_end_matches('0').trim_end_matches('.').to_string()
+        }
+    };
+    if formatted == "-0" {
+        String::from("0")
+    } else {
+        formatted
+    }
+}
+
+// On log scales, a tick for each power of ten is enough
+fn tick_count(range: &Range<f64>, log: bool, ticks: usize) -> usize {
+    if log {
+        ticks
+            .min((range.end.ceil() - range.start.floor()) as usize + 1)
+            .max(2)
+    } else {
+        ticks
+    }
+}
+
+fn draw_chart(
+    ctx: &mut PlotContext,
+    opts: &ChartOptions,
+    frame: Frame,
+    series: Vec<Series>,
+) -> Result<String, String> {
+    let x_log = opts.x_log && frame.categories.is_none();
+    let x_format = |v: &f64| match &frame.categories {
+        // Categories are placed at whole numbers, so the ticks between them are left empty
+        Some(categories) if (v - v.round()).abs() < 1e-9 && *v >= 0.0 => categories
+            .get(v.round() as usize)
+            .cloned()
+            .unwrap_or_default(),
+        Some(_) => String::new(),
+        None => format_tick(*v, x_log, opts.tick_decimals),
+    };
+    let y_format = |v: &f64| format_tick(*v, opts.y_log, opts.tick_decimals);
+
+    let x_ticks = match &frame.categories {
+        Some(categories) => categories.len().max(1),
+        None => tick_count(&frame.x, x_log, opts.x_ticks),
+    };
+    let y_ticks = tick_count(&frame.y, opts.y_log, opts.y_ticks);
+
+    // Used to ensure that wide labels on the y-axis are not cut off
+    let y_label_width = [frame.y.start, frame.y.end]
+        .iter()
+        .map(|v| y_format(v).len())
+        .max()
+        .unwrap_or(1) as f64;
+    let desc_size = |desc: &str| if desc.is_empty() { 0.0 } else { 20.0 };
+
+    let mut buf = String::new();
+    // Scope to drop root and chart before buf is needed again
+    {
+        let root = SVGBackend::with_string(&mut buf, (600, 600)).into_drawing_area();
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(10)
+            .x_label_area_size(20.0 + desc_size(&opts.x_label))
+            .y_label_area_size(10.0 + 7.0 * y_label_width + desc_size(&opts.y_label))
+            .build_cartesian_2d(frame.x.clone(), frame.y.clone())
+            .map_err(|_| String::from("Failed to build coordinate system."))?;
+
+        chart
+            .configure_mesh()
+            .max_light_lines(0)
+            .x_labels(x_ticks)
+            .y_labels(y_ticks)
+            .x_label_formatter(&x_format)
+            .y_label_formatter(&y_format)
+            .x_desc(opts.x_label.as_str())
+            .y_desc(opts.y_label.as_str())
+            .draw()
+            .map_err(|_| String::from("Failed to draw coordinate system."))?;
+
+        let point_size = ctx.point_size.unwrap_or(3) as u32;
+        let mut has_names = false;
+
+        for Series { name, shape } in series {
+            let style = ctx.get_style();
+            let annotation = match shape {
+                Shape::Bars(bars) => chart.draw_series(
+                    bars.into_iter()
+                        .map(|corners| Rectangle::new(corners, style)),
+                ),
+                Shape::Points(points) => chart.draw_series(
+                    points
+                        .into_iter()
+                        .map(|point| Circle::new(point, point_size, style)),
+                ),
+                Shape::Line(points) => chart.draw_series(LineSeries::new(points, style)),
+            }
+            .map_err(|_| String::from("Failed to plot the data."))?;
+
+            if let Some(name) = name {
+                has_names = true;
+                annotation
+                    .label(name)
+                    .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], style));
+            }
+        }
+
+        if let (Some(position), true) = (&opts.legend, has_names) {
+            let position = match position {
+                SeriesLabelPosition::UpperLeft => SeriesLabelPosition::UpperLeft,
+                SeriesLabelPosition::LowerLeft => SeriesLabelPosition::LowerLeft,
+                SeriesLabelPosition::LowerRight => SeriesLabelPosition::LowerRight,
+                _ => SeriesLabelPosition::UpperRight,
+            };
+            chart
+                .configure_series_labels()
+                .position(position)
+                .background_style(WHITE.mix(0.8))
+                .border_style(BLACK)
+                .draw()
+                .map_err(|_| String::from("Failed to draw the legend."))?;
+        }
+
+        root.present()
+            .map_err(|_| String::from("Failed to create SVG."))?;
+    }
+    Ok(buf)
+}
+
+// Bar charts have one group of bars for each row, with one bar for each column of values. Stacked
+// bars are placed on top of each other instead of next to each other
+pub fn get_bar_chart_svg(ctx: &mut PlotContext, opts: &ChartOptions) -> Result<String, String> {
+    let categories: Vec<String> = opts
+        .rows
+        .iter()
+        .map(|row| row.get(opts.x_column).cloned().unwrap_or_default())
+        .collect();
+    let columns: Vec<Vec<f64>> = opts
+        .y_columns
+        .iter()
+        .map(|&column| {
+            opts.numbers(column)
+                .into_iter()
+                .map(|v| v.unwrap_or(0.0))
+                .collect()
+        })
+        .collect();
+
+    // The bars as (left, right, bottom, top), before the scale of the y-axis is applied
+    let group_width = 0.8;
+    let bar_width = if opts.stacked {
+        group_width
+    } else {
+        group_width / columns.len().max(1) as f64
+    };
+    let mut positive = vec![0.0; categories.len()];
+    let mut negative = vec![0.0; categories.len()];
+    let bars: Vec<Vec<(f64, f64, f64, f64)>> = columns
+        .iter()
+        .enumerate()
+        .map(|(series, values)| {
+            values
+                .iter()
+                .enumerate()
+                .map(|(idx, &value)| {
+                    let (left, bottom) = if opts.stacked {
+                        let stack = if value < 0.0 {
+                            &mut negative[idx]
+                        } else {
+                            &mut positive[idx]
+                        };
+                        let bottom = *stack;
+                        *stack += value;
+                        (idx as f64 - group_width / 2.0, bottom)
+                    } else {
+                        let left = idx as f64 - group_width / 2.0 + series as f64 * bar_width;
+                        (left, 0.0)
+                    };
+                    (left, left + bar_width, bottom, bottom + value)
+                })
+                .collect()
+        })
+        .collect();
+
+    let ends = bars
+        .iter()
+        .flatten()
+        .flat_map(|&(_, _, bottom, top)| [bottom, top]);
+    let y = if opts.y_log {
+        let range = padded_range(ends.filter_map(|v| scale(v, true)), false);
+        range.start.floor()..range.end
+    } else {
+        padded_range(ends, true)
+    };
+
+    // Bars that start at zero start at the bottom of log scales
+    let series = bars
+        .into_iter()
+        .zip(&opts.y_columns)
+        .map(|(bars, &column)| Series {
+            name: Some(opts.name(column)),
+            shape: Shape::Bars(
+                bars.into_iter()
+                    .filter_map(|(left, right, bottom, top)| {
+                        let bottom = scale(bottom, opts.y_log).unwrap_or(y.start);
+                        let top = scale(top, opts.y_log)?;
+                        Some([(left, bottom), (right, top)])
+                    })
+                    .collect(),
+            ),
+        })
+        .collect();
+
+    let frame = Frame {
+        x: -0.5..(categories.len() as f64 - 0.5).max(0.5),
+        y,
+        categories: Some(categories),
+    };
+    draw_chart(ctx, opts, frame, series)
+}
+
+// Histograms count how many values of each column are within each of the bins, which are equally
+// wide and cover all values
+pub fn get_histogram_svg(ctx: &mut PlotContext, opts: &ChartOptions) -> Result<String, String> {
+    // All columns of numbers are counted, unless some are given
+    let columns: Vec<usize> = if opts.y_given {
+        opts.y_columns.clone()
+    } else {
+        (0..opts.header.len())
+            .filter(|&column| opts.numbers(column).iter().any(Option::is_some))
+            .collect()
+    };
+    let values: Vec<Vec<f64>> = columns
+        .iter()
+        .map(|&column| opts.numbers(column).into_iter().flatten().collect())
+        .collect();
+
+    let (min, max) = values
+        .iter()
+        .flatten()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        });
+    if !min.is_finite() {
+        return Err(String::from("There are no numbers to make a histogram of."));
+    }
+    let bin_width = if max > min {
+        (max - min) / opts.bins as f64
+    } else {
+        1.0
+    };
+
+    let counts: Vec<Vec<f64>> = values
+        .iter()
+        .map(|values| {
+            let mut counts = vec![0.0; opts.bins];
+            for v in values {
+                let bin = (((v - min) / bin_width) as usize).min(opts.bins - 1);
+                counts[bin] += 1.0;
+            }
+            counts
+        })
+        .collect();
+
+    let y = if opts.y_log {
+        let range = padded_range(
+            counts.iter().flatten().filter_map(|&v| scale(v, true)),
+            false,
+        );
+        range.start.floor()..range.end
+    } else {
+        padded_range(counts.iter().flatten().copied(), true)
+    };
+
+    let series = counts
+        .into_iter()
+        .zip(&columns)
+        .map(|(counts, &column)| Series {
+            name: (columns.len() > 1).then(|| opts.name(column)),
+            shape: Shape::Bars(
+                counts
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(bin, count)| {
+                        let left = min + bin as f64 * bin_width;
+                        let top = scale(count, opts.y_log)?;
+                        Some([(left, y.start), (left + bin_width, top)])
+                    })
+                    .collect(),
+            ),
+        })
+        .collect();
+
+    let frame = Frame {
+        x: min..(min + bin_width * opts.bins as f64),
+        y,
+        categories: None,
+    };
+    draw_chart(ctx, opts, frame, series)
+}
+
+// Gets the points of each column of values against the column of x-values, on the scales of
+// the axes
+fn get_xy_series(opts: &ChartOptions, line: bool) -> (Frame, Vec<Series>) {
+    let xs = opts.numbers(opts.x_column);
+    let points: Vec<Vec<(f64, f64)>> = opts
+        .y_columns
+        .iter()
+        .map(|&column| {
+            xs.iter()
+                .zip(opts.numbers(column))
+                .filter_map(|(x, y)| Some((scale((*x)?, opts.x_log)?, scale(y?, opts.y_log)?)))
+                .collect()
+        })
+        .collect();
+
+    let frame = Frame {
+        x: padded_range(points.iter().flatten().map(|p| p.0), false),
+        y: padded_range(points.iter().flatten().map(|p| p.1), false),
+        categories: None,
+    };
+    let series = points
+        .into_iter()
+        .zip(&opts.y_columns)
+        .map(|(points, &column)| Series {
+            name: Some(opts.name(column)),
+            shape: if line {
+                Shape::Line(points)
+            } else {
+                Shape::Points(points)
+            },
+        })
+        .collect();
+    (frame, series)
+}
+
+pub fn get_scatter_svg(ctx: &mut PlotContext, opts: &ChartOptions) -> Result<String, String> {
+    let (frame, series) = get_xy_series(opts, false);
+    draw_chart(ctx, opts, frame, series)
+}
+
+pub fn get_line_chart_svg(ctx: &mut PlotContext, opts: &ChartOptions) -> Result<String, String> {
+    let (frame, series) = get_xy_series(opts, true);
+    draw_chart(ctx, opts, frame, series)
+}
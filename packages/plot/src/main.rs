@@ -1,9 +1,14 @@
+use charts::{
+    get_bar_chart_svg, get_histogram_svg, get_line_chart_svg, get_scatter_svg, ChartOptions,
+};
 use plots::{get_function_svg, get_list_svg};
+use serde_json::Value;
 use std::env;
 use std::io::{self, Read};
 use utils::*;
 use utils::{print_manifest, print_svg_html};
 
+mod charts;
 mod eval;
 mod plots;
 mod utils;
@@ -25,13 +30,13 @@ fn main() {
 }
 
 fn transform(from: &str, to: &str) {
-    let input = {
+    let input: Value = {
         let mut buffer = String::new();
         io::stdin().read_to_string(&mut buffer).unwrap();
         serde_json::from_str(&buffer).unwrap()
     };
 
-    let mut ctx = match PlotContext::new(input) {
+    let mut ctx = match PlotContext::new(&input) {
         Ok(ctx) => ctx,
         Err(e) => {
             eprintln!("{e}");
@@ -42,6 +47,21 @@ fn transform(from: &str, to: &str) {
     let res = match from {
         "plot" => get_function_svg(&mut ctx),
         "plot-list" => get_list_svg(&mut ctx),
+        "bar-chart" | "histogram" | "scatter-plot" | "line-chart" => {
+            let opts = match ChartOptions::new(&input, &ctx.data) {
+                Ok(opts) => opts,
+                Err(e) => {
+                    eprintln!("{e}");
+                    return;
+                }
+            };
+            match from {
+                "bar-chart" => get_bar_chart_svg(&mut ctx, &opts),
+                "histogram" => get_histogram_svg(&mut ctx, &opts),
+                "scatter-plot" => get_scatter_svg(&mut ctx, &opts),
+                _ => get_line_chart_svg(&mut ctx, &opts),
+            }
+        }
         other => {
             eprintln!("Package does not support {other}");
             return;
@@ -24,14 +24,21 @@ pub struct PlotContext {
 }
 
 impl PlotContext {
-    pub fn new(input: Value) -> Result<Self, String> {
+    pub fn new(input: &Value) -> Result<Self, String> {
         let data = input["data"].as_str().unwrap().to_string();
-        let x_fr = input["arguments"]["x_from"].as_f64().unwrap();
-        let x_to = input["arguments"]["x_to"].as_f64().unwrap();
-        let y_fr = input["arguments"]["y_from"].as_f64().unwrap();
-        let y_to = input["arguments"]["y_to"].as_f64().unwrap();
         let samples = input["arguments"]["samples"].as_u64().unwrap_or(0);
 
+        // Charts of data get their ranges from the data, so only plots have these arguments
+        let rm = if ["plot", "plot-list"].contains(&input["name"].as_str().unwrap_or("")) {
+            let x_fr = input["arguments"]["x_from"].as_f64().unwrap();
+            let x_to = input["arguments"]["x_to"].as_f64().unwrap();
+            let y_fr = input["arguments"]["y_from"].as_f64().unwrap();
+            let y_to = input["arguments"]["y_to"].as_f64().unwrap();
+            RangeManager::new(samples, x_fr, x_to, y_fr, y_to)
+        } else {
+            RangeManager::default()
+        };
+
         let line_width = input["arguments"]["line_width"].as_u64().unwrap();
         let color_arg = match input["arguments"]["color"].as_str() {
             Some(color_arg) => color_arg,
@@ -49,7 +56,6 @@ impl PlotContext {
         let caption = input["arguments"]["caption"].as_str().unwrap().to_string();
         let save = input["arguments"]["save"].as_str().unwrap().to_string();
 
-        let rm = RangeManager::new(samples, x_fr, x_to, y_fr, y_to);
         let fn_ctx = new_function_context();
         let fn_idx = 0;
 
@@ -182,6 +188,7 @@ impl TryFrom<FloatIterator> for Range<f64> {
 }
 
 // Should be relatively easy to extend this to three axes if needed
+#[derive(Default)]
 pub struct RangeManager {
     user_range_x: (f64, f64),
     user_range_y: (f64, f64),
@@ -500,8 +507,165 @@ pub fn print_manifest() {
                         },
                     ],
                 },
+                chart_transform(
+                    "bar-chart",
+                    "Draw a bar chart from a CSV file, or from CSV data in the module. Each row is a group of bars, named by the x column, with one bar for each y column.",
+                    vec![
+                        json!({
+                            "name": "mode",
+                            "description": "Whether the bars of a group are placed next to each other or on top of each other.",
+                            "type": ["grouped", "stacked"],
+                            "default": "grouped",
+                        }),
+                    ],
+                ),
+                chart_transform(
+                    "histogram",
+                    "Draw a histogram of the numbers in the y columns of a CSV file, or of CSV data in the module.",
+                    vec![
+                        json!({
+                            "name": "bins",
+                            "description": "The number of equally wide bins that the numbers are counted in.",
+                            "type": "uint",
+                            "default": 10,
+                        }),
+                    ],
+                ),
+                chart_transform(
+                    "scatter-plot",
+                    "Draw a scatter plot from a CSV file, or from CSV data in the module. Each y column is plotted against the x column.",
+                    vec![
+                        json!({
+                            "name": "point_size",
+                            "description": "The diameter of each plotted point, given in pixels.",
+                            "type": "uint",
+                            "default": 3,
+                        }),
+                    ],
+                ),
+                chart_transform(
+                    "line-chart",
+                    "Draw a line chart from a CSV file, or from CSV data in the module. Each y column is a line, plotted against the x column.",
+                    vec![],
+                ),
             ]
             }
         )
     );
 }
+
+// Gets the manifest entry of a chart of data, which has the arguments that all charts share
+fn chart_transform(from: &str, description: &str, extra_arguments: Vec<Value>) -> Value {
+    let mut arguments = vec![
+        json!({
+            "name": "path",
+            "description": "The path to the CSV or TSV file. The data is read from the module if this is left empty. The first row names the columns.",
+            "default": "",
+        }),
+        json!({
+            "name": "delimiter",
+            "description": "The delimiter between values, which is a single character or tab. By default, .tsv files use tabs and other data uses commas.",
+            "default": "",
+        }),
+        json!({
+            "name": "x",
+            "description": "The name or number of the column of x-values.",
+            "default": "1",
+        }),
+        json!({
+            "name": "y",
+            "description": "The names or numbers of the columns of y-values, separated by commas. All other columns are used by default.",
+            "default": "",
+        }),
+        json!({
+            "name": "x_label",
+            "description": "The label of the x-axis.",
+            "default": "",
+        }),
+        json!({
+            "name": "y_label",
+            "description": "The label of the y-axis.",
+            "default": "",
+        }),
+        json!({
+            "name": "x_scale",
+            "description": "The scale of the x-axis. Bar charts and histograms always use a linear x-axis.",
+            "type": ["linear", "log"],
+            "default": "linear",
+        }),
+        json!({
+            "name": "y_scale",
+            "description": "The scale of the y-axis.",
+            "type": ["linear", "log"],
+            "default": "linear",
+        }),
+        json!({
+            "name": "x_ticks",
+            "description": "The largest number of labelled ticks on the x-axis.",
+            "type": "uint",
+            "default": 10,
+        }),
+        json!({
+            "name": "y_ticks",
+            "description": "The largest number of labelled ticks on the y-axis.",
+            "type": "uint",
+            "default": 10,
+        }),
+        json!({
+            "name": "tick_decimals",
+            "description": "The number of decimals in the labels of the ticks. Trailing zeros are left out by default.",
+            "default": "",
+        }),
+        json!({
+            "name": "legend",
+            "description": "Where the legend is placed.",
+            "type": ["upper-right", "upper-left", "lower-right", "lower-left", "none"],
+            "default": "upper-right",
+        }),
+        json!({
+            "name": "caption",
+            "description": "Caption for the chart.",
+            "default": "",
+        }),
+        json!({
+            "name": "label",
+            "description": "Label to use for the chart, to be able to refer to it from the document.",
+            "default": "",
+        }),
+        json!({
+            "name": "width",
+            "description": "Width of the chart. For HTML this is given as the ratio to the width of the surrounding figure tag (created automatically).",
+            "default": 1.0,
+            "type": "f64",
+        }),
+        json!({
+            "name": "save",
+            "description": "The name of the SVG file that is saved. No file is saved if this argument is left empty.",
+            "default": "",
+        }),
+        json!({
+            "name": "line_width",
+            "description": "The width of the lines that are used in the chart.",
+            "type": "uint",
+            "default": 1,
+        }),
+        json!({
+            "name": "colors",
+            "description": "A list of colors that are used for the series of data. They should be comma-separated and given in hexadecimal. Each hex code should be preceded by a hashtag.",
+            "default": "#0072BD,#D95319,#EDB120,#7E2F8E,#77AC30,#4DBEEE,#A2142F"
+        }),
+    ];
+    arguments.extend(extra_arguments);
+
+    json!({
+        "from": from,
+        "to": ["html", "latex"],
+        "description": description,
+        "unknown-content": true,
+        "variables": {
+            "caption_style": {"type": "const", "access": "read"},
+            "imports": {"type": "set", "access": "add"},
+        },
+        "arguments": arguments,
+    })
+}
@@ -16,6 +16,8 @@ pub struct StructureCounter {
     headings: Vec<u64>,
     figures: u64,
     tables: u64,
+    equations: u64,
+    listings: u64,
 }
 
 #[derive(PartialEq)]
@@ -43,6 +45,8 @@ impl StructureCounter {
             headings: vec![0],
             figures: 0,
             tables: 0,
+            equations: 0,
+            listings: 0,
         }
     }
     // Enforce a limit of 256 levels, to avoid creating an extremely long vec from malformed input.
@@ -54,6 +58,8 @@ impl StructureCounter {
         if level == 1 {
             self.figures = 0;
             self.tables = 0;
+            self.equations = 0;
+            self.listings = 0;
         }
 
         while self.headings.len() < level {
@@ -75,6 +81,14 @@ impl StructureCounter {
         self.tables += 1;
     }
 
+    pub fn push_equation(&mut self) {
+        self.equations += 1;
+    }
+
+    pub fn push_listing(&mut self) {
+        self.listings += 1;
+    }
+
     pub fn get_heading(&self) -> String {
         self.headings
             .iter()
@@ -92,6 +106,15 @@ impl StructureCounter {
     pub fn get_table(&self) -> String {
         format!("{}.{}", self.headings[0], self.tables)
     }
+
+    // Equation numbers are in parentheses, like they are in LaTeX
+    pub fn get_equation(&self) -> String {
+        format!("({}.{})", self.headings[0], self.equations)
+    }
+
+    pub fn get_listing(&self) -> String {
+        format!("{}.{}", self.headings[0], self.listings)
+    }
 }
 
 impl TocEntry {
@@ -173,6 +196,22 @@ impl Toc {
         to_html_helper(&self.table, &mut counter, &mut json, 0);
         serde_json::to_string(&json).unwrap()
     }
+
+    pub fn to_plaintext(&self) -> String {
+        let mut counter = StructureCounter::new();
+        let mut json = vec![json!("\n")];
+        to_plaintext_helper(&self.table, &mut counter, &mut json, 0);
+        json.push(json!("\n"));
+        serde_json::to_string(&json).unwrap()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut counter = StructureCounter::new();
+        let mut json = vec![json!("\n")];
+        to_markdown_helper(&self.table, &mut counter, &mut json, 0);
+        json.push(json!("\n"));
+        serde_json::to_string(&json).unwrap()
+    }
 }
 
 //
@@ -210,6 +249,70 @@ fn to_html_helper(
     }
 }
 
+// Each entry is a list item with a link, indented two spaces per level. Since Markdown can't have
+// list items without a marker, empty entries are left out and their children are kept indented.
+fn to_markdown_helper(
+    pointer: &TocEntry,
+    counter: &mut StructureCounter,
+    json: &mut Vec<Value>,
+    level: usize,
+) {
+    let indent = "  ".repeat(level.saturating_sub(1));
+    if pointer.mode == Numbered {
+        counter.push_heading(level);
+
+        let number = counter.get_heading();
+        let id = pointer.id.as_ref().unwrap().clone();
+        let contents = pointer.contents.as_ref().unwrap().clone();
+
+        json.push(json!(format!("{indent}- [{number} ")));
+        json.push(contents);
+        json.push(json!(format!("](#{id})\n")));
+    } else if pointer.mode == Unnumbered {
+        let id = pointer.id.as_ref().unwrap().clone();
+        let contents = pointer.contents.as_ref().unwrap().clone();
+
+        json.push(json!(format!("{indent}- [")));
+        json.push(contents);
+        json.push(json!(format!("](#{id})\n")));
+    }
+
+    for child in &pointer.children {
+        to_markdown_helper(child, counter, json, level + 1);
+    }
+}
+
+// Each entry is a block in the layout of the plaintext package, indented two spaces per level, so
+// that long entries are wrapped and line up with the text after the number
+fn to_plaintext_helper(
+    pointer: &TocEntry,
+    counter: &mut StructureCounter,
+    json: &mut Vec<Value>,
+    level: usize,
+) {
+    let indent = "  ".repeat(level.saturating_sub(1));
+    if pointer.mode == Numbered {
+        counter.push_heading(level);
+
+        let number = counter.get_heading();
+        let contents = pointer.contents.as_ref().unwrap().clone();
+
+        json.push(json!(format!("\u{E000}{indent}{number} \u{E001}")));
+        json.push(contents);
+        json.push(json!("\u{E002}\n"));
+    } else if pointer.mode == Unnumbered {
+        let contents = pointer.contents.as_ref().unwrap().clone();
+
+        json.push(json!(format!("\u{E000}{indent}\u{E001}")));
+        json.push(contents);
+        json.push(json!("\u{E002}\n"));
+    }
+
+    for child in &pointer.children {
+        to_plaintext_helper(child, counter, json, level + 1);
+    }
+}
+
 // Get a list of Values from the "structure" environment variable
 // Labels are not added to the list. Instead the are attached to the
 // previous element (as an alias) if possible.
@@ -235,3 +338,20 @@ pub fn get_structure_list() -> Vec<Value> {
 
     structure
 }
+
+// Get the LaTeX command of a heading, which depends on the document class set in the config.
+// Classes like book and report have chapters above sections, which level 1 headings become.
+pub fn latex_sectioning_command(level: usize) -> String {
+    let class = env::var("document_class").unwrap_or_default();
+    if matches!(
+        class.as_str(),
+        "book" | "report" | "scrbook" | "scrreprt" | "memoir"
+    ) {
+        match level.clamp(1, 4) {
+            1 => "chapter".to_string(),
+            level => format!("{}section", "sub".repeat(level - 2)),
+        }
+    } else {
+        format!("{}section", "sub".repeat(level.clamp(1, 3) - 1))
+    }
+}
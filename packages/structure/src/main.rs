@@ -24,7 +24,7 @@ fn transform_label_to_key(input: Value) {
     println!("[{}]", json!(label));
 }
 
-fn transform_element_number(input: Value) {
+fn transform_element_number(input: Value, to: &str) {
     let key = input["data"].as_str().unwrap().to_string();
     let structure = get_structure_list();
     let mut counter = StructureCounter::new();
@@ -39,6 +39,8 @@ fn transform_element_number(input: Value) {
             "numbered-heading" => counter.push_heading(level),
             "figure" => counter.push_figure(),
             "table" => counter.push_table(),
+            "equation" => counter.push_equation(),
+            "listing" => counter.push_listing(),
             _ => {}
         }
 
@@ -47,9 +49,16 @@ fn transform_element_number(input: Value) {
                 "numbered-heading" => counter.get_heading(),
                 "figure" => counter.get_figure(),
                 "table" => counter.get_figure(),
+                "equation" => counter.get_equation(),
+                "listing" => counter.get_listing(),
                 _ => key,
             };
-            println!("[{}]", json!(output));
+            // The number is an inline of its own in the Pandoc AST, rather than raw text
+            if to == "pandoc-json" {
+                println!("[{}]", json!(format!("{},", json!({"t": "Str", "c": output}))));
+            } else {
+                println!("[{}]", json!(output));
+            }
             return;
         }
     }
@@ -74,7 +83,8 @@ fn transform_heading(input: Value, to: &str, element: &str) {
             })
             .to_string();
 
-            json.push(json!(format!("<h{level}>")));
+            // The id is the key that the table of contents links to
+            json.push(json!(format!("<h{level} id=\"heading:{id}\">")));
 
             if element == "numbered-heading" {
                 let invocation = format!("[element-number](heading:{id}) ");
@@ -97,26 +107,165 @@ fn transform_heading(input: Value, to: &str, element: &str) {
             let mut json = vec![];
             let contents = input["data"].as_str().unwrap();
             let level_arg = input["arguments"]["level"].as_str().unwrap();
-            let level = level_arg.parse::<usize>().unwrap().clamp(1, 3);
-            let subs = "sub".repeat(level-1);
+            let level = level_arg.parse::<usize>().unwrap();
+            let command = latex_sectioning_command(level);
 
             if element == "unnumbered-heading" {
-                json.push(json!(format!("\\{subs}section*{{")));
+                json.push(json!(format!("\\{command}*{{")));
             } else {
-                json.push(json!(format!("\\{subs}section{{")));
+                json.push(json!(format!("\\{command}{{")));
             }
 
             json.push(inline_content!(contents));
             json.push(json!("}\n"));
 
             if element == "unnumbered-heading" {
-                json.push(json!(format!("\\addcontentsline{{toc}}{{{subs}section}}{{")));
+                json.push(json!(format!("\\addcontentsline{{toc}}{{{command}}}{{")));
                 json.push(inline_content!(contents));
                 json.push(json!("}\n"));
             }
 
             print!("{}", serde_json::to_string(&json).unwrap());
         }
+        "markdown" => {
+            let mut json = vec![];
+            let contents = input["data"].as_str().unwrap();
+            let level_arg = input["arguments"]["level"].as_str().unwrap();
+            let id = input["id"].as_u64().unwrap();
+            let level = level_arg.parse::<usize>().unwrap().clamp(1, 6);
+
+            let structure_data = json!({
+                "element": element,
+                "level": level,
+                "key": format!("heading:{id}"),
+                "contents": inline_content!(contents),
+            })
+            .to_string();
+
+            json.push(json!(format!("\n{} ", "#".repeat(level))));
+
+            if element == "numbered-heading" {
+                let invocation = format!("[element-number](heading:{id}) ");
+                json.push(inline_content!(invocation));
+            }
+
+            json.push(inline_content!(contents));
+            json.push(json!(format!(" <a id=\"heading:{id}\"></a>\n")));
+            json.push(json!(
+                {
+                    "name": "list-push",
+                    "arguments": {"name": "structure"},
+                    "data": structure_data,
+                }
+            ));
+
+            print!("{}", serde_json::to_string(&json).unwrap());
+        }
+        "plaintext" => {
+            let mut json = vec![];
+            let contents = input["data"].as_str().unwrap();
+            let level_arg = input["arguments"]["level"].as_str().unwrap();
+            let id = input["id"].as_u64().unwrap();
+            let level = level_arg.parse::<usize>().unwrap();
+
+            let structure_data = json!({
+                "element": element,
+                "level": level,
+                "key": format!("heading:{id}"),
+                "contents": inline_content!(contents),
+            })
+            .to_string();
+
+            json.push(json!(format!("\n\u{E004}{}\u{E001}", underline(level))));
+
+            if element == "numbered-heading" {
+                let invocation = format!("[element-number](heading:{id}) ");
+                json.push(inline_content!(invocation));
+            }
+
+            json.push(inline_content!(contents));
+            json.push(json!("\u{E002}\n"));
+            json.push(json!(
+                {
+                    "name": "list-push",
+                    "arguments": {"name": "structure"},
+                    "data": structure_data,
+                }
+            ));
+
+            print!("{}", serde_json::to_string(&json).unwrap());
+        }
+        "typst" => {
+            let mut json = vec![];
+            let contents = input["data"].as_str().unwrap();
+            let level_arg = input["arguments"]["level"].as_str().unwrap();
+            let id = input["id"].as_u64().unwrap();
+            let level = level_arg.parse::<usize>().unwrap().max(1);
+
+            let structure_data = json!({
+                "element": element,
+                "level": level,
+                "key": format!("heading:{id}"),
+                "contents": inline_content!(contents),
+            })
+            .to_string();
+
+            json.push(json!(format!("\n{} ", "=".repeat(level))));
+
+            if element == "numbered-heading" {
+                let invocation = format!("[element-number](heading:{id}) ");
+                json.push(inline_content!(invocation));
+            }
+
+            json.push(inline_content!(contents));
+            json.push(json!(format!(" <heading:{id}>\n")));
+            json.push(json!(
+                {
+                    "name": "list-push",
+                    "arguments": {"name": "structure"},
+                    "data": structure_data,
+                }
+            ));
+
+            print!("{}", serde_json::to_string(&json).unwrap());
+        }
+        "pandoc-json" => {
+            let mut json = vec![];
+            let contents = input["data"].as_str().unwrap();
+            let level_arg = input["arguments"]["level"].as_str().unwrap();
+            let id = input["id"].as_u64().unwrap();
+            let level = level_arg.parse::<usize>().unwrap().max(1);
+
+            let structure_data = json!({
+                "element": element,
+                "level": level,
+                "key": format!("heading:{id}"),
+                "contents": inline_content!(contents),
+            })
+            .to_string();
+
+            // Pandoc numbers headings by itself unless they have the unnumbered class
+            json.push(json!(format!(
+                r#"{{"t":"Header","c":[{level},["heading:{id}",["unnumbered"],[]],["#
+            )));
+
+            if element == "numbered-heading" {
+                let invocation = format!("[element-number](heading:{id}) ");
+                json.push(inline_content!(invocation));
+            }
+
+            json.push(inline_content!(contents));
+            json.push(json!("]]},"));
+            json.push(json!(
+                {
+                    "name": "list-push",
+                    "arguments": {"name": "structure"},
+                    "data": structure_data,
+                }
+            ));
+
+            print!("{}", serde_json::to_string(&json).unwrap());
+        }
         other => eprintln!("Cannot convert {element} to {other}!"),
     }
 }
@@ -139,25 +288,90 @@ fn transform_standalone_heading(input: Value, to: &str) {
             let mut json = vec![];
             let contents = input["data"].as_str().unwrap();
             let level_arg = input["arguments"]["level"].as_str().unwrap();
-            let level = level_arg.parse::<usize>().unwrap().clamp(1, 3);
-            let subs = "sub".repeat(level-1);
+            let level = level_arg.parse::<usize>().unwrap();
+            let command = latex_sectioning_command(level);
 
-            json.push(json!(format!("\\{subs}section*{{")));
+            json.push(json!(format!("\\{command}*{{")));
             json.push(inline_content!(contents));
             json.push(json!("}\n"));
 
             print!("{}", serde_json::to_string(&json).unwrap());
         }
+        "markdown" => {
+            let mut json = vec![];
+            let contents = input["data"].as_str().unwrap();
+            let level_arg = input["arguments"]["level"].as_str().unwrap();
+            let level = level_arg.parse::<usize>().unwrap().clamp(1, 6);
+
+            json.push(json!(format!("\n{} ", "#".repeat(level))));
+            json.push(inline_content!(contents));
+            json.push(json!("\n"));
+
+            print!("{}", serde_json::to_string(&json).unwrap());
+        }
+        "plaintext" => {
+            let mut json = vec![];
+            let contents = input["data"].as_str().unwrap();
+            let level_arg = input["arguments"]["level"].as_str().unwrap();
+            let level = level_arg.parse::<usize>().unwrap();
+
+            json.push(json!(format!("\n\u{E004}{}\u{E001}", underline(level))));
+            json.push(inline_content!(contents));
+            json.push(json!("\u{E002}\n"));
+
+            print!("{}", serde_json::to_string(&json).unwrap());
+        }
+        "typst" => {
+            let mut json = vec![];
+            let contents = input["data"].as_str().unwrap();
+            let level_arg = input["arguments"]["level"].as_str().unwrap();
+            let level = level_arg.parse::<usize>().unwrap().max(1);
+
+            // Typst puts all headings in the outline unless told otherwise
+            json.push(json!(format!("\n#heading(level: {level}, outlined: false)[")));
+            json.push(inline_content!(contents));
+            json.push(json!("]\n"));
+
+            print!("{}", serde_json::to_string(&json).unwrap());
+        }
+        "pandoc-json" => {
+            let mut json = vec![];
+            let contents = input["data"].as_str().unwrap();
+            let level_arg = input["arguments"]["level"].as_str().unwrap();
+            let level = level_arg.parse::<usize>().unwrap().max(1);
+
+            json.push(json!(format!(
+                r#"{{"t":"Header","c":[{level},["",["unnumbered","unlisted"],[]],["#
+            )));
+            json.push(inline_content!(contents));
+            json.push(json!("]]},"));
+
+            print!("{}", serde_json::to_string(&json).unwrap());
+        }
         other => eprintln!("Cannot convert standalone-heading to {other}!"),
     }
 }
 
+// The character used by the plaintext package to underline a heading of some level
+fn underline(level: usize) -> char {
+    match level {
+        0 | 1 => '=',
+        2 => '-',
+        _ => '~',
+    }
+}
+
 fn transform_toc(input: Value, to: &str) {
     let max_level = input["arguments"]["max-level"].as_u64().unwrap() as usize;
     let toc = Toc::build_from_list(max_level);
 
     match to {
         "html" => print!("{}", toc.to_html()),
+        "markdown" => print!("{}", toc.to_markdown()),
+        "plaintext" => print!("{}", toc.to_plaintext()),
+        // The numbers of numbered headings are a part of their text, so Typst's own outline
+        // shows the same entries as the other formats
+        "typst" => print!("{}", json!([format!("\n#outline(depth: {max_level})\n")])),
         other => eprintln!("Cannot convert table-of-contents to {other}!"),
     }
 }
@@ -174,7 +388,7 @@ fn transform(from: &str, to: &str) {
         "unnumbered-heading" => transform_heading(input, to, from),
         "numbered-heading" => transform_heading(input, to, from),
         "standalone-heading" => transform_standalone_heading(input, to),
-        "element-number" => transform_element_number(input),
+        "element-number" => transform_element_number(input, to),
         "label-to-key" => transform_label_to_key(input),
         other => {
             eprintln!("Package does not support {other}");
@@ -188,11 +402,11 @@ fn manifest() {
         json!({
             "name": "structure",
             "version": "0.1",
-            "description": "This package manages document structure to provide numbering for headings, figures and tables.",
+            "description": "This package manages document structure to provide numbering for headings, figures, tables, equations and code listings.",
             "transforms": [
                 {
                     "from": "table-of-contents",
-                    "to": ["html"],
+                    "to": ["html", "markdown", "plaintext", "typst"],
                     "description": "Creates a table of contents using headings the document.",
                     "arguments": [
                         {"name": "max-level", "type": "uint", "default": 4, "description": "Specifies the highest level of headings that will be included in the TOC. Examples: 2 -> 1.1, 4 -> 1.1.1.1."},
@@ -203,7 +417,7 @@ fn manifest() {
                 },
                 {
                     "from": "unnumbered-heading",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "markdown", "plaintext", "typst", "pandoc-json"],
                     "description": "A heading that does not include a number and is not numbered in a table of contents.",
                     "type": "inline-module",
                     "arguments": [
@@ -214,12 +428,13 @@ fn manifest() {
                         }
                     ],
                     "variables": {
-                        "structure": {"type": "list", "access": "push"}
+                        "structure": {"type": "list", "access": "push"},
+                        "document_class": {"type": "const", "access": "read"}
                     }
                 },
                 {
                     "from": "numbered-heading",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "markdown", "plaintext", "typst", "pandoc-json"],
                     "type": "inline-module",
                     "description": "A heading that includes number and is numbered in a table of contents.",
                     "arguments": [
@@ -230,12 +445,13 @@ fn manifest() {
                         }
                     ],
                     "variables": {
-                        "structure": {"type": "list", "access": "push"}
+                        "structure": {"type": "list", "access": "push"},
+                        "document_class": {"type": "const", "access": "read"}
                     }
                 },
                 {
                     "from": "standalone-heading",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "markdown", "plaintext", "typst", "pandoc-json"],
                     "type": "inline-module",
                     "description": "A heading is not included in the document's structure or table of contents.",
                     "arguments": [
@@ -245,6 +461,9 @@ fn manifest() {
                             "default": "1"
                         }
                     ],
+                    "variables": {
+                        "document_class": {"type": "const", "access": "read"}
+                    }
                 },
                 {
                     "from": "element-number",
@@ -1,5 +1,6 @@
 use std::convert::TryInto;
 use std::env;
+use std::fs;
 use std::io::{self, Read};
 
 use serde_json::{json, Value};
@@ -59,7 +60,7 @@ fn manifest() {
             "transforms": [
                 {
                     "from": "table",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "markdown", "plaintext", "docx", "typst", "pandoc-json"],
                     "arguments": [
                         {"name": "caption", "default": "", "description": "The caption for the table"},
                         {"name": "label", "default":"", "description": "The label to use for the table, to be able to refer to it from the document"},
@@ -74,7 +75,7 @@ fn manifest() {
                 },
                 {
                     "from": "big-table",
-                    "to": ["html", "latex"],
+                    "to": ["html", "latex", "markdown", "plaintext", "docx", "typst", "pandoc-json"],
                     "arguments": [
                         {"name": "caption", "default": "", "description": "The caption for the table"},
                         {"name": "label", "default":"", "description": "The label to use for the table, to be able to refer to it from the document"},
@@ -85,6 +86,23 @@ fn manifest() {
                     ],
                     "unknown-content": true,
                     "description": "Large variant of the table, which accepts block content. Write the content of each cell on multiple lines, and use column-delimiter between cells on the same row. Then, use row-delimiter between rows."
+                },
+                {
+                    "from": "table-file",
+                    "to": ["html", "latex", "markdown", "plaintext", "docx", "typst", "pandoc-json"],
+                    "arguments": [
+                        {"name": "path", "description": "The path to the CSV or TSV file"},
+                        {"name": "caption", "default": "", "description": "The caption for the table"},
+                        {"name": "label", "default":"", "description": "The label to use for the table, to be able to refer to it from the document"},
+                        {"name": "header", "default": "auto", "type": ["auto", "bold", "none"], "description": "Style to apply to heading, none/bold. With auto, the first row is a heading if none of its cells are empty or numbers"},
+                        {"name": "alignment", "default": "auto", "description": "Horizontal alignment in cells, left/center/right or l/c/r for each column. With auto, columns of numbers are right-aligned and other columns are left-aligned"},
+                        {"name": "borders", "default": "all", "type": ["all", "horizontal", "vertical", "outer", "none"], "description": "Which borders to draw"},
+                        {"name": "delimiter", "default": "auto", "description": "The delimiter between cells, which is a single character or tab. With auto, .tsv and .tab files use tabs and other files use commas"},
+                        {"name": "columns", "default": "", "description": "The columns to include, such as 1,3-4 or names in the heading. All columns are included by default"},
+                        {"name": "decimals", "default": "", "description": "The number of decimals to show in numbers. Numbers are shown as they are written by default"},
+                        {"name": "thousands", "default": "", "description": "The separator between groups of thousands in numbers, such as a comma or a space"}
+                    ],
+                    "description": "Makes a table from a CSV or TSV file. Fields may be quoted like in RFC 4180"
                 }
             ],
             "variables": {
@@ -98,6 +116,7 @@ fn transform(from: &str, to: &str) {
     match from {
         "table" => transform_table(to, false),
         "big-table" => transform_table(to, true),
+        "table-file" => transform_table_file(to),
         other => {
             eprintln!("Package does not support {other}");
         }
@@ -106,8 +125,9 @@ fn transform(from: &str, to: &str) {
 
 fn transform_table(to: &str, big: bool) {
     // We make sure to exit early if invalid format, not to do unnecessary calculations
-    if to != "latex" && to != "html" {
-        eprintln!("Unsupported format {to}, only HTML and LaTeX are supported!");
+    if !["html", "latex", "markdown", "plaintext", "docx", "typst", "pandoc-json"].contains(&to) {
+        eprintln!("Unsupported format {to}, only html, latex, markdown, plaintext, docx, typst and \
+            pandoc-json are supported");
         return;
     }
 
@@ -124,11 +144,45 @@ fn transform_table(to: &str, big: bool) {
         return;
     };
 
-    // If table was valid, execute! (also, we know that we have nothing else than latex/html so
-    // anything else is unreachable)
+    // If table was valid, execute! (also, we know that we have nothing else than
+    // latex/html/markdown/plaintext/docx/typst/pandoc-json so anything else is unreachable)
+    match to {
+        "html" => println!("{}", table.to_html()),
+        "latex" => println!("{}", table.to_latex()),
+        "markdown" => println!("{}", table.to_markdown()),
+        "plaintext" => println!("{}", table.to_plaintext()),
+        "docx" => println!("{}", table.to_docx()),
+        "typst" => println!("{}", table.to_typst()),
+        "pandoc-json" => println!("{}", table.to_pandoc()),
+        _ => unreachable!(),
+    }
+}
+
+fn transform_table_file(to: &str) {
+    if !["html", "latex", "markdown", "plaintext", "docx", "typst", "pandoc-json"].contains(&to) {
+        eprintln!("Unsupported format {to}, only html, latex, markdown, plaintext, docx, typst and \
+            pandoc-json are supported");
+        return;
+    }
+
+    let input: Value = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        serde_json::from_str(&buffer).unwrap()
+    };
+
+    let Some(table) = parse_table_file(&input) else {
+        return;
+    };
+
     match to {
         "html" => println!("{}", table.to_html()),
         "latex" => println!("{}", table.to_latex()),
+        "markdown" => println!("{}", table.to_markdown()),
+        "plaintext" => println!("{}", table.to_plaintext()),
+        "docx" => println!("{}", table.to_docx()),
+        "typst" => println!("{}", table.to_typst()),
+        "pandoc-json" => println!("{}", table.to_pandoc()),
         _ => unreachable!(),
     }
 }
@@ -234,6 +288,38 @@ impl ColumnAlignment {
         }
     }
 
+    fn markdown_delimiter(&self) -> &str {
+        match self {
+            ColumnAlignment::Left => ":---",
+            ColumnAlignment::Center => ":---:",
+            ColumnAlignment::Right => "---:",
+        }
+    }
+
+    fn docx_justification(&self) -> &str {
+        match self {
+            ColumnAlignment::Left => "left",
+            ColumnAlignment::Center => "center",
+            ColumnAlignment::Right => "right",
+        }
+    }
+
+    fn typst_alignment(&self) -> &str {
+        match self {
+            ColumnAlignment::Left => "left",
+            ColumnAlignment::Center => "center",
+            ColumnAlignment::Right => "right",
+        }
+    }
+
+    fn pandoc_alignment(&self) -> &str {
+        match self {
+            ColumnAlignment::Left => "AlignLeft",
+            ColumnAlignment::Center => "AlignCenter",
+            ColumnAlignment::Right => "AlignRight",
+        }
+    }
+
     fn html_style(&self) -> &str {
         match self {
             ColumnAlignment::Left => "text-align: left;",
@@ -271,6 +357,73 @@ impl TryFrom<&str> for Borders {
     }
 }
 
+// The content of a cell, which is either markup from the document or text that is shown as it is,
+// such as the data of a file
+#[derive(Debug, Clone, PartialEq)]
+enum CellContent<'a> {
+    Markup(&'a str),
+    Text(String),
+}
+
+// A cell of a table. A cell may span several columns and rows, and the cells it spans over are
+// covered by it, which is given by the row and column of the spanning cell
+#[derive(Debug, Clone, PartialEq)]
+struct Cell<'a> {
+    content: CellContent<'a>,
+    colspan: usize,
+    rowspan: usize,
+    covered_by: Option<(usize, usize)>,
+}
+
+impl<'a> Cell<'a> {
+    fn markup(text: &'a str) -> Self {
+        Cell {
+            content: CellContent::Markup(text),
+            colspan: 1,
+            rowspan: 1,
+            covered_by: None,
+        }
+    }
+
+    fn text(text: String) -> Self {
+        Cell {
+            content: CellContent::Text(text),
+            colspan: 1,
+            rowspan: 1,
+            covered_by: None,
+        }
+    }
+
+    // Gets the content of this cell as JSON, which is bold if it is in the header of a table that
+    // isn't able to style the header by itself
+    fn value(&self, big: bool, bold: bool) -> Value {
+        match &self.content {
+            CellContent::Markup(text) if bold => dynamic_content!(big, format!("**{text}**")),
+            CellContent::Markup(text) => dynamic_content!(big, text),
+            CellContent::Text(text) if bold => json!({
+                "name": "__bold",
+                "arguments": {},
+                "children": [{"name": "__text", "data": text}]
+            }),
+            CellContent::Text(text) => json!({"name": "__text", "data": text}),
+        }
+    }
+
+    // Gets the content of this cell on a single line, for formats where cells can't contain line
+    // breaks
+    fn single_line_value(&self, big: bool) -> Value {
+        match &self.content {
+            CellContent::Markup(text) if big => {
+                inline_content!(text.split_whitespace().collect::<Vec<_>>().join(" "))
+            }
+            CellContent::Text(text) => {
+                json!({"name": "__text", "data": text.split_whitespace().collect::<Vec<_>>().join(" ")})
+            }
+            _ => self.value(big, false),
+        }
+    }
+}
+
 // The struct holding a table. Since the text contained within holds pointers to stdin, we need
 // lifetimes to avoid copying
 #[derive(Debug)]
@@ -278,7 +431,7 @@ impl TryFrom<&str> for Borders {
 struct Table<'a> {
     width: usize,
     height: usize,
-    content: Vec<Vec<&'a str>>,
+    content: Vec<Vec<Cell<'a>>>,
     alignment: Alignment,
     borders: Borders,
     header: bool,
@@ -288,6 +441,29 @@ struct Table<'a> {
 }
 
 impl Table<'_> {
+    fn has_spans(&self) -> bool {
+        self.content
+            .iter()
+            .flatten()
+            .any(|cell| cell.colspan > 1 || cell.rowspan > 1)
+    }
+
+    // Gets the column specification of a LaTeX cell that spans several columns, which has the
+    // alignment of its first column and the borders that the columns it spans over have
+    fn latex_multicolumn_spec(&self, idx: usize, colspan: usize) -> String {
+        let alignment = self.alignment.for_column(idx).latex_char();
+        let (left, right) = match self.borders {
+            Borders::All | Borders::Vertical => (idx == 0, true),
+            Borders::Horizontal | Borders::Outer => (idx == 0, idx + colspan == self.width),
+            Borders::None => (false, false),
+        };
+        format!(
+            "{}{alignment}{}",
+            if left { "|" } else { "" },
+            if right { "|" } else { "" }
+        )
+    }
+
     // Turns this table to LaTeX and gets a JSON value (containing mostly raw stuff) to return
     fn to_latex(&self) -> Value {
         let mut vec: Vec<Value> = vec![];
@@ -305,6 +481,9 @@ impl Table<'_> {
         };
 
         vec.push(import!(r"\usepackage{float}"));
+        if self.content.iter().flatten().any(|cell| cell.rowspan > 1) {
+            vec.push(import!(r"\usepackage{multirow}"));
+        }
         vec.push(json!("\\begin{table}[H]\n"));
         vec.push(json!("\\centering\n"));
 
@@ -325,31 +504,69 @@ impl Table<'_> {
         }
 
         // Loop though all rows
-        for (idx, row) in self.content.iter().enumerate() {
-            // Collect all inline_content values, if heading add bold tags **
-            let values = if idx == 0 && self.header {
-                row.iter()
-                    .map(|c| format!("**{c}**"))
-                    .map(|c| dynamic_content!(self.big, c))
-                    .collect::<Vec<Value>>()
-            } else {
-                row.iter()
-                    .map(|c| dynamic_content!(self.big, c))
-                    .collect::<Vec<Value>>()
-            };
-
-            // For each cell in the row, push it and add & between, and \\\n to the end
-            for (idx, val) in values.into_iter().enumerate() {
+        for (row_idx, row) in self.content.iter().enumerate() {
+            // If heading add bold tags **
+            let bold = row_idx == 0 && self.header;
+
+            // For each cell in the row, push it and add & between, and \\\n to the end. Cells
+            // that span several columns are one cell in LaTeX, and the cells below a cell that
+            // spans several rows are left empty
+            let mut idx = 0;
+            while idx < self.width {
                 if idx != 0 {
                     vec.push(json!(" & "));
                 }
-                vec.push(val);
+                let cell = &row[idx];
+                let (colspan, value) = match cell.covered_by {
+                    None if cell.rowspan > 1 => (
+                        cell.colspan,
+                        vec![
+                            json!(format!("\\multirow{{{}}}{{*}}{{", cell.rowspan)),
+                            cell.value(self.big, bold),
+                            json!("}"),
+                        ],
+                    ),
+                    None => (cell.colspan, vec![cell.value(self.big, bold)]),
+                    Some((origin_row, origin_col)) => {
+                        (self.content[origin_row][origin_col].colspan, vec![])
+                    }
+                };
+                if colspan > 1 {
+                    let spec = self.latex_multicolumn_spec(idx, colspan);
+                    vec.push(json!(format!("\\multicolumn{{{colspan}}}{{{spec}}}{{")));
+                    vec.extend(value);
+                    vec.push(json!("}"));
+                } else {
+                    vec.extend(value);
+                }
+                idx += colspan.max(1);
             }
             vec.push(json!(" \\\\\n"));
 
-            // If we should have a border in-between all rows, add it
+            // If we should have a border in-between all rows, add it. Cells that span over the
+            // border are left out of it
             if self.borders == Borders::All || self.borders == Borders::Horizontal {
-                vec.push(json!("\\hline\n"));
+                let crossed = |idx: usize| {
+                    self.content
+                        .get(row_idx + 1)
+                        .and_then(|next| next[idx].covered_by)
+                        .is_some_and(|(origin_row, _)| origin_row <= row_idx)
+                };
+                if (0..self.width).any(crossed) {
+                    let mut start = None;
+                    for idx in 0..=self.width {
+                        match (start, idx < self.width && !crossed(idx)) {
+                            (None, true) => start = Some(idx),
+                            (Some(first), false) => {
+                                vec.push(json!(format!("\\cline{{{}-{}}}\n", first + 1, idx)));
+                                start = None;
+                            }
+                            _ => {}
+                        }
+                    }
+                } else {
+                    vec.push(json!("\\hline\n"));
+                }
             }
         }
 
@@ -396,7 +613,6 @@ impl Table<'_> {
         vec.push(json!(tag));
 
         if let Some(caption) = self.caption {
-
             vec.push(json!("<caption>"));
             if env::var("caption_style").unwrap_or(String::new()) == "numbered" {
                 vec.push(inline_content!(format!("**Table [element-number]({}):** ", key)));
@@ -420,25 +636,25 @@ impl Table<'_> {
         for (idx, row) in self.content.iter().enumerate() {
             vec.push(json!("<tr>"));
 
-            // If it is the header, use th, else use td
-            if idx == 0 && self.header {
-                for (idx, elem) in row.iter().enumerate() {
-                    let alignment = self.alignment.for_column(idx).html_style();
-                    vec.push(json!(format!(
-                        r#"<th style="{alignment}{inside_border_style}">"#
-                    )));
-                    vec.push(dynamic_content!(self.big, elem));
-                    vec.push(json!("</th>"));
+            // If it is the header, use th, else use td. Cells that are spanned over are left out
+            let tag = if idx == 0 && self.header { "th" } else { "td" };
+            for (idx, elem) in row.iter().enumerate() {
+                if elem.covered_by.is_some() {
+                    continue;
                 }
-            } else {
-                for (idx, elem) in row.iter().enumerate() {
-                    let alignment = self.alignment.for_column(idx).html_style();
-                    vec.push(json!(format!(
-                        r#"<td style="{alignment}{inside_border_style}">"#
-                    )));
-                    vec.push(dynamic_content!(self.big, elem));
-                    vec.push(json!("</td>"));
+                let alignment = self.alignment.for_column(idx).html_style();
+                let mut spans = String::new();
+                if elem.colspan > 1 {
+                    spans.push_str(&format!(r#" colspan="{}""#, elem.colspan));
+                }
+                if elem.rowspan > 1 {
+                    spans.push_str(&format!(r#" rowspan="{}""#, elem.rowspan));
                 }
+                vec.push(json!(format!(
+                    r#"<{tag}{spans} style="{alignment}{inside_border_style}">"#
+                )));
+                vec.push(elem.value(self.big, false));
+                vec.push(json!(format!("</{tag}>")));
             }
 
             vec.push(json!("</tr>"));
@@ -448,6 +664,457 @@ impl Table<'_> {
 
         json!(vec)
     }
+
+    // Turns this table to a GFM pipe table and gets a JSON value to return. Pipe tables always
+    // have a header row and no control over borders, and each cell has to fit on one line
+    fn to_markdown(&self) -> Value {
+        let mut vec: Vec<Value> = vec![];
+        let key = self.label.unwrap_or("");
+        let structure_data = json!({
+                "element": "figure",
+                "key": key,
+        })
+        .to_string();
+
+        vec.push(json!(
+            {
+                "name": "list-push",
+                "arguments": {"name": "structure"},
+                "data": structure_data,
+            }
+        ));
+
+        if self.borders != Borders::All {
+            eprintln!("Borders are not supported in Markdown");
+        }
+        if self.big {
+            eprintln!("Cells in Markdown tables can only contain inline content");
+        }
+        if self.has_spans() {
+            eprintln!("Cells that span several columns or rows are not supported in Markdown");
+        }
+        // A pipe table needs at least one column
+        if self.width == 0 {
+            return json!(vec);
+        }
+
+        vec.push(json!("\n"));
+        if let Some(label) = self.label {
+            vec.push(json!(format!(r#"<a id="{label}"></a>"#)));
+        }
+        if let Some(caption) = self.caption {
+            vec.push(json!("_"));
+            if env::var("caption_style").unwrap_or_default() == "numbered" {
+                vec.push(inline_content!(format!("**Table [element-number]({}):** ", key)));
+            }
+            vec.push(inline_content!(caption));
+            vec.push(json!("_"));
+        }
+        if self.label.is_some() || self.caption.is_some() {
+            vec.push(json!("\n\n"));
+        }
+
+        // The header row has to exist, so it is left empty if the table has no header
+        let (header, body) = match self.content.split_first() {
+            Some((first, rest)) if self.header => (Some(first), rest),
+            _ => (None, &self.content[..]),
+        };
+
+        let push_row = |vec: &mut Vec<Value>, row: Option<&Vec<Cell>>| {
+            let Some(row) = row else {
+                vec.push(json!(format!("{}|\n", "|   ".repeat(self.width))));
+                return;
+            };
+            for (idx, cell) in row.iter().enumerate() {
+                vec.push(json!(if idx == 0 { "| " } else { " | " }));
+                // Markdown tables can't contain line breaks, and cells that are spanned over are
+                // left empty
+                if cell.covered_by.is_none() {
+                    vec.push(cell.single_line_value(self.big));
+                }
+            }
+            vec.push(json!(" |\n"));
+        };
+
+        push_row(&mut vec, header);
+        let delimiters = (0..self.width)
+            .map(|idx| format!("| {} ", self.alignment.for_column(idx).markdown_delimiter()))
+            .collect::<String>();
+        vec.push(json!(format!("{delimiters}|\n")));
+        for row in body {
+            push_row(&mut vec, Some(row));
+        }
+
+        json!(vec)
+    }
+
+    // Turns this table to plain text and gets a JSON value to return. The table is drawn by the
+    // layout of the plaintext package, which needs to know the alignment of each column and the
+    // borders to draw, followed by the cells separated by markers
+    fn to_plaintext(&self) -> Value {
+        let mut vec: Vec<Value> = vec![];
+        let key = self.label.unwrap_or("");
+        let structure_data = json!({
+                "element": "figure",
+                "key": key,
+        })
+        .to_string();
+
+        vec.push(json!(
+            {
+                "name": "list-push",
+                "arguments": {"name": "structure"},
+                "data": structure_data,
+            }
+        ));
+
+        if let Some(caption) = self.caption {
+            vec.push(json!("\n\u{E000}\u{E001}"));
+            if env::var("caption_style").unwrap_or_default() == "numbered" {
+                vec.push(inline_content!(format!("**Table [element-number]({}):** ", key)));
+            }
+            vec.push(inline_content!(caption));
+            vec.push(json!("\u{E002}\n"));
+        }
+
+        // The layout uses the same alignment characters as LaTeX
+        let alignment = (0..self.width)
+            .map(|idx| self.alignment.for_column(idx).latex_char())
+            .collect::<String>();
+        let mut borders = match self.borders {
+            Borders::All => "hvo",
+            Borders::Horizontal => "ho",
+            Borders::Vertical => "vo",
+            Borders::Outer => "o",
+            Borders::None => "",
+        }
+        .to_string();
+        if self.header {
+            borders.push('H');
+        }
+        vec.push(json!(format!("\n\u{E008}{alignment}:{borders}\u{E001}")));
+
+        // The layout draws every cell, so cells that are spanned over are left empty
+        if self.has_spans() {
+            eprintln!("Cells that span several columns or rows are not supported in plain text");
+        }
+        for row in &self.content {
+            for (idx, cell) in row.iter().enumerate() {
+                if idx != 0 {
+                    vec.push(json!("\u{E009}"));
+                }
+                if cell.covered_by.is_none() {
+                    vec.push(cell.value(self.big, false));
+                }
+            }
+            vec.push(json!("\u{E00A}"));
+        }
+        vec.push(json!("\u{E002}\n"));
+
+        json!(vec)
+    }
+
+    // Turns this table to a Word table and gets a JSON value to return. The caption is put above
+    // the table, and the cells are paragraphs so that they can be aligned
+    fn to_docx(&self) -> Value {
+        let mut vec: Vec<Value> = vec![];
+        let key = self.label.unwrap_or("");
+        let structure_data = json!({
+                "element": "figure",
+                "key": key,
+        })
+        .to_string();
+
+        vec.push(json!(
+            {
+                "name": "list-push",
+                "arguments": {"name": "structure"},
+                "data": structure_data,
+            }
+        ));
+
+        if let Some(caption) = self.caption {
+            vec.push(json!(r#"<w:p><w:pPr><w:pStyle w:val="Caption"/></w:pPr>"#));
+            if env::var("caption_style").unwrap_or_default() == "numbered" {
+                vec.push(inline_content!(format!("**Table [element-number]({}):** ", key)));
+            }
+            vec.push(inline_content!(caption));
+            vec.push(json!("</w:p>\n"));
+        }
+
+        let border = |side: &str| {
+            format!(r#"<w:{side} w:val="single" w:sz="4" w:space="0" w:color="000000"/>"#)
+        };
+        let sides: &[&str] = match self.borders {
+            Borders::All => &["top", "left", "bottom", "right", "insideH", "insideV"],
+            Borders::Horizontal => &["top", "left", "bottom", "right", "insideH"],
+            Borders::Vertical => &["top", "left", "bottom", "right", "insideV"],
+            Borders::Outer => &["top", "left", "bottom", "right"],
+            Borders::None => &[],
+        };
+        let borders = sides.iter().map(|side| border(side)).collect::<String>();
+
+        // The columns share the width of the text area of an A4 page, in twentieths of a point
+        let column_width = 9026 / self.width.max(1);
+        let grid = format!(r#"<w:gridCol w:w="{column_width}"/>"#).repeat(self.width);
+
+        vec.push(json!(format!(
+            "<w:tbl><w:tblPr><w:tblW w:w=\"5000\" w:type=\"pct\"/>\
+            <w:tblBorders>{borders}</w:tblBorders></w:tblPr><w:tblGrid>{grid}</w:tblGrid>"
+        )));
+
+        for (row_idx, row) in self.content.iter().enumerate() {
+            let is_header = row_idx == 0 && self.header;
+            // The header row is repeated on every page the table spans
+            vec.push(json!(if is_header {
+                "<w:tr><w:trPr><w:tblHeader/></w:trPr>"
+            } else {
+                "<w:tr>"
+            }));
+            // Cells that span several columns are one cell, and the cells below a cell that spans
+            // several rows continue its vertical merge
+            let mut idx = 0;
+            while idx < self.width {
+                let cell = &row[idx];
+                let (origin, merge) = match cell.covered_by {
+                    None if cell.rowspan > 1 => (cell, r#"<w:vMerge w:val="restart"/>"#),
+                    None => (cell, ""),
+                    Some((origin_row, origin_col)) => {
+                        (&self.content[origin_row][origin_col], "<w:vMerge/>")
+                    }
+                };
+                let colspan = origin.colspan.max(1);
+                let span = if colspan > 1 {
+                    format!(r#"<w:gridSpan w:val="{colspan}"/>"#)
+                } else {
+                    String::new()
+                };
+                let justification = self.alignment.for_column(idx).docx_justification();
+                vec.push(json!(format!(
+                    "<w:tc><w:tcPr><w:tcW w:w=\"{}\" w:type=\"dxa\"/>{span}{merge}</w:tcPr>\
+                    <w:p><w:pPr><w:jc w:val=\"{justification}\"/></w:pPr>",
+                    column_width * colspan
+                )));
+                if cell.covered_by.is_none() {
+                    if is_header {
+                        vec.push(json!("<mm:b>"));
+                    }
+                    vec.push(cell.value(self.big, false));
+                    if is_header {
+                        vec.push(json!("</mm:b>"));
+                    }
+                }
+                vec.push(json!("</w:p></w:tc>"));
+                idx += colspan;
+            }
+            vec.push(json!("</w:tr>"));
+        }
+
+        vec.push(json!("</w:tbl>\n"));
+
+        json!(vec)
+    }
+
+    // Turns this table to Typst and gets a JSON value to return. Tables with a caption or label are
+    // wrapped in a figure, which is left unnumbered since the numbering comes from the structure
+    fn to_typst(&self) -> Value {
+        let mut vec: Vec<Value> = vec![];
+        let key = self.label.unwrap_or("");
+        let structure_data = json!({
+                "element": "figure",
+                "key": key,
+        })
+        .to_string();
+
+        vec.push(json!(
+            {
+                "name": "list-push",
+                "arguments": {"name": "structure"},
+                "data": structure_data,
+            }
+        ));
+
+        let figure = self.caption.is_some() || self.label.is_some();
+        let indent = if figure { "    " } else { "  " };
+        vec.push(json!(if figure { "\n#figure(\n  table(\n" } else { "\n#table(\n" }));
+
+        let alignment = (0..self.width)
+            .map(|idx| self.alignment.for_column(idx).typst_alignment())
+            .collect::<Vec<_>>()
+            .join(", ");
+        // A trailing comma is needed for an array with a single element
+        let comma = if self.width == 1 { "," } else { "" };
+
+        // Typst draws all borders by default, and the other options are drawn by a function of the
+        // column and row of each cell
+        let last_column = self.width.saturating_sub(1);
+        let last_row = self.height.saturating_sub(1);
+        let stroke = match self.borders {
+            Borders::All => None,
+            Borders::Horizontal => Some(format!(
+                "(x, y) => (top: 0.5pt, bottom: 0.5pt, left: if x == 0 {{ 0.5pt }}, \
+                right: if x == {last_column} {{ 0.5pt }})"
+            )),
+            Borders::Vertical => Some(format!(
+                "(x, y) => (left: 0.5pt, right: 0.5pt, top: if y == 0 {{ 0.5pt }}, \
+                bottom: if y == {last_row} {{ 0.5pt }})"
+            )),
+            Borders::Outer => Some(format!(
+                "(x, y) => (left: if x == 0 {{ 0.5pt }}, right: if x == {last_column} \
+                {{ 0.5pt }}, top: if y == 0 {{ 0.5pt }}, bottom: if y == {last_row} {{ 0.5pt }})"
+            )),
+            Borders::None => Some("none".to_string()),
+        };
+
+        vec.push(json!(format!(
+            "{indent}columns: {},\n{indent}align: ({alignment}{comma}),\n",
+            self.width
+        )));
+        if let Some(stroke) = stroke {
+            vec.push(json!(format!("{indent}stroke: {stroke},\n")));
+        }
+
+        for (row_idx, row) in self.content.iter().enumerate() {
+            let is_header = row_idx == 0 && self.header;
+            // The header row is repeated on every page the table spans
+            vec.push(json!(if is_header {
+                format!("{indent}table.header(")
+            } else {
+                indent.to_string()
+            }));
+            // Cells that are spanned over are left out
+            let cells = row.iter().filter(|cell| cell.covered_by.is_none());
+            for (idx, cell) in cells.enumerate() {
+                let mut spans = vec![];
+                if cell.colspan > 1 {
+                    spans.push(format!("colspan: {}", cell.colspan));
+                }
+                if cell.rowspan > 1 {
+                    spans.push(format!("rowspan: {}", cell.rowspan));
+                }
+                let open = if spans.is_empty() {
+                    "[".to_string()
+                } else {
+                    format!("table.cell({})[", spans.join(", "))
+                };
+                vec.push(json!(if idx == 0 { open } else { format!(" {open}") }));
+                if is_header {
+                    vec.push(json!("#strong["));
+                }
+                vec.push(cell.value(self.big, false));
+                if is_header {
+                    vec.push(json!("]"));
+                }
+                vec.push(json!("],"));
+            }
+            vec.push(json!(if is_header { "),\n" } else { "\n" }));
+        }
+
+        if !figure {
+            vec.push(json!(")\n"));
+            return json!(vec);
+        }
+
+        vec.push(json!("  ),\n"));
+        if let Some(caption) = self.caption {
+            vec.push(json!("  caption: ["));
+            if env::var("caption_style").unwrap_or_default() == "numbered" {
+                vec.push(inline_content!(format!("**Table [element-number]({}):** ", key)));
+            }
+            vec.push(inline_content!(caption));
+            vec.push(json!("],\n"));
+        }
+        vec.push(json!("  numbering: none,\n)"));
+        if let Some(label) = self.label {
+            vec.push(json!(format!(" <{label}>")));
+        }
+        vec.push(json!("\n"));
+
+        json!(vec)
+    }
+
+    // Turns this table to the Pandoc AST and gets a JSON value to return. Every element is
+    // followed by a comma, which the pandoc package removes at the end of each list
+    fn to_pandoc(&self) -> Value {
+        let mut vec: Vec<Value> = vec![];
+        let key = self.label.unwrap_or("");
+        let structure_data = json!({
+                "element": "figure",
+                "key": key,
+        })
+        .to_string();
+
+        vec.push(json!(
+            {
+                "name": "list-push",
+                "arguments": {"name": "structure"},
+                "data": structure_data,
+            }
+        ));
+
+        if self.borders != Borders::All {
+            eprintln!("Borders are not supported in Pandoc");
+        }
+
+        // The label is the identifier of the table, and the caption is a plain block
+        vec.push(json!(format!(r#"{{"t":"Table","c":[[{},[],[]],[null,["#, json!(key))));
+        if let Some(caption) = self.caption {
+            vec.push(json!(r#"{"t":"Plain","c":["#));
+            if env::var("caption_style").unwrap_or_default() == "numbered" {
+                vec.push(inline_content!(format!("**Table [element-number]({}):** ", key)));
+            }
+            vec.push(inline_content!(caption));
+            vec.push(json!("]},"));
+        }
+        vec.push(json!("]],["));
+        for idx in 0..self.width {
+            let alignment = self.alignment.for_column(idx).pandoc_alignment();
+            vec.push(json!(format!(r#"[{{"t":"{alignment}"}},{{"t":"ColWidthDefault"}}],"#)));
+        }
+        vec.push(json!("],"));
+
+        let (header, body) = match self.content.split_first() {
+            Some((first, rest)) if self.header => (Some(first), rest),
+            _ => (None, &self.content[..]),
+        };
+
+        // Cells that are spanned over are left out
+        let push_row = |vec: &mut Vec<Value>, row: &Vec<Cell>| {
+            vec.push(json!(r#"[["",[],[]],["#));
+            for (idx, cell) in row.iter().enumerate() {
+                if cell.covered_by.is_some() {
+                    continue;
+                }
+                let alignment = self.alignment.for_column(idx).pandoc_alignment();
+                vec.push(json!(format!(
+                    r#"[["",[],[]],{{"t":"{alignment}"}},{},{},["#,
+                    cell.rowspan, cell.colspan
+                )));
+                if self.big {
+                    vec.push(cell.value(self.big, false));
+                } else {
+                    vec.push(json!(r#"{"t":"Plain","c":["#));
+                    vec.push(cell.value(self.big, false));
+                    vec.push(json!("]},"));
+                }
+                vec.push(json!("]],"));
+            }
+            vec.push(json!("]],"));
+        };
+
+        // The table has a head, a single body and an empty foot
+        vec.push(json!(r#"[["",[],[]],["#));
+        if let Some(row) = header {
+            push_row(&mut vec, row);
+        }
+        vec.push(json!(r#"]],[[["",[],[]],0,[],["#));
+        for row in body {
+            push_row(&mut vec, row);
+        }
+        vec.push(json!(r#"]]],[["",[],[]],[]]]},"#));
+
+        json!(vec)
+    }
 }
 
 // Parses the JSON input to a table, if possible. Warnings/errors are printed out when running this.
@@ -513,7 +1180,7 @@ fn parse_table(input: &Value, big: bool) -> Option<Table> {
 
     let body = input["data"].as_str().unwrap();
 
-    let mut content = if big {
+    let grid = if big {
         let row_delimiter = input["arguments"]["row-delimiter"].as_str().unwrap();
         let column_delimiter = input["arguments"]["column-delimiter"].as_str().unwrap();
         parse_big_content(body, row_delimiter, column_delimiter)
@@ -526,6 +1193,11 @@ fn parse_table(input: &Value, big: bool) -> Option<Table> {
         parse_content(body, delimiter, strip_whitespace)
     };
 
+    let mut content: Vec<Vec<Cell>> = grid
+        .into_iter()
+        .map(|row| row.into_iter().map(Cell::markup).collect())
+        .collect();
+
     let height = content.len();
 
     if height == 0 {
@@ -548,9 +1220,13 @@ fn parse_table(input: &Value, big: bool) -> Option<Table> {
     // If any row differ from this, it is jagged and we make sure to resize the arrays
     if content.iter().any(|r| r.len() != width) {
         eprintln!("The table is jagged; some rows are wider than others.");
-        content.iter_mut().for_each(|r| r.resize(width, ""))
+        content
+            .iter_mut()
+            .for_each(|r| r.resize_with(width, || Cell::markup("")))
     }
 
+    apply_spans(&mut content)?;
+
     // This is an fatal error since we don't want users that have specified column-by-column to get
     // completely overwritten, so we fail here
     if let Some(w) = alignment.width() {
@@ -573,6 +1249,324 @@ fn parse_table(input: &Value, big: bool) -> Option<Table> {
     })
 }
 
+// Parses the JSON input of a [table-file] module to a table, by reading the file it refers to.
+// Cells from files are text rather than markup, so that the data is shown as it is written
+fn parse_table_file(input: &Value) -> Option<Table<'_>> {
+    let arg = |name: &str| input["arguments"][name].as_str().unwrap_or("");
+
+    let path = arg("path");
+    let Ok(text) = fs::read_to_string(path) else {
+        eprintln!("File could not be accessed at {path}");
+        return None;
+    };
+
+    let delimiter = match arg("delimiter") {
+        "auto" | "" => {
+            if path.ends_with(".tsv") || path.ends_with(".tab") {
+                b'\t'
+            } else {
+                b','
+            }
+        }
+        "tab" | "\\t" => b'\t',
+        s if s.len() == 1 && s != "\"" => s.as_bytes()[0],
+        s => {
+            eprintln!("Invalid 'delimiter' arg '{s}', choose a single ASCII character or tab");
+            return None;
+        }
+    };
+
+    let mut rows = match parse_csv(&text, delimiter) {
+        Ok(rows) => rows,
+        Err(error) => {
+            eprintln!("The file at {path} could not be read as CSV: {error}");
+            return None;
+        }
+    };
+
+    let header = match arg("header").to_ascii_lowercase().as_str() {
+        "bold" => true,
+        "none" => false,
+        s => {
+            if s != "auto" {
+                eprintln!("Invalid 'header' arg, choose one of auto/bold/none");
+            }
+            rows.len() > 1
+                && rows[0]
+                    .iter()
+                    .all(|cell| !cell.trim().is_empty() && parse_number(cell).is_none())
+        }
+    };
+
+    // Columns are selected by their number, starting at 1, or by their name in the header
+    if !arg("columns").is_empty() {
+        let names = if header { rows[0].clone() } else { vec![] };
+        let columns = parse_columns(arg("columns"), &names)?;
+        rows = rows
+            .into_iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|&idx| row.get(idx).cloned().unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+    }
+
+    let decimals = match arg("decimals") {
+        "" => None,
+        s => match s.parse::<usize>() {
+            Ok(decimals) => Some(decimals),
+            Err(_) => {
+                eprintln!("Invalid 'decimals' arg, expected a number");
+                None
+            }
+        },
+    };
+    let thousands = arg("thousands");
+
+    let height = rows.len();
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if rows.iter().any(|r| r.len() != width) {
+        eprintln!("The table is jagged; some rows are wider than others.");
+        rows.iter_mut().for_each(|r| r.resize(width, String::new()));
+    }
+
+    // Columns where every cell in the body is a number are right-aligned with auto
+    let body = if header {
+        &rows[1.min(height)..]
+    } else {
+        &rows[..]
+    };
+    let alignment = match arg("alignment") {
+        "auto" => Alignment::Columns(
+            (0..width)
+                .map(|col| {
+                    let cells: Vec<&str> = body
+                        .iter()
+                        .map(|row| row[col].trim())
+                        .filter(|c| !c.is_empty())
+                        .collect();
+                    if !cells.is_empty() && cells.iter().all(|c| parse_number(c).is_some()) {
+                        ColumnAlignment::Right
+                    } else {
+                        ColumnAlignment::Left
+                    }
+                })
+                .collect(),
+        ),
+        s => match s.try_into() {
+            Ok(alignment) => alignment,
+            Err(()) => {
+                eprintln!(
+                    "Invalid 'alignment' arg, choose one of auto/left/center/right or l/c/r for \
+                    each column"
+                );
+                Alignment::All(ColumnAlignment::Left)
+            }
+        },
+    };
+
+    if let Some(w) = alignment.width() {
+        if w != width {
+            eprintln!("Alignment given for {} columns but {} exist", w, width);
+            return None;
+        }
+    }
+
+    let borders = match arg("borders").try_into() {
+        Ok(border) => border,
+        Err(()) => {
+            eprintln!("Invalid 'borders' arg, choose one of all/horizontal/vertical/outer/none");
+            Borders::All
+        }
+    };
+
+    let content = rows
+        .into_iter()
+        .enumerate()
+        .map(|(row_idx, row)| {
+            row.into_iter()
+                .map(|cell| {
+                    let cell = cell.trim();
+                    if row_idx == 0 && header {
+                        return Cell::text(cell.to_string());
+                    }
+                    match parse_number(cell) {
+                        Some(number) => {
+                            Cell::text(format_number(cell, number, decimals, thousands))
+                        }
+                        None => Cell::text(cell.to_string()),
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    if height == 0 {
+        eprintln!("Empty table");
+    }
+
+    Some(Table {
+        width,
+        height,
+        content,
+        alignment,
+        borders,
+        header,
+        caption: Some(arg("caption")).filter(|s| !s.is_empty()),
+        label: Some(arg("label")).filter(|s| !s.is_empty()),
+        big: false,
+    })
+}
+
+// Parses comma-separated values as described in RFC 4180, with some other delimiter if wanted.
+// Fields may be quoted, in which case they may contain delimiters, line breaks and quotes written
+// as two quotes. Rows may have different numbers of fields, and empty lines are skipped
+fn parse_csv(text: &str, delimiter: u8) -> Result<Vec<Vec<String>>, csv::Error> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(text.as_bytes())
+        .records()
+        .map(|record| Ok(record?.iter().map(str::to_string).collect()))
+        .collect()
+}
+
+// Parses the selection of columns, such as "1,3-4,Name", to the indices of the columns
+fn parse_columns(selection: &str, names: &[String]) -> Option<Vec<usize>> {
+    let mut columns = vec![];
+    for part in selection.split(',').map(str::trim) {
+        let range = part.split_once('-').and_then(|(first, last)| {
+            Some((
+                first.trim().parse::<usize>().ok()?,
+                last.trim().parse::<usize>().ok()?,
+            ))
+        });
+        if let Some((first, last)) = range {
+            columns.extend((first.max(1)..=last).map(|n| n - 1));
+        } else if let Ok(n) = part.parse::<usize>() {
+            columns.push(n.max(1) - 1);
+        } else if let Some(idx) = names.iter().position(|name| name.trim() == part) {
+            columns.push(idx);
+        } else {
+            eprintln!("Unknown column '{part}' in 'columns' arg");
+            return None;
+        }
+    }
+    Some(columns)
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    if text.is_empty() || text.contains(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    text.parse::<f64>().ok()
+}
+
+// Formats a number with the given number of decimals, and a separator between groups of thousands
+fn format_number(text: &str, number: f64, decimals: Option<usize>, thousands: &str) -> String {
+    let formatted = match decimals {
+        Some(decimals) => format!("{number:.decimals$}"),
+        None => text.to_string(),
+    };
+    if thousands.is_empty() {
+        return formatted;
+    }
+
+    let (sign, unsigned) = match formatted.strip_prefix(['-', '+']) {
+        Some(rest) => (&formatted[..1], rest),
+        None => ("", formatted.as_str()),
+    };
+    let (integer, fraction) = match unsigned.find('.') {
+        Some(idx) => unsigned.split_at(idx),
+        None => (unsigned, ""),
+    };
+    let mut grouped = String::new();
+    for (idx, digit) in integer.chars().enumerate() {
+        if idx != 0 && (integer.len() - idx) % 3 == 0 {
+            grouped.push_str(thousands);
+        }
+        grouped.push(digit);
+    }
+    format!("{sign}{grouped}{fraction}")
+}
+
+// Merges the cells that only contain "<" with the cell to their left, and the cells that only
+// contain "^" with the cell above them. Spans have to be rectangular, so a table where they
+// aren't is a fatal error
+fn apply_spans(content: &mut [Vec<Cell>]) -> Option<()> {
+    for row in 0..content.len() {
+        for col in 0..content[row].len() {
+            let CellContent::Markup(text) = content[row][col].content else {
+                continue;
+            };
+            let (origin_row, origin_col) = match text.trim() {
+                "<" if col == 0 => {
+                    eprintln!("A cell in the first column can't span from the left; use \\< for a literal <");
+                    continue;
+                }
+                "^" if row == 0 => {
+                    eprintln!(
+                        "A cell in the first row can't span from above; use \\^ for a literal ^"
+                    );
+                    continue;
+                }
+                "<" => {
+                    let left = &content[row][col - 1];
+                    left.covered_by.unwrap_or((row, col - 1))
+                }
+                "^" => {
+                    let above = &content[row - 1][col];
+                    above.covered_by.unwrap_or((row - 1, col))
+                }
+                _ => continue,
+            };
+
+            let origin = &mut content[origin_row][origin_col];
+            if origin_row == row {
+                origin.colspan = origin.colspan.max(col - origin_col + 1);
+            } else if origin_col == col {
+                origin.rowspan = origin.rowspan.max(row - origin_row + 1);
+            } else if col >= origin_col + origin.colspan || row >= origin_row + origin.rowspan {
+                eprintln!(
+                    "The cell at row {}, column {} doesn't continue a rectangular span",
+                    row + 1,
+                    col + 1
+                );
+                return None;
+            }
+            content[row][col].covered_by = Some((origin_row, origin_col));
+        }
+    }
+
+    // Every cell within a span has to be covered by it, or the span isn't rectangular
+    for (row, cells) in content.iter().enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            if cell.covered_by.is_some() {
+                continue;
+            }
+            let rectangular = content[row..row + cell.rowspan]
+                .iter()
+                .flat_map(|cells| &cells[col..col + cell.colspan])
+                .skip(1)
+                .all(|other| other.covered_by == Some((row, col)));
+            if !rectangular {
+                eprintln!(
+                    "The span starting at row {}, column {} isn't rectangular",
+                    row + 1,
+                    col + 1
+                );
+                return None;
+            }
+        }
+    }
+
+    Some(())
+}
+
 fn parse_content<'a>(input: &'a str, delimiter: &'a str, trim: bool) -> Vec<Vec<&'a str>> {
     input
         .lines()
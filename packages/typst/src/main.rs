@@ -0,0 +1,433 @@
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, Read},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, json, to_value, Value};
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum JsonEntry {
+    ParentNode {
+        name: String,
+        arguments: HashMap<String, Value>,
+        children: Vec<Self>,
+        id: u64,
+    },
+    Module {
+        name: String,
+        #[serde(default)]
+        data: String,
+        #[serde(default)]
+        arguments: HashMap<String, Value>,
+        #[serde(default = "default_inline")]
+        inline: bool,
+    },
+    Compound(Vec<Self>),
+    Raw(String),
+}
+
+/// This is just a helper to ensure that omitted "inline" fields
+/// default to true.
+fn default_inline() -> bool {
+    true
+}
+
+macro_rules! inline_content {
+    ($expr:expr) => {
+        json!({
+            "name": "inline_content",
+            "data": $expr
+        })
+    }
+}
+
+macro_rules! block_content {
+    ($expr:expr) => {
+        json!({
+            "name": "block_content",
+            "data": $expr
+        })
+    }
+}
+
+macro_rules! dynamic_content {
+    ($cond:expr, $expr:expr) => {
+        if $cond {
+            block_content!($expr)
+        } else {
+            inline_content!($expr)
+        }
+    };
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let Some(action) = args.first() else {
+        eprintln!("No action was provided.");
+        return;
+    };
+
+    match action.as_str() {
+        "manifest" => print!("{}", &manifest()),
+        "transform" => {
+            let from = args.get(1).unwrap();
+            let format = args.get(2).unwrap();
+
+            if "typst" != format {
+                eprintln!("Output format not supported");
+                return;
+            }
+
+            print!("{}", transform(from));
+        }
+        other => eprintln!("Invalid action '{other}'"),
+    }
+}
+
+fn transform(from: &str) -> String {
+    let input: JsonEntry = {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        from_str(&buffer).unwrap()
+    };
+
+    // The function syntax is used for all tags, since the shorthands for bold and italic text only
+    // work at word boundaries
+    match from {
+        "__bold" => transform_tag(input, "strong"),
+        "__italic" => transform_tag(input, "emph"),
+        "__superscript" => transform_tag(input, "super"),
+        "__subscript" => transform_tag(input, "sub"),
+        "__underlined" => transform_tag(input, "underline"),
+        "__strikethrough" => transform_tag(input, "strike"),
+        "__verbatim" => transform_verbatim(input),
+        "__paragraph" => transform_paragraph(to_value(input).unwrap()),
+        "__document" => transform_document(to_value(input).unwrap()),
+        "__math" => transform_math(to_value(input).unwrap()),
+        "__text" => escape_text(to_value(input).unwrap()),
+        "__heading" => transform_heading(to_value(input).unwrap()),
+        _ => panic!("element not supported"),
+    }
+}
+
+fn transform_paragraph(paragraph: Value) -> String {
+    let mut result = vec![json!("\n")];
+    if let Value::Array(children) = &paragraph["children"] {
+        result.extend(children.iter().cloned());
+    }
+    result.push(json!("\n"));
+
+    serde_json::to_string(&result).unwrap()
+}
+
+fn transform_tag(node: JsonEntry, function: &str) -> String {
+    let mut result: Vec<Value> = vec![];
+    result.push(Value::from(format!("#{function}[")));
+
+    match node {
+        JsonEntry::ParentNode { children, .. } => {
+            result.extend(children.into_iter().map(|x| to_value(x).unwrap()));
+        }
+        JsonEntry::Module { data, inline, .. } => {
+            result.push(dynamic_content!(inline, data));
+        }
+        _ => {}
+    }
+
+    result.push(Value::from("]"));
+    serde_json::to_string(&result).unwrap()
+}
+
+fn transform_verbatim(node: JsonEntry) -> String {
+    let result = match node {
+        JsonEntry::Module { data, inline, .. } => {
+            if inline {
+                vec![Value::from(format!("#raw({})", string_literal(&data)))]
+            } else {
+                vec![Value::from(format!(
+                    "\n#raw(block: true, {})\n",
+                    string_literal(&data)
+                ))]
+            }
+        }
+        JsonEntry::ParentNode { children, .. } => {
+            // Verbatim is evaluated before its children, so all text is collected into one string
+            let mut content = String::new();
+            for child in children {
+                match child {
+                    JsonEntry::Module { name, data, .. } if name == "__text" => {
+                        content.push_str(&data);
+                    }
+                    JsonEntry::Module { name, .. } | JsonEntry::ParentNode { name, .. } => {
+                        eprintln!("Modules are not allowed in verbatim tags; found module {name}");
+                    }
+                    _ => {}
+                }
+            }
+            vec![Value::from(format!("#raw({})", string_literal(&content)))]
+        }
+        _ => vec![],
+    };
+    serde_json::to_string(&result).unwrap()
+}
+
+/// Make a Typst string literal with the given contents
+fn string_literal(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{escaped}\"")
+}
+
+fn transform_heading(heading: Value) -> String {
+    let mut vec = vec![];
+
+    let Value::String(s) = &heading["arguments"]["level"] else {
+        panic!();
+    };
+    let level = s.parse::<u8>().unwrap().max(1);
+    let id = heading["id"].as_u64().unwrap();
+    let key = format!("heading:{id}");
+    let heading_style = env::var("heading_style").unwrap_or_default();
+
+    vec.push(json!(format!("\n{} ", "=".repeat(level as usize))));
+
+    let element = if heading_style == "numbered" {
+        vec.push(inline_content!(format!("[element-number]({key}) ")));
+        "numbered-heading"
+    } else {
+        "unnumbered-heading"
+    };
+
+    if let Value::Array(children) = &heading["children"] {
+        for child in children {
+            vec.push(child.clone());
+        }
+    }
+
+    // A label at the end of the heading is attached to it, so that it can be referenced
+    vec.push(json!(format!(" <{key}>\n")));
+
+    let structure_data = json!({
+        "element": element,
+        "level": level,
+        "key": key,
+        "contents": heading["children"],
+    })
+    .to_string();
+
+    vec.push(json!(
+        {
+            "name": "list-push",
+            "arguments": {"name": "structure"},
+            "data": structure_data,
+        }
+    ));
+
+    serde_json::to_string(&vec).unwrap()
+}
+
+fn transform_math(node: Value) -> String {
+    // We know that the math tag is a non-recursively parsed tag, which means that it may only
+    // contain __text and modules. For now, we collect all __text nodes and
+    if let Value::Array(children) = &node["children"] {
+        let mut content = String::new();
+        for child in children {
+            let name = child["name"].as_str().unwrap();
+            if name == "__text" {
+                content.push_str(child["data"].as_str().unwrap());
+            } else {
+                eprintln!("Modules are not allowed in math tags; found module {name}");
+            }
+        }
+        if content.is_empty() {
+            format!("{}", json!([]))
+        } else {
+            format!(
+                "{}",
+                json!([
+                    {
+                      "name": "math",
+                      "data": content,
+                      "arguments": {},
+                      "inline": true
+                    }
+                ])
+            )
+        }
+    } else {
+        eprintln!("Unexpected __math structure");
+        String::new()
+    }
+}
+
+fn transform_document(mut doc: Value) -> String {
+    let mut result: Vec<Value> = vec![];
+
+    if let Some(vec) = doc.get_mut("children").and_then(Value::as_array_mut) {
+        result.append(vec);
+    }
+
+    serde_json::to_string(&result).unwrap()
+}
+
+fn escape_text(module: Value) -> String {
+    if let Value::String(s) = &module["data"] {
+        let s = s
+            .split('\n')
+            .map(escape_line)
+            .collect::<Vec<String>>()
+            .join("\n");
+        format!("{}", json! {[{"name":"raw","data":s}]})
+    } else {
+        panic!("Malformed text module");
+    }
+}
+
+/// Escape one line of text. Since we can't know what comes before a text node, the start of
+/// the text is treated as the start of a line, which at worst adds a redundant backslash.
+fn escape_line(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let indent = line.len() - line.trim_start().len();
+    result.push_str(&line[..indent]);
+    let line = &line[indent..];
+
+    // Characters that only have a meaning at the start of a line, such as headings and list
+    // markers
+    let mut chars = line.chars().peekable();
+    match chars.peek() {
+        Some('-' | '+' | '=') => {
+            result.push('\\');
+            result.push(chars.next().unwrap());
+        }
+        Some(c) if c.is_ascii_digit() => {
+            while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                result.push(c);
+            }
+            if let Some(c) = chars.next_if(|c| *c == '.') {
+                result.push('\\');
+                result.push(c);
+            }
+        }
+        _ => {}
+    }
+
+    while let Some(c) = chars.next() {
+        // Dashes are only escaped when they would be turned into an en or em dash, or a soft
+        // hyphen
+        let dash = c == '-' && matches!(chars.peek(), Some('-' | '?'));
+        if dash
+            || matches!(
+                c,
+                '\\' | '#' | '*' | '_' | '`' | '$' | '<' | '>' | '@' | '[' | ']' | '~' | '/'
+            )
+        {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+fn manifest() -> String {
+    serde_json::to_string(&json!(
+        {
+            "version": "0.1",
+            "name": "typst",
+            "description": "This packages provides Typst support for the basic Modmark features.",
+            "transforms": [
+                {
+                    "from": "__bold",
+                    "to": ["typst"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__italic",
+                    "to": ["typst"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__superscript",
+                    "to": ["typst"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__subscript",
+                    "to": ["typst"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__strikethrough",
+                    "to": ["typst"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__underlined",
+                    "to": ["typst"],
+                    "arguments": [],
+                    "type": "any"
+                },
+                {
+                    "from": "__math",
+                    "to": ["typst"],
+                    "arguments": [],
+                    "evaluate-before-children": true,
+                    "type": "parent"
+                },
+                {
+                    "from": "__document",
+                    "to": ["typst"],
+                    "arguments": [],
+                    "type": "parent"
+                },
+                {
+                    "from": "__text",
+                    "to": ["typst"],
+                    "arguments": []
+                },
+                {
+                    "from": "__paragraph",
+                    "to": ["typst"],
+                    "arguments": [],
+                    "type": "parent"
+                },
+                {
+                    "from": "__verbatim",
+                    "to": ["typst"],
+                    "arguments": [],
+                    "evaluate-before-children": true,
+                    "type": "any"
+                },
+                {
+                    "from": "__heading",
+                    "to": ["typst"],
+                    "arguments": [
+                        {
+                            "name": "level",
+                            "description": "The level of the heading",
+                            "default": "1"
+                        }
+                    ],
+                    "type": "parent",
+                    "variables": {
+                        "structure": {"type": "list", "access": "push"},
+                        "heading_style": {"type": "const", "access": "read"},
+                    },
+                },
+            ]
+        }
+    ))
+    .unwrap()
+}
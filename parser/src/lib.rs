@@ -123,6 +123,9 @@ pub struct Paragraph {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Document {
     pub elements: Vec<Ast>,
+    /// The line in the source document that each element in `elements` starts on, counting
+    /// from 1
+    pub lines: Vec<usize>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -175,10 +178,16 @@ fn parse_document_with_config(input: &str) -> Result<(Document, Option<Config>),
         .finish()
         .map_err(ParseError::ConfigError)
         .and_then(|(rest, cfg)| {
+            // The document starts after the config module, so the lines need to be offset by
+            // the lines the config module spans
+            let config_lines = line_of(input, rest) - 1;
             parse_document(rest)
                 .finish()
                 .map_err(Into::into)
-                .map(|(_, x)| (x, cfg))
+                .map(|(_, mut x)| {
+                    x.lines.iter_mut().for_each(|line| *line += config_lines);
+                    (x, cfg)
+                })
         })
 }
 
@@ -191,11 +200,21 @@ fn parse_document_with_config(input: &str) -> Result<(Document, Option<Config>),
 ///
 /// returns: Result<(&str, Element), Err<Error<I>>>
 fn parse_document(input: &str) -> IResult<&str, Document> {
-    map(parse_document_blocks, |blocks| Document {
-        elements: blocks,
+    map(parse_located_document_blocks, |blocks| {
+        let (lines, elements) = blocks
+            .into_iter()
+            .map(|(remaining, block)| (line_of(input, remaining), block))
+            .unzip();
+        Document { elements, lines }
     })(input)
 }
 
+/// Gets the line (counting from 1) that `remaining` starts on, given that `remaining` is a suffix
+/// of `input`
+fn line_of(input: &str, remaining: &str) -> usize {
+    input[..input.len() - remaining.len()].matches('\n').count() + 1
+}
+
 pub fn parse_blocks(input: &str) -> Result<Vec<Ast>, ParseError> {
     let (_, blocks) = parse_document_blocks(input).finish()?;
     Ok(blocks)
@@ -216,13 +235,30 @@ pub fn parse_inline(input: &str) -> Result<Vec<Ast>, ParseError> {
 ///
 /// returns: A vector of ASTs where each AST is either a multiline module or a paragraph
 fn parse_document_blocks(input: &str) -> IResult<&str, Vec<Ast>> {
+    map(parse_located_document_blocks, |blocks| {
+        blocks.into_iter().map(|(_, block)| block).collect()
+    })(input)
+}
+
+/// Parses blocks just like `parse_document_blocks`, but pairs each block with the input remaining
+/// where the block starts, so that the position of the block in the source can be found.
+///
+/// # Arguments
+///
+/// * `input`: The text to parse
+///
+/// returns: A vector of pairs of the remaining input and the parsed block
+fn parse_located_document_blocks(input: &str) -> IResult<&str, Vec<(&str, Ast)>> {
     preceded(
         many0(line_ending),
         separated_list0(
             many1(line_ending),
-            map(module::parse_multiline_module, Ast::Module)
-                .or(map(parse_heading, Ast::Heading))
-                .or(map(parse_nonempty_paragraph, Ast::Paragraph)),
+            pair(
+                peek(rest),
+                map(module::parse_multiline_module, Ast::Module)
+                    .or(map(parse_heading, Ast::Heading))
+                    .or(map(parse_nonempty_paragraph, Ast::Paragraph)),
+            ),
         ),
     )(input)
 }
@@ -466,7 +502,7 @@ fn pretty_ast(ast: &Ast) -> Vec<String> {
         Text(str) => str.lines().enumerate().for_each(|(idx, line)| {
             strs.push(format!("{} {line}", if idx == 0 { '>' } else { '|' }))
         }),
-        Ast::Document(Document { elements }) => {
+        Ast::Document(Document { elements, .. }) => {
             strs.push("Document:".to_string());
             strs.append(&mut children_ast(elements));
         }